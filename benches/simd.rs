@@ -0,0 +1,56 @@
+// Compares the scalar and vectorised dot products and distances on
+// 256-dimensional embeddings.
+//
+// Run with `cargo bench --bench simd --features simd`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use ndimpoint::Point;
+
+const DIM: usize = 256;
+const ITERS: usize = 200_000;
+
+fn time(label: &str, f: impl Fn() -> f64) -> Duration {
+    black_box(f());
+    let start = Instant::now();
+    black_box(f());
+    let elapsed = start.elapsed();
+    println!("{label:<16} {:>10.2?} ({:.1} ns/iter)", elapsed, {
+        elapsed.as_nanos() as f64 / ITERS as f64
+    });
+    elapsed
+}
+
+fn main() {
+    let a = Point::new((0..DIM).map(|i| (i as f64 * 0.37).sin()).collect());
+    let b = Point::new((0..DIM).map(|i| (i as f64 * 0.11).cos()).collect());
+
+    let scalar = time("dot", || {
+        (0..ITERS).map(|_| black_box(&a).dot(black_box(&b))).sum()
+    });
+    let simd = time("simd_dot", || {
+        (0..ITERS)
+            .map(|_| black_box(&a).simd_dot(black_box(&b)))
+            .sum()
+    });
+    println!(
+        "speedup          {:.2}x\n",
+        scalar.as_secs_f64() / simd.as_secs_f64()
+    );
+
+    let scalar = time("distance_to", || {
+        (0..ITERS)
+            .map(|_| black_box(&a).distance_to(black_box(&b)))
+            .sum()
+    });
+    let simd = time("simd_distance", || {
+        (0..ITERS)
+            .map(|_| black_box(&a).simd_distance(black_box(&b)))
+            .sum()
+    });
+    println!(
+        "speedup          {:.2}x",
+        scalar.as_secs_f64() / simd.as_secs_f64()
+    );
+}
@@ -0,0 +1,53 @@
+// Compares 3D point arithmetic on the inline storage path against the same
+// work done with plain heap-allocated `Vec`s.
+//
+// Run with `cargo bench --bench storage`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use ndimpoint::Point;
+
+const ITERS: usize = 1_000_000;
+
+fn time(label: &str, f: impl Fn() -> f64) -> Duration {
+    // Warm up once so both runs start from the same allocator state.
+    black_box(f());
+    let start = Instant::now();
+    black_box(f());
+    let elapsed = start.elapsed();
+    println!("{label:<12} {:>10.2?} ({:.1} ns/iter)", elapsed, {
+        elapsed.as_nanos() as f64 / ITERS as f64
+    });
+    elapsed
+}
+
+fn main() {
+    let inline = time("point", || {
+        let a = Point::new(vec![1.0, 2.0, 3.0]);
+        let b = Point::new(vec![0.5, 0.25, 0.125]);
+        let mut acc = 0.0;
+        for i in 0..ITERS {
+            let c = &(&a + &b) * black_box(i as f64);
+            acc += c.data()[2];
+        }
+        acc
+    });
+
+    let heap = time("vec", || {
+        let a = [1.0, 2.0, 3.0];
+        let b = [0.5, 0.25, 0.125];
+        let mut acc = 0.0;
+        for i in 0..ITERS {
+            let s: Vec<f64> = a.iter().zip(&b).map(|(x, y)| x + y).collect();
+            let c: Vec<f64> = s.iter().map(|x| x * black_box(i as f64)).collect();
+            acc += c[2];
+        }
+        acc
+    });
+
+    println!(
+        "speedup      {:.2}x",
+        heap.as_secs_f64() / inline.as_secs_f64()
+    );
+}
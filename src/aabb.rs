@@ -0,0 +1,225 @@
+// Axis-aligned bounding boxes in any dimension.
+//
+// A box is closed: points on its faces are inside and boxes that only touch
+// intersect. It is generic over the coordinate type so integer grids keep
+// exact extents; `center` and `volume` are computed in `f64`.
+
+use std::ops::Sub;
+
+use crate::{Error, Point, PointCloud, Storage};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aabb<T> {
+    lower: Vec<T>,
+    upper: Vec<T>,
+}
+
+impl<T> Aabb<T>
+where
+    T: Copy + PartialOrd,
+{
+    /// The box with corners `a` and `b`, in either order on every axis.
+    pub fn new(a: &[T], b: &[T]) -> Result<Self, Error> {
+        if a.is_empty() {
+            return Err(Error::ZeroDimension);
+        }
+        if a.len() != b.len() {
+            return Err(Error::DimensionMismatch {
+                expected: a.len(),
+                found: b.len(),
+            });
+        }
+        let (lower, upper) = a
+            .iter()
+            .zip(b)
+            .map(|(&x, &y)| if y < x { (y, x) } else { (x, y) })
+            .unzip();
+        Ok(Aabb { lower, upper })
+    }
+
+    /// The smallest box around `points`; `None` if there are none. Panics
+    /// if they don't share a dimension.
+    pub fn from_points<S: Storage<T>>(points: &[Point<T, S>]) -> Option<Self> {
+        let first = points.first()?.as_slice();
+        let mut b = Aabb {
+            lower: first.to_vec(),
+            upper: first.to_vec(),
+        };
+        for p in &points[1..] {
+            b.expand(p);
+        }
+        Some(b)
+    }
+
+    /// The smallest box around the points of `cloud`; `None` if it is
+    /// empty.
+    pub fn from_cloud(cloud: &PointCloud<T>) -> Option<Self> {
+        let (lower, upper) = cloud.bounding_box()?;
+        Some(Aabb {
+            lower: lower.as_slice().to_vec(),
+            upper: upper.as_slice().to_vec(),
+        })
+    }
+
+    pub fn dim(&self) -> usize {
+        self.lower.len()
+    }
+
+    pub fn lower(&self) -> &[T] {
+        &self.lower
+    }
+
+    pub fn upper(&self) -> &[T] {
+        &self.upper
+    }
+
+    /// Whether `p` lies inside or on the box. Panics if the dimensions
+    /// differ.
+    pub fn contains<S: Storage<T>>(&self, p: &Point<T, S>) -> bool {
+        let p = p.as_slice();
+        assert_eq!(p.len(), self.dim(), "point and box dimensions differ");
+        p.iter()
+            .zip(self.lower.iter().zip(&self.upper))
+            .all(|(v, (lo, hi))| lo <= v && v <= hi)
+    }
+
+    /// Whether the boxes share at least one point. Panics if the dimensions
+    /// differ.
+    pub fn intersects(&self, other: &Aabb<T>) -> bool {
+        assert_eq!(self.dim(), other.dim(), "box dimensions differ");
+        (0..self.dim()).all(|i| self.lower[i] <= other.upper[i] && other.lower[i] <= self.upper[i])
+    }
+
+    /// The overlap of the boxes; `None` if they don't intersect. Panics if
+    /// the dimensions differ.
+    pub fn intersection(&self, other: &Aabb<T>) -> Option<Aabb<T>> {
+        if !self.intersects(other) {
+            return None;
+        }
+        Some(Aabb {
+            lower: zip_with(&self.lower, &other.lower, |a, b| if a < b { b } else { a }),
+            upper: zip_with(&self.upper, &other.upper, |a, b| if b < a { b } else { a }),
+        })
+    }
+
+    /// Grows the box just enough to contain `p`. Panics if the dimensions
+    /// differ.
+    pub fn expand<S: Storage<T>>(&mut self, p: &Point<T, S>) {
+        let p = p.as_slice();
+        assert_eq!(p.len(), self.dim(), "point and box dimensions differ");
+        for ((lo, hi), &v) in self.lower.iter_mut().zip(&mut self.upper).zip(p) {
+            if v < *lo {
+                *lo = v;
+            }
+            if v > *hi {
+                *hi = v;
+            }
+        }
+    }
+
+    /// The smallest box containing both. Panics if the dimensions differ.
+    pub fn union(&self, other: &Aabb<T>) -> Aabb<T> {
+        assert_eq!(self.dim(), other.dim(), "box dimensions differ");
+        Aabb {
+            lower: zip_with(&self.lower, &other.lower, |a, b| if b < a { b } else { a }),
+            upper: zip_with(&self.upper, &other.upper, |a, b| if a < b { b } else { a }),
+        }
+    }
+
+    /// Side lengths along every axis.
+    pub fn extent(&self) -> Point<T>
+    where
+        T: Sub<Output = T>,
+    {
+        Point::from_storage(zip_with(&self.upper, &self.lower, |hi, lo| hi - lo).into())
+    }
+
+    pub fn center(&self) -> Point<f64>
+    where
+        T: Into<f64>,
+    {
+        Point::new(zip_with(&self.lower, &self.upper, |lo, hi| {
+            (lo.into() + hi.into()) / 2.0
+        }))
+    }
+
+    /// Product of the side lengths; zero for a flat box.
+    pub fn volume(&self) -> f64
+    where
+        T: Into<f64>,
+    {
+        self.lower
+            .iter()
+            .zip(&self.upper)
+            .map(|(&lo, &hi)| hi.into() - lo.into())
+            .product()
+    }
+}
+
+fn zip_with<T: Copy, U>(a: &[T], b: &[T], f: impl Fn(T, T) -> U) -> Vec<U> {
+    a.iter().zip(b).map(|(&x, &y)| f(x, y)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_and_measures() {
+        let b = Aabb::new(&[4, 0], &[0, 2]).unwrap();
+        assert_eq!((b.lower(), b.upper()), (&[0, 0][..], &[4, 2][..]));
+        assert_eq!(b.extent().data(), [4, 2]);
+        assert_eq!(b.center().data(), [2.0, 1.0]);
+        assert_eq!(b.volume(), 8.0);
+        assert_eq!(Aabb::<f64>::new(&[], &[]), Err(Error::ZeroDimension));
+        assert!(Aabb::new(&[1.0], &[1.0, 2.0]).is_err());
+
+        let pts = [
+            Point::new(vec![1.0, -1.0, 0.0]),
+            Point::new(vec![-2.0, 3.0, 0.5]),
+            Point::new(vec![0.0, 0.0, 0.0]),
+        ];
+        let b = Aabb::from_points(&pts).unwrap();
+        assert_eq!(b, Aabb::new(&[-2.0, -1.0, 0.0], &[1.0, 3.0, 0.5]).unwrap());
+        let cloud = PointCloud::from_flat(&[1.0, -1.0, 0.0, -2.0, 3.0, 0.5], 3).unwrap();
+        assert_eq!(Aabb::from_cloud(&cloud), Some(b));
+        assert!(Aabb::<f64>::from_points::<crate::VecStorage<f64>>(&[]).is_none());
+    }
+
+    #[test]
+    fn containment_and_overlap() {
+        let a = Aabb::new(&[0.0, 0.0], &[2.0, 2.0]).unwrap();
+        assert!(a.contains(&Point::new(vec![2.0, 1.0])));
+        assert!(!a.contains(&Point::new(vec![2.5, 1.0])));
+        let touching = Aabb::new(&[2.0, 2.0], &[3.0, 3.0]).unwrap();
+        assert!(a.intersects(&touching));
+        assert_eq!(a.intersection(&touching).unwrap().volume(), 0.0);
+        let apart = Aabb::new(&[2.1, 0.0], &[3.0, 1.0]).unwrap();
+        assert!(!a.intersects(&apart) && a.intersection(&apart).is_none());
+        let overlap = Aabb::new(&[1.0, -1.0], &[3.0, 1.0]).unwrap();
+        assert_eq!(
+            a.intersection(&overlap),
+            Aabb::new(&[1.0, 0.0], &[2.0, 1.0]).ok()
+        );
+        assert_eq!(
+            a.union(&apart),
+            Aabb::new(&[0.0, 0.0], &[3.0, 2.0]).unwrap()
+        );
+    }
+
+    #[test]
+    fn expands_to_cover_points() {
+        let mut b = Aabb::new(&[0, 0], &[1, 1]).unwrap();
+        b.expand(&Point::new(vec![-3, 5]));
+        b.expand(&Point::from_array([0, 0]));
+        assert_eq!(b, Aabb::new(&[-3, 0], &[1, 5]).unwrap());
+        assert!(
+            std::panic::catch_unwind(|| {
+                Aabb::new(&[0], &[1])
+                    .unwrap()
+                    .contains(&Point::new(vec![0, 0]))
+            })
+            .is_err()
+        );
+    }
+}
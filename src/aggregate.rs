@@ -0,0 +1,225 @@
+// Aggregates of a point attribute over boxes and balls.
+//
+// Every node of the underlying k-d tree stores the count, sum, minimum and
+// maximum of the attribute over its points. A query adds up the summaries of
+// nodes that lie wholly inside the region and looks at individual points only
+// in nodes the region's boundary cuts through, so nothing is materialised and
+// large regions are as cheap as small ones.
+
+use crate::Point;
+use crate::boxtree::{BoxTree, Overlap};
+use crate::index::squared_distance;
+
+/// Count, sum, minimum and maximum of some attribute values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Summary {
+    /// The summary of no values: `min` is infinite and `max` negative
+    /// infinite, so merging into it is exact.
+    pub const EMPTY: Summary = Summary {
+        count: 0,
+        sum: 0.0,
+        min: f64::INFINITY,
+        max: f64::NEG_INFINITY,
+    };
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+
+    pub fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    pub fn merge(&mut self, other: &Summary) {
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+}
+
+impl Default for Summary {
+    fn default() -> Self {
+        Summary::EMPTY
+    }
+}
+
+/// Points with one attribute value each, for aggregate range queries. Build
+/// one tree per attribute.
+#[derive(Debug, Clone)]
+pub struct AggregateTree {
+    tree: BoxTree,
+    // Attribute values in slot order.
+    values: Vec<f64>,
+    summaries: Vec<Summary>,
+}
+
+impl AggregateTree {
+    /// Builds the tree; `values[i]` is the attribute of `points[i]`. Panics if
+    /// the lengths differ or the points don't share a dimension.
+    pub fn new<T>(points: &[Point<T>], values: &[f64]) -> Self
+    where
+        T: Into<f64> + Copy,
+    {
+        assert_eq!(points.len(), values.len(), "one value per point");
+        let dim = points.first().map_or(0, |p| p.dim());
+        let tree = BoxTree::build(points, dim).expect("points must share a dimension");
+        let slot_values = (0..points.len()).map(|s| values[tree.kd.id(s)]).collect();
+        let mut agg = AggregateTree {
+            summaries: vec![Summary::EMPTY; tree.kd.node_count()],
+            tree,
+            values: slot_values,
+        };
+        agg.summarise(0);
+        agg
+    }
+
+    fn summarise(&mut self, node: usize) -> Summary {
+        let mut s = Summary::EMPTY;
+        match self.tree.kd.children(node) {
+            None => {
+                let (lo, hi) = self.tree.slots[node];
+                self.values[lo..hi].iter().for_each(|&v| s.add(v));
+            }
+            Some((l, r)) => {
+                s = self.summarise(l);
+                s.merge(&self.summarise(r));
+            }
+        }
+        self.summaries[node] = s;
+        s
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Summary over the points in the closed box `[lower, upper]`.
+    pub fn in_box(&self, lower: &[f64], upper: &[f64]) -> Summary {
+        self.query(&|node| self.tree.box_overlap(node, lower, upper), &|p| {
+            p.iter()
+                .zip(lower.iter().zip(upper))
+                .all(|(v, (lo, hi))| lo <= v && v <= hi)
+        })
+    }
+
+    /// Summary over the points at most `radius` from `center`.
+    pub fn in_ball(&self, center: &[f64], radius: f64) -> Summary {
+        let r2 = radius * radius;
+        self.query(&|node| self.tree.ball_overlap(node, center, r2), &|p| {
+            squared_distance(p, center) <= r2
+        })
+    }
+
+    fn query(
+        &self,
+        overlap: &dyn Fn(usize) -> Overlap,
+        contains: &dyn Fn(&[f64]) -> bool,
+    ) -> Summary {
+        let mut out = Summary::EMPTY;
+        if !self.is_empty() {
+            self.query_in(0, overlap, contains, &mut out);
+        }
+        out
+    }
+
+    fn query_in(
+        &self,
+        node: usize,
+        overlap: &dyn Fn(usize) -> Overlap,
+        contains: &dyn Fn(&[f64]) -> bool,
+        out: &mut Summary,
+    ) {
+        match overlap(node) {
+            Overlap::Disjoint => {}
+            Overlap::Inside => out.merge(&self.summaries[node]),
+            Overlap::Partial => match self.tree.kd.children(node) {
+                Some((l, r)) => {
+                    self.query_in(l, overlap, contains, out);
+                    self.query_in(r, overlap, contains, out);
+                }
+                None => {
+                    let (lo, hi) = self.tree.slots[node];
+                    for slot in lo..hi {
+                        if contains(self.tree.kd.point(slot)) {
+                            out.add(self.values[slot]);
+                        }
+                    }
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = Rng::seed_from_u64(42);
+        let pts: Vec<Point<f64>> = (0..500)
+            .map(|_| Point::new((0..2).map(|_| rng.range_f64(0.0, 10.0)).collect()))
+            .collect();
+        let values: Vec<f64> = (0..500).map(|i| (i % 17) as f64 - 3.0).collect();
+        let tree = AggregateTree::new(&pts, &values);
+        for _ in 0..20 {
+            let c = [rng.range_f64(0.0, 10.0), rng.range_f64(0.0, 10.0)];
+            let r = rng.range_f64(0.5, 4.0);
+            let (lower, upper) = ([c[0] - r, c[1] - r], [c[0] + r, c[1] + r]);
+            let mut in_box = Summary::EMPTY;
+            let mut in_ball = Summary::EMPTY;
+            for (p, &v) in pts.iter().zip(&values) {
+                if (0..2).all(|k| lower[k] <= p[k] && p[k] <= upper[k]) {
+                    in_box.add(v);
+                }
+                if (p[0] - c[0]).hypot(p[1] - c[1]) <= r {
+                    in_ball.add(v);
+                }
+            }
+            let got = tree.in_box(&lower, &upper);
+            assert_eq!(
+                (got.count, got.min, got.max),
+                (in_box.count, in_box.min, in_box.max)
+            );
+            assert!((got.sum - in_box.sum).abs() < 1e-9);
+            let got = tree.in_ball(&c, r);
+            assert_eq!(got.count, in_ball.count);
+            assert!((got.sum - in_ball.sum).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn summary() {
+        let tree = AggregateTree::new(&[Point::new(vec![0.0]), Point::new(vec![2.0])], &[4.0, 8.0]);
+        let s = tree.in_box(&[-1.0], &[5.0]);
+        assert_eq!(s.mean(), Some(6.0));
+        assert_eq!((s.min, s.max), (4.0, 8.0));
+        assert!(tree.in_ball(&[10.0], 1.0).is_empty());
+        assert_eq!(Summary::default().mean(), None);
+        assert!(
+            AggregateTree::new::<f64>(&[], &[])
+                .in_box(&[0.0], &[1.0])
+                .is_empty()
+        );
+    }
+}
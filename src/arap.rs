@@ -0,0 +1,238 @@
+// As-rigid-as-possible deformation of point sets (Sorkine and Alexa, 2007).
+//
+// Every point is joined to its nearest neighbours. Dragging a few handle
+// points to new positions, the rest follow so that each neighbourhood moves
+// as close to rigidly as it can: the solver alternates a local step, fitting
+// the best rotation to every neighbourhood, with a global step, solving the
+// Laplacian system that places the points to agree with those rotations.
+// Each round lowers the rigidity energy. Edges are weighted uniformly, since
+// a bare point set has no cotangent weights, and the sparse system is
+// solved by conjugate gradients warm-started from the previous round.
+//
+// A group of points with no path to a handle keeps its shape but has
+// nothing pinning it in place.
+
+use crate::linalg::{Matrix, dot};
+use crate::{Point, PointCloud};
+
+const CG_TOLERANCE: f64 = 1e-12;
+
+#[derive(Debug, Clone)]
+pub struct Arap {
+    rest: PointCloud<f64>,
+    neighbors: Vec<Vec<usize>>,
+}
+
+impl Arap {
+    /// Prepares to deform `rest`, joining every point to its `k` nearest
+    /// others (and to any point that counts it among theirs).
+    pub fn new(rest: PointCloud<f64>, k: usize) -> Self {
+        let n = rest.len();
+        let mut neighbors = vec![Vec::new(); n];
+        for i in 0..n {
+            let p = rest.get(i).expect("index in range");
+            let mut near: Vec<(usize, f64)> = (0..n)
+                .filter(|&j| j != i)
+                .map(|j| (j, squared_distance(p, rest.get(j).expect("index in range"))))
+                .collect();
+            near.sort_by(|a, b| a.1.total_cmp(&b.1));
+            for &(j, _) in near.iter().take(k) {
+                neighbors[i].push(j);
+                neighbors[j].push(i);
+            }
+        }
+        for list in &mut neighbors {
+            list.sort_unstable();
+            list.dedup();
+        }
+        Arap { rest, neighbors }
+    }
+
+    pub fn rest(&self) -> &PointCloud<f64> {
+        &self.rest
+    }
+
+    /// The points joined to point `i`, ascending.
+    pub fn neighbors(&self, i: usize) -> &[usize] {
+        &self.neighbors[i]
+    }
+
+    /// The rest shape deformed so each `(index, position)` handle sits at
+    /// its position, after `iterations` rounds of local and global steps.
+    /// Panics if a handle names a point that doesn't exist or has the wrong
+    /// dimension.
+    pub fn deform(&self, handles: &[(usize, Point<f64>)], iterations: usize) -> PointCloud<f64> {
+        let (n, dim) = (self.rest.len(), self.rest.dim());
+        let mut fixed = vec![false; n];
+        let mut current = self.rest.as_flat().to_vec();
+        for (i, target) in handles {
+            assert!(*i < n, "handle must name a point");
+            assert_eq!(target.dim(), dim, "handle dimension differs");
+            fixed[*i] = true;
+            current[i * dim..(i + 1) * dim].copy_from_slice(target.as_slice());
+        }
+        for _ in 0..iterations {
+            let rotations = self.rotations(&current);
+            // Right-hand side: sum_j (R_i + R_j) / 2 (p_i - p_j).
+            let mut rhs = vec![0.0; n * dim];
+            for i in 0..n {
+                let p = self.rest.get(i).expect("index in range");
+                for &j in &self.neighbors[i] {
+                    let q = self.rest.get(j).expect("index in range");
+                    let e: Vec<f64> = p.iter().zip(q).map(|(a, b)| a - b).collect();
+                    let (a, b) = (rotations[i].mul_vec(&e), rotations[j].mul_vec(&e));
+                    for k in 0..dim {
+                        rhs[i * dim + k] += (a[k] + b[k]) / 2.0;
+                    }
+                }
+            }
+            for k in 0..dim {
+                let b: Vec<f64> = (0..n).map(|i| rhs[i * dim + k]).collect();
+                let mut x: Vec<f64> = (0..n).map(|i| current[i * dim + k]).collect();
+                self.solve(&fixed, &b, &mut x);
+                for i in 0..n {
+                    current[i * dim + k] = x[i];
+                }
+            }
+        }
+        PointCloud::from_flat_vec(current, dim).expect("dimension unchanged")
+    }
+
+    // The rotation best taking every point's rest neighbourhood to its
+    // current one.
+    fn rotations(&self, current: &[f64]) -> Vec<Matrix> {
+        let dim = self.rest.dim();
+        (0..self.rest.len())
+            .map(|i| {
+                let p = self.rest.get(i).expect("index in range");
+                let p2 = &current[i * dim..(i + 1) * dim];
+                let mut s = Matrix::zeros(dim, dim);
+                for &j in &self.neighbors[i] {
+                    let q = self.rest.get(j).expect("index in range");
+                    let q2 = &current[j * dim..(j + 1) * dim];
+                    for r in 0..dim {
+                        for c in 0..dim {
+                            s[(r, c)] += (p2[r] - q2[r]) * (p[c] - q[c]);
+                        }
+                    }
+                }
+                s.nearest_rotation()
+            })
+            .collect()
+    }
+
+    // Solves the graph Laplacian system `L x = b` for the free entries of
+    // `x` by conjugate gradients, holding the fixed ones.
+    fn solve(&self, fixed: &[bool], b: &[f64], x: &mut [f64]) {
+        let n = x.len();
+        // L restricted to the free entries, applied to `v`.
+        let apply = |v: &[f64]| -> Vec<f64> {
+            (0..n)
+                .map(|i| {
+                    if fixed[i] {
+                        return 0.0;
+                    }
+                    let free_sum: f64 = self.neighbors[i]
+                        .iter()
+                        .filter(|&&j| !fixed[j])
+                        .map(|&j| v[j])
+                        .sum();
+                    self.neighbors[i].len() as f64 * v[i] - free_sum
+                })
+                .collect()
+        };
+        // Residual of the full system with the fixed entries moved across.
+        let full: Vec<f64> = (0..n)
+            .map(|i| {
+                let sum: f64 = self.neighbors[i].iter().map(|&j| x[j]).sum();
+                self.neighbors[i].len() as f64 * x[i] - sum
+            })
+            .collect();
+        let mut r: Vec<f64> = (0..n)
+            .map(|i| if fixed[i] { 0.0 } else { b[i] - full[i] })
+            .collect();
+        let scale = dot(b, b).max(1.0);
+        let mut d = r.clone();
+        let mut rr = dot(&r, &r);
+        for _ in 0..n.max(1) * 2 {
+            if rr <= CG_TOLERANCE * CG_TOLERANCE * scale {
+                break;
+            }
+            let ad = apply(&d);
+            let step = rr / dot(&d, &ad);
+            x.iter_mut().zip(&d).for_each(|(x, d)| *x += step * d);
+            r.iter_mut().zip(&ad).for_each(|(r, a)| *r -= step * a);
+            let next = dot(&r, &r);
+            d.iter_mut()
+                .zip(&r)
+                .for_each(|(d, r)| *d = r + next / rr * *d);
+            rr = next;
+        }
+    }
+}
+
+fn squared_distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 2 by 10 strip of points along x.
+    fn strip() -> PointCloud<f64> {
+        let flat: Vec<f64> = (0..20)
+            .flat_map(|i| [(i / 2) as f64, (i % 2) as f64])
+            .collect();
+        PointCloud::from_flat_vec(flat, 2).unwrap()
+    }
+
+    fn distance(cloud: &PointCloud<f64>, i: usize, j: usize) -> f64 {
+        squared_distance(cloud.get(i).unwrap(), cloud.get(j).unwrap()).sqrt()
+    }
+
+    #[test]
+    fn rigid_handles_move_everything_rigidly() {
+        let arap = Arap::new(strip(), 3);
+        assert!(arap.neighbors(0).contains(&1) && arap.neighbors(0).contains(&2));
+        // Handles at both ends shifted and turned by a quarter turn.
+        let turn = |p: &[f64]| Point::new(vec![3.0 - p[1], 1.0 + p[0]]);
+        let rest = arap.rest().clone();
+        let handles: Vec<(usize, Point<f64>)> = [0, 1, 18, 19]
+            .iter()
+            .map(|&i| (i, turn(rest.get(i).unwrap())))
+            .collect();
+        // Convergence is linear, so a big turn takes a good many rounds.
+        let out = arap.deform(&handles, 100);
+        for i in 0..rest.len() {
+            let want = turn(rest.get(i).unwrap());
+            assert!(squared_distance(out.get(i).unwrap(), want.as_slice()) < 1e-9);
+        }
+    }
+
+    #[test]
+    fn bending_keeps_neighbourhoods_rigid() {
+        let arap = Arap::new(strip(), 3);
+        let rest = arap.rest().clone();
+        // Pin the left end, lift the right end.
+        let mut handles = vec![
+            (0, Point::new(vec![0.0, 0.0])),
+            (1, Point::new(vec![0.0, 1.0])),
+        ];
+        handles.push((18, Point::new(vec![8.0, 4.0])));
+        handles.push((19, Point::new(vec![7.5, 4.9])));
+        let out = arap.deform(&handles, 30);
+        assert_eq!(out.get(0).unwrap(), &[0.0, 0.0]);
+        assert_eq!(out.get(18).unwrap(), &[8.0, 4.0]);
+        // Naive interpolation would stretch the strip; ARAP bends it with
+        // edges close to their rest lengths.
+        for i in 0..rest.len() {
+            for &j in arap.neighbors(i) {
+                let stretch = distance(&out, i, j) / distance(&rest, i, j);
+                assert!((stretch - 1.0).abs() < 0.15, "edge {i}-{j}: {stretch}");
+            }
+        }
+        // And without handles nothing moves.
+        assert_eq!(arap.deform(&[], 5), rest);
+    }
+}
@@ -0,0 +1,255 @@
+// Random augmentation of point clouds, for training point-based models.
+//
+// An `Augmentation` is a list of steps applied in order. Each call draws
+// fresh parameters from the caller's `Rng`, so a seed reproduces a whole
+// epoch of augmented clouds. Rotations, scales and flips act about the
+// origin; centre and normalise clouds first if that's what the model wants.
+
+use crate::PointCloud;
+use crate::rng::Rng;
+
+/// One augmentation step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Augment {
+    /// A uniformly random rotation of the whole space, or only of the two
+    /// coordinates in `plane` (e.g. about the vertical axis).
+    Rotate { plane: Option<[usize; 2]> },
+    /// Independent Gaussian noise of deviation `sigma` on every coordinate,
+    /// each offset clipped to `[-clip, clip]` when `clip` is set.
+    Jitter { sigma: f64, clip: Option<f64> },
+    /// Drops each point with probability `rate`, keeping at least one.
+    Dropout { rate: f64 },
+    /// Scales every coordinate by one factor drawn uniformly from
+    /// `[min, max)`.
+    Scale { min: f64, max: f64 },
+    /// Mirrors coordinate `axis` with probability `probability`.
+    Flip { axis: usize, probability: f64 },
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Augmentation {
+    steps: Vec<Augment>,
+}
+
+impl Augmentation {
+    pub fn new() -> Self {
+        Augmentation { steps: Vec::new() }
+    }
+
+    /// Appends a step.
+    pub fn then(mut self, step: Augment) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    pub fn steps(&self) -> &[Augment] {
+        &self.steps
+    }
+
+    /// An augmented copy of `cloud`. Panics if a step names an axis the cloud
+    /// doesn't have.
+    pub fn apply(&self, cloud: &PointCloud<f64>, rng: &mut Rng) -> PointCloud<f64> {
+        let dim = cloud.dim();
+        let mut data = cloud.as_flat().to_vec();
+        for step in &self.steps {
+            match *step {
+                Augment::Rotate { plane } => {
+                    let r = match plane {
+                        None => random_rotation(dim, rng),
+                        Some([u, v]) => plane_rotation(dim, u, v, rng),
+                    };
+                    for p in data.chunks_exact_mut(dim) {
+                        let q: Vec<f64> = r
+                            .iter()
+                            .map(|row| row.iter().zip(&*p).map(|(a, b)| a * b).sum())
+                            .collect();
+                        p.copy_from_slice(&q);
+                    }
+                }
+                Augment::Jitter { sigma, clip } => {
+                    for v in &mut data {
+                        let e = sigma * rng.normal();
+                        *v += clip.map_or(e, |c| e.clamp(-c, c));
+                    }
+                }
+                Augment::Dropout { rate } => {
+                    let n = data.len() / dim;
+                    let keep: Vec<bool> = (0..n).map(|_| rng.next_f64() >= rate).collect();
+                    let rescue = (n > 0 && !keep.contains(&true)).then(|| rng.below(n));
+                    data = data
+                        .chunks_exact(dim)
+                        .enumerate()
+                        .filter(|&(i, _)| keep[i] || rescue == Some(i))
+                        .flat_map(|(_, p)| p.iter().copied())
+                        .collect();
+                }
+                Augment::Scale { min, max } => {
+                    let s = rng.range_f64(min, max);
+                    data.iter_mut().for_each(|v| *v *= s);
+                }
+                Augment::Flip { axis, probability } => {
+                    assert!(axis < dim, "flip axis out of range");
+                    if rng.next_f64() < probability {
+                        data.iter_mut()
+                            .skip(axis)
+                            .step_by(dim)
+                            .for_each(|v| *v = -*v);
+                    }
+                }
+            }
+        }
+        PointCloud::from_flat_vec(data, dim).expect("augmentation keeps the dimension")
+    }
+
+    /// `count` augmented copies of `cloud`, each from its own forked
+    /// generator.
+    pub fn batch(
+        &self,
+        cloud: &PointCloud<f64>,
+        count: usize,
+        rng: &mut Rng,
+    ) -> Vec<PointCloud<f64>> {
+        (0..count)
+            .map(|_| self.apply(cloud, &mut rng.fork()))
+            .collect()
+    }
+}
+
+// A rotation drawn uniformly from SO(dim): Gram-Schmidt on Gaussian rows,
+// then one row negated if that gave a mirror.
+fn random_rotation(dim: usize, rng: &mut Rng) -> Vec<Vec<f64>> {
+    let mut rows: Vec<Vec<f64>> = Vec::with_capacity(dim);
+    while rows.len() < dim {
+        let mut v: Vec<f64> = (0..dim).map(|_| rng.normal()).collect();
+        for r in &rows {
+            let d: f64 = v.iter().zip(r).map(|(a, b)| a * b).sum();
+            v.iter_mut().zip(r).for_each(|(a, b)| *a -= d * b);
+        }
+        let len = v.iter().map(|a| a * a).sum::<f64>().sqrt();
+        // Retry the vanishingly rare draw that is nearly dependent.
+        if len > 1e-9 {
+            v.iter_mut().for_each(|a| *a /= len);
+            rows.push(v);
+        }
+    }
+    if determinant_sign(&rows) < 0.0 {
+        rows[0].iter_mut().for_each(|a| *a = -*a);
+    }
+    rows
+}
+
+fn plane_rotation(dim: usize, u: usize, v: usize, rng: &mut Rng) -> Vec<Vec<f64>> {
+    assert!(u < dim && v < dim && u != v, "rotation plane out of range");
+    let (s, c) = rng.range_f64(0.0, std::f64::consts::TAU).sin_cos();
+    let mut r: Vec<Vec<f64>> = (0..dim)
+        .map(|i| (0..dim).map(|j| f64::from(u8::from(i == j))).collect())
+        .collect();
+    r[u][u] = c;
+    r[u][v] = -s;
+    r[v][u] = s;
+    r[v][v] = c;
+    r
+}
+
+// Sign of the determinant, by Gaussian elimination with partial pivoting.
+fn determinant_sign(rows: &[Vec<f64>]) -> f64 {
+    let mut m = rows.to_vec();
+    let n = m.len();
+    let mut sign = 1.0;
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs()))
+            .unwrap();
+        if pivot != col {
+            m.swap(pivot, col);
+            sign = -sign;
+        }
+        let p = m[col][col];
+        if p < 0.0 {
+            sign = -sign;
+        }
+        for r in col + 1..n {
+            let f = m[r][col] / p;
+            let (top, rest) = m.split_at_mut(r);
+            rest[0]
+                .iter_mut()
+                .zip(&top[col])
+                .for_each(|(a, b)| *a -= f * b);
+        }
+    }
+    sign
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn norms(c: &PointCloud<f64>) -> Vec<f64> {
+        c.iter()
+            .map(|p| p.iter().map(|v| v * v).sum::<f64>().sqrt())
+            .collect()
+    }
+
+    fn cloud() -> PointCloud<f64> {
+        let data: Vec<f64> = (0..60).map(|i| (i as f64 * 0.37).sin() * 3.0).collect();
+        PointCloud::from_flat_vec(data, 3).unwrap()
+    }
+
+    #[test]
+    fn rotations_preserve_lengths() {
+        let c = cloud();
+        let aug = Augmentation::new().then(Augment::Rotate { plane: None });
+        let mut rng = Rng::seed_from_u64(7);
+        for _ in 0..5 {
+            let r = random_rotation(4, &mut rng);
+            assert!(determinant_sign(&r) > 0.0);
+            let out = aug.apply(&c, &mut rng);
+            for (a, b) in norms(&c).iter().zip(norms(&out)) {
+                assert!((a - b).abs() < 1e-9);
+            }
+        }
+        let about_z = Augmentation::new().then(Augment::Rotate {
+            plane: Some([0, 1]),
+        });
+        let out = about_z.apply(&c, &mut rng);
+        for (p, q) in c.iter().zip(out.iter()) {
+            assert_eq!(p[2], q[2]);
+        }
+    }
+
+    #[test]
+    fn seeded_pipeline() {
+        let c = cloud();
+        let aug = Augmentation::new()
+            .then(Augment::Dropout { rate: 0.5 })
+            .then(Augment::Scale { min: 0.8, max: 1.2 })
+            .then(Augment::Flip {
+                axis: 0,
+                probability: 0.5,
+            })
+            .then(Augment::Jitter {
+                sigma: 0.01,
+                clip: Some(0.02),
+            });
+        assert_eq!(aug.steps().len(), 4);
+        let a = aug.batch(&c, 3, &mut Rng::seed_from_u64(1));
+        let b = aug.batch(&c, 3, &mut Rng::seed_from_u64(1));
+        assert_eq!(a, b);
+        assert_ne!(a[0], a[1]);
+        assert!(a.iter().all(|o| !o.is_empty() && o.len() < c.len()));
+
+        let all = Augmentation::new().then(Augment::Dropout { rate: 1.0 });
+        assert_eq!(all.apply(&c, &mut Rng::seed_from_u64(2)).len(), 1);
+        let jitter = Augmentation::new().then(Augment::Jitter {
+            sigma: 5.0,
+            clip: Some(0.1),
+        });
+        let out = jitter.apply(&c, &mut Rng::seed_from_u64(3));
+        assert!(
+            c.as_flat()
+                .iter()
+                .zip(out.as_flat())
+                .all(|(a, b)| (a - b).abs() <= 0.1 + 1e-12)
+        );
+    }
+}
@@ -0,0 +1,537 @@
+// Ball trees: nearest-neighbour search that holds up in high dimensions.
+//
+// Every node is a ball, a centre and a radius covering its points. A node
+// splits its points at the median of their projections onto the line
+// between two far-apart points, which follows the spread of the data rather
+// than a coordinate axis; that is what keeps the tree useful for
+// embeddings with hundreds of dimensions, where a k-d tree's axis splits
+// stop pruning. A query skips any ball whose nearest possible point,
+// `|q - c| - r`, is farther than the current k-th best.
+
+#[cfg(feature = "io")]
+use std::io::{self, Read, Write};
+
+use crate::Point;
+use crate::index::{IndexStats, KBest, Neighbor, SpatialIndex, squared_distance};
+#[cfg(feature = "io")]
+use crate::persist::{self, invalid};
+
+const LEAF_SIZE: usize = 16;
+#[cfg(feature = "io")]
+const MAGIC: &[u8; 4] = b"NDBT";
+#[cfg(feature = "io")]
+const VERSION: u32 = 1;
+
+#[derive(Debug, Clone)]
+struct Node {
+    // Slots `lo..hi` of the tree-ordered points.
+    lo: usize,
+    hi: usize,
+    radius: f64,
+    children: Option<(usize, usize)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BallTree {
+    dim: usize,
+    coords: Vec<f64>,
+    ids: Vec<usize>,
+    nodes: Vec<Node>,
+    // Centre of node `i` at `i * dim`.
+    centers: Vec<f64>,
+}
+
+impl BallTree {
+    /// Builds a tree over `points`; results refer to positions in `points`.
+    /// Panics if the points don't share a dimension.
+    pub fn new<T>(points: &[Point<T>]) -> Self
+    where
+        T: Into<f64> + Copy,
+    {
+        let dim = points.first().map_or(0, |p| p.dim());
+        let mut flat = Vec::with_capacity(points.len() * dim);
+        for p in points {
+            assert_eq!(p.dim(), dim, "points must share a dimension");
+            flat.extend(p.iter().map(|&v| v.into()));
+        }
+        let mut perm: Vec<usize> = (0..points.len()).collect();
+        let mut tree = BallTree {
+            dim,
+            coords: Vec::new(),
+            ids: Vec::new(),
+            nodes: Vec::new(),
+            centers: Vec::new(),
+        };
+        if !points.is_empty() {
+            tree.build(&flat, &mut perm, 0);
+        }
+        tree.coords = perm
+            .iter()
+            .flat_map(|&i| flat[i * dim..(i + 1) * dim].iter().copied())
+            .collect();
+        tree.ids = perm;
+        tree
+    }
+
+    fn point(&self, slot: usize) -> &[f64] {
+        &self.coords[slot * self.dim..(slot + 1) * self.dim]
+    }
+
+    fn center(&self, node: usize) -> &[f64] {
+        &self.centers[node * self.dim..(node + 1) * self.dim]
+    }
+
+    // Builds the node over `perm`, which sits at slot `lo`, and returns its
+    // index.
+    fn build(&mut self, flat: &[f64], perm: &mut [usize], lo: usize) -> usize {
+        let dim = self.dim;
+        let at = |i: usize| &flat[i * dim..(i + 1) * dim];
+        let mut center = vec![0.0; dim];
+        for &i in perm.iter() {
+            center.iter_mut().zip(at(i)).for_each(|(c, v)| *c += v);
+        }
+        center.iter_mut().for_each(|c| *c /= perm.len() as f64);
+        let farthest_from = |c: &[f64]| {
+            perm.iter()
+                .copied()
+                .max_by(|&a, &b| squared_distance(at(a), c).total_cmp(&squared_distance(at(b), c)))
+                .expect("node is not empty")
+        };
+        let far = farthest_from(&center);
+        let radius = squared_distance(at(far), &center).sqrt();
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            lo,
+            hi: lo + perm.len(),
+            radius,
+            children: None,
+        });
+        self.centers.extend_from_slice(&center);
+        if perm.len() <= LEAF_SIZE || radius == 0.0 {
+            return node;
+        }
+        // Split along the line between two far-apart points.
+        let a = far;
+        let b = farthest_from(at(a));
+        let dir: Vec<f64> = at(b).iter().zip(at(a)).map(|(x, y)| x - y).collect();
+        let proj = |i: usize| at(i).iter().zip(&dir).map(|(x, d)| x * d).sum::<f64>();
+        let mid = perm.len() / 2;
+        perm.select_nth_unstable_by(mid, |&x, &y| proj(x).total_cmp(&proj(y)));
+        let (left, right) = perm.split_at_mut(mid);
+        let l = self.build(flat, left, lo);
+        let r = self.build(flat, right, lo + mid);
+        self.nodes[node].children = Some((l, r));
+        node
+    }
+
+    // Distance from `query` to the centre of `node`.
+    fn center_distance(&self, node: usize, query: &[f64]) -> f64 {
+        squared_distance(self.center(node), query).sqrt()
+    }
+
+    fn search_k(
+        &self,
+        node: usize,
+        d: f64,
+        query: &[f64],
+        best: &mut KBest,
+        keep: &dyn Fn(usize) -> bool,
+    ) {
+        let n = &self.nodes[node];
+        if d - n.radius > best.worst() {
+            return;
+        }
+        match n.children {
+            None => {
+                for slot in n.lo..n.hi {
+                    if keep(self.ids[slot]) {
+                        let dist = squared_distance(self.point(slot), query).sqrt();
+                        best.push(self.ids[slot], dist);
+                    }
+                }
+            }
+            Some((l, r)) => {
+                // Nearer ball first so `worst` shrinks sooner.
+                let (dl, dr) = (
+                    self.center_distance(l, query),
+                    self.center_distance(r, query),
+                );
+                if dl <= dr {
+                    self.search_k(l, dl, query, best, keep);
+                    self.search_k(r, dr, query, best, keep);
+                } else {
+                    self.search_k(r, dr, query, best, keep);
+                    self.search_k(l, dl, query, best, keep);
+                }
+            }
+        }
+    }
+
+    fn search_radius(
+        &self,
+        node: usize,
+        query: &[f64],
+        radius: f64,
+        out: &mut Vec<Neighbor>,
+        keep: &dyn Fn(usize) -> bool,
+    ) {
+        let n = &self.nodes[node];
+        if self.center_distance(node, query) - n.radius > radius {
+            return;
+        }
+        match n.children {
+            None => {
+                for slot in n.lo..n.hi {
+                    let index = self.ids[slot];
+                    if keep(index) {
+                        let distance = squared_distance(self.point(slot), query).sqrt();
+                        if distance <= radius {
+                            out.push(Neighbor { index, distance });
+                        }
+                    }
+                }
+            }
+            Some((l, r)) => {
+                self.search_radius(l, query, radius, out, keep);
+                self.search_radius(r, query, radius, out, keep);
+            }
+        }
+    }
+
+    // Like `search_k` with negated distances, so the kept "best" are the
+    // farthest; a ball is skipped when even its far side, `|q - c| + r`,
+    // can't beat the current k-th farthest.
+    fn search_far(
+        &self,
+        node: usize,
+        d: f64,
+        query: &[f64],
+        best: &mut KBest,
+        keep: &dyn Fn(usize) -> bool,
+    ) {
+        let n = &self.nodes[node];
+        if -(d + n.radius) > best.worst() {
+            return;
+        }
+        match n.children {
+            None => {
+                for slot in n.lo..n.hi {
+                    if keep(self.ids[slot]) {
+                        let dist = squared_distance(self.point(slot), query).sqrt();
+                        best.push(self.ids[slot], -dist);
+                    }
+                }
+            }
+            Some((l, r)) => {
+                let (dl, dr) = (
+                    self.center_distance(l, query),
+                    self.center_distance(r, query),
+                );
+                if dl + self.nodes[l].radius >= dr + self.nodes[r].radius {
+                    self.search_far(l, dl, query, best, keep);
+                    self.search_far(r, dr, query, best, keep);
+                } else {
+                    self.search_far(r, dr, query, best, keep);
+                    self.search_far(l, dl, query, best, keep);
+                }
+            }
+        }
+    }
+
+    pub fn stats(&self) -> IndexStats {
+        let mut leaves = Vec::new();
+        if !self.nodes.is_empty() {
+            self.collect_leaves(0, 0, &mut leaves);
+        }
+        let memory = std::mem::size_of::<Self>()
+            + (self.coords.capacity() + self.centers.capacity()) * std::mem::size_of::<f64>()
+            + self.ids.capacity() * std::mem::size_of::<usize>()
+            + self.nodes.capacity() * std::mem::size_of::<Node>();
+        IndexStats::from_leaves(self.ids.len(), self.nodes.len(), &leaves, LEAF_SIZE, memory)
+    }
+
+    fn collect_leaves(&self, node: usize, depth: usize, out: &mut Vec<(usize, usize)>) {
+        let n = &self.nodes[node];
+        match n.children {
+            None => out.push((depth, n.hi - n.lo)),
+            Some((l, r)) => {
+                self.collect_leaves(l, depth + 1, out);
+                self.collect_leaves(r, depth + 1, out);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "io")]
+impl BallTree {
+    /// Writes the tree in a versioned binary format, so it can be loaded
+    /// without rebuilding.
+    pub fn save<W: Write>(&self, mut w: W) -> io::Result<()> {
+        persist::write_header(&mut w, MAGIC, VERSION)?;
+        persist::write_usize(&mut w, self.dim)?;
+        persist::write_f64s(&mut w, &self.coords)?;
+        persist::write_usizes(&mut w, &self.ids)?;
+        persist::write_f64s(&mut w, &self.centers)?;
+        persist::write_usize(&mut w, self.nodes.len())?;
+        for n in &self.nodes {
+            persist::write_usize(&mut w, n.lo)?;
+            persist::write_usize(&mut w, n.hi)?;
+            persist::write_f64(&mut w, n.radius)?;
+            match n.children {
+                None => persist::write_u8(&mut w, 0)?,
+                Some((l, r)) => {
+                    persist::write_u8(&mut w, 1)?;
+                    persist::write_usize(&mut w, l)?;
+                    persist::write_usize(&mut w, r)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a tree written by [`BallTree::save`]. Fails with
+    /// `ErrorKind::InvalidData` on a foreign, newer or inconsistent file.
+    pub fn load<R: Read>(mut r: R) -> io::Result<Self> {
+        persist::read_header(&mut r, MAGIC, VERSION)?;
+        let dim = persist::read_usize(&mut r)?;
+        let coords = persist::read_f64s(&mut r)?;
+        let ids = persist::read_usizes(&mut r)?;
+        let centers = persist::read_f64s(&mut r)?;
+        let count = persist::read_usize(&mut r)?;
+        let mut nodes = Vec::with_capacity(count.min(1 << 16));
+        for _ in 0..count {
+            let lo = persist::read_usize(&mut r)?;
+            let hi = persist::read_usize(&mut r)?;
+            let radius = persist::read_f64(&mut r)?;
+            let children = match persist::read_u8(&mut r)? {
+                0 => None,
+                1 => Some((persist::read_usize(&mut r)?, persist::read_usize(&mut r)?)),
+                _ => return Err(invalid("unknown node tag")),
+            };
+            nodes.push(Node {
+                lo,
+                hi,
+                radius,
+                children,
+            });
+        }
+        let n = ids.len();
+        let consistent = n.checked_mul(dim) == Some(coords.len())
+            && nodes.len().checked_mul(dim) == Some(centers.len())
+            && nodes.is_empty() == (n == 0);
+        if !consistent {
+            return Err(invalid("inconsistent ball tree"));
+        }
+        let tree = BallTree {
+            dim,
+            coords,
+            ids,
+            nodes,
+            centers,
+        };
+        if !tree.well_formed() {
+            return Err(invalid("inconsistent ball tree"));
+        }
+        Ok(tree)
+    }
+
+    // Whether the ids are a permutation of the point positions and a walk
+    // from the root reaches every node exactly once, each child's slots
+    // nesting inside its parent's. Queries recurse through the children,
+    // so a cyclic or shared child in a corrupt file must be caught here.
+    fn well_formed(&self) -> bool {
+        let n = self.ids.len();
+        let mut seen_ids = vec![false; n];
+        for &id in &self.ids {
+            if id >= n || std::mem::replace(&mut seen_ids[id], true) {
+                return false;
+            }
+        }
+        if self.nodes.is_empty() {
+            return true;
+        }
+        let root = &self.nodes[0];
+        if root.lo != 0 || root.hi != n {
+            return false;
+        }
+        let mut seen_nodes = vec![false; self.nodes.len()];
+        let mut stack = vec![0];
+        while let Some(i) = stack.pop() {
+            if std::mem::replace(&mut seen_nodes[i], true) {
+                return false;
+            }
+            let node = &self.nodes[i];
+            if let Some((l, r)) = node.children {
+                for child in [l, r] {
+                    let nested = self
+                        .nodes
+                        .get(child)
+                        .is_some_and(|c| node.lo <= c.lo && c.lo <= c.hi && c.hi <= node.hi);
+                    if !nested {
+                        return false;
+                    }
+                    stack.push(child);
+                }
+            }
+        }
+        seen_nodes.into_iter().all(|s| s)
+    }
+}
+
+impl SpatialIndex for BallTree {
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    fn k_nearest_where(
+        &self,
+        query: &[f64],
+        k: usize,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor> {
+        let mut best = KBest::new(k);
+        if !self.nodes.is_empty() {
+            let d = self.center_distance(0, query);
+            self.search_k(0, d, query, &mut best, keep);
+        }
+        best.into_neighbors()
+    }
+
+    fn within_radius_where(
+        &self,
+        query: &[f64],
+        radius: f64,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor> {
+        let mut out = Vec::new();
+        if !self.nodes.is_empty() {
+            self.search_radius(0, query, radius, &mut out, keep);
+        }
+        out.sort_unstable();
+        out
+    }
+
+    fn k_farthest_where(
+        &self,
+        query: &[f64],
+        k: usize,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor> {
+        let mut best = KBest::new(k);
+        if !self.nodes.is_empty() {
+            let d = self.center_distance(0, query);
+            self.search_far(0, d, query, &mut best, keep);
+        }
+        best.into_neighbors()
+            .into_iter()
+            .map(|n| Neighbor {
+                index: n.index,
+                distance: -n.distance,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn random_points(rng: &mut Rng, n: usize, dim: usize) -> Vec<Point<f64>> {
+        (0..n)
+            .map(|_| Point::new((0..dim).map(|_| rng.normal()).collect()))
+            .collect()
+    }
+
+    // Every point's distance to `q`, nearest first, ties by index.
+    fn brute(pts: &[Point<f64>], q: &[f64]) -> Vec<Neighbor> {
+        let mut all: Vec<Neighbor> = pts
+            .iter()
+            .enumerate()
+            .map(|(index, p)| Neighbor {
+                index,
+                distance: squared_distance(p, q).sqrt(),
+            })
+            .collect();
+        all.sort_unstable();
+        all
+    }
+
+    #[test]
+    fn matches_brute_force_in_high_dimensions() {
+        let mut rng = Rng::seed_from_u64(261);
+        let pts = random_points(&mut rng, 600, 128);
+        let tree = BallTree::new(&pts);
+        assert_eq!(tree.len(), 600);
+        for q in random_points(&mut rng, 5, 128) {
+            let all = brute(&pts, &q);
+            assert_eq!(tree.k_nearest(&q, 7), all[..7]);
+            let r = all[20].distance;
+            assert_eq!(tree.within_radius(&q, r), all[..21]);
+            let far: Vec<usize> = all.iter().rev().take(3).map(|n| n.index).collect();
+            let got: Vec<usize> = tree.k_farthest(&q, 3).iter().map(|n| n.index).collect();
+            assert_eq!(got, far);
+            let even = tree.k_nearest_where(&q, 3, &|i| i % 2 == 0);
+            let want: Vec<Neighbor> = all
+                .iter()
+                .filter(|n| n.index % 2 == 0)
+                .take(3)
+                .copied()
+                .collect();
+            assert_eq!(even, want);
+        }
+        assert_eq!(tree.nearest(&pts[42]).unwrap().index, 42);
+        let stats = tree.stats();
+        assert_eq!(stats.points, 600);
+        assert!(stats.max_leaf <= LEAF_SIZE);
+    }
+
+    #[test]
+    fn duplicates_and_empty() {
+        let pts = vec![Point::new(vec![1.0, 1.0]); 40];
+        let tree = BallTree::new(&pts);
+        assert_eq!(tree.within_radius(&[1.0, 1.0], 0.0).len(), 40);
+        let empty = BallTree::new::<f64>(&[]);
+        assert!(empty.is_empty() && empty.k_nearest(&[0.0], 3).is_empty());
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn save_and_load() {
+        let mut rng = Rng::seed_from_u64(2611);
+        let pts = random_points(&mut rng, 200, 32);
+        let tree = BallTree::new(&pts);
+        let mut buf = Vec::new();
+        tree.save(&mut buf).unwrap();
+        let loaded = BallTree::load(&buf[..]).unwrap();
+        let q = &pts[3];
+        assert_eq!(loaded.k_nearest(q, 5), tree.k_nearest(q, 5));
+        assert!(BallTree::load(&buf[..buf.len() - 1]).is_err());
+        let mut bad = buf.clone();
+        bad[4] = 9;
+        assert_eq!(
+            BallTree::load(&bad[..]).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        // A child pointing back at the root, and an id repeated, are both
+        // rejected rather than loaded.
+        let mut cyclic = tree.clone();
+        let (_, r) = cyclic.nodes[0].children.unwrap();
+        cyclic.nodes[0].children = Some((0, r));
+        let mut repeated = tree.clone();
+        repeated.ids[1] = repeated.ids[0];
+        for corrupt in [cyclic, repeated] {
+            let mut buf = Vec::new();
+            corrupt.save(&mut buf).unwrap();
+            assert_eq!(
+                BallTree::load(&buf[..]).unwrap_err().kind(),
+                io::ErrorKind::InvalidData
+            );
+        }
+    }
+}
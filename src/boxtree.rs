@@ -0,0 +1,150 @@
+// A k-d tree annotated with a bounding box and slot range for every node.
+//
+// The dual-tree joins and the aggregate queries prune whole nodes by their
+// boxes; this computes the boxes once from a built `KdTree` so the tree
+// itself stays lean for plain searches.
+
+use crate::kdtree::KdTree;
+use crate::{Error, Point};
+
+/// How a node's box relates to a query region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Overlap {
+    Disjoint,
+    Partial,
+    Inside,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct BoxTree {
+    pub(crate) kd: KdTree,
+    dim: usize,
+    lo: Vec<f64>,
+    hi: Vec<f64>,
+    pub(crate) slots: Vec<(usize, usize)>,
+}
+
+impl BoxTree {
+    /// Builds the tree; fails unless every point has dimension `dim`.
+    pub(crate) fn build<T>(points: &[Point<T>], dim: usize) -> Result<Self, Error>
+    where
+        T: Into<f64> + Copy,
+    {
+        let mut flat = Vec::with_capacity(points.len() * dim);
+        for p in points {
+            if p.dim() != dim {
+                return Err(Error::DimensionMismatch {
+                    expected: dim,
+                    found: p.dim(),
+                });
+            }
+            flat.extend(p.iter().map(|&v| v.into()));
+        }
+        let kd = KdTree::from_parts(dim, (0..points.len()).collect(), &flat);
+        Ok(BoxTree::new(kd, dim))
+    }
+
+    fn new(kd: KdTree, dim: usize) -> Self {
+        let n = kd.node_count();
+        let mut tree = BoxTree {
+            kd,
+            dim,
+            lo: vec![f64::INFINITY; n * dim],
+            hi: vec![f64::NEG_INFINITY; n * dim],
+            slots: vec![(0, 0); n],
+        };
+        tree.fill(0);
+        tree
+    }
+
+    fn fill(&mut self, node: usize) -> (usize, usize) {
+        let dim = self.dim;
+        let range = match self.kd.children(node) {
+            None => {
+                let (s, e) = self.kd.leaf_slots(node).expect("node is a leaf");
+                for slot in s..e {
+                    let p = self.kd.point(slot);
+                    for (j, &v) in p.iter().enumerate() {
+                        self.lo[node * dim + j] = self.lo[node * dim + j].min(v);
+                        self.hi[node * dim + j] = self.hi[node * dim + j].max(v);
+                    }
+                }
+                (s, e)
+            }
+            Some((l, r)) => {
+                let (ls, le) = self.fill(l);
+                let (rs, re) = self.fill(r);
+                for j in 0..dim {
+                    self.lo[node * dim + j] = self.lo[l * dim + j].min(self.lo[r * dim + j]);
+                    self.hi[node * dim + j] = self.hi[l * dim + j].max(self.hi[r * dim + j]);
+                }
+                (ls.min(rs), le.max(re))
+            }
+        };
+        self.slots[node] = range;
+        range
+    }
+
+    pub(crate) fn size(&self, node: usize) -> usize {
+        self.slots[node].1 - self.slots[node].0
+    }
+
+    // Squared distance between the boxes of `node` and `other`'s `onode`.
+    pub(crate) fn min_sq(&self, node: usize, other: &BoxTree, onode: usize) -> f64 {
+        let d = self.dim;
+        (0..d)
+            .map(|j| {
+                let gap = (other.lo[onode * d + j] - self.hi[node * d + j])
+                    .max(self.lo[node * d + j] - other.hi[onode * d + j])
+                    .max(0.0);
+                gap * gap
+            })
+            .sum()
+    }
+
+    // Squared distances from `q` to the nearest and farthest points of the
+    // box of `node`.
+    pub(crate) fn min_sq_to(&self, node: usize, q: &[f64]) -> f64 {
+        let d = self.dim;
+        (0..d)
+            .map(|j| {
+                let gap = (self.lo[node * d + j] - q[j])
+                    .max(q[j] - self.hi[node * d + j])
+                    .max(0.0);
+                gap * gap
+            })
+            .sum()
+    }
+
+    pub(crate) fn max_sq_to(&self, node: usize, q: &[f64]) -> f64 {
+        let d = self.dim;
+        (0..d)
+            .map(|j| {
+                let far = (q[j] - self.lo[node * d + j]).max(self.hi[node * d + j] - q[j]);
+                far * far
+            })
+            .sum()
+    }
+
+    pub(crate) fn box_overlap(&self, node: usize, lower: &[f64], upper: &[f64]) -> Overlap {
+        let d = self.dim;
+        let (lo, hi) = (&self.lo[node * d..][..d], &self.hi[node * d..][..d]);
+        if (0..d).any(|j| hi[j] < lower[j] || lo[j] > upper[j]) {
+            Overlap::Disjoint
+        } else if (0..d).all(|j| lower[j] <= lo[j] && hi[j] <= upper[j]) {
+            Overlap::Inside
+        } else {
+            Overlap::Partial
+        }
+    }
+
+    pub(crate) fn ball_overlap(&self, node: usize, center: &[f64], r2: f64) -> Overlap {
+        if self.min_sq_to(node, center) > r2 {
+            Overlap::Disjoint
+        } else if self.max_sq_to(node, center) <= r2 {
+            Overlap::Inside
+        } else {
+            Overlap::Partial
+        }
+    }
+}
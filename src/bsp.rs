@@ -0,0 +1,470 @@
+// Binary space partitioning trees.
+//
+// Every internal node splits its points with a hyperplane, by default the one
+// through the median along the principal axis of the points. The tree works
+// as a spatial index (nearest neighbour and radius queries prune on the
+// distance to each plane) and as a decision structure (`locate` tells which
+// leaf cell a point falls in).
+
+use crate::index::IndexStats;
+use crate::linalg::{dot, norm};
+use crate::polytope::HalfSpace;
+use crate::{Error, Point};
+
+#[derive(Debug, Clone)]
+enum Node {
+    Split {
+        plane: HalfSpace,
+        inside: usize,
+        outside: usize,
+    },
+    Leaf {
+        id: usize,
+        indices: Vec<usize>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct BspTree {
+    points: Vec<Vec<f64>>,
+    nodes: Vec<Node>,
+    leaves: usize,
+    leaf_size: usize,
+}
+
+impl BspTree {
+    /// Builds a tree with at most `leaf_size` points per leaf, splitting along
+    /// principal axes.
+    pub fn new<T>(points: &[Point<T>], leaf_size: usize) -> Self
+    where
+        T: Into<f64> + Copy,
+    {
+        BspTree::with_splitter(points, leaf_size, principal_split)
+    }
+
+    /// Builds a tree whose planes come from `splitter`, called with the
+    /// points of each node. Points in the half-space go to the inside child.
+    /// Returning `None`, or a plane that leaves one side empty, makes a leaf.
+    pub fn with_splitter<T, F>(points: &[Point<T>], leaf_size: usize, splitter: F) -> Self
+    where
+        T: Into<f64> + Copy,
+        F: Fn(&[&[f64]]) -> Option<HalfSpace>,
+    {
+        let points: Vec<Vec<f64>> = points
+            .iter()
+            .map(|p| p.p.iter().map(|&v| v.into()).collect())
+            .collect();
+        let mut tree = BspTree {
+            points,
+            nodes: Vec::new(),
+            leaves: 0,
+            leaf_size: leaf_size.max(1),
+        };
+        let all: Vec<usize> = (0..tree.points.len()).collect();
+        tree.build(all, tree.leaf_size, &splitter);
+        tree
+    }
+
+    fn build<F>(&mut self, indices: Vec<usize>, leaf_size: usize, splitter: &F) -> usize
+    where
+        F: Fn(&[&[f64]]) -> Option<HalfSpace>,
+    {
+        let node = self.nodes.len();
+        if indices.len() > leaf_size {
+            let subset: Vec<&[f64]> = indices.iter().map(|&i| &self.points[i][..]).collect();
+            if let Some(plane) = splitter(&subset) {
+                let (inside, outside): (Vec<usize>, Vec<usize>) = indices
+                    .iter()
+                    .partition(|&&i| dot(&plane.normal, &self.points[i]) <= plane.offset);
+                if !inside.is_empty() && !outside.is_empty() {
+                    // Placeholder until the children's positions are known.
+                    self.nodes.push(Node::Leaf {
+                        id: 0,
+                        indices: Vec::new(),
+                    });
+                    let inside = self.build(inside, leaf_size, splitter);
+                    let outside = self.build(outside, leaf_size, splitter);
+                    self.nodes[node] = Node::Split {
+                        plane,
+                        inside,
+                        outside,
+                    };
+                    return node;
+                }
+            }
+        }
+        self.nodes.push(Node::Leaf {
+            id: self.leaves,
+            indices,
+        });
+        self.leaves += 1;
+        node
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Number of leaf cells.
+    pub fn leaf_count(&self) -> usize {
+        self.leaves
+    }
+
+    /// Leaves can exceed the leaf size when the splitter gives up, so
+    /// `fill_factor` may be above 1.
+    pub fn stats(&self) -> IndexStats {
+        let mut leaves = Vec::new();
+        self.collect_leaves(0, 0, &mut leaves);
+        let dim = self.points.first().map_or(0, |p| p.len());
+        let mut memory = std::mem::size_of::<Self>()
+            + self.points.capacity() * (std::mem::size_of::<Vec<f64>>() + dim * 8)
+            + self.nodes.capacity() * std::mem::size_of::<Node>();
+        for node in &self.nodes {
+            memory += match node {
+                Node::Split { plane, .. } => plane.normal.dim() * 8,
+                Node::Leaf { indices, .. } => indices.capacity() * std::mem::size_of::<usize>(),
+            };
+        }
+        IndexStats::from_leaves(
+            self.points.len(),
+            self.nodes.len(),
+            &leaves,
+            self.leaf_size,
+            memory,
+        )
+    }
+
+    fn collect_leaves(&self, node: usize, depth: usize, out: &mut Vec<(usize, usize)>) {
+        match &self.nodes[node] {
+            Node::Leaf { indices, .. } => out.push((depth, indices.len())),
+            Node::Split {
+                inside, outside, ..
+            } => {
+                self.collect_leaves(*inside, depth + 1, out);
+                self.collect_leaves(*outside, depth + 1, out);
+            }
+        }
+    }
+
+    /// Checks that every point sits in exactly one leaf, on the correct side
+    /// of every plane above it.
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut seen = vec![false; self.points.len()];
+        let mut path = Vec::new();
+        self.validate_in(0, &mut path, &mut seen, 0)?;
+        if !seen.iter().all(|&s| s) {
+            return Err(Error::InvalidIndex("point in no leaf"));
+        }
+        Ok(())
+    }
+
+    fn validate_in<'a>(
+        &'a self,
+        node: usize,
+        path: &mut Vec<(&'a HalfSpace, bool)>,
+        seen: &mut [bool],
+        depth: usize,
+    ) -> Result<(), Error> {
+        if node >= self.nodes.len() || depth > self.nodes.len() {
+            return Err(Error::InvalidIndex("node out of range or cycle"));
+        }
+        match &self.nodes[node] {
+            Node::Leaf { indices, .. } => {
+                for &i in indices {
+                    if i >= seen.len() || std::mem::replace(&mut seen[i], true) {
+                        return Err(Error::InvalidIndex("point in two leaves"));
+                    }
+                    let p = &self.points[i];
+                    let ok = path
+                        .iter()
+                        .all(|(h, inside)| (dot(&h.normal, p) <= h.offset) == *inside);
+                    if !ok {
+                        return Err(Error::InvalidIndex("point on the wrong side of a plane"));
+                    }
+                }
+                Ok(())
+            }
+            Node::Split {
+                plane,
+                inside,
+                outside,
+            } => {
+                path.push((plane, true));
+                self.validate_in(*inside, path, seen, depth + 1)?;
+                path.pop();
+                path.push((plane, false));
+                self.validate_in(*outside, path, seen, depth + 1)?;
+                path.pop();
+                Ok(())
+            }
+        }
+    }
+
+    /// The leaf cell containing `p`, as an id in `0..leaf_count()`.
+    pub fn locate<T>(&self, p: &Point<T>) -> usize
+    where
+        T: Into<f64> + Copy,
+    {
+        let q = to_f64(p);
+        let mut node = 0;
+        loop {
+            match &self.nodes[node] {
+                Node::Split {
+                    plane,
+                    inside,
+                    outside,
+                } => {
+                    node = if dot(&plane.normal, &q) <= plane.offset {
+                        *inside
+                    } else {
+                        *outside
+                    };
+                }
+                Node::Leaf { id, .. } => return *id,
+            }
+        }
+    }
+
+    /// Indices of the input points stored in leaf `id`.
+    pub fn leaf(&self, id: usize) -> &[usize] {
+        self.nodes
+            .iter()
+            .find_map(|n| match n {
+                Node::Leaf { id: i, indices } if *i == id => Some(&indices[..]),
+                _ => None,
+            })
+            .unwrap_or(&[])
+    }
+
+    /// Index of and distance to the closest point, or `None` if empty.
+    pub fn nearest<T>(&self, p: &Point<T>) -> Option<(usize, f64)>
+    where
+        T: Into<f64> + Copy,
+    {
+        if self.is_empty() {
+            return None;
+        }
+        let q = to_f64(p);
+        let mut best = (usize::MAX, f64::INFINITY);
+        self.nearest_in(0, &q, &mut best);
+        Some(best)
+    }
+
+    fn nearest_in(&self, node: usize, q: &[f64], best: &mut (usize, f64)) {
+        match &self.nodes[node] {
+            Node::Leaf { indices, .. } => {
+                for &i in indices {
+                    let d = distance(&self.points[i], q);
+                    if d < best.1 {
+                        *best = (i, d);
+                    }
+                }
+            }
+            Node::Split {
+                plane,
+                inside,
+                outside,
+            } => {
+                let s = plane_distance(plane, q);
+                let (near, far) = if s <= 0.0 {
+                    (*inside, *outside)
+                } else {
+                    (*outside, *inside)
+                };
+                self.nearest_in(near, q, best);
+                if s.abs() < best.1 {
+                    self.nearest_in(far, q, best);
+                }
+            }
+        }
+    }
+
+    /// Indices of all points within `radius` of `p`, in ascending order.
+    pub fn within_radius<T>(&self, p: &Point<T>, radius: f64) -> Vec<usize>
+    where
+        T: Into<f64> + Copy,
+    {
+        let mut out = Vec::new();
+        if !self.is_empty() {
+            self.radius_in(0, &to_f64(p), radius, &mut out);
+        }
+        out.sort_unstable();
+        out
+    }
+
+    fn radius_in(&self, node: usize, q: &[f64], radius: f64, out: &mut Vec<usize>) {
+        match &self.nodes[node] {
+            Node::Leaf { indices, .. } => {
+                out.extend(
+                    indices
+                        .iter()
+                        .filter(|&&i| distance(&self.points[i], q) <= radius),
+                );
+            }
+            Node::Split {
+                plane,
+                inside,
+                outside,
+            } => {
+                let s = plane_distance(plane, q);
+                if s <= radius {
+                    self.radius_in(*inside, q, radius, out);
+                }
+                if s >= -radius {
+                    self.radius_in(*outside, q, radius, out);
+                }
+            }
+        }
+    }
+}
+
+fn to_f64<T: Into<f64> + Copy>(p: &Point<T>) -> Vec<f64> {
+    p.p.iter().map(|&v| v.into()).collect()
+}
+
+fn distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f64>()
+        .sqrt()
+}
+
+fn plane_distance(plane: &HalfSpace, q: &[f64]) -> f64 {
+    (dot(&plane.normal, q) - plane.offset) / norm(&plane.normal)
+}
+
+/// The default splitter: the plane normal to the principal axis through the
+/// median projection.
+pub fn principal_split(points: &[&[f64]]) -> Option<HalfSpace> {
+    let n = points.len();
+    let dim = points.first()?.len();
+    let mut mean = vec![0.0; dim];
+    for p in points {
+        for k in 0..dim {
+            mean[k] += p[k] / n as f64;
+        }
+    }
+    let mut cov = vec![vec![0.0; dim]; dim];
+    for p in points {
+        for i in 0..dim {
+            for j in 0..dim {
+                cov[i][j] += (p[i] - mean[i]) * (p[j] - mean[j]);
+            }
+        }
+    }
+    // Power iteration from the axis of largest variance.
+    let start = (0..dim).max_by(|&a, &b| cov[a][a].total_cmp(&cov[b][b]))?;
+    if cov[start][start] == 0.0 {
+        return None;
+    }
+    let mut axis = vec![0.0; dim];
+    axis[start] = 1.0;
+    for _ in 0..50 {
+        let next: Vec<f64> = cov.iter().map(|row| dot(row, &axis)).collect();
+        let len = norm(&next);
+        if len == 0.0 {
+            break;
+        }
+        axis = next.iter().map(|v| v / len).collect();
+    }
+    let mut proj: Vec<f64> = points.iter().map(|p| dot(&axis, p)).collect();
+    proj.sort_by(f64::total_cmp);
+    let offset = (proj[n / 2 - 1] + proj[n / 2]) / 2.0;
+    Some(HalfSpace::new(Point::new(axis), offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn cloud(n: usize, seed: u64) -> Vec<Point<f64>> {
+        let mut rng = Rng::seed_from_u64(seed);
+        (0..n)
+            .map(|_| Point::new(vec![rng.range_f64(-5.0, 5.0), rng.range_f64(-1.0, 1.0)]))
+            .collect()
+    }
+
+    fn brute_nearest(pts: &[Point<f64>], q: &Point<f64>) -> usize {
+        (0..pts.len())
+            .min_by(|&a, &b| (&pts[a] - q).dist().total_cmp(&(&pts[b] - q).dist()))
+            .unwrap()
+    }
+
+    #[test]
+    fn leaves_partition_points() {
+        let pts = cloud(100, 1);
+        let tree = BspTree::new(&pts, 8);
+        assert!(tree.leaf_count() >= 100 / 8);
+        let mut all: Vec<usize> = (0..tree.leaf_count())
+            .flat_map(|id| tree.leaf(id).to_vec())
+            .collect();
+        all.sort();
+        assert_eq!(all, (0..100).collect::<Vec<_>>());
+        for (i, p) in pts.iter().enumerate() {
+            assert!(tree.leaf(tree.locate(p)).contains(&i));
+        }
+    }
+
+    #[test]
+    fn stats_and_validate() {
+        let pts = cloud(64, 6);
+        let tree = BspTree::new(&pts, 4);
+        let stats = tree.stats();
+        assert_eq!(stats.points, 64);
+        assert_eq!(stats.nodes, 2 * stats.leaves - 1);
+        assert!(stats.max_leaf <= 4);
+        assert_eq!(tree.validate(), Ok(()));
+
+        let mut broken = tree.clone();
+        broken.points[0] = vec![1e9, 1e9];
+        broken.points[1] = vec![-1e9, -1e9];
+        assert!(broken.validate().is_err());
+    }
+
+    #[test]
+    fn nearest() {
+        let pts = cloud(200, 2);
+        let tree = BspTree::new(&pts, 4);
+        let mut rng = Rng::seed_from_u64(3);
+        for _ in 0..50 {
+            let q = Point::new(vec![rng.range_f64(-6.0, 6.0), rng.range_f64(-2.0, 2.0)]);
+            let (i, d) = tree.nearest(&q).unwrap();
+            assert_eq!(i, brute_nearest(&pts, &q));
+            assert!((d - (&pts[i] - &q).dist()).abs() < 1e-12);
+        }
+        let empty: BspTree = BspTree::new::<f64>(&[], 4);
+        assert!(empty.nearest(&Point::new(vec![0.0, 0.0])).is_none());
+    }
+
+    #[test]
+    fn within_radius() {
+        let pts = cloud(150, 4);
+        let tree = BspTree::new(&pts, 5);
+        let q = Point::new(vec![0.5, 0.0]);
+        let expected: Vec<usize> = (0..pts.len())
+            .filter(|&i| (&pts[i] - &q).dist() <= 1.5)
+            .collect();
+        assert_eq!(tree.within_radius(&q, 1.5), expected);
+    }
+
+    #[test]
+    fn custom_splitter_and_duplicates() {
+        // Split on x = 0 only: two leaves regardless of leaf size.
+        let pts = cloud(50, 5);
+        let tree = BspTree::with_splitter(&pts, 1, |ps| {
+            let both = ps.iter().any(|p| p[0] <= 0.0) && ps.iter().any(|p| p[0] > 0.0);
+            both.then(|| HalfSpace::new(Point::new(vec![1.0, 0.0]), 0.0))
+        });
+        assert_eq!(tree.leaf_count(), 2);
+        assert_eq!(tree.locate(&Point::new(vec![-1.0, 0.0])), 0);
+
+        let same = vec![Point::new(vec![1.0, 1.0]); 10];
+        assert_eq!(BspTree::new(&same, 2).leaf_count(), 1);
+    }
+}
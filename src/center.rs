@@ -0,0 +1,214 @@
+// Robust centres of point sets.
+//
+// The geometric median minimises the sum of distances to the points, so a
+// few outliers barely move it; it is found with Weiszfeld's iteration, using
+// Vardi and Zhang's correction when an iterate lands on a data point. The
+// minimax centre minimises the largest distance, giving the smallest
+// enclosing ball; it is found with Yildirim's Frank-Wolfe iteration with away
+// steps, which works in any dimension and stops within a relative tolerance.
+
+use crate::Point;
+use crate::weighted::{check, weight};
+
+const MAX_ITERATIONS: usize = 10_000;
+
+/// A ball: every point of the set it was computed for lies within `radius`
+/// of `center`.
+#[derive(Debug, Clone)]
+pub struct Ball {
+    pub center: Point<f64>,
+    pub radius: f64,
+}
+
+fn coords<T: Into<f64> + Copy>(points: &[Point<T>]) -> Vec<Vec<f64>> {
+    points
+        .iter()
+        .map(|p| p.iter().map(|&v| v.into()).collect())
+        .collect()
+}
+
+fn dist2(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+/// The point minimising the weighted sum of distances to `points`, to
+/// within `tolerance` in position. `None` for an empty set or zero total
+/// weight; panics like [`crate::weighted::centroid`].
+pub fn geometric_median<T>(
+    points: &[Point<T>],
+    weights: Option<&[f64]>,
+    tolerance: f64,
+) -> Option<Point<f64>>
+where
+    T: Into<f64> + Copy,
+{
+    let (dim, total) = check(points, weights)?;
+    let pts = coords(points);
+    let mut y = vec![0.0; dim];
+    for (i, p) in pts.iter().enumerate() {
+        let w = weight(weights, i) / total;
+        y.iter_mut().zip(p).for_each(|(y, v)| *y += w * v);
+    }
+    // Iterates this close to a data point count as on it.
+    let scale = pts.iter().flatten().fold(1.0f64, |m, v| m.max(v.abs()));
+    let on_point = 1e-12 * scale;
+    for _ in 0..MAX_ITERATIONS {
+        let mut pull = vec![0.0; dim];
+        let mut inv = 0.0;
+        let mut resultant = vec![0.0; dim];
+        // Weight sitting exactly on `y`.
+        let mut here = 0.0;
+        for (i, p) in pts.iter().enumerate() {
+            let w = weight(weights, i);
+            let d = dist2(p, &y).sqrt();
+            if d <= on_point {
+                here += w;
+                continue;
+            }
+            inv += w / d;
+            for ((t, r), (&v, &c)) in pull.iter_mut().zip(&mut resultant).zip(p.iter().zip(&y)) {
+                *t += w * v / d;
+                *r += w * (v - c) / d;
+            }
+        }
+        if inv == 0.0 {
+            break;
+        }
+        pull.iter_mut().for_each(|t| *t /= inv);
+        let next: Vec<f64> = if here == 0.0 {
+            pull
+        } else {
+            // A data point is the median when the others pull on it with
+            // less force than its own weight.
+            let r = resultant.iter().map(|v| v * v).sum::<f64>().sqrt();
+            let step = if r > 0.0 {
+                (1.0 - here / r).max(0.0)
+            } else {
+                0.0
+            };
+            pull.iter()
+                .zip(&y)
+                .map(|(t, c)| step * t + (1.0 - step) * c)
+                .collect()
+        };
+        let moved = dist2(&next, &y).sqrt();
+        y = next;
+        if moved <= tolerance {
+            break;
+        }
+    }
+    Some(Point::new(y))
+}
+
+/// The centre of the smallest ball around `points`, with a radius at most
+/// `1 + tolerance` times the optimum. `None` for an empty set; panics if the
+/// points don't share a dimension.
+pub fn minimax_center<T>(points: &[Point<T>], tolerance: f64) -> Option<Ball>
+where
+    T: Into<f64> + Copy,
+{
+    check(points, None)?;
+    let pts = coords(points);
+    let n = pts.len();
+    let farthest_from = |c: &[f64]| {
+        (0..n)
+            .max_by(|&a, &b| dist2(&pts[a], c).total_cmp(&dist2(&pts[b], c)))
+            .unwrap()
+    };
+    // Start from the two ends of a long chord.
+    let a = farthest_from(&pts[0]);
+    let b = farthest_from(&pts[a]);
+    let mut u = vec![0.0; n];
+    u[a] += 0.5;
+    u[b] += 0.5;
+    let bound = (1.0 + tolerance.max(0.0)).powi(2);
+    let mut center = vec![0.0; pts[0].len()];
+    for _ in 0..MAX_ITERATIONS {
+        center.iter_mut().for_each(|c| *c = 0.0);
+        for (p, &ui) in pts.iter().zip(&u) {
+            center.iter_mut().zip(p).for_each(|(c, v)| *c += ui * v);
+        }
+        let d2: Vec<f64> = pts.iter().map(|p| dist2(p, &center)).collect();
+        // The dual objective, a lower bound on the squared optimal radius.
+        let gamma: f64 = u.iter().zip(&d2).map(|(ui, d)| ui * d).sum();
+        let j = (0..n).max_by(|&x, &y| d2[x].total_cmp(&d2[y])).unwrap();
+        if gamma <= 0.0 || d2[j] <= bound * gamma {
+            break;
+        }
+        let k = (0..n)
+            .filter(|&i| u[i] > 0.0)
+            .min_by(|&x, &y| d2[x].total_cmp(&d2[y]))
+            .unwrap();
+        let grow = d2[j] / gamma - 1.0;
+        let shrink = 1.0 - d2[k] / gamma;
+        if grow >= shrink || u[k] >= 1.0 {
+            let lambda = grow / (2.0 * (1.0 + grow));
+            u.iter_mut().for_each(|ui| *ui *= 1.0 - lambda);
+            u[j] += lambda;
+        } else {
+            // Away step: move weight off the point nearest the centre.
+            let lambda = (shrink / (2.0 * (1.0 - shrink))).min(u[k] / (1.0 - u[k]));
+            u.iter_mut().for_each(|ui| *ui *= 1.0 + lambda);
+            u[k] -= lambda;
+            u[k] = u[k].max(0.0);
+        }
+    }
+    let radius = pts
+        .iter()
+        .map(|p| dist2(p, &center))
+        .fold(0.0, f64::max)
+        .sqrt();
+    Some(Ball {
+        center: Point::new(center),
+        radius,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn pts(v: &[(f64, f64)]) -> Vec<Point<f64>> {
+        v.iter().map(|&(x, y)| Point::new(vec![x, y])).collect()
+    }
+
+    #[test]
+    fn median_resists_outliers() {
+        let mut p = pts(&[(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)]);
+        p.push(Point::new(vec![1000.0, 1000.0]));
+        let m = geometric_median(&p, None, 1e-10).unwrap();
+        assert!(m.data().iter().all(|&v| v > 0.4 && v < 1.1));
+        // Three collinear points: the middle one is the median.
+        let line = pts(&[(0.0, 0.0), (1.0, 0.0), (5.0, 0.0)]);
+        let m = geometric_median(&line, None, 1e-12).unwrap();
+        assert!((m[0] - 1.0).abs() < 1e-6 && m[1].abs() < 1e-9);
+        // A heavy point wins outright.
+        let m = geometric_median(&line, Some(&[1.0, 1.0, 5.0]), 1e-12).unwrap();
+        assert!((m[0] - 5.0).abs() < 1e-6);
+        assert!(geometric_median::<f64>(&[], None, 1e-9).is_none());
+    }
+
+    #[test]
+    fn minimax_ball() {
+        let square = pts(&[(0.0, 0.0), (2.0, 0.0), (0.0, 2.0), (2.0, 2.0), (1.0, 1.5)]);
+        let ball = minimax_center(&square, 1e-9).unwrap();
+        assert!((ball.radius - 2f64.sqrt()).abs() < 1e-6);
+        assert!((ball.center[0] - 1.0).abs() < 1e-4 && (ball.center[1] - 1.0).abs() < 1e-4);
+
+        let mut rng = Rng::seed_from_u64(52);
+        let cloud: Vec<Point<f64>> = (0..300)
+            .map(|_| Point::new((0..4).map(|_| rng.normal()).collect()))
+            .collect();
+        let ball = minimax_center(&cloud, 1e-4).unwrap();
+        let lower = cloud
+            .iter()
+            .flat_map(|a| cloud.iter().map(move |b| dist2(a, b)))
+            .fold(0.0, f64::max)
+            .sqrt()
+            / 2.0;
+        assert!(ball.radius >= lower && ball.radius <= lower * 1.5);
+        let one = minimax_center(&pts(&[(3.0, 4.0)]), 1e-9).unwrap();
+        assert_eq!((one.center.data(), one.radius), (&[3.0, 4.0][..], 0.0));
+    }
+}
@@ -0,0 +1,727 @@
+// A collection of same-dimension points.
+//
+// Coordinates live in one flat buffer, point after point, so a cloud of a
+// million points is one allocation rather than a million small ones.
+//
+// Callbacks registered with `on_change` hear about every edit made through
+// the cloud's own methods, so views and derived indices can follow along
+// instead of rebuilding. They belong to this instance: clones start without
+// any, and they take no part in equality.
+
+use std::fmt;
+use std::ops::{Add, Mul};
+use std::slice::ChunksExact;
+
+use crate::dims::{ResizePolicy, resize_into};
+use crate::smallbuf::SmallBuf;
+use crate::storage::{SliceStorage, VecStorage};
+use crate::{Error, Point};
+
+/// An edit reported to `on_change` callbacks, after it has been applied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change<'a, T> {
+    /// A point now sits at `index`; later points moved up by one.
+    Inserted { index: usize, coords: &'a [T] },
+    /// The point at `index` was taken out; later points moved down by one.
+    Removed { index: usize, coords: &'a [T] },
+    /// The point at `index` changed from `old` to `new`.
+    Modified {
+        index: usize,
+        old: &'a [T],
+        new: &'a [T],
+    },
+}
+
+/// Handle for removing a callback with `PointCloud::remove_observer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(u64);
+
+type Callback<T> = Box<dyn FnMut(&Change<'_, T>) + Send + Sync>;
+
+struct Observers<T> {
+    next: u64,
+    list: Vec<(ObserverId, Callback<T>)>,
+}
+
+impl<T> Observers<T> {
+    fn new() -> Self {
+        Observers {
+            next: 0,
+            list: Vec::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    fn notify(&mut self, change: &Change<'_, T>) {
+        for (_, f) in &mut self.list {
+            f(change);
+        }
+    }
+}
+
+impl<T> Clone for Observers<T> {
+    fn clone(&self) -> Self {
+        Observers::new()
+    }
+}
+
+impl<T> PartialEq for Observers<T> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> fmt::Debug for Observers<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} observers", self.list.len())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointCloud<T> {
+    data: Vec<T>,
+    dim: usize,
+    observers: Observers<T>,
+}
+
+impl<T> PointCloud<T>
+where
+    T: Copy,
+{
+    /// An empty cloud of `dim`-dimensional points.
+    pub fn new(dim: usize) -> Result<Self, Error> {
+        if dim == 0 {
+            return Err(Error::ZeroDimension);
+        }
+        Ok(PointCloud {
+            data: Vec::new(),
+            dim,
+            observers: Observers::new(),
+        })
+    }
+
+    /// Copies a flat `[x0, y0, z0, x1, y1, z1, ...]` buffer into a cloud.
+    pub fn from_flat(data: &[T], dim: usize) -> Result<Self, Error> {
+        Self::from_flat_vec(data.to_vec(), dim)
+    }
+
+    /// Like `from_flat`, but takes ownership of the buffer without copying.
+    pub fn from_flat_vec(data: Vec<T>, dim: usize) -> Result<Self, Error> {
+        if dim == 0 {
+            return Err(Error::ZeroDimension);
+        }
+        if !data.len().is_multiple_of(dim) {
+            return Err(Error::RaggedBuffer {
+                len: data.len(),
+                dim,
+            });
+        }
+        Ok(PointCloud {
+            data,
+            dim,
+            observers: Observers::new(),
+        })
+    }
+
+    /// Gathers `dim` coordinates starting at `offset + i * stride` for every
+    /// point `i` that fits in `data`.
+    ///
+    /// This reads one attribute out of an interleaved vertex buffer, e.g.
+    /// positions at offset 0 and normals at offset 3 of an `xyz nx ny nz`
+    /// layout with stride 6.
+    pub fn from_strided(
+        data: &[T],
+        dim: usize,
+        stride: usize,
+        offset: usize,
+    ) -> Result<Self, Error> {
+        if dim == 0 {
+            return Err(Error::ZeroDimension);
+        }
+        if stride == 0 {
+            return Err(Error::ZeroStride);
+        }
+        // The first point's end; an offset too large to add can't fit.
+        let n = match offset.checked_add(dim) {
+            Some(end) if end <= data.len() => (data.len() - end) / stride + 1,
+            _ => 0,
+        };
+        let mut flat = Vec::with_capacity(n * dim);
+        for i in 0..n {
+            let start = offset + i * stride;
+            flat.extend_from_slice(&data[start..start + dim]);
+        }
+        Ok(PointCloud {
+            data: flat,
+            dim,
+            observers: Observers::new(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len() / self.dim
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// The coordinates of point `i`.
+    pub fn get(&self, i: usize) -> Option<&[T]> {
+        let start = i.checked_mul(self.dim)?;
+        self.data.get(start..start + self.dim)
+    }
+
+    /// Point `i` as an owned `Point`.
+    pub fn point(&self, i: usize) -> Option<Point<T>> {
+        self.get(i)
+            .map(|s| Point::from_storage(VecStorage::from_slice(s)))
+    }
+
+    /// Point `i` as a `Point` that borrows the cloud's coordinates.
+    pub fn view(&self, i: usize) -> Option<Point<T, SliceStorage<'_, T>>> {
+        self.get(i).map(Point::view)
+    }
+
+    /// Iterates over the points as coordinate slices.
+    pub fn iter(&self) -> ChunksExact<'_, T> {
+        self.data.chunks_exact(self.dim)
+    }
+
+    /// The underlying flat buffer.
+    pub fn as_flat(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn into_flat(self) -> Vec<T> {
+        self.data
+    }
+
+    /// Coordinates of point `i`, for editing in place. Edits made this way
+    /// are not reported to observers; use `set` or `update` for that.
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut [T]> {
+        let start = i.checked_mul(self.dim)?;
+        self.data.get_mut(start..start + self.dim)
+    }
+
+    /// Appends a point and returns its index.
+    pub fn push(&mut self, coords: &[T]) -> Result<usize, Error> {
+        self.check_dim(coords)?;
+        self.data.extend_from_slice(coords);
+        let index = self.len() - 1;
+        self.observers.notify(&Change::Inserted { index, coords });
+        Ok(index)
+    }
+
+    /// Inserts a point at `i`, shifting later points up. Panics if `i` is past
+    /// the end, like `Vec::insert`.
+    pub fn insert(&mut self, i: usize, coords: &[T]) -> Result<(), Error> {
+        self.check_dim(coords)?;
+        assert!(i <= self.len(), "insert index {i} out of range");
+        let at = i * self.dim;
+        self.data.splice(at..at, coords.iter().copied());
+        self.observers
+            .notify(&Change::Inserted { index: i, coords });
+        Ok(())
+    }
+
+    /// Removes point `i`, shifting later points down.
+    pub fn remove(&mut self, i: usize) -> Option<Point<T>> {
+        if i >= self.len() {
+            return None;
+        }
+        let at = i * self.dim;
+        let p: Vec<T> = self.data.drain(at..at + self.dim).collect();
+        if !self.observers.is_empty() {
+            self.observers.notify(&Change::Removed {
+                index: i,
+                coords: &p,
+            });
+        }
+        Some(p.into())
+    }
+
+    /// Overwrites point `i`; false if there is no such point.
+    pub fn set(&mut self, i: usize, coords: &[T]) -> Result<bool, Error> {
+        self.check_dim(coords)?;
+        Ok(self.update(i, |p| p.copy_from_slice(coords)))
+    }
+
+    /// Edits point `i` in place with `f`; false if there is no such point.
+    pub fn update<F: FnOnce(&mut [T])>(&mut self, i: usize, f: F) -> bool {
+        // Only keep the old coordinates if someone will be told about them.
+        let watched = !self.observers.is_empty();
+        let Some(coords) = self.get_mut(i) else {
+            return false;
+        };
+        let old = watched.then(|| SmallBuf::from_slice(coords));
+        f(coords);
+        if let Some(old) = old {
+            let at = i * self.dim;
+            self.observers.notify(&Change::Modified {
+                index: i,
+                old: &old,
+                new: &self.data[at..at + self.dim],
+            });
+        }
+        true
+    }
+
+    /// Registers `f` to be called after every reported edit.
+    pub fn on_change<F>(&mut self, f: F) -> ObserverId
+    where
+        F: FnMut(&Change<'_, T>) + Send + Sync + 'static,
+    {
+        let id = ObserverId(self.observers.next);
+        self.observers.next += 1;
+        self.observers.list.push((id, Box::new(f)));
+        id
+    }
+
+    /// Unregisters a callback; false if it was already gone.
+    pub fn remove_observer(&mut self, id: ObserverId) -> bool {
+        let before = self.observers.list.len();
+        self.observers.list.retain(|(o, _)| *o != id);
+        self.observers.list.len() != before
+    }
+
+    fn check_dim(&self, coords: &[T]) -> Result<(), Error> {
+        if coords.len() != self.dim {
+            return Err(Error::DimensionMismatch {
+                expected: self.dim,
+                found: coords.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+// Summaries and bulk edits. The edits make one pass over the flat buffer and
+// then report every moved point to the observers, if there are any.
+impl<T> PointCloud<T>
+where
+    T: Copy,
+{
+    /// The mean of the points; `None` for an empty cloud.
+    pub fn centroid(&self) -> Option<Point<f64>>
+    where
+        T: Into<f64>,
+    {
+        if self.is_empty() {
+            return None;
+        }
+        let mut sum = vec![0.0; self.dim];
+        for p in self.iter() {
+            sum.iter_mut().zip(p).for_each(|(s, &v)| *s += v.into());
+        }
+        let n = self.len() as f64;
+        Some(Point::new(sum.into_iter().map(|s| s / n).collect()))
+    }
+
+    /// The lower and upper corners of the smallest axis-aligned box around
+    /// the points; `None` for an empty cloud.
+    pub fn bounding_box(&self) -> Option<(Point<T>, Point<T>)>
+    where
+        T: PartialOrd,
+    {
+        let first = self.get(0)?;
+        let (mut lower, mut upper) = (first.to_vec(), first.to_vec());
+        for p in self.iter().skip(1) {
+            for ((lo, hi), &v) in lower.iter_mut().zip(&mut upper).zip(p) {
+                if v < *lo {
+                    *lo = v;
+                }
+                if v > *hi {
+                    *hi = v;
+                }
+            }
+        }
+        Some((lower.into(), upper.into()))
+    }
+
+    /// Moves every point by `offset`.
+    pub fn translate(&mut self, offset: &[T]) -> Result<(), Error>
+    where
+        T: Add<Output = T>,
+    {
+        self.check_dim(offset)?;
+        self.update_all(|_, p| {
+            p.iter_mut().zip(offset).for_each(|(v, &o)| *v = *v + o);
+        });
+        Ok(())
+    }
+
+    /// `self += alpha * x`, point by point. Panics unless the clouds have
+    /// the same length and dimension.
+    pub fn axpy(&mut self, alpha: T, x: &PointCloud<T>)
+    where
+        T: Add<Output = T> + Mul<Output = T>,
+    {
+        self.check_shape(x);
+        let dim = self.dim;
+        self.update_all(|i, p| {
+            let xi = &x.data[i * dim..(i + 1) * dim];
+            p.iter_mut().zip(xi).for_each(|(v, &x)| *v = *v + alpha * x);
+        });
+    }
+
+    /// `self += a * b` coordinate-wise. Panics unless the three clouds have
+    /// the same length and dimension.
+    pub fn fma(&mut self, a: &PointCloud<T>, b: &PointCloud<T>)
+    where
+        T: Add<Output = T> + Mul<Output = T>,
+    {
+        self.check_shape(a);
+        self.check_shape(b);
+        let dim = self.dim;
+        self.update_all(|i, p| {
+            let span = i * dim..(i + 1) * dim;
+            for (v, (&x, &y)) in p
+                .iter_mut()
+                .zip(a.data[span.clone()].iter().zip(&b.data[span]))
+            {
+                *v = *v + x * y;
+            }
+        });
+    }
+
+    fn check_shape(&self, other: &PointCloud<T>) {
+        assert!(
+            self.len() == other.len() && self.dim == other.dim,
+            "clouds must have the same shape"
+        );
+    }
+
+    /// Multiplies every coordinate by `k`, scaling about the origin.
+    pub fn scale(&mut self, k: T)
+    where
+        T: Mul<Output = T>,
+    {
+        self.update_all(|_, p| p.iter_mut().for_each(|v| *v = *v * k));
+    }
+
+    // Edits every point with `f(index, coords)` in one pass, then reports
+    // the changes.
+    fn update_all<F: FnMut(usize, &mut [T])>(&mut self, mut f: F) {
+        let old = (!self.observers.is_empty()).then(|| self.data.clone());
+        for (i, p) in self.data.chunks_exact_mut(self.dim).enumerate() {
+            f(i, p);
+        }
+        if let Some(old) = old {
+            self.report_all(&old);
+        }
+    }
+
+    // Tells the observers about every point, given the coordinates from
+    // before a bulk edit.
+    fn report_all(&mut self, old: &[T]) {
+        let dim = self.dim;
+        for (index, (old, new)) in old
+            .chunks_exact(dim)
+            .zip(self.data.chunks_exact(dim))
+            .enumerate()
+        {
+            self.observers.notify(&Change::Modified { index, old, new });
+        }
+    }
+}
+
+// Rayon-parallel bulk operations, for clouds of millions of points. The
+// work is spread over all cores; observers are still called one edit at a
+// time, in index order, once the parallel part is done.
+#[cfg(feature = "parallel")]
+impl<T> PointCloud<T>
+where
+    T: Copy + Send + Sync,
+{
+    /// [`PointCloud::centroid`] summed in parallel.
+    pub fn par_centroid(&self) -> Option<Point<f64>>
+    where
+        T: Into<f64>,
+    {
+        use rayon::prelude::*;
+        if self.is_empty() {
+            return None;
+        }
+        let dim = self.dim;
+        let sum = self
+            .data
+            .par_chunks_exact(dim)
+            .fold(
+                || vec![0.0; dim],
+                |mut acc, p| {
+                    acc.iter_mut().zip(p).for_each(|(s, &v)| *s += v.into());
+                    acc
+                },
+            )
+            .reduce(
+                || vec![0.0; dim],
+                |mut a, b| {
+                    a.iter_mut().zip(b).for_each(|(x, y)| *x += y);
+                    a
+                },
+            );
+        let n = self.len() as f64;
+        Some(Point::new(sum.into_iter().map(|s| s / n).collect()))
+    }
+
+    /// Edits every point in place with `f`, in parallel.
+    pub fn par_update_all<F>(&mut self, f: F)
+    where
+        F: Fn(&mut [T]) + Sync,
+    {
+        use rayon::prelude::*;
+        let old = (!self.observers.is_empty()).then(|| self.data.clone());
+        self.data.par_chunks_exact_mut(self.dim).for_each(&f);
+        if let Some(old) = old {
+            self.report_all(&old);
+        }
+    }
+
+    /// Euclidean distances between all pairs of points, in parallel, as the
+    /// condensed upper triangle: `(0, 1), (0, 2), ..., (0, n-1), (1, 2), ...`,
+    /// `n (n - 1) / 2` entries.
+    pub fn par_pairwise_distances(&self) -> Vec<f64>
+    where
+        T: Into<f64>,
+    {
+        use rayon::prelude::*;
+        let n = self.len();
+        (0..n)
+            .into_par_iter()
+            .flat_map_iter(|i| {
+                let a = &self.data[i * self.dim..(i + 1) * self.dim];
+                self.iter().skip(i + 1).map(move |b| {
+                    a.iter()
+                        .zip(b)
+                        .map(|(&x, &y)| {
+                            let d = x.into() - y.into();
+                            d * d
+                        })
+                        .sum::<f64>()
+                        .sqrt()
+                })
+            })
+            .collect()
+    }
+}
+
+impl<T> PointCloud<T>
+where
+    T: Copy + Default,
+{
+    /// Gathers points of possibly different dimensions into one cloud of the
+    /// largest dimension among them, resizing the others as `policy` says.
+    /// Fails with `Error::ZeroDimension` if there are no points or all are
+    /// empty.
+    pub fn harmonize(points: &[Point<T>], policy: ResizePolicy) -> Result<Self, Error> {
+        let dim = points.iter().map(|p| p.len()).max().unwrap_or(0);
+        let mut data = Vec::with_capacity(points.len() * dim);
+        for p in points {
+            resize_into(p, dim, policy, &mut data)?;
+        }
+        Self::from_flat_vec(data, dim)
+    }
+
+    /// A copy of the cloud with every point resized to `n` coordinates.
+    pub fn resize_dim(&self, n: usize, policy: ResizePolicy) -> Result<Self, Error> {
+        let mut data = Vec::with_capacity(self.len() * n);
+        for p in self.iter() {
+            resize_into(p, n, policy, &mut data)?;
+        }
+        Self::from_flat_vec(data, n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_flat() {
+        let cloud = PointCloud::from_flat(&[1, 2, 3, 4, 5, 6], 3).unwrap();
+        assert_eq!(cloud.len(), 2);
+        assert_eq!(cloud.dim(), 3);
+        assert_eq!(cloud.get(1), Some(&[4, 5, 6][..]));
+        assert_eq!(cloud.get(2), None);
+        assert_eq!(cloud.point(0).unwrap().data(), &[1, 2, 3]);
+
+        assert_eq!(
+            PointCloud::from_flat(&[1, 2, 3], 2),
+            Err(Error::RaggedBuffer { len: 3, dim: 2 })
+        );
+        assert_eq!(PointCloud::from_flat(&[1], 0), Err(Error::ZeroDimension));
+    }
+
+    #[test]
+    fn from_strided() {
+        // x y z nx ny nz, two vertices
+        let buf = [0.0, 1.0, 2.0, 0.0, 0.0, 1.0, 3.0, 4.0, 5.0, 1.0, 0.0, 0.0];
+        let pos = PointCloud::from_strided(&buf, 3, 6, 0).unwrap();
+        let nrm = PointCloud::from_strided(&buf, 3, 6, 3).unwrap();
+        assert_eq!(pos.as_flat(), &[0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(nrm.as_flat(), &[0.0, 0.0, 1.0, 1.0, 0.0, 0.0]);
+
+        // a trailing partial record is ignored
+        let rows = PointCloud::from_strided(&[1, 2, 9, 3, 4, 9, 5], 2, 3, 0).unwrap();
+        assert_eq!(rows.len(), 2);
+
+        let none = PointCloud::from_strided(&[1, 2], 3, 3, 0).unwrap();
+        assert!(none.is_empty());
+        assert_eq!(
+            PointCloud::from_strided(&[1, 2], 1, 0, 0),
+            Err(Error::ZeroStride)
+        );
+        assert!(
+            PointCloud::from_strided(&[1, 2], 1, 1, usize::MAX)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn iter() {
+        let cloud = PointCloud::from_flat_vec(vec![1, 2, 3, 4], 2).unwrap();
+        let sums: Vec<i32> = cloud.iter().map(|p| p.iter().sum()).collect();
+        assert_eq!(sums, vec![3, 7]);
+        assert_eq!(cloud.into_flat(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn edit() {
+        let mut cloud = PointCloud::new(2).unwrap();
+        assert_eq!(cloud.push(&[1, 2]), Ok(0));
+        assert_eq!(cloud.push(&[5, 6]), Ok(1));
+        cloud.insert(1, &[3, 4]).unwrap();
+        assert_eq!(
+            cloud.push(&[1]),
+            Err(Error::DimensionMismatch {
+                expected: 2,
+                found: 1
+            })
+        );
+        cloud.get_mut(2).unwrap()[0] = 7;
+        assert_eq!(cloud.as_flat(), &[1, 2, 3, 4, 7, 6]);
+        assert_eq!(cloud.remove(0).unwrap().data(), &[1, 2]);
+        assert!(cloud.remove(2).is_none());
+        assert_eq!(cloud.as_flat(), &[3, 4, 7, 6]);
+    }
+
+    #[test]
+    fn unobserved_edits() {
+        let mut cloud = PointCloud::from_flat(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10], 5).unwrap();
+        assert!(cloud.update(1, |p| p[4] = 0));
+        assert!(!cloud.update(2, |_| {}));
+        assert_eq!(cloud.remove(0).unwrap().data(), &[1, 2, 3, 4, 5]);
+        assert_eq!(cloud.as_flat(), &[6, 7, 8, 9, 0]);
+    }
+
+    #[test]
+    fn on_change() {
+        use std::sync::{Arc, Mutex};
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut cloud = PointCloud::new(2).unwrap();
+        let sink = Arc::clone(&log);
+        let id = cloud.on_change(move |c: &Change<'_, i32>| {
+            sink.lock().unwrap().push(match *c {
+                Change::Inserted { index, coords } => format!("+{index} {coords:?}"),
+                Change::Removed { index, coords } => format!("-{index} {coords:?}"),
+                Change::Modified { index, old, new } => format!("~{index} {old:?} {new:?}"),
+            });
+        });
+        cloud.push(&[1, 2]).unwrap();
+        cloud.insert(0, &[3, 4]).unwrap();
+        cloud.update(1, |p| p[0] = 9);
+        assert_eq!(cloud.set(1, &[5, 5]), Ok(true));
+        assert!(!cloud.update(7, |_| {}));
+        cloud.remove(0);
+        // clones don't carry observers
+        let mut copy = cloud.clone();
+        copy.push(&[0, 0]).unwrap();
+        assert_eq!(copy.len(), 2);
+        assert!(cloud.remove_observer(id));
+        cloud.push(&[0, 0]).unwrap();
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "+0 [1, 2]",
+                "+0 [3, 4]",
+                "~1 [1, 2] [9, 2]",
+                "~1 [9, 2] [5, 5]",
+                "-0 [3, 4]",
+            ]
+        );
+        assert!(!cloud.remove_observer(id));
+    }
+
+    #[test]
+    fn summaries_and_bulk_edits() {
+        use std::sync::{Arc, Mutex};
+
+        let mut cloud = PointCloud::from_flat(&[1, 5, 3, -1, 2, 2], 2).unwrap();
+        assert_eq!(cloud.centroid().unwrap().data(), &[2.0, 2.0]);
+        let (lo, hi) = cloud.bounding_box().unwrap();
+        assert_eq!((lo.data(), hi.data()), (&[1, -1][..], &[3, 5][..]));
+
+        let moved = Arc::new(Mutex::new(0));
+        let count = Arc::clone(&moved);
+        cloud.on_change(move |_| *count.lock().unwrap() += 1);
+        cloud.translate(&[1, 1]).unwrap();
+        cloud.scale(2);
+        assert_eq!(cloud.as_flat(), &[4, 12, 8, 0, 6, 6]);
+        assert_eq!(*moved.lock().unwrap(), 6);
+        assert!(cloud.translate(&[1]).is_err());
+        let step = PointCloud::from_flat(&[1, 0, 0, 1, 1, 1], 2).unwrap();
+        cloud.axpy(-2, &step);
+        cloud.fma(&step, &step);
+        assert_eq!(cloud.as_flat(), &[3, 12, 8, -1, 5, 5]);
+        assert!(PointCloud::<f64>::new(2).unwrap().centroid().is_none());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_bulk_operations() {
+        use std::sync::{Arc, Mutex};
+
+        let mut c = PointCloud::from_flat(&[0.0, 0.0, 3.0, 4.0, 6.0, 8.0], 2).unwrap();
+        assert_eq!(
+            c.par_centroid().unwrap().data(),
+            c.centroid().unwrap().data()
+        );
+        assert_eq!(c.par_pairwise_distances(), [5.0, 10.0, 5.0]);
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&seen);
+        c.on_change(move |ch| {
+            if let Change::Modified { index, .. } = ch {
+                log.lock().unwrap().push(*index);
+            }
+        });
+        c.par_update_all(|p| p.iter_mut().for_each(|v| *v += 1.0));
+        assert_eq!(c.as_flat(), [1.0, 1.0, 4.0, 5.0, 7.0, 9.0]);
+        assert_eq!(*seen.lock().unwrap(), [0, 1, 2]);
+        assert!(PointCloud::<f64>::new(2).unwrap().par_centroid().is_none());
+    }
+
+    #[test]
+    fn harmonize() {
+        let pts = vec![Point::new(vec![1.0, 2.0]), Point::new(vec![3.0, 4.0, 5.0])];
+        let cloud = PointCloud::harmonize(&pts, ResizePolicy::ZeroPad).unwrap();
+        assert_eq!(cloud.as_flat(), &[1.0, 2.0, 0.0, 3.0, 4.0, 5.0]);
+        assert!(PointCloud::harmonize(&pts, ResizePolicy::Error).is_err());
+        assert_eq!(
+            PointCloud::<f64>::harmonize(&[], ResizePolicy::ZeroPad),
+            Err(Error::ZeroDimension)
+        );
+        let flat = cloud.resize_dim(2, ResizePolicy::Repeat).unwrap();
+        assert_eq!(flat.as_flat(), &[1.0, 2.0, 3.0, 4.0]);
+    }
+}
@@ -0,0 +1,367 @@
+// PLY and XYZ files for 3D point clouds, the formats scanners and tools
+// such as MeshLab and CloudCompare exchange.
+//
+// XYZ is one point per line, `x y z` separated by whitespace. Extra columns
+// (intensity, colour, normals) are ignored on reading, as are blank lines
+// and `#` comments.
+//
+// A PLY file is a text header declaring elements and their typed
+// properties, then the data in ASCII or binary of either byte order.
+// Reading takes the `x`, `y` and `z` properties of the `vertex` element,
+// of any numeric type, and skips its other properties; elements before the
+// vertices are skipped if they have no list properties, and anything after
+// them is not read at all, so meshes load as their vertices. Writing emits
+// just the vertices, as `float` for `f32` clouds and `double` for `f64`.
+
+use std::fmt::Display;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use crate::PointCloud;
+use crate::persist::invalid;
+
+/// How [`PointCloud::write_ply`] encodes the points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+/// Coordinate types that PLY and XYZ clouds can hold: `f32` and `f64`.
+pub trait FileScalar: Copy + Display + FromStr + Into<f64> {
+    /// The PLY property type written for it.
+    const PLY_TYPE: &'static str;
+
+    /// Converts, rounding if needed.
+    fn from_f64(v: f64) -> Self;
+
+    fn write_binary<W: Write>(self, w: &mut W, big_endian: bool) -> io::Result<()>;
+}
+
+macro_rules! file_scalar {
+    ($t:ty, $name:literal) => {
+        impl FileScalar for $t {
+            const PLY_TYPE: &'static str = $name;
+
+            fn from_f64(v: f64) -> Self {
+                v as $t
+            }
+
+            fn write_binary<W: Write>(self, w: &mut W, big_endian: bool) -> io::Result<()> {
+                if big_endian {
+                    w.write_all(&self.to_be_bytes())
+                } else {
+                    w.write_all(&self.to_le_bytes())
+                }
+            }
+        }
+    };
+}
+
+file_scalar!(f32, "float");
+file_scalar!(f64, "double");
+
+// The byte size of a PLY scalar type, under its old and new names.
+fn type_size(name: &str) -> Option<usize> {
+    match name {
+        "char" | "uchar" | "int8" | "uint8" => Some(1),
+        "short" | "ushort" | "int16" | "uint16" => Some(2),
+        "int" | "uint" | "float" | "int32" | "uint32" | "float32" => Some(4),
+        "double" | "float64" => Some(8),
+        _ => None,
+    }
+}
+
+// Decodes one binary value of PLY type `name` from `bytes`.
+fn decode(name: &str, bytes: &[u8], big_endian: bool) -> f64 {
+    macro_rules! from {
+        ($t:ty) => {{
+            let b = bytes.try_into().expect("size checked by caller");
+            (if big_endian {
+                <$t>::from_be_bytes(b)
+            } else {
+                <$t>::from_le_bytes(b)
+            }) as f64
+        }};
+    }
+    match name {
+        "char" | "int8" => from!(i8),
+        "uchar" | "uint8" => from!(u8),
+        "short" | "int16" => from!(i16),
+        "ushort" | "uint16" => from!(u16),
+        "int" | "int32" => from!(i32),
+        "uint" | "uint32" => from!(u32),
+        "float" | "float32" => from!(f32),
+        _ => from!(f64),
+    }
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    // Type and name of each property; `None` types are lists.
+    properties: Vec<(Option<String>, String)>,
+}
+
+// Reads up to and including `end_header`.
+fn read_ply_header<R: BufRead>(r: &mut R) -> io::Result<(PlyFormat, Vec<Element>)> {
+    let mut line = String::new();
+    let mut next_line = |r: &mut R| -> io::Result<String> {
+        line.clear();
+        if r.read_line(&mut line)? == 0 {
+            return Err(invalid("PLY header ends early"));
+        }
+        Ok(line.trim().to_string())
+    };
+    if next_line(r)? != "ply" {
+        return Err(invalid("not a PLY file"));
+    }
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    loop {
+        let line = next_line(r)?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["end_header"] => break,
+            [] | ["comment", ..] | ["obj_info", ..] => {}
+            ["format", f, "1.0"] => {
+                format = Some(match *f {
+                    "ascii" => PlyFormat::Ascii,
+                    "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                    "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                    _ => return Err(invalid("unknown PLY format")),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| invalid("bad element count"))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", _, _, name] => elements
+                .last_mut()
+                .ok_or_else(|| invalid("property outside an element"))?
+                .properties
+                .push((None, name.to_string())),
+            ["property", ty, name] => {
+                type_size(ty).ok_or_else(|| invalid("unknown PLY property type"))?;
+                elements
+                    .last_mut()
+                    .ok_or_else(|| invalid("property outside an element"))?
+                    .properties
+                    .push((Some(ty.to_string()), name.to_string()));
+            }
+            _ => return Err(invalid("malformed PLY header line")),
+        }
+    }
+    Ok((
+        format.ok_or_else(|| invalid("PLY format missing"))?,
+        elements,
+    ))
+}
+
+impl<T: FileScalar> PointCloud<T> {
+    /// Reads an XYZ file: the first three numbers of every line. Fails with
+    /// `ErrorKind::InvalidData` on a line with fewer.
+    pub fn read_xyz<R: BufRead>(r: R) -> io::Result<Self> {
+        let mut data = Vec::new();
+        for line in r.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            for _ in 0..3 {
+                let f = fields
+                    .next()
+                    .ok_or_else(|| invalid("fewer than three fields"))?;
+                data.push(f.parse().map_err(|_| invalid("bad coordinate"))?);
+            }
+        }
+        Ok(PointCloud::from_flat_vec(data, 3).expect("three per point"))
+    }
+
+    /// Writes one `x y z` line per point. Fails with
+    /// `ErrorKind::InvalidInput` unless the cloud is three-dimensional.
+    pub fn write_xyz<W: Write>(&self, mut w: W) -> io::Result<()> {
+        check_3d(self.dim())?;
+        for p in self.iter() {
+            writeln!(w, "{} {} {}", p[0], p[1], p[2])?;
+        }
+        Ok(())
+    }
+
+    /// Reads the vertices of an ASCII or binary PLY file. Fails with
+    /// `ErrorKind::InvalidData` on a malformed file, one without `x`, `y`
+    /// and `z` vertex properties, or one with list properties before or
+    /// among the vertex properties.
+    pub fn read_ply<R: BufRead>(mut r: R) -> io::Result<Self> {
+        let (format, elements) = read_ply_header(&mut r)?;
+        let mut data = Vec::new();
+        for element in &elements {
+            if element.properties.iter().any(|(ty, _)| ty.is_none()) {
+                return Err(invalid(
+                    "list properties are only supported after the vertices",
+                ));
+            }
+            let is_vertex = element.name == "vertex";
+            let slot = |axis: &str| element.properties.iter().position(|(_, n)| n == axis);
+            let slots = if is_vertex {
+                match (slot("x"), slot("y"), slot("z")) {
+                    (Some(x), Some(y), Some(z)) => [x, y, z],
+                    _ => return Err(invalid("vertices lack x, y or z")),
+                }
+            } else {
+                [usize::MAX; 3]
+            };
+            let mut row = vec![0.0; element.properties.len()];
+            let mut line = String::new();
+            for _ in 0..element.count {
+                match format {
+                    PlyFormat::Ascii => {
+                        line.clear();
+                        if r.read_line(&mut line)? == 0 {
+                            return Err(invalid("PLY data ends early"));
+                        }
+                        let mut fields = line.split_whitespace();
+                        for v in &mut row {
+                            *v = fields
+                                .next()
+                                .ok_or_else(|| invalid("too few PLY values"))?
+                                .parse()
+                                .map_err(|_| invalid("bad PLY value"))?;
+                        }
+                    }
+                    _ => {
+                        let big_endian = format == PlyFormat::BinaryBigEndian;
+                        let mut buf = [0u8; 8];
+                        for (v, (ty, _)) in row.iter_mut().zip(&element.properties) {
+                            let ty = ty.as_deref().expect("lists rejected above");
+                            let bytes = &mut buf[..type_size(ty).expect("checked in header")];
+                            r.read_exact(bytes)?;
+                            *v = decode(ty, bytes, big_endian);
+                        }
+                    }
+                }
+                if is_vertex {
+                    data.extend(slots.map(|s| T::from_f64(row[s])));
+                }
+            }
+            if is_vertex {
+                return Ok(PointCloud::from_flat_vec(data, 3).expect("three per point"));
+            }
+        }
+        Err(invalid("no vertex element"))
+    }
+
+    /// Writes the points as the vertices of a PLY file. Fails with
+    /// `ErrorKind::InvalidInput` unless the cloud is three-dimensional.
+    pub fn write_ply<W: Write>(&self, mut w: W, format: PlyFormat) -> io::Result<()> {
+        check_3d(self.dim())?;
+        let name = match format {
+            PlyFormat::Ascii => "ascii",
+            PlyFormat::BinaryLittleEndian => "binary_little_endian",
+            PlyFormat::BinaryBigEndian => "binary_big_endian",
+        };
+        writeln!(w, "ply\nformat {name} 1.0\nelement vertex {}", self.len())?;
+        for axis in ["x", "y", "z"] {
+            writeln!(w, "property {} {axis}", T::PLY_TYPE)?;
+        }
+        writeln!(w, "end_header")?;
+        match format {
+            PlyFormat::Ascii => self.write_xyz(w),
+            _ => {
+                let big_endian = format == PlyFormat::BinaryBigEndian;
+                for &v in self.as_flat() {
+                    v.write_binary(&mut w, big_endian)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn check_3d(dim: usize) -> io::Result<()> {
+    if dim == 3 {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "PLY and XYZ files hold 3D points",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> PointCloud<f64> {
+        PointCloud::from_flat(&[0.0, 1.5, -2.0, 1e-3, 4.0, 1e10, 0.1, 0.2, 0.3], 3).unwrap()
+    }
+
+    #[test]
+    fn xyz_round_trip() {
+        let cloud = sample();
+        let mut buf = Vec::new();
+        cloud.write_xyz(&mut buf).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&buf).lines().next(),
+            Some("0 1.5 -2")
+        );
+        assert_eq!(PointCloud::<f64>::read_xyz(&buf[..]).unwrap(), cloud);
+
+        let scan = "# x y z intensity\n1 2 3 0.5\n\n4 5 6 0.25\n";
+        let read = PointCloud::<f32>::read_xyz(scan.as_bytes()).unwrap();
+        assert_eq!(read.as_flat(), &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert!(PointCloud::<f32>::read_xyz("1 2\n".as_bytes()).is_err());
+        let flat = PointCloud::from_flat(&[1.0, 2.0], 2).unwrap();
+        assert!(flat.write_xyz(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn ply_round_trips() {
+        let cloud = sample();
+        for format in [
+            PlyFormat::Ascii,
+            PlyFormat::BinaryLittleEndian,
+            PlyFormat::BinaryBigEndian,
+        ] {
+            let mut buf = Vec::new();
+            cloud.write_ply(&mut buf, format).unwrap();
+            assert_eq!(PointCloud::<f64>::read_ply(&buf[..]).unwrap(), cloud);
+
+            let single = PointCloud::from_flat(&[1.0f32, -2.5, 3.25], 3).unwrap();
+            let mut buf = Vec::new();
+            single.write_ply(&mut buf, format).unwrap();
+            assert_eq!(PointCloud::<f32>::read_ply(&buf[..]).unwrap(), single);
+        }
+    }
+
+    #[test]
+    fn reads_meshes_and_other_types() {
+        let mut mesh = b"ply\nformat binary_little_endian 1.0\ncomment made by hand\n\
+            element vertex 2\nproperty uchar red\nproperty short z\nproperty float x\n\
+            property int y\nelement face 1\nproperty list uchar int vertex_indices\n\
+            end_header\n"
+            .to_vec();
+        for (red, z, x, y) in [(255u8, -3i16, 1.5f32, 7i32), (0, 4, -0.5, -1)] {
+            mesh.push(red);
+            mesh.extend(z.to_le_bytes());
+            mesh.extend(x.to_le_bytes());
+            mesh.extend(y.to_le_bytes());
+        }
+        mesh.extend([3, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        let cloud = PointCloud::<f64>::read_ply(&mesh[..]).unwrap();
+        assert_eq!(cloud.as_flat(), &[1.5, 7.0, -3.0, -0.5, -1.0, 4.0]);
+
+        let no_z = "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\n\
+            property float y\nend_header\n1 2\n";
+        assert!(PointCloud::<f64>::read_ply(no_z.as_bytes()).is_err());
+        let short = "ply\nformat ascii 1.0\nelement vertex 2\nproperty float x\n\
+            property float y\nproperty float z\nend_header\n1 2 3\n";
+        assert!(PointCloud::<f64>::read_ply(short.as_bytes()).is_err());
+        assert!(PointCloud::<f64>::read_ply("xyz\n".as_bytes()).is_err());
+    }
+}
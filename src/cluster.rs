@@ -0,0 +1,315 @@
+// K-means and DBSCAN clustering.
+//
+// Lloyd's algorithm: assign every point to its nearest centroid, move each
+// centroid to the mean of its points, repeat until nothing moves. The
+// starting centroids come from k-means++, which picks each next one with
+// probability proportional to the squared distance to the nearest already
+// picked, and keeps the result within O(log k) of the optimum in
+// expectation. A cluster left empty is restarted at the point farthest from
+// its centroid.
+//
+// DBSCAN instead grows clusters from dense points, those with at least
+// `min_pts` points (themselves included) within `eps`, and leaves points
+// reachable from no dense point as noise. Neighbourhoods come from a
+// `KdTree` radius query, so it runs in about O(n log n) on spread-out data
+// rather than comparing every pair.
+
+use crate::Point;
+use crate::index::SpatialIndex;
+use crate::kdtree::KdTree;
+use crate::rng::Rng;
+
+#[derive(Debug, Clone)]
+pub struct KMeans {
+    pub centroids: Vec<Point<f64>>,
+    /// For every point, the position in `centroids` of its cluster.
+    pub assignment: Vec<usize>,
+    /// Sum of squared distances from each point to its centroid.
+    pub inertia: f64,
+    /// Lloyd iterations run; fewer than `max_iter` means it converged.
+    pub iterations: usize,
+}
+
+/// Clusters `points` into `k` groups with a fixed seed, so the same input
+/// gives the same clusters; see [`kmeans_with_rng`]. Panics unless
+/// `1 <= k <= points.len()` or if the points don't share a dimension.
+pub fn kmeans<T>(points: &[Point<T>], k: usize, max_iter: usize) -> KMeans
+where
+    T: Into<f64> + Copy,
+{
+    kmeans_with_rng(points, k, max_iter, &mut Rng::seed_from_u64(0))
+}
+
+/// Like [`kmeans`], drawing the k-means++ seeds from `rng`.
+pub fn kmeans_with_rng<T>(points: &[Point<T>], k: usize, max_iter: usize, rng: &mut Rng) -> KMeans
+where
+    T: Into<f64> + Copy,
+{
+    lloyd(points, k, max_iter, rng, assign)
+}
+
+/// [`kmeans`] with the assignment step, which dominates the cost, spread
+/// over all cores. Gives the same result as `kmeans`.
+#[cfg(feature = "parallel")]
+pub fn par_kmeans<T>(points: &[Point<T>], k: usize, max_iter: usize) -> KMeans
+where
+    T: Into<f64> + Copy,
+{
+    par_kmeans_with_rng(points, k, max_iter, &mut Rng::seed_from_u64(0))
+}
+
+/// Like [`par_kmeans`], drawing the k-means++ seeds from `rng`.
+#[cfg(feature = "parallel")]
+pub fn par_kmeans_with_rng<T>(
+    points: &[Point<T>],
+    k: usize,
+    max_iter: usize,
+    rng: &mut Rng,
+) -> KMeans
+where
+    T: Into<f64> + Copy,
+{
+    lloyd(points, k, max_iter, rng, par_assign)
+}
+
+// Each point's nearest centroid and squared distance, into `out`.
+type Assign = fn(&[Vec<f64>], &[Vec<f64>], &mut [(usize, f64)]);
+
+fn assign(data: &[Vec<f64>], centroids: &[Vec<f64>], out: &mut [(usize, f64)]) {
+    for (o, x) in out.iter_mut().zip(data) {
+        *o = nearest(centroids, x);
+    }
+}
+
+#[cfg(feature = "parallel")]
+fn par_assign(data: &[Vec<f64>], centroids: &[Vec<f64>], out: &mut [(usize, f64)]) {
+    use rayon::prelude::*;
+    out.par_iter_mut()
+        .zip(data)
+        .for_each(|(o, x)| *o = nearest(centroids, x));
+}
+
+fn lloyd<T>(points: &[Point<T>], k: usize, max_iter: usize, rng: &mut Rng, assign: Assign) -> KMeans
+where
+    T: Into<f64> + Copy,
+{
+    assert!(
+        (1..=points.len()).contains(&k),
+        "k must be between 1 and the number of points"
+    );
+    let dim = points[0].dim();
+    let data: Vec<Vec<f64>> = points
+        .iter()
+        .map(|p| {
+            assert_eq!(p.dim(), dim, "points must share a dimension");
+            p.iter().map(|&v| v.into()).collect()
+        })
+        .collect();
+
+    let mut centroids = plus_plus(&data, k, rng);
+    // Each point's cluster and squared distance to its centroid.
+    let mut nearest = vec![(0, 0.0); data.len()];
+    let mut next = nearest.clone();
+    let mut sums = vec![vec![0.0; dim]; k];
+    let mut counts = vec![0usize; k];
+    let mut iterations = 0;
+    while iterations < max_iter {
+        iterations += 1;
+        assign(&data, &centroids, &mut next);
+        let changed = iterations == 1 || next.iter().zip(&nearest).any(|(a, b)| a.0 != b.0);
+        std::mem::swap(&mut nearest, &mut next);
+        if !changed {
+            break;
+        }
+        sums.iter_mut().for_each(|s| s.fill(0.0));
+        counts.fill(0);
+        for (x, &(c, _)) in data.iter().zip(&nearest) {
+            sums[c].iter_mut().zip(x).for_each(|(s, v)| *s += v);
+            counts[c] += 1;
+        }
+        for c in 0..k {
+            if counts[c] > 0 {
+                let n = counts[c] as f64;
+                centroids[c]
+                    .iter_mut()
+                    .zip(&sums[c])
+                    .for_each(|(m, s)| *m = s / n);
+            } else {
+                let far = (0..data.len())
+                    .max_by(|&a, &b| nearest[a].1.total_cmp(&nearest[b].1))
+                    .expect("points are not empty");
+                centroids[c].copy_from_slice(&data[far]);
+                nearest[far].1 = 0.0;
+            }
+        }
+    }
+    assign(&data, &centroids, &mut nearest);
+    KMeans {
+        centroids: centroids.into_iter().map(Point::new).collect(),
+        assignment: nearest.iter().map(|&(c, _)| c).collect(),
+        inertia: nearest.iter().map(|&(_, d)| d).sum(),
+        iterations,
+    }
+}
+
+/// DBSCAN labels, one per point: `Some(cluster)` numbered from 0 in order of
+/// discovery, or `None` for noise. A border point within reach of several
+/// clusters joins the first found. Panics if the points don't share a
+/// dimension.
+pub fn dbscan<T>(points: &[Point<T>], eps: f64, min_pts: usize) -> Vec<Option<usize>>
+where
+    T: Into<f64> + Copy,
+{
+    let tree = KdTree::new(points);
+    let neighbours = |i: usize| -> Vec<usize> {
+        let q: Vec<f64> = points[i].iter().map(|&v| v.into()).collect();
+        tree.within_radius(&q, eps)
+            .iter()
+            .map(|n| n.index)
+            .collect()
+    };
+    let mut labels = vec![None; points.len()];
+    let mut visited = vec![false; points.len()];
+    let mut clusters = 0;
+    for start in 0..points.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let mut frontier = neighbours(start);
+        if frontier.len() < min_pts {
+            continue;
+        }
+        labels[start] = Some(clusters);
+        while let Some(i) = frontier.pop() {
+            if labels[i].is_none() {
+                labels[i] = Some(clusters);
+            }
+            if visited[i] {
+                continue;
+            }
+            visited[i] = true;
+            let near = neighbours(i);
+            if near.len() >= min_pts {
+                frontier.extend(near);
+            }
+        }
+        clusters += 1;
+    }
+    labels
+}
+
+fn squared(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+// The closest centroid to `x` and its squared distance, lowest index on ties.
+fn nearest(centroids: &[Vec<f64>], x: &[f64]) -> (usize, f64) {
+    centroids
+        .iter()
+        .map(|c| squared(c, x))
+        .enumerate()
+        .fold(
+            (0, f64::INFINITY),
+            |best, (i, d)| if d < best.1 { (i, d) } else { best },
+        )
+}
+
+fn plus_plus(data: &[Vec<f64>], k: usize, rng: &mut Rng) -> Vec<Vec<f64>> {
+    let mut centroids = vec![data[rng.below(data.len())].clone()];
+    let mut d2: Vec<f64> = data.iter().map(|x| squared(x, &centroids[0])).collect();
+    while centroids.len() < k {
+        let total: f64 = d2.iter().sum();
+        // With every point on a centroid already, any choice is as good.
+        let next = if total > 0.0 {
+            let mut target = rng.next_f64() * total;
+            d2.iter()
+                .position(|&d| {
+                    target -= d;
+                    target < 0.0
+                })
+                .unwrap_or_else(|| d2.iter().rposition(|&d| d > 0.0).unwrap())
+        } else {
+            rng.below(data.len())
+        };
+        let c = data[next].clone();
+        d2.iter_mut()
+            .zip(data)
+            .for_each(|(d, x)| *d = d.min(squared(x, &c)));
+        centroids.push(c);
+    }
+    centroids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blobs(rng: &mut Rng) -> Vec<Point<f64>> {
+        let centers = [[0.0, 0.0], [10.0, 0.0], [0.0, 10.0]];
+        (0..90)
+            .map(|i| {
+                let c = centers[i % 3];
+                Point::new(vec![c[0] + 0.5 * rng.normal(), c[1] + 0.5 * rng.normal()])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn separates_blobs() {
+        let pts = blobs(&mut Rng::seed_from_u64(262));
+        let km = kmeans(&pts, 3, 100);
+        assert_eq!(km.centroids.len(), 3);
+        assert!(km.iterations < 100);
+        // Points from the same blob share a cluster, and blobs don't.
+        for i in 0..pts.len() {
+            assert_eq!(km.assignment[i], km.assignment[i % 3]);
+        }
+        let mut firsts = km.assignment[..3].to_vec();
+        firsts.sort();
+        firsts.dedup();
+        assert_eq!(firsts.len(), 3);
+        for (i, &c) in km.assignment.iter().enumerate() {
+            assert!(pts[i].distance_to(&km.centroids[c]) < 3.0);
+        }
+        assert!(km.inertia < 90.0);
+        assert_eq!(kmeans(&pts, 3, 100).assignment, km.assignment);
+        #[cfg(feature = "parallel")]
+        {
+            let par = par_kmeans(&pts, 3, 100);
+            assert_eq!(par.assignment, km.assignment);
+            assert_eq!(par.inertia, km.inertia);
+        }
+    }
+
+    #[test]
+    fn dbscan_finds_dense_regions() {
+        let mut pts = blobs(&mut Rng::seed_from_u64(263));
+        pts.push(Point::new(vec![5.0, 5.0]));
+        pts.push(Point::new(vec![30.0, -30.0]));
+        let labels = dbscan(&pts, 1.5, 4);
+        for i in 0..90 {
+            assert_eq!(labels[i], labels[i % 3]);
+            assert!(labels[i].is_some());
+        }
+        assert_eq!(labels[..3], [Some(0), Some(1), Some(2)]);
+        assert_eq!(labels[90..], [None, None]);
+
+        // Too sparse for any cluster.
+        assert!(dbscan(&pts, 0.01, 2).iter().all(Option::is_none));
+        assert!(dbscan::<f64>(&[], 1.0, 1).is_empty());
+    }
+
+    #[test]
+    fn degenerate_inputs() {
+        let same = vec![Point::new(vec![2, 2]); 5];
+        let km = kmeans(&same, 3, 10);
+        assert_eq!(km.inertia, 0.0);
+        assert!(km.centroids.iter().all(|c| c.data() == [2.0, 2.0]));
+
+        let pts: Vec<Point<i32>> = (0..4).map(|i| Point::new(vec![i * 10])).collect();
+        let km = kmeans(&pts, 4, 10);
+        assert_eq!(km.inertia, 0.0);
+        assert!(std::panic::catch_unwind(|| kmeans(&pts, 5, 10)).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+// Conversions between points and arrays, tuples, slices and vectors.
+
+use crate::storage::VecStorage;
+use crate::{Error, Point};
+
+impl<T: Copy> From<Vec<T>> for Point<T> {
+    fn from(p: Vec<T>) -> Self {
+        Point::from_storage(p.into())
+    }
+}
+
+impl<T: Copy, const N: usize> From<[T; N]> for Point<T> {
+    fn from(p: [T; N]) -> Self {
+        Point::from_storage(VecStorage::from_slice(&p))
+    }
+}
+
+// Every slice is a valid point, so this also provides `TryFrom<&[T]>`.
+impl<T: Copy> From<&[T]> for Point<T> {
+    fn from(p: &[T]) -> Self {
+        Point::from_storage(VecStorage::from_slice(p))
+    }
+}
+
+impl<T: Copy> From<(T, T)> for Point<T> {
+    fn from((x, y): (T, T)) -> Self {
+        Point::from([x, y])
+    }
+}
+
+impl<T: Copy> From<(T, T, T)> for Point<T> {
+    fn from((x, y, z): (T, T, T)) -> Self {
+        Point::from([x, y, z])
+    }
+}
+
+impl<T: Copy> From<Point<T>> for Vec<T> {
+    fn from(point: Point<T>) -> Self {
+        point.p.into_vec()
+    }
+}
+
+impl<T: Copy, const N: usize> TryFrom<&Point<T>> for [T; N] {
+    type Error = Error;
+
+    fn try_from(point: &Point<T>) -> Result<Self, Error> {
+        <[T; N]>::try_from(&point.p[..]).map_err(|_| Error::DimensionMismatch {
+            expected: N,
+            found: point.p.len(),
+        })
+    }
+}
+
+impl<T: Copy, const N: usize> TryFrom<Point<T>> for [T; N] {
+    type Error = Error;
+
+    fn try_from(point: Point<T>) -> Result<Self, Error> {
+        <[T; N]>::try_from(&point)
+    }
+}
+
+impl<T: Copy> TryFrom<Point<T>> for (T, T) {
+    type Error = Error;
+
+    fn try_from(point: Point<T>) -> Result<Self, Error> {
+        let [x, y] = <[T; 2]>::try_from(&point)?;
+        Ok((x, y))
+    }
+}
+
+impl<T: Copy> TryFrom<Point<T>> for (T, T, T) {
+    type Error = Error;
+
+    fn try_from(point: Point<T>) -> Result<Self, Error> {
+        let [x, y, z] = <[T; 3]>::try_from(&point)?;
+        Ok((x, y, z))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_array_tuple_slice() {
+        assert_eq!(Point::from([1, 2, 3]).data(), &[1, 2, 3]);
+        assert_eq!(Point::from((1.0, 2.0)).data(), &[1.0, 2.0]);
+        assert_eq!(Point::from((1, 2, 3)).data(), &[1, 2, 3]);
+        assert_eq!(Point::from(&[4, 5][..]).data(), &[4, 5]);
+        assert_eq!(Point::from(vec![1; 6]).dim(), 6);
+    }
+
+    #[test]
+    fn into_array_tuple_vec() {
+        let p = Point::new(vec![1, 2, 3]);
+        let arr: [i32; 3] = (&p).try_into().unwrap();
+        assert_eq!(arr, [1, 2, 3]);
+        let (x, y, z) = p.clone().try_into().unwrap();
+        assert_eq!((x, y, z), (1, 2, 3));
+        assert_eq!(Vec::from(p.clone()), vec![1, 2, 3]);
+
+        let err = <(i32, i32)>::try_from(p).unwrap_err();
+        assert_eq!(
+            err,
+            Error::DimensionMismatch {
+                expected: 2,
+                found: 3
+            }
+        );
+    }
+}
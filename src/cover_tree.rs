@@ -0,0 +1,204 @@
+// Cover trees for exact nearest-neighbour search in metric spaces.
+//
+// This is the simplified cover tree of Izbicki and Shelton: a node at level
+// `l` covers its children within `2^l`, and every node records `maxdist`, the
+// distance to its farthest descendant, which is all a query needs to prune.
+// Items can be inserted at any time. When a new item falls outside the root's
+// cover the root's level is raised instead of restructuring the tree.
+
+use crate::index::{KBest, MetricIndex, MetricItem, Neighbor, RadiusResults};
+
+#[derive(Debug, Clone)]
+struct Node {
+    level: i32,
+    max_dist: f64,
+    children: Vec<usize>,
+}
+
+/// Node `i` holds item `i`; item 0 is the root.
+#[derive(Debug, Clone)]
+pub struct CoverTree<I> {
+    items: Vec<I>,
+    nodes: Vec<Node>,
+}
+
+fn cover(level: i32) -> f64 {
+    2f64.powi(level)
+}
+
+impl<I: MetricItem> CoverTree<I> {
+    pub fn new(items: Vec<I>) -> Self {
+        let mut tree = CoverTree {
+            items: Vec::with_capacity(items.len()),
+            nodes: Vec::with_capacity(items.len()),
+        };
+        for item in items {
+            tree.insert(item);
+        }
+        tree
+    }
+
+    pub fn items(&self) -> &[I] {
+        &self.items
+    }
+
+    /// Adds an item and returns its index.
+    pub fn insert(&mut self, item: I) -> usize {
+        let id = self.items.len();
+        self.items.push(item);
+        if id == 0 {
+            self.nodes.push(Node {
+                level: 0,
+                max_dist: 0.0,
+                children: Vec::new(),
+            });
+            return id;
+        }
+        let d = self.items[0].distance(&self.items[id]);
+        let root = &mut self.nodes[0];
+        while d > cover(root.level) {
+            root.level += 1;
+        }
+        self.insert_below(0, id, d);
+        id
+    }
+
+    // `d` is the distance from `node` to the new item, which `node` covers.
+    fn insert_below(&mut self, node: usize, id: usize, d: f64) {
+        let n = &mut self.nodes[node];
+        n.max_dist = n.max_dist.max(d);
+        let level = n.level;
+        for i in 0..self.nodes[node].children.len() {
+            let child = self.nodes[node].children[i];
+            let dc = self.items[child].distance(&self.items[id]);
+            if dc <= cover(self.nodes[child].level) {
+                self.insert_below(child, id, dc);
+                return;
+            }
+        }
+        self.nodes.push(Node {
+            level: level - 1,
+            max_dist: 0.0,
+            children: Vec::new(),
+        });
+        self.nodes[node].children.push(id);
+    }
+
+    fn search(
+        &self,
+        node: usize,
+        d: f64,
+        query: &I,
+        best: &mut KBest,
+        keep: &dyn Fn(usize) -> bool,
+    ) {
+        if keep(node) {
+            best.push(node, d);
+        }
+        let mut children: Vec<(f64, usize)> = self.nodes[node]
+            .children
+            .iter()
+            .map(|&c| (self.items[c].distance(query), c))
+            .collect();
+        children.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (dc, c) in children {
+            if dc - self.nodes[c].max_dist <= best.worst() {
+                self.search(c, dc, query, best, keep);
+            }
+        }
+    }
+
+    fn search_radius(
+        &self,
+        node: usize,
+        d: f64,
+        query: &I,
+        radius: f64,
+        out: &mut RadiusResults,
+        keep: &dyn Fn(usize) -> bool,
+    ) {
+        if keep(node) {
+            out.push(node, d);
+        }
+        for &c in &self.nodes[node].children {
+            let dc = self.items[c].distance(query);
+            if dc - self.nodes[c].max_dist <= radius {
+                self.search_radius(c, dc, query, radius, out, keep);
+            }
+        }
+    }
+}
+
+impl<I: MetricItem> MetricIndex<I> for CoverTree<I> {
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn k_nearest_where(&self, query: &I, k: usize, keep: &dyn Fn(usize) -> bool) -> Vec<Neighbor> {
+        let mut best = KBest::new(k);
+        if !self.items.is_empty() {
+            let d = self.items[0].distance(query);
+            self.search(0, d, query, &mut best, keep);
+        }
+        best.into_neighbors()
+    }
+
+    fn within_radius_where(
+        &self,
+        query: &I,
+        radius: f64,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor> {
+        let mut out = RadiusResults::new(radius);
+        if !self.items.is_empty() {
+            let d = self.items[0].distance(query);
+            self.search_radius(0, d, query, radius, &mut out, keep);
+        }
+        out.into_neighbors()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point;
+    use crate::index::LinearScan;
+    use crate::rng::Rng;
+
+    #[test]
+    fn matches_linear_scan() {
+        let mut rng = Rng::seed_from_u64(32);
+        let pts: Vec<Point<f64>> = (0..300)
+            .map(|_| Point::new((0..3).map(|_| rng.range_f64(0.0, 10.0)).collect()))
+            .collect();
+        let tree = CoverTree::new(pts.clone());
+        let scan = LinearScan::new(pts);
+        for _ in 0..25 {
+            let q = Point::new((0..3).map(|_| rng.range_f64(-1.0, 11.0)).collect());
+            assert_eq!(tree.k_nearest(&q, 5), scan.k_nearest(&q, 5));
+            assert_eq!(tree.within_radius(&q, 2.0), scan.within_radius(&q, 2.0));
+            let keep = |i: usize| i.is_multiple_of(3);
+            assert_eq!(
+                tree.k_nearest_where(&q, 5, &keep),
+                scan.k_nearest_where(&q, 5, &keep)
+            );
+            assert_eq!(
+                tree.within_radius_where(&q, 3.0, &keep),
+                scan.within_radius_where(&q, 3.0, &keep)
+            );
+        }
+    }
+
+    #[test]
+    fn insert_and_duplicates() {
+        let mut tree = CoverTree::new(Vec::new());
+        assert!(tree.nearest(&Point::new(vec![0.0])).is_none());
+        for x in [0.0, 100.0, 0.001, 0.001, -50.0] {
+            tree.insert(Point::new(vec![x]));
+        }
+        assert_eq!(tree.len(), 5);
+        let hits = tree.k_nearest(&Point::new(vec![0.001]), 2);
+        assert_eq!(hits.iter().map(|n| n.index).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(tree.nearest(&Point::new(vec![-40.0])).unwrap().index, 4);
+    }
+}
@@ -0,0 +1,287 @@
+// Non-linear deformations of point sets.
+//
+// Bend, twist and taper are Barr's classic operators: each one varies a
+// simple transform with a point's coordinate along some axis. `Lattice` is
+// Sederberg and Parry's free-form deformation: the points inside a box are
+// written as Bernstein-weighted blends of a grid of control points, and
+// moving the control points drags the points along smoothly. Every operator
+// works in any dimension; axes are coordinate indices.
+
+use crate::PointCloud;
+
+pub trait Deformer {
+    /// The deformed position of `p`.
+    fn deform(&self, p: &[f64]) -> Vec<f64>;
+
+    /// Deforms every point of `cloud` in place, reporting each edit to its
+    /// observers.
+    fn apply(&self, cloud: &mut PointCloud<f64>) {
+        for i in 0..cloud.len() {
+            cloud.update(i, |p| {
+                let q = self.deform(p);
+                p.copy_from_slice(&q);
+            });
+        }
+    }
+}
+
+/// Bends the `axis` direction into a circular arc curving towards the
+/// `toward` direction. Points at `axis` coordinate zero stay put and the arc
+/// has radius `1 / curvature`; zero curvature leaves points unchanged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bend {
+    pub axis: usize,
+    pub toward: usize,
+    pub curvature: f64,
+}
+
+impl Deformer for Bend {
+    fn deform(&self, p: &[f64]) -> Vec<f64> {
+        let mut q = p.to_vec();
+        if self.curvature != 0.0 {
+            let r = 1.0 / self.curvature;
+            let (s, c) = (self.curvature * p[self.axis]).sin_cos();
+            let arm = r - p[self.toward];
+            q[self.axis] = arm * s;
+            q[self.toward] = r - arm * c;
+        }
+        q
+    }
+}
+
+/// Rotates the `plane` coordinates by `rate` radians per unit along `axis`,
+/// turning the first plane axis towards the second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Twist {
+    pub axis: usize,
+    pub plane: [usize; 2],
+    pub rate: f64,
+}
+
+impl Deformer for Twist {
+    fn deform(&self, p: &[f64]) -> Vec<f64> {
+        let mut q = p.to_vec();
+        let [u, v] = self.plane;
+        let (s, c) = (self.rate * p[self.axis]).sin_cos();
+        q[u] = c * p[u] - s * p[v];
+        q[v] = s * p[u] + c * p[v];
+        q
+    }
+}
+
+/// Scales every coordinate but `axis` by `1 + rate * x[axis]`, narrowing or
+/// widening the set along `axis`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Taper {
+    pub axis: usize,
+    pub rate: f64,
+}
+
+impl Deformer for Taper {
+    fn deform(&self, p: &[f64]) -> Vec<f64> {
+        let f = 1.0 + self.rate * p[self.axis];
+        p.iter()
+            .enumerate()
+            .map(|(i, &v)| if i == self.axis { v } else { f * v })
+            .collect()
+    }
+}
+
+/// A free-form deformation lattice over the box `[lower, upper]`. Points
+/// outside the box are left alone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lattice {
+    lower: Vec<f64>,
+    upper: Vec<f64>,
+    // Control points per axis, one more than the divisions.
+    counts: Vec<usize>,
+    // Control points, flat, the first axis varying slowest.
+    controls: Vec<f64>,
+}
+
+impl Lattice {
+    /// A lattice with `divisions[i]` cells along axis `i`, its control points
+    /// spread evenly over the box so that it starts out as the identity.
+    /// Panics if the lengths differ, the box is empty or flat, or an axis has
+    /// no divisions.
+    pub fn new(lower: &[f64], upper: &[f64], divisions: &[usize]) -> Self {
+        let dim = lower.len();
+        assert!(dim > 0, "lattice needs at least one axis");
+        assert!(
+            upper.len() == dim && divisions.len() == dim,
+            "bounds and divisions must share a dimension"
+        );
+        assert!(
+            lower.iter().zip(upper).all(|(lo, hi)| lo < hi),
+            "lattice box must have positive extent"
+        );
+        assert!(
+            divisions.iter().all(|&d| d > 0),
+            "divisions must be positive"
+        );
+        let counts: Vec<usize> = divisions.iter().map(|d| d + 1).collect();
+        let mut lattice = Lattice {
+            lower: lower.to_vec(),
+            upper: upper.to_vec(),
+            controls: vec![0.0; counts.iter().product::<usize>() * dim],
+            counts,
+        };
+        for n in 0..lattice.control_count() {
+            let idx = lattice.unflatten(n);
+            for (k, &i) in idx.iter().enumerate() {
+                let t = i as f64 / divisions[k] as f64;
+                lattice.controls[n * dim + k] = lower[k] + t * (upper[k] - lower[k]);
+            }
+        }
+        lattice
+    }
+
+    pub fn dim(&self) -> usize {
+        self.lower.len()
+    }
+
+    /// Control points along each axis.
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    pub fn control_count(&self) -> usize {
+        self.controls.len() / self.dim()
+    }
+
+    /// The control point at grid position `index`. Panics if `index` is out
+    /// of range.
+    pub fn control(&self, index: &[usize]) -> &[f64] {
+        let n = self.flatten(index);
+        &self.controls[n * self.dim()..(n + 1) * self.dim()]
+    }
+
+    /// Moves the control point at grid position `index`.
+    pub fn control_mut(&mut self, index: &[usize]) -> &mut [f64] {
+        let (n, dim) = (self.flatten(index), self.dim());
+        &mut self.controls[n * dim..(n + 1) * dim]
+    }
+
+    fn flatten(&self, index: &[usize]) -> usize {
+        assert_eq!(index.len(), self.dim(), "one grid index per axis");
+        index.iter().zip(&self.counts).fold(0, |n, (&i, &c)| {
+            assert!(i < c, "grid index out of range");
+            n * c + i
+        })
+    }
+
+    fn unflatten(&self, mut n: usize) -> Vec<usize> {
+        let mut idx = vec![0; self.dim()];
+        for (i, &c) in idx.iter_mut().zip(&self.counts).rev() {
+            *i = n % c;
+            n /= c;
+        }
+        idx
+    }
+}
+
+// The Bernstein basis polynomials of degree `n` at `t`.
+fn bernstein(n: usize, t: f64) -> Vec<f64> {
+    // De Casteljau's triangle, from the degree-zero basis up.
+    let mut b = vec![0.0; n + 1];
+    b[0] = 1.0;
+    for j in 1..=n {
+        for i in (1..=j).rev() {
+            b[i] = (1.0 - t) * b[i] + t * b[i - 1];
+        }
+        b[0] *= 1.0 - t;
+    }
+    b
+}
+
+impl Deformer for Lattice {
+    /// Panics if `p` has the wrong dimension.
+    fn deform(&self, p: &[f64]) -> Vec<f64> {
+        let dim = self.dim();
+        assert_eq!(p.len(), dim, "point and lattice dimensions differ");
+        let inside = p
+            .iter()
+            .zip(self.lower.iter().zip(&self.upper))
+            .all(|(v, (lo, hi))| lo <= v && v <= hi);
+        if !inside {
+            return p.to_vec();
+        }
+        let basis: Vec<Vec<f64>> = (0..dim)
+            .map(|k| {
+                let t = (p[k] - self.lower[k]) / (self.upper[k] - self.lower[k]);
+                bernstein(self.counts[k] - 1, t)
+            })
+            .collect();
+        let mut q = vec![0.0; dim];
+        for (n, c) in self.controls.chunks_exact(dim).enumerate() {
+            let w: f64 = self
+                .unflatten(n)
+                .iter()
+                .zip(&basis)
+                .map(|(&i, b)| b[i])
+                .product();
+            for (qk, ck) in q.iter_mut().zip(c) {
+                *qk += w * ck;
+            }
+        }
+        q
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: &[f64], b: &[f64]) -> bool {
+        a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-9)
+    }
+
+    #[test]
+    fn bend_twist_taper() {
+        // A quarter of the circle of radius 2 reaches the top of the arc.
+        let bend = Bend {
+            axis: 0,
+            toward: 1,
+            curvature: 0.5,
+        };
+        let half_pi = std::f64::consts::FRAC_PI_2;
+        assert!(close(&bend.deform(&[2.0 * half_pi, 0.0]), &[2.0, 2.0]));
+        assert!(close(&bend.deform(&[0.0, 0.3]), &[0.0, 0.3]));
+
+        let twist = Twist {
+            axis: 2,
+            plane: [0, 1],
+            rate: half_pi,
+        };
+        assert!(close(&twist.deform(&[1.0, 0.0, 1.0]), &[0.0, 1.0, 1.0]));
+        assert!(close(&twist.deform(&[1.0, 0.0, 0.0]), &[1.0, 0.0, 0.0]));
+
+        let taper = Taper {
+            axis: 1,
+            rate: -0.5,
+        };
+        assert_eq!(taper.deform(&[4.0, 1.0, 2.0]), vec![2.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn lattice() {
+        let mut lattice = Lattice::new(&[0.0, 0.0], &[2.0, 1.0], &[2, 3]);
+        assert_eq!(lattice.counts(), &[3, 4]);
+        assert_eq!(lattice.control(&[1, 3]), &[1.0, 1.0]);
+        // Untouched, the lattice is the identity.
+        assert!(close(&lattice.deform(&[0.5, 0.7]), &[0.5, 0.7]));
+
+        lattice.control_mut(&[2, 0])[0] = 3.0;
+        assert!(close(&lattice.deform(&[2.0, 0.0]), &[3.0, 0.0]));
+        // Corners of a Bezier patch are interpolated, the far side is not
+        // affected by the moved control point.
+        assert!(close(&lattice.deform(&[0.0, 0.5]), &[0.0, 0.5]));
+        let moved = lattice.deform(&[1.5, 0.2]);
+        assert!(moved[0] > 1.5 && (moved[1] - 0.2).abs() < 1e-9);
+        assert_eq!(lattice.deform(&[5.0, 0.5]), vec![5.0, 0.5]);
+
+        let mut cloud = PointCloud::from_flat(&[2.0, 0.0, 9.0, 9.0], 2).unwrap();
+        lattice.apply(&mut cloud);
+        assert!(close(cloud.as_flat(), &[3.0, 0.0, 9.0, 9.0]));
+    }
+}
@@ -0,0 +1,371 @@
+// Delaunay triangulations and Voronoi diagrams.
+//
+// The Delaunay triangulation is read off a convex hull one dimension up:
+// lift every point onto the paraboloid `z = |x|^2` and the downward-facing
+// facets of the lifted hull project back to simplices whose circumspheres
+// hold no other point. Points are first centred and scaled to the unit ball
+// so the hull's tolerances don't depend on where the data lives, and one
+// extra point is added high above the centroid. It only ever joins upward
+// facets, but it keeps the lifted hull full-dimensional when every point is
+// cospherical (the corners of a square, a regular polygon), whose lifted
+// images would otherwise be flat.
+//
+// Cospherical points have many valid triangulations and one is picked
+// arbitrarily. Of duplicate points (or points within the hull's tolerance
+// of each other) only one joins any simplex.
+//
+// The Voronoi diagram is the dual: its vertices are the simplices'
+// circumcentres, and a point's cell is spanned by the circumcentres of the
+// simplices around it.
+
+use std::collections::HashMap;
+
+use crate::hull::convex_hull;
+use crate::linalg::{Matrix, dot};
+use crate::{Point, Storage};
+
+// Lifted facets whose unit normal points down by less than this are
+// vertical: they project to flat simplices along the boundary.
+const VERTICAL: f64 = 1e-9;
+
+/// One simplex of a triangulation.
+#[derive(Debug, Clone)]
+pub struct Simplex {
+    /// Indices of the corners among the input points, positively oriented
+    /// (counter-clockwise in 2D).
+    pub vertices: Vec<usize>,
+    /// `neighbors[k]` is the simplex across the face opposite
+    /// `vertices[k]`; `None` on the boundary.
+    pub neighbors: Vec<Option<usize>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Triangulation {
+    pub simplices: Vec<Simplex>,
+}
+
+/// The Voronoi cell of one input point.
+#[derive(Debug, Clone, Default)]
+pub struct Cell {
+    /// Indices into [`Voronoi::vertices`]; counter-clockwise around the
+    /// point in 2D, ascending otherwise. Points on the convex hull have
+    /// unbounded cells, whose edges also run off to infinity.
+    pub vertices: Vec<usize>,
+    /// The input points whose cells share a face with this one, ascending.
+    pub neighbors: Vec<usize>,
+    pub bounded: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Voronoi {
+    /// Circumcentres of the Delaunay simplices, in the same order.
+    pub vertices: Vec<Point<f64>>,
+    /// One cell per input point; empty for points left out of the
+    /// triangulation.
+    pub cells: Vec<Cell>,
+}
+
+/// The Delaunay triangulation of `points`; `None` if they don't span their
+/// space. Panics if they don't share a dimension.
+pub fn triangulate<T, S>(points: &[Point<T, S>]) -> Option<Triangulation>
+where
+    T: Into<f64> + Copy,
+    S: Storage<T>,
+{
+    let pts = normalised(points)?;
+    let dim = pts[0].len();
+    let mut lifted: Vec<Point<f64>> = pts
+        .iter()
+        .map(|p| {
+            let mut v = p.clone();
+            v.push(dot(p, p));
+            Point::new(v)
+        })
+        .collect();
+    let mut apex = vec![0.0; dim];
+    apex.push(2.0);
+    lifted.push(Point::new(apex));
+    let hull = convex_hull(&lifted)?;
+
+    let mut simplices: Vec<Simplex> = hull
+        .facets
+        .into_iter()
+        .filter(|f| f.plane.normal[dim] < -VERTICAL)
+        .map(|f| {
+            let mut vertices = f.vertices;
+            if orientation(&pts, &vertices) < 0.0 {
+                vertices.swap(0, 1);
+            }
+            Simplex {
+                neighbors: vec![None; vertices.len()],
+                vertices,
+            }
+        })
+        .collect();
+    if simplices.is_empty() {
+        return None;
+    }
+    // Match simplices up by their shared faces.
+    let mut faces: HashMap<Vec<usize>, (usize, usize)> = HashMap::new();
+    for s in 0..simplices.len() {
+        for k in 0..=dim {
+            let mut face = simplices[s].vertices.clone();
+            face.remove(k);
+            face.sort_unstable();
+            if let Some((t, j)) = faces.remove(&face) {
+                simplices[s].neighbors[k] = Some(t);
+                simplices[t].neighbors[j] = Some(s);
+            } else {
+                faces.insert(face, (s, k));
+            }
+        }
+    }
+    Some(Triangulation { simplices })
+}
+
+/// The Voronoi diagram of `points`, dual to their [`triangulate`]; `None`
+/// if they don't span their space. Panics if they don't share a dimension.
+pub fn voronoi<T, S>(points: &[Point<T, S>]) -> Option<Voronoi>
+where
+    T: Into<f64> + Copy,
+    S: Storage<T>,
+{
+    let tri = triangulate(points)?;
+    let pts: Vec<Vec<f64>> = points
+        .iter()
+        .map(|p| p.as_slice().iter().map(|&v| v.into()).collect())
+        .collect();
+    let dim = pts[0].len();
+    let vertices: Vec<Point<f64>> = tri
+        .simplices
+        .iter()
+        .map(|s| circumcentre(&pts, &s.vertices))
+        .collect();
+    let mut cells = vec![Cell::default(); pts.len()];
+    let mut boundary = vec![false; pts.len()];
+    for (i, s) in tri.simplices.iter().enumerate() {
+        for (k, &v) in s.vertices.iter().enumerate() {
+            cells[v].vertices.push(i);
+            cells[v]
+                .neighbors
+                .extend(s.vertices.iter().filter(|&&w| w != v));
+            if s.neighbors
+                .iter()
+                .enumerate()
+                .any(|(j, n)| j != k && n.is_none())
+            {
+                boundary[v] = true;
+            }
+        }
+    }
+    for (p, (cell, on_boundary)) in cells.iter_mut().zip(boundary).enumerate() {
+        cell.neighbors.sort_unstable();
+        cell.neighbors.dedup();
+        cell.bounded = !cell.vertices.is_empty() && !on_boundary;
+        if dim == 2 {
+            let angle = |&i: &usize| {
+                let c = &vertices[i];
+                (c[1] - pts[p][1]).atan2(c[0] - pts[p][0])
+            };
+            cell.vertices.sort_by(|a, b| angle(a).total_cmp(&angle(b)));
+        }
+    }
+    Some(Voronoi { vertices, cells })
+}
+
+// The points as `f64`, centred on their centroid and scaled into the unit
+// ball; `None` if there are none.
+fn normalised<T, S>(points: &[Point<T, S>]) -> Option<Vec<Vec<f64>>>
+where
+    T: Into<f64> + Copy,
+    S: Storage<T>,
+{
+    let dim = points.first()?.as_slice().len();
+    let mut pts: Vec<Vec<f64>> = points
+        .iter()
+        .map(|p| {
+            assert_eq!(p.as_slice().len(), dim, "points must share a dimension");
+            p.as_slice().iter().map(|&v| v.into()).collect()
+        })
+        .collect();
+    let mut centroid = vec![0.0; dim];
+    for p in &pts {
+        centroid
+            .iter_mut()
+            .zip(p)
+            .for_each(|(c, v)| *c += v / points.len() as f64);
+    }
+    for p in &mut pts {
+        p.iter_mut().zip(&centroid).for_each(|(v, c)| *v -= c);
+    }
+    let radius = pts.iter().map(|p| dot(p, p)).fold(0.0, f64::max).sqrt();
+    if radius > 0.0 {
+        pts.iter_mut().flatten().for_each(|v| *v /= radius);
+    }
+    Some(pts)
+}
+
+// The determinant of the simplex's edge vectors: positive when positively
+// oriented.
+fn orientation(pts: &[Vec<f64>], vertices: &[usize]) -> f64 {
+    let base = &pts[vertices[0]];
+    let rows: Vec<Vec<f64>> = vertices[1..]
+        .iter()
+        .map(|&v| pts[v].iter().zip(base).map(|(a, b)| a - b).collect())
+        .collect();
+    Matrix::from_rows(&rows).determinant()
+}
+
+// The centre of the sphere through the simplex's corners: `c` with
+// `2 (v_i - v_0) . c = |v_i|^2 - |v_0|^2`.
+fn circumcentre(pts: &[Vec<f64>], vertices: &[usize]) -> Point<f64> {
+    let base = &pts[vertices[0]];
+    let rows: Vec<Vec<f64>> = vertices[1..]
+        .iter()
+        .map(|&v| {
+            pts[v]
+                .iter()
+                .zip(base)
+                .map(|(a, b)| 2.0 * (a - b))
+                .collect()
+        })
+        .collect();
+    let rhs: Vec<f64> = vertices[1..]
+        .iter()
+        .map(|&v| dot(&pts[v], &pts[v]) - dot(base, base))
+        .collect();
+    Point::new(
+        Matrix::from_rows(&rows)
+            .solve(&rhs)
+            .expect("Delaunay simplices are not flat"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn random_points(dim: usize, n: usize, rng: &mut Rng) -> Vec<Point<f64>> {
+        (0..n)
+            .map(|_| Point::new((0..dim).map(|_| rng.next_f64() * 10.0).collect()))
+            .collect()
+    }
+
+    // Empty circumspheres, positive orientation and symmetric neighbours.
+    fn check(points: &[Point<f64>], tri: &Triangulation) {
+        let pts: Vec<Vec<f64>> = points.iter().map(|p| p.data().to_vec()).collect();
+        for (i, s) in tri.simplices.iter().enumerate() {
+            assert!(orientation(&pts, &s.vertices) > 0.0);
+            let c = circumcentre(&pts, &s.vertices);
+            let r = c.distance_to(&points[s.vertices[0]]);
+            for p in points {
+                assert!(c.distance_to(p) >= r - 1e-9);
+            }
+            for (k, n) in s.neighbors.iter().enumerate() {
+                if let Some(n) = *n {
+                    let back = &tri.simplices[n];
+                    assert!(back.neighbors.contains(&Some(i)));
+                    assert!(!back.vertices.contains(&s.vertices[k]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn triangulates_the_plane() {
+        let mut rng = Rng::seed_from_u64(277);
+        let pts = random_points(2, 200, &mut rng);
+        let tri = triangulate(&pts).unwrap();
+        check(&pts, &tri);
+        // Euler: 2n - 2 - h triangles for n points, h on the hull.
+        let h = convex_hull(&pts).unwrap().vertices.len();
+        assert_eq!(tri.simplices.len(), 2 * pts.len() - 2 - h);
+
+        // A square with a repeated corner: cocircular, two triangles.
+        let square: Vec<Point<i32>> = [[0, 0], [4, 0], [4, 4], [0, 4], [4, 4]]
+            .iter()
+            .map(|p| Point::new(p.to_vec()))
+            .collect();
+        let tri = triangulate(&square).unwrap();
+        assert_eq!(tri.simplices.len(), 2);
+        let mut used: Vec<usize> = tri
+            .simplices
+            .iter()
+            .flat_map(|s| s.vertices.clone())
+            .collect();
+        used.sort_unstable();
+        used.dedup();
+        assert_eq!(used.len(), 4);
+        // A grid: cocircular cells and collinear boundary points.
+        let grid: Vec<Point<f64>> = (0..25)
+            .map(|i| Point::new(vec![(i % 5) as f64, (i / 5) as f64]))
+            .collect();
+        let tri = triangulate(&grid).unwrap();
+        assert_eq!(tri.simplices.len(), 32);
+        check(&grid, &tri);
+        let line: Vec<Point<f64>> = (0..4).map(|i| Point::new(vec![i as f64, 0.0])).collect();
+        assert!(triangulate(&line).is_none());
+    }
+
+    #[test]
+    fn triangulates_space() {
+        let mut rng = Rng::seed_from_u64(2771);
+        let pts = random_points(3, 120, &mut rng);
+        let tri = triangulate(&pts).unwrap();
+        check(&pts, &tri);
+        // The tetrahedra fill the hull.
+        let volume: f64 = tri
+            .simplices
+            .iter()
+            .map(|s| {
+                let pts: Vec<Vec<f64>> = pts.iter().map(|p| p.data().to_vec()).collect();
+                orientation(&pts, &s.vertices) / 6.0
+            })
+            .sum();
+        let hull = convex_hull(&pts).unwrap();
+        let hull_volume: f64 = hull
+            .facets
+            .iter()
+            .map(|f| f.plane.offset * facet_area(&pts, &f.vertices) / 3.0)
+            .sum();
+        assert!((volume - hull_volume).abs() < 1e-9 * hull_volume);
+    }
+
+    fn facet_area(pts: &[Point<f64>], v: &[usize]) -> f64 {
+        let [a, b, c] =
+            [0, 1, 2].map(|k| crate::Point3::new(pts[v[k]][0], pts[v[k]][1], pts[v[k]][2]));
+        (&b - &a).cross(&(&c - &a)).dist() / 2.0
+    }
+
+    #[test]
+    fn voronoi_cells() {
+        // A regular pentagon around its centre.
+        let mut pts = vec![Point::new(vec![0.0, 0.0])];
+        pts.extend((0..5).map(|k| {
+            let a = k as f64 * std::f64::consts::TAU / 5.0;
+            Point::new(vec![a.cos(), a.sin()])
+        }));
+        let v = voronoi(&pts).unwrap();
+        let centre = &v.cells[0];
+        assert!(centre.bounded);
+        assert_eq!(centre.vertices.len(), 5);
+        assert_eq!(centre.neighbors, vec![1, 2, 3, 4, 5]);
+        assert!(v.cells[1..].iter().all(|c| !c.bounded));
+        assert_eq!(v.cells[1].neighbors, vec![0, 2, 5]);
+        // Counter-clockwise, each vertex equidistant from its cell's point
+        // and that point's nearest others.
+        let angles: Vec<f64> = centre
+            .vertices
+            .iter()
+            .map(|&i| v.vertices[i][1].atan2(v.vertices[i][0]))
+            .collect();
+        assert!(angles.windows(2).all(|w| w[0] < w[1]));
+        for (p, cell) in v.cells.iter().enumerate() {
+            for &i in &cell.vertices {
+                let d = v.vertices[i].distance_to(&pts[p]);
+                assert!(pts.iter().all(|q| v.vertices[i].distance_to(q) >= d - 1e-9));
+            }
+        }
+    }
+}
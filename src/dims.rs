@@ -0,0 +1,385 @@
+// Fixed-dimension points.
+//
+// `Point2`, `Point3` and `Point4` guarantee their dimension at construction
+// and add conveniences that only make sense for that dimension. They deref to
+// `Point<T>`, so the whole generic API stays available.
+//
+// `PointN<T, N>` is instead a `Point` stored in a `[T; N]`: no heap
+// allocation, `Copy` when `T` is, and arithmetic between two of them stays
+// in an array. It converts to and from `Point<T>`.
+
+use std::ops::{Add, Deref, Mul, Neg, Sub};
+
+use crate::{ArrayStorage, Error, Point};
+
+macro_rules! fixed_point {
+    ($name:ident, $dim:literal, $($field:ident),+) => {
+        #[derive(Debug, Clone)]
+        pub struct $name<T>(Point<T>);
+
+        impl<T> $name<T>
+        where
+            T: Into<f64> + Copy,
+        {
+            pub fn new($($field: T),+) -> Self {
+                $name(Point::from([$($field),+]))
+            }
+
+            $(
+                pub fn $field(&self) -> T {
+                    self.0.p[fixed_point!(@index $field)]
+                }
+            )+
+
+            pub fn into_point(self) -> Point<T> {
+                self.0
+            }
+        }
+
+        impl<T> Deref for $name<T> {
+            type Target = Point<T>;
+
+            fn deref(&self) -> &Point<T> {
+                &self.0
+            }
+        }
+
+        impl<T> From<$name<T>> for Point<T> {
+            fn from(p: $name<T>) -> Self {
+                p.0
+            }
+        }
+
+        impl<T> TryFrom<Point<T>> for $name<T> {
+            type Error = Error;
+
+            fn try_from(p: Point<T>) -> Result<Self, Error> {
+                if p.p.len() == $dim {
+                    Ok($name(p))
+                } else {
+                    Err(Error::DimensionMismatch {
+                        expected: $dim,
+                        found: p.p.len(),
+                    })
+                }
+            }
+        }
+
+        impl<T> Add<&$name<T>> for &$name<T>
+        where
+            T: Add<Output = T> + Copy,
+        {
+            type Output = $name<T>;
+
+            fn add(self, other: &$name<T>) -> Self::Output {
+                $name(&self.0 + &other.0)
+            }
+        }
+
+        impl<T> Sub<&$name<T>> for &$name<T>
+        where
+            T: Sub<Output = T> + Copy,
+        {
+            type Output = $name<T>;
+
+            fn sub(self, other: &$name<T>) -> Self::Output {
+                $name(&self.0 - &other.0)
+            }
+        }
+
+        impl<T> Mul<T> for &$name<T>
+        where
+            T: Mul<Output = T> + Copy,
+        {
+            type Output = $name<T>;
+
+            fn mul(self, scalar: T) -> Self::Output {
+                $name(&self.0 * scalar)
+            }
+        }
+    };
+    (@index x) => { 0 };
+    (@index y) => { 1 };
+    (@index z) => { 2 };
+    (@index w) => { 3 };
+}
+
+fixed_point!(Point2, 2, x, y);
+fixed_point!(Point3, 3, x, y, z);
+fixed_point!(Point4, 4, x, y, z, w);
+
+impl<T> Point2<T>
+where
+    T: Into<f64> + Copy + Neg<Output = T>,
+{
+    /// The vector rotated a quarter turn counter-clockwise, `(-y, x)`.
+    pub fn perp(&self) -> Point2<T> {
+        Point2::new(-self.y(), self.x())
+    }
+}
+
+impl<T> Point2<T>
+where
+    T: Into<f64> + Copy,
+{
+    /// Angle from the positive x axis in radians, in `(-pi, pi]`.
+    pub fn angle(&self) -> f64 {
+        self.y().into().atan2(self.x().into())
+    }
+
+    /// Rotates counter-clockwise about the origin by `theta` radians.
+    pub fn rotate(&self, theta: f64) -> Point2<f64> {
+        let (x, y) = (self.x().into(), self.y().into());
+        let (sin, cos) = theta.sin_cos();
+        Point2::new(x * cos - y * sin, x * sin + y * cos)
+    }
+
+    /// The 2D cross product `x1 * y2 - y1 * x2`; positive when `other` is
+    /// counter-clockwise from `self`.
+    pub fn perp_dot(&self, other: &Point2<T>) -> f64 {
+        self.x().into() * other.y().into() - self.y().into() * other.x().into()
+    }
+}
+
+impl Point2<f64> {
+    pub fn from_angle(theta: f64, radius: f64) -> Self {
+        let (sin, cos) = theta.sin_cos();
+        Point2::new(radius * cos, radius * sin)
+    }
+}
+
+impl<T> Point3<T>
+where
+    T: Into<f64> + Copy + Mul<Output = T> + Sub<Output = T>,
+{
+    pub fn cross(&self, other: &Point3<T>) -> Point3<T> {
+        let (a, b) = (&self.0.p, &other.0.p);
+        Point3::new(
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        )
+    }
+}
+
+/// A point with its dimension in the type, stored inline.
+pub type PointN<T, const N: usize> = Point<T, ArrayStorage<T, N>>;
+
+impl<T, const N: usize> Point<T, ArrayStorage<T, N>> {
+    pub fn to_array(self) -> [T; N] {
+        self.into_storage().0
+    }
+}
+
+impl<T: Copy, const N: usize> From<PointN<T, N>> for Point<T> {
+    fn from(p: PointN<T, N>) -> Self {
+        p.to_point()
+    }
+}
+
+impl<T: Copy, const N: usize> TryFrom<&Point<T>> for PointN<T, N> {
+    type Error = Error;
+
+    fn try_from(p: &Point<T>) -> Result<Self, Error> {
+        let coords: [T; N] = p.p[..].try_into().map_err(|_| Error::DimensionMismatch {
+            expected: N,
+            found: p.p.len(),
+        })?;
+        Ok(PointN::from_array(coords))
+    }
+}
+
+impl<T: Copy, const N: usize> TryFrom<Point<T>> for PointN<T, N> {
+    type Error = Error;
+
+    fn try_from(p: Point<T>) -> Result<Self, Error> {
+        PointN::try_from(&p)
+    }
+}
+
+/// What `resize_dim` does when a point has the wrong number of coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizePolicy {
+    /// Pad with zeros (`T::default()`), truncate extra coordinates.
+    ZeroPad,
+    /// Pad by repeating the last coordinate, truncate extra coordinates.
+    Repeat,
+    /// Fail with `Error::DimensionMismatch` unless the dimension already
+    /// matches.
+    Error,
+}
+
+// Appends `coords` resized to `n` to `out`.
+pub(crate) fn resize_into<T: Copy + Default>(
+    coords: &[T],
+    n: usize,
+    policy: ResizePolicy,
+    out: &mut Vec<T>,
+) -> Result<(), Error> {
+    if n == 0 {
+        return Err(Error::ZeroDimension);
+    }
+    let fill = match policy {
+        ResizePolicy::Error if coords.len() != n => {
+            return Err(Error::DimensionMismatch {
+                expected: n,
+                found: coords.len(),
+            });
+        }
+        ResizePolicy::Repeat => coords.last().copied().unwrap_or_default(),
+        _ => T::default(),
+    };
+    let keep = coords.len().min(n);
+    out.extend_from_slice(&coords[..keep]);
+    out.extend(std::iter::repeat_n(fill, n - keep));
+    Ok(())
+}
+
+impl<T> Point<T>
+where
+    T: Into<f64> + Copy + Default,
+{
+    /// A copy of the point with exactly `n` coordinates, padded or truncated
+    /// as `policy` says.
+    pub fn resize_dim(&self, n: usize, policy: ResizePolicy) -> Result<Point<T>, Error> {
+        let mut out = Vec::with_capacity(n);
+        resize_into(&self.p, n, policy, &mut out)?;
+        Ok(Point::new(out))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn named_fields() {
+        let p = Point2::new(1, 2);
+        assert_eq!((p.x(), p.y()), (1, 2));
+        let q = Point4::new(1.0, 2.0, 3.0, 4.0);
+        assert_eq!(q.w(), 4.0);
+        assert_eq!(q.dim(), 4);
+        assert_eq!(q.into_point().data(), &[1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn perp() {
+        let p = Point2::new(3, 1).perp();
+        assert_eq!(p.data(), &[-1, 3]);
+    }
+
+    #[test]
+    fn angles() {
+        use std::f64::consts::FRAC_PI_2;
+
+        assert_eq!(Point2::new(0, 2).angle(), FRAC_PI_2);
+        let r = Point2::new(1.0, 0.0).rotate(FRAC_PI_2);
+        assert!(r.x().abs() < 1e-12 && (r.y() - 1.0).abs() < 1e-12);
+        assert_eq!(Point2::new(1, 0).perp_dot(&Point2::new(0, 1)), 1.0);
+        assert_eq!(Point2::new(0, 1).perp_dot(&Point2::new(1, 0)), -1.0);
+
+        let p = Point2::from_angle(FRAC_PI_2, 3.0);
+        assert!(p.x().abs() < 1e-12 && (p.y() - 3.0).abs() < 1e-12);
+        assert!((p.angle() - FRAC_PI_2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn cross() {
+        let x = Point3::new(1, 0, 0);
+        let y = Point3::new(0, 1, 0);
+        assert_eq!(x.cross(&y).data(), &[0, 0, 1]);
+        assert_eq!(y.cross(&x).data(), &[0, 0, -1]);
+    }
+
+    #[test]
+    fn conversions() {
+        let p: Point3<i32> = Point::new(vec![1, 2, 3]).try_into().unwrap();
+        assert_eq!(p.z(), 3);
+        let err = Point2::try_from(Point::new(vec![1, 2, 3])).unwrap_err();
+        assert_eq!(
+            err,
+            Error::DimensionMismatch {
+                expected: 2,
+                found: 3
+            }
+        );
+        let back: Point<i32> = p.into();
+        assert_eq!(back.dim(), 3);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = Point2::new(1, 2);
+        let b = Point2::new(3, 4);
+        assert_eq!((&a + &b).x(), 4);
+        assert_eq!((&b - &a).y(), 2);
+        assert_eq!((&a * 3).y(), 6);
+    }
+
+    #[test]
+    fn resize() {
+        let p = Point::new(vec![1, 2, 3]);
+        assert_eq!(
+            p.resize_dim(5, ResizePolicy::ZeroPad).unwrap().data(),
+            &[1, 2, 3, 0, 0]
+        );
+        assert_eq!(
+            p.resize_dim(4, ResizePolicy::Repeat).unwrap().data(),
+            &[1, 2, 3, 3]
+        );
+        assert_eq!(
+            p.resize_dim(2, ResizePolicy::Repeat).unwrap().data(),
+            &[1, 2]
+        );
+        assert_eq!(
+            p.resize_dim(3, ResizePolicy::Error).unwrap().data(),
+            &[1, 2, 3]
+        );
+        assert_eq!(
+            p.resize_dim(2, ResizePolicy::Error).unwrap_err(),
+            Error::DimensionMismatch {
+                expected: 2,
+                found: 3
+            }
+        );
+        assert_eq!(
+            p.resize_dim(0, ResizePolicy::ZeroPad).unwrap_err(),
+            Error::ZeroDimension
+        );
+    }
+
+    #[test]
+    fn point_n() {
+        let a = PointN::from_array([1.0, 2.0, 2.0]);
+        let b = PointN::from_array([0.5, 0.5, 0.5]);
+        assert_eq!(a.dim(), 3);
+        assert_eq!(a.dist(), 3.0);
+        assert_eq!((a + b).to_array(), [1.5, 2.5, 2.5]);
+        assert_eq!((a - b).to_array(), [0.5, 1.5, 1.5]);
+        assert_eq!((a * b)[1], 1.0);
+        assert_eq!((a * 2.0 / 4.0 + 1.0).to_array(), [1.5, 2.0, 2.0]);
+        assert_eq!((-a)[0], -1.0);
+        let mut c = a;
+        c += b;
+        c *= 2.0;
+        c -= PointN::from_array([1.0, 1.0, 1.0]);
+        c /= 2.0;
+        assert_eq!(c.to_array(), [1.0, 2.0, 2.0]);
+
+        // Mixed with other storage, arrays on the left stay arrays.
+        let v = Point::new(vec![1.0, 1.0, 1.0]);
+        assert_eq!((&a - &v).to_array(), [0.0, 1.0, 1.0]);
+        assert_eq!((&v + &a).into_vec(), vec![2.0, 3.0, 3.0]);
+
+        let p: Point<f64> = a.into();
+        assert_eq!(p.data(), &[1.0, 2.0, 2.0]);
+        assert_eq!(PointN::<f64, 3>::try_from(&p), Ok(a));
+        assert_eq!(
+            PointN::<f64, 2>::try_from(p),
+            Err(Error::DimensionMismatch {
+                expected: 2,
+                found: 3
+            })
+        );
+    }
+}
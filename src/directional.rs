@@ -0,0 +1,191 @@
+// Statistics of angles and directions.
+//
+// Angles are in radians and directions are points read as vectors, of any
+// length and dimension; each is normalised before use. Everything rests on
+// the mean resultant: the average of the unit vectors, whose direction is
+// the mean direction and whose length `R` in [0, 1] measures concentration,
+// 1 when all directions agree. The concentration `kappa` of a von Mises
+// (circle) or von Mises-Fisher (sphere) distribution is then estimated from
+// `R` in closed form: Fisher's approximation on the circle, Banerjee et
+// al.'s in higher dimensions. Neither is exact, but both are close enough
+// to seed a numerical fit.
+
+use std::f64::consts::TAU;
+
+use crate::{Point, Storage};
+
+// Resultant lengths at or below this are rounding error around zero.
+const CANCELLED: f64 = 1e-12;
+
+// Mean of the unit vectors of `angles`: (cos, sin), or `None` when empty.
+fn resultant(angles: &[f64]) -> Option<(f64, f64)> {
+    if angles.is_empty() {
+        return None;
+    }
+    let n = angles.len() as f64;
+    let (c, s) = angles
+        .iter()
+        .fold((0.0, 0.0), |(c, s), a| (c + a.cos(), s + a.sin()));
+    Some((c / n, s / n))
+}
+
+/// Length `R` of the mean resultant of `angles`, in [0, 1]; `None` if
+/// there are none.
+pub fn mean_resultant_length(angles: &[f64]) -> Option<f64> {
+    resultant(angles).map(|(c, s)| c.hypot(s))
+}
+
+/// The mean angle in `[0, 2pi)`, so 350° and 10° average to 0°, not 180°.
+/// `None` if there are no angles or they cancel out, up to rounding.
+pub fn circular_mean(angles: &[f64]) -> Option<f64> {
+    let (c, s) = resultant(angles)?;
+    (c.hypot(s) > CANCELLED).then(|| s.atan2(c).rem_euclid(TAU))
+}
+
+/// `1 - R`, from 0 for identical angles to 1 for ones that cancel out.
+pub fn circular_variance(angles: &[f64]) -> Option<f64> {
+    mean_resultant_length(angles).map(|r| 1.0 - r)
+}
+
+/// `sqrt(-2 ln R)`, the circular analogue of the standard deviation.
+pub fn circular_std(angles: &[f64]) -> Option<f64> {
+    mean_resultant_length(angles).map(|r| (-2.0 * r.ln()).sqrt())
+}
+
+/// Mean direction of a set of vectors, as a unit vector, and the mean
+/// resultant length; `None` if there are none, they cancel out, or one is
+/// zero. Panics if the dimensions differ.
+pub fn mean_direction<T, S>(directions: &[Point<T, S>]) -> Option<(Point<f64>, f64)>
+where
+    T: Into<f64> + Copy,
+    S: Storage<T>,
+{
+    let dim = directions.first()?.dim();
+    let mut sum = vec![0.0; dim];
+    for d in directions {
+        assert_eq!(d.dim(), dim, "points must share a dimension");
+        let u = d.try_normalize().ok()?;
+        sum.iter_mut().zip(u.iter()).for_each(|(s, v)| *s += v);
+    }
+    let sum = Point::new(sum);
+    let r = sum.dist() / directions.len() as f64;
+    if r <= CANCELLED {
+        return None;
+    }
+    Some((sum.try_normalize().ok()?, r))
+}
+
+/// A von Mises distribution on the circle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VonMises {
+    /// Mean angle in `[0, 2pi)`.
+    pub mu: f64,
+    /// Concentration; 0 is uniform, infinite when every sample agrees.
+    pub kappa: f64,
+}
+
+/// Fits a von Mises distribution to `angles`; `None` if there are none or
+/// they cancel out.
+pub fn fit_von_mises(angles: &[f64]) -> Option<VonMises> {
+    let mu = circular_mean(angles)?;
+    let r = mean_resultant_length(angles)?;
+    // Fisher (1993), inverting A1(kappa) = I1(kappa) / I0(kappa) = R.
+    let kappa = if r >= 1.0 {
+        f64::INFINITY
+    } else if r < 0.53 {
+        2.0 * r + r.powi(3) + 5.0 * r.powi(5) / 6.0
+    } else if r < 0.85 {
+        -0.4 + 1.39 * r + 0.43 / (1.0 - r)
+    } else {
+        1.0 / (r.powi(3) - 4.0 * r * r + 3.0 * r)
+    };
+    Some(VonMises { mu, kappa })
+}
+
+/// A von Mises-Fisher distribution on the unit sphere of any dimension.
+#[derive(Debug, Clone)]
+pub struct VonMisesFisher {
+    /// Unit mean direction.
+    pub mean: Point<f64>,
+    /// Concentration; 0 is uniform, infinite when every sample agrees.
+    pub kappa: f64,
+}
+
+/// Fits a von Mises-Fisher distribution to `directions`; `None` as for
+/// [`mean_direction`].
+pub fn fit_von_mises_fisher<T, S>(directions: &[Point<T, S>]) -> Option<VonMisesFisher>
+where
+    T: Into<f64> + Copy,
+    S: Storage<T>,
+{
+    let (mean, r) = mean_direction(directions)?;
+    let p = mean.dim() as f64;
+    // Banerjee et al. (2005).
+    let kappa = if r >= 1.0 {
+        f64::INFINITY
+    } else {
+        r * (p - r * r) / (1.0 - r * r)
+    };
+    Some(VonMisesFisher { mean, kappa })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tol: f64) -> bool {
+        (a - b).abs() < tol
+    }
+
+    #[test]
+    fn circular_summaries() {
+        let angles = [350f64.to_radians(), 10f64.to_radians()];
+        let mean = circular_mean(&angles).unwrap();
+        assert!(close(mean, 0.0, 1e-12) || close(mean, TAU, 1e-12));
+        let r = 10f64.to_radians().cos();
+        assert!(close(mean_resultant_length(&angles).unwrap(), r, 1e-12));
+        assert!(close(circular_variance(&angles).unwrap(), 1.0 - r, 1e-12));
+        assert!(close(circular_std(&[1.0, 1.0]).unwrap(), 0.0, 1e-7));
+        assert_eq!(circular_mean(&[0.0, std::f64::consts::PI]), None);
+        assert_eq!(circular_mean(&[]), None);
+        let m = circular_mean(&[3.0, 3.2]).unwrap();
+        assert!(close(m, 3.1, 1e-12));
+    }
+
+    #[test]
+    fn von_mises_fits() {
+        let spread = [-0.5, 0.5];
+        let fit = fit_von_mises(&spread).unwrap();
+        assert!(close(fit.mu, 0.0, 1e-12));
+        // R = cos 0.5 = 0.878, in the third branch.
+        let r = 0.5f64.cos();
+        assert!(close(
+            fit.kappa,
+            1.0 / (r.powi(3) - 4.0 * r * r + 3.0 * r),
+            1e-12
+        ));
+        let wide = fit_von_mises(&[-1.2, 1.2]).unwrap();
+        assert!(wide.kappa < fit.kappa);
+        assert_eq!(fit_von_mises(&[2.0, 2.0]).unwrap().kappa, f64::INFINITY);
+    }
+
+    #[test]
+    fn directions_on_spheres() {
+        let dirs = [
+            Point::new(vec![1.0, 0.1, 0.0]),
+            Point::new(vec![5.0, -0.5, 0.0]),
+            Point::new(vec![2.0, 0.0, 0.2]),
+            Point::new(vec![3.0, 0.0, -0.3]),
+        ];
+        let (mean, r) = mean_direction(&dirs).unwrap();
+        assert!(close(mean[0], 1.0, 1e-12) && close(mean.dist(), 1.0, 1e-12));
+        assert!(r < 1.0 && r > 0.99);
+        let fit = fit_von_mises_fisher(&dirs).unwrap();
+        assert!(close(fit.kappa, r * (3.0 - r * r) / (1.0 - r * r), 1e-9));
+        assert!(fit.kappa > 100.0);
+
+        let opposite = [Point::new(vec![0, 1]), Point::new(vec![0, -1])];
+        assert!(mean_direction(&opposite).is_none());
+        assert!(mean_direction::<f64, _>(&[Point::new(vec![0.0, 0.0])]).is_none());
+    }
+}
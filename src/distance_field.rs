@@ -0,0 +1,266 @@
+// Chamfer distance transforms of rasterised point sets.
+//
+// The points are dropped into a regular grid of cubic cells, and every cell
+// gets the distance to the nearest occupied cell by two raster sweeps
+// (Rosenfeld and Pfaltz; Borgefors): the forward sweep pulls distances from
+// the neighbours already visited, the backward sweep from the rest. A step
+// to a neighbour costs the distance between the cell centres, so one, two or
+// three changed coordinates cost 1, sqrt 2 or sqrt 3 cells. Chaining those
+// steps overestimates the straight-line distance a little: by at most
+// about 8% in 2D and a few percent more in 3D. Building the field is
+// linear in the number of cells, after which every proximity query against
+// the static point set is a single lookup.
+//
+// Cells are stored row-major with the last axis varying fastest, like the
+// planner's occupancy grid.
+
+use crate::aabb::Aabb;
+use crate::{Point, Storage};
+
+#[derive(Debug, Clone)]
+pub struct DistanceField {
+    lower: Vec<f64>,
+    cell_size: f64,
+    shape: Vec<usize>,
+    distances: Vec<f64>,
+}
+
+impl DistanceField {
+    /// The distance field of `points` over `bounds`, in cells of side
+    /// `cell_size`. Points outside the box are ignored; with none inside,
+    /// every distance is infinite. Panics unless `cell_size` is positive and
+    /// the points match the box's dimension.
+    pub fn new<T, P>(
+        points: impl IntoIterator<Item = P>,
+        bounds: &Aabb<f64>,
+        cell_size: f64,
+    ) -> Self
+    where
+        T: Into<f64> + Copy,
+        P: AsRef<[T]>,
+    {
+        assert!(cell_size > 0.0, "cell size must be positive");
+        let lower = bounds.lower().to_vec();
+        let shape: Vec<usize> = lower
+            .iter()
+            .zip(bounds.upper())
+            .map(|(lo, hi)| ((hi - lo) / cell_size).floor() as usize + 1)
+            .collect();
+        let mut field = DistanceField {
+            distances: vec![f64::INFINITY; shape.iter().product()],
+            lower,
+            cell_size,
+            shape,
+        };
+        for p in points {
+            let p: Vec<f64> = p.as_ref().iter().map(|&v| v.into()).collect();
+            assert_eq!(p.len(), field.dim(), "point and box dimensions differ");
+            if let Some(i) = field.locate(&p) {
+                field.distances[i] = 0.0;
+            }
+        }
+        field.sweep();
+        field
+    }
+
+    /// The distance field over the bounding box of `points` grown by
+    /// `margin` on every side; `None` if there are no points. Panics as for
+    /// [`DistanceField::new`].
+    pub fn around<T, P>(
+        points: impl IntoIterator<Item = P>,
+        cell_size: f64,
+        margin: f64,
+    ) -> Option<Self>
+    where
+        T: Into<f64> + Copy,
+        P: AsRef<[T]>,
+    {
+        let pts: Vec<Point<f64>> = points
+            .into_iter()
+            .map(|p| Point::new(p.as_ref().iter().map(|&v| v.into()).collect()))
+            .collect();
+        let mut bounds = Aabb::from_points(&pts)?;
+        let grown_lower: Vec<f64> = bounds.lower().iter().map(|v| v - margin).collect();
+        let grown_upper: Vec<f64> = bounds.upper().iter().map(|v| v + margin).collect();
+        bounds.expand(&Point::new(grown_lower));
+        bounds.expand(&Point::new(grown_upper));
+        Some(DistanceField::new(&pts, &bounds, cell_size))
+    }
+
+    pub fn dim(&self) -> usize {
+        self.shape.len()
+    }
+
+    /// The number of cells along each axis.
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    /// Every cell's distance, row-major.
+    pub fn distances(&self) -> &[f64] {
+        &self.distances
+    }
+
+    /// The distance stored for `cell`; `None` outside the grid.
+    pub fn get(&self, cell: &[usize]) -> Option<f64> {
+        self.index(cell).map(|i| self.distances[i])
+    }
+
+    /// The distance from `p` to the nearest point, as stored for the cell
+    /// containing it; `None` outside the grid. Panics if the dimensions
+    /// differ.
+    pub fn distance<T, S>(&self, p: &Point<T, S>) -> Option<f64>
+    where
+        T: Into<f64> + Copy,
+        S: Storage<T>,
+    {
+        let p: Vec<f64> = p.as_slice().iter().map(|&v| v.into()).collect();
+        assert_eq!(p.len(), self.dim(), "point and field dimensions differ");
+        self.locate(&p).map(|i| self.distances[i])
+    }
+
+    // The index of the cell containing `p`, if any.
+    fn locate(&self, p: &[f64]) -> Option<usize> {
+        let cell: Option<Vec<usize>> = p
+            .iter()
+            .zip(&self.lower)
+            .map(|(v, lo)| {
+                let c = ((v - lo) / self.cell_size).floor();
+                (c >= 0.0).then_some(c as usize)
+            })
+            .collect();
+        self.index(&cell?)
+    }
+
+    fn index(&self, cell: &[usize]) -> Option<usize> {
+        if cell.len() != self.shape.len() {
+            return None;
+        }
+        let mut i = 0;
+        for (&c, &s) in cell.iter().zip(&self.shape) {
+            if c >= s {
+                return None;
+            }
+            i = i * s + c;
+        }
+        Some(i)
+    }
+
+    // The two chamfer passes.
+    fn sweep(&mut self) {
+        let dim = self.dim();
+        // Offsets in {-1, 0, 1}^dim whose first non-zero entry is -1: the
+        // neighbours a forward raster has already visited. Their negations
+        // serve the backward pass.
+        let mut backward_offsets = Vec::new();
+        for code in 0..3usize.pow(dim as u32) {
+            let mut offset = Vec::with_capacity(dim);
+            let mut c = code;
+            for _ in 0..dim {
+                offset.push(c as isize % 3 - 1);
+                c /= 3;
+            }
+            offset.reverse();
+            if offset.iter().find(|&&o| o != 0) == Some(&-1) {
+                let moved = offset.iter().filter(|&&o| o != 0).count();
+                backward_offsets.push((offset, (moved as f64).sqrt() * self.cell_size));
+            }
+        }
+        let n = self.distances.len();
+        let mut cell = vec![0usize; dim];
+        for sign in [1isize, -1] {
+            let order: Box<dyn Iterator<Item = usize>> = if sign == 1 {
+                Box::new(0..n)
+            } else {
+                Box::new((0..n).rev())
+            };
+            for i in order {
+                self.unflatten(i, &mut cell);
+                let mut best = self.distances[i];
+                for (offset, cost) in &backward_offsets {
+                    let neighbour: Option<Vec<usize>> = cell
+                        .iter()
+                        .zip(offset)
+                        .map(|(&c, &o)| c.checked_add_signed(sign * o))
+                        .collect();
+                    if let Some(j) = neighbour.and_then(|nb| self.index(&nb)) {
+                        best = best.min(self.distances[j] + cost);
+                    }
+                }
+                self.distances[i] = best;
+            }
+        }
+    }
+
+    fn unflatten(&self, mut index: usize, cell: &mut [usize]) {
+        for (c, &s) in cell.iter_mut().zip(&self.shape).rev() {
+            *c = index % s;
+            index /= s;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    #[test]
+    fn distances_from_one_point() {
+        let bounds = Aabb::new(&[0.0, 0.0], &[10.0, 10.0]).unwrap();
+        let field = DistanceField::new([[5.5, 5.5]], &bounds, 1.0);
+        assert_eq!(field.shape(), [11, 11]);
+        assert_eq!(field.get(&[5, 5]), Some(0.0));
+        assert_eq!(field.get(&[5, 9]), Some(4.0));
+        assert!((field.get(&[2, 2]).unwrap() - 3.0 * 2f64.sqrt()).abs() < 1e-12);
+        // A knight's move costs one straight and one diagonal step.
+        assert!((field.get(&[6, 7]).unwrap() - (1.0 + 2f64.sqrt())).abs() < 1e-12);
+        assert_eq!(field.distance(&Point::new(vec![9.9, 5.2])), Some(4.0));
+        assert_eq!(field.distance(&Point::new(vec![-0.1, 5.0])), None);
+        assert_eq!(field.get(&[11, 0]), None);
+
+        let empty = DistanceField::new(Vec::<[f64; 2]>::new(), &bounds, 2.0);
+        assert!(empty.distances().iter().all(|d| d.is_infinite()));
+        assert!(std::panic::catch_unwind(|| DistanceField::new([[0.0]], &bounds, 1.0)).is_err());
+    }
+
+    #[test]
+    fn close_to_euclidean() {
+        let mut rng = Rng::seed_from_u64(281);
+        for (dim, side, tolerance) in [(2, 40usize, 0.083), (3, 16, 0.14)] {
+            let pts: Vec<Vec<f64>> = (0..6)
+                .map(|_| (0..dim).map(|_| rng.next_f64() * side as f64).collect())
+                .collect();
+            let bounds = Aabb::new(&vec![0.0; dim], &vec![side as f64 - 0.5; dim]).unwrap();
+            let field = DistanceField::new(&pts, &bounds, 1.0);
+            // Exact distances between cell centres.
+            let centre = |p: &[f64]| -> Vec<f64> { p.iter().map(|v| v.floor() + 0.5).collect() };
+            let seeds: Vec<Vec<f64>> = pts.iter().map(|p| centre(p)).collect();
+            let mut cell = vec![0; dim];
+            for i in 0..field.distances().len() {
+                field.unflatten(i, &mut cell);
+                let c: Vec<f64> = cell.iter().map(|&v| v as f64 + 0.5).collect();
+                let exact = seeds
+                    .iter()
+                    .map(|s| {
+                        s.iter()
+                            .zip(&c)
+                            .map(|(a, b)| (a - b) * (a - b))
+                            .sum::<f64>()
+                            .sqrt()
+                    })
+                    .fold(f64::INFINITY, f64::min);
+                let d = field.distances()[i];
+                assert!(d >= exact - 1e-9 && d <= exact * (1.0 + tolerance) + 1e-9);
+            }
+        }
+        let around = DistanceField::around([[1.0, 1.0, 1.0], [3.0, 1.0, 1.0]], 0.5, 1.0).unwrap();
+        assert_eq!(around.shape(), [9, 5, 5]);
+        assert_eq!(around.distance(&Point::new(vec![2.1, 1.1, 1.1])), Some(1.0));
+        assert!(DistanceField::around(Vec::<[f64; 2]>::new(), 1.0, 0.0).is_none());
+    }
+}
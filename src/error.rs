@@ -0,0 +1,40 @@
+use std::fmt;
+
+/// Errors reported by the fallible constructors and operations in this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// An operation needed a point of a different dimension.
+    DimensionMismatch { expected: usize, found: usize },
+    /// Points must have at least one coordinate.
+    ZeroDimension,
+    /// A flat buffer's length isn't a multiple of the point dimension.
+    RaggedBuffer { len: usize, dim: usize },
+    /// A strided layout with a zero stride.
+    ZeroStride,
+    /// A zero vector has no direction to normalise to.
+    ZeroLength,
+    /// An index failed its self-check; the message names the broken invariant.
+    InvalidIndex(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DimensionMismatch { expected, found } => {
+                write!(f, "expected dimension {expected}, found {found}")
+            }
+            Error::ZeroDimension => write!(f, "dimension must be at least 1"),
+            Error::RaggedBuffer { len, dim } => {
+                write!(
+                    f,
+                    "buffer of length {len} is not a multiple of dimension {dim}"
+                )
+            }
+            Error::ZeroStride => write!(f, "stride must be at least 1"),
+            Error::ZeroLength => write!(f, "a zero-length point has no direction"),
+            Error::InvalidIndex(what) => write!(f, "invalid index: {what}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
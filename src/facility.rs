@@ -0,0 +1,263 @@
+// Facility location: choosing k of the input points as centers.
+//
+// `k_center` minimises the largest distance from a point to its center (the
+// greedy farthest-point algorithm, a 2-approximation); `k_median` minimises
+// the sum of those distances by single-swap local search, a
+// 5-approximation. Both take the metric as a closure.
+
+use crate::Point;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Facilities {
+    /// Indices of the chosen centers in the input.
+    pub centers: Vec<usize>,
+    /// For every point, the position in `centers` of its closest center.
+    pub assignment: Vec<usize>,
+    /// For every point, the distance to its closest center.
+    pub costs: Vec<f64>,
+    /// The value being minimised: the largest cost for k-center, the sum for
+    /// k-median.
+    pub objective: f64,
+}
+
+fn distance_matrix<T, M>(points: &[Point<T>], metric: &M) -> Vec<Vec<f64>>
+where
+    M: Fn(&Point<T>, &Point<T>) -> f64,
+{
+    let n = points.len();
+    let mut d = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in i + 1..n {
+            let v = metric(&points[i], &points[j]);
+            d[i][j] = v;
+            d[j][i] = v;
+        }
+    }
+    d
+}
+
+fn assign(d: &[Vec<f64>], centers: &[usize]) -> (Vec<usize>, Vec<f64>) {
+    (0..d.len())
+        .map(|i| {
+            centers
+                .iter()
+                .enumerate()
+                .map(|(c, &j)| (c, d[i][j]))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap()
+        })
+        .unzip()
+}
+
+fn greedy_centers(d: &[Vec<f64>], k: usize, first: usize) -> Vec<usize> {
+    let n = d.len();
+    let mut centers = vec![first];
+    let mut nearest: Vec<f64> = d[first].clone();
+    while centers.len() < k.min(n) {
+        let far = (0..n)
+            .max_by(|&a, &b| nearest[a].total_cmp(&nearest[b]))
+            .unwrap();
+        if nearest[far] == 0.0 {
+            break;
+        }
+        centers.push(far);
+        for i in 0..n {
+            nearest[i] = nearest[i].min(d[far][i]);
+        }
+    }
+    centers
+}
+
+/// Greedy k-center starting from point `first`.
+///
+/// Stops early if every point already coincides with a center. Panics if
+/// `points` is empty or `k == 0`.
+pub fn k_center<T, M>(points: &[Point<T>], k: usize, first: usize, metric: M) -> Facilities
+where
+    M: Fn(&Point<T>, &Point<T>) -> f64,
+{
+    assert!(
+        !points.is_empty() && k > 0,
+        "k_center needs points and k > 0"
+    );
+    let d = distance_matrix(points, &metric);
+    let centers = greedy_centers(&d, k, first);
+    let (assignment, costs) = assign(&d, &centers);
+    let objective = costs.iter().copied().fold(0.0, f64::max);
+    Facilities {
+        centers,
+        assignment,
+        costs,
+        objective,
+    }
+}
+
+/// k-median by local search, seeded with the greedy k-center solution.
+///
+/// Each round applies the best single swap of a center for a non-center until
+/// none improves the total cost or `max_rounds` is reached. Panics if
+/// `points` is empty or `k == 0`.
+pub fn k_median<T, M>(points: &[Point<T>], k: usize, max_rounds: usize, metric: M) -> Facilities
+where
+    M: Fn(&Point<T>, &Point<T>) -> f64,
+{
+    k_median_with(points, None, k, max_rounds, metric)
+}
+
+/// [`k_median`] where point `i` counts `weights[i]` times in the objective,
+/// so heavy points pull centers towards them. Panics if there isn't one
+/// weight per point or a weight is negative.
+pub fn weighted_k_median<T, M>(
+    points: &[Point<T>],
+    weights: &[f64],
+    k: usize,
+    max_rounds: usize,
+    metric: M,
+) -> Facilities
+where
+    M: Fn(&Point<T>, &Point<T>) -> f64,
+{
+    assert_eq!(weights.len(), points.len(), "one weight per point");
+    assert!(
+        weights.iter().all(|&w| w >= 0.0),
+        "weights must be non-negative"
+    );
+    k_median_with(points, Some(weights), k, max_rounds, metric)
+}
+
+fn k_median_with<T, M>(
+    points: &[Point<T>],
+    weights: Option<&[f64]>,
+    k: usize,
+    max_rounds: usize,
+    metric: M,
+) -> Facilities
+where
+    M: Fn(&Point<T>, &Point<T>) -> f64,
+{
+    assert!(
+        !points.is_empty() && k > 0,
+        "k_median needs points and k > 0"
+    );
+    let d = distance_matrix(points, &metric);
+    let n = points.len();
+    let mut centers = greedy_centers(&d, k, 0);
+    let total = |centers: &[usize]| -> f64 {
+        (0..n)
+            .map(|i| {
+                let w = weights.map_or(1.0, |w| w[i]);
+                w * centers
+                    .iter()
+                    .map(|&c| d[i][c])
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .sum()
+    };
+    let mut cost = total(&centers);
+
+    for _ in 0..max_rounds {
+        let mut best: Option<(usize, usize, f64)> = None;
+        for slot in 0..centers.len() {
+            for candidate in 0..n {
+                if centers.contains(&candidate) {
+                    continue;
+                }
+                let mut trial = centers.clone();
+                trial[slot] = candidate;
+                let c = total(&trial);
+                if c < best.map_or(cost, |b| b.2) - 1e-12 {
+                    best = Some((slot, candidate, c));
+                }
+            }
+        }
+        match best {
+            Some((slot, candidate, c)) => {
+                centers[slot] = candidate;
+                cost = c;
+            }
+            None => break,
+        }
+    }
+
+    let (assignment, costs) = assign(&d, &centers);
+    Facilities {
+        centers,
+        assignment,
+        costs,
+        objective: cost,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn euclid(a: &Point<f64>, b: &Point<f64>) -> f64 {
+        (a - b).dist()
+    }
+
+    fn three_groups() -> Vec<Point<f64>> {
+        let mut pts = Vec::new();
+        for &(cx, cy) in &[(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)] {
+            for &(dx, dy) in &[(0.0, 0.0), (0.5, 0.0), (0.0, 0.5), (-0.5, 0.0), (0.0, -0.5)] {
+                pts.push(Point::new(vec![cx + dx, cy + dy]));
+            }
+        }
+        pts
+    }
+
+    #[test]
+    fn k_center_covers_groups() {
+        let pts = three_groups();
+        let sol = k_center(&pts, 3, 0, euclid);
+        assert_eq!(sol.centers.len(), 3);
+        assert!(sol.objective <= 1.0);
+        // Every group got its own center.
+        let mut groups: Vec<usize> = sol.centers.iter().map(|&c| c / 5).collect();
+        groups.sort();
+        assert_eq!(groups, vec![0, 1, 2]);
+        assert_eq!(sol.costs[0], 0.0);
+    }
+
+    #[test]
+    fn k_center_stops_on_duplicates() {
+        let pts = vec![Point::new(vec![1.0]); 4];
+        let sol = k_center(&pts, 3, 0, euclid);
+        assert_eq!(sol.centers, vec![0]);
+        assert_eq!(sol.objective, 0.0);
+    }
+
+    #[test]
+    fn k_median_picks_group_middles() {
+        let pts = three_groups();
+        let sol = k_median(&pts, 3, 100, euclid);
+        let mut centers = sol.centers.clone();
+        centers.sort();
+        // The first point of each group sits in its middle.
+        assert_eq!(centers, vec![0, 5, 10]);
+        assert!((sol.objective - 6.0).abs() < 1e-12);
+        assert_eq!(sol.assignment[7], sol.assignment[5]);
+    }
+
+    #[test]
+    fn k_median_one_center() {
+        let pts: Vec<Point<f64>> = [0.0, 1.0, 2.0, 3.0, 100.0]
+            .iter()
+            .map(|&x| Point::new(vec![x]))
+            .collect();
+        let sol = k_median(&pts, 1, 100, euclid);
+        // The 1-median of a line is the median point, unlike the mean.
+        assert_eq!(sol.centers, vec![2]);
+    }
+
+    #[test]
+    fn weighted_k_median_follows_mass() {
+        let pts: Vec<Point<f64>> = [0.0, 1.0, 2.0, 3.0, 100.0]
+            .iter()
+            .map(|&x| Point::new(vec![x]))
+            .collect();
+        let sol = weighted_k_median(&pts, &[1.0, 1.0, 1.0, 1.0, 10.0], 1, 100, euclid);
+        assert_eq!(sol.centers, vec![4]);
+        assert!((sol.objective - (100.0 + 99.0 + 98.0 + 97.0)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,212 @@
+// View frustums, and any other convex region bounded by a few hyperplanes,
+// for culling.
+//
+// A `Frustum` keeps its half-spaces with unit normals, so each test is one
+// dot product per plane. `intersects` tests are conservative, as usual for
+// culling: they never reject an object that overlaps the frustum, but a box
+// or ball just beyond a corner, outside two planes' intersection yet inside
+// each plane, can still pass.
+
+use crate::polytope::HalfSpace;
+use crate::{Error, Point, PointCloud, Storage};
+
+#[derive(Debug, Clone)]
+pub struct Frustum {
+    dim: usize,
+    // Unit normal of plane `i` at `i * dim`; inside is `n . x <= offsets[i]`.
+    normals: Vec<f64>,
+    offsets: Vec<f64>,
+}
+
+impl Frustum {
+    /// The intersection of `planes`, in `dim` dimensions. Fails on a plane
+    /// of another dimension or with a zero normal.
+    pub fn new(dim: usize, planes: &[HalfSpace]) -> Result<Self, Error> {
+        let mut normals = Vec::with_capacity(planes.len() * dim);
+        let mut offsets = Vec::with_capacity(planes.len());
+        for h in planes {
+            if h.normal.dim() != dim {
+                return Err(Error::DimensionMismatch {
+                    expected: dim,
+                    found: h.normal.dim(),
+                });
+            }
+            let len = h.normal.dist();
+            if len == 0.0 {
+                return Err(Error::ZeroLength);
+            }
+            normals.extend(h.normal.iter().map(|v| v / len));
+            offsets.push(h.offset / len);
+        }
+        Ok(Frustum {
+            dim,
+            normals,
+            offsets,
+        })
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    // Each plane's unit normal and offset.
+    fn planes(&self) -> impl Iterator<Item = (&[f64], f64)> {
+        self.normals
+            .chunks_exact(self.dim.max(1))
+            .zip(self.offsets.iter().copied())
+    }
+
+    // Signed distance of each plane to `x`, negative inside.
+    fn distances<'a>(&'a self, x: &'a [f64]) -> impl Iterator<Item = f64> + 'a {
+        self.planes()
+            .map(move |(n, b)| n.iter().zip(x).map(|(a, v)| a * v).sum::<f64>() - b)
+    }
+
+    fn check(&self, found: usize) {
+        assert_eq!(found, self.dim, "frustum and object must share a dimension");
+    }
+
+    /// Whether `p` lies inside, or on the boundary. Panics on a dimension
+    /// mismatch, as do the other tests.
+    pub fn contains<T, S>(&self, p: &Point<T, S>) -> bool
+    where
+        T: Into<f64> + Copy,
+        S: Storage<T>,
+    {
+        self.check(p.dim());
+        let x: Vec<f64> = p.iter().map(|&v| v.into()).collect();
+        self.distances(&x).all(|d| d <= 0.0)
+    }
+
+    // The box corners nearest to and farthest along each plane's normal,
+    // as signed distances.
+    fn box_extent<'a>(
+        &'a self,
+        lower: &'a [f64],
+        upper: &'a [f64],
+    ) -> impl Iterator<Item = (f64, f64)> + 'a {
+        self.check(lower.len());
+        self.check(upper.len());
+        self.planes().map(move |(n, b)| {
+            let (mut near, mut far) = (-b, -b);
+            for ((&a, &lo), &hi) in n.iter().zip(lower).zip(upper) {
+                let (x, y) = (a * lo, a * hi);
+                near += x.min(y);
+                far += x.max(y);
+            }
+            (near, far)
+        })
+    }
+
+    /// Whether the axis-aligned box `[lower, upper]` lies wholly inside.
+    pub fn contains_box(&self, lower: &[f64], upper: &[f64]) -> bool {
+        self.box_extent(lower, upper).all(|(_, far)| far <= 0.0)
+    }
+
+    /// Whether the box `[lower, upper]` may overlap the frustum; `false`
+    /// means it is certainly outside.
+    pub fn intersects_box(&self, lower: &[f64], upper: &[f64]) -> bool {
+        self.box_extent(lower, upper).all(|(near, _)| near <= 0.0)
+    }
+
+    fn center_distances<T, S>(&self, center: &Point<T, S>) -> Vec<f64>
+    where
+        T: Into<f64> + Copy,
+        S: Storage<T>,
+    {
+        self.check(center.dim());
+        let x: Vec<f64> = center.iter().map(|&v| v.into()).collect();
+        self.distances(&x).collect()
+    }
+
+    /// Whether the ball of `radius` around `center` lies wholly inside.
+    pub fn contains_ball<T, S>(&self, center: &Point<T, S>, radius: f64) -> bool
+    where
+        T: Into<f64> + Copy,
+        S: Storage<T>,
+    {
+        self.center_distances(center).iter().all(|&d| d <= -radius)
+    }
+
+    /// Whether the ball of `radius` around `center` may overlap the frustum;
+    /// `false` means it is certainly outside.
+    pub fn intersects_ball<T, S>(&self, center: &Point<T, S>, radius: f64) -> bool
+    where
+        T: Into<f64> + Copy,
+        S: Storage<T>,
+    {
+        self.center_distances(center).iter().all(|&d| d <= radius)
+    }
+
+    /// Which points of `cloud` are inside, as a mask in cloud order.
+    pub fn cull<T>(&self, cloud: &PointCloud<T>) -> Vec<bool>
+    where
+        T: Into<f64> + Copy,
+    {
+        self.check(cloud.dim());
+        let mut x = vec![0.0; self.dim];
+        cloud
+            .iter()
+            .map(|row| {
+                x.iter_mut().zip(row).for_each(|(a, &v)| *a = v.into());
+                self.distances(&x).all(|d| d <= 0.0)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The square pyramid `|x| <= y`, `y <= 10`: a 2D view from the origin
+    // looking up the y axis with a 90 degree field of view.
+    fn view() -> Frustum {
+        let planes = [
+            HalfSpace::new(Point::new(vec![1.0, -1.0]), 0.0),
+            HalfSpace::new(Point::new(vec![-1.0, -1.0]), 0.0),
+            HalfSpace::new(Point::new(vec![0.0, 2.0]), 20.0),
+        ];
+        Frustum::new(2, &planes).unwrap()
+    }
+
+    #[test]
+    fn points_boxes_and_balls() {
+        let f = view();
+        assert!(f.contains(&Point::new(vec![0.0, 5.0])));
+        assert!(f.contains(&Point::from_array([5, 5])));
+        assert!(!f.contains(&Point::new(vec![0.0, 11.0])));
+        assert!(!f.contains(&Point::new(vec![-3.0, 2.0])));
+
+        assert!(f.contains_box(&[-1.0, 4.0], &[1.0, 6.0]));
+        assert!(!f.contains_box(&[-1.0, 9.0], &[1.0, 11.0]));
+        assert!(f.intersects_box(&[-1.0, 9.0], &[1.0, 11.0]));
+        assert!(!f.intersects_box(&[-1.0, 12.0], &[1.0, 13.0]));
+        assert!(!f.intersects_box(&[-5.0, -1.0], &[-3.0, 2.0]));
+
+        let c = Point::new(vec![0.0, 5.0]);
+        assert!(f.contains_ball(&c, 3.0) && !f.contains_ball(&c, 4.0));
+        let beyond = Point::new(vec![0.0, 12.0]);
+        assert!(f.intersects_ball(&beyond, 2.5) && !f.intersects_ball(&beyond, 1.5));
+    }
+
+    #[test]
+    fn culls_a_cloud() {
+        let cloud = PointCloud::from_flat(&[0.0, 1.0, 2.0, 1.0, 0.0, 10.0, 0.0, -1.0], 2).unwrap();
+        assert_eq!(view().cull(&cloud), [true, false, true, false]);
+    }
+
+    #[test]
+    fn rejects_bad_planes() {
+        let flat = HalfSpace::new(Point::new(vec![0.0, 0.0]), 1.0);
+        assert_eq!(Frustum::new(2, &[flat]).unwrap_err(), Error::ZeroLength);
+        let wrong = HalfSpace::new(Point::new(vec![1.0]), 1.0);
+        assert!(matches!(
+            Frustum::new(2, &[wrong]),
+            Err(Error::DimensionMismatch {
+                expected: 2,
+                found: 1
+            })
+        ));
+    }
+}
@@ -0,0 +1,291 @@
+// Navigation on the sphere.
+//
+// Geographic points are `Point2<f64>` holding `(longitude, latitude)` in
+// degrees, the same order GeoJSON uses. Distances are in metres on a sphere of
+// radius `EARTH_RADIUS`; bearings are in degrees clockwise from north.
+//
+// Two kinds of path join points: the great circle, the shortest, whose
+// bearing keeps changing, and the rhumb line (loxodrome), which holds one
+// bearing and is a straight line on a Mercator chart. The rhumb functions
+// work in the Mercator stretched latitude `psi`, along which longitude
+// changes linearly.
+
+use crate::Point2;
+
+/// Mean Earth radius in metres (IUGG).
+pub const EARTH_RADIUS: f64 = 6_371_008.8;
+
+fn radians(p: &Point2<f64>) -> (f64, f64) {
+    (p.x().to_radians(), p.y().to_radians())
+}
+
+/// Angular distance in radians between two points (haversine formula).
+fn central_angle(a: &Point2<f64>, b: &Point2<f64>) -> f64 {
+    let (lon1, lat1) = radians(a);
+    let (lon2, lat2) = radians(b);
+    let h = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * h.sqrt().min(1.0).asin()
+}
+
+/// Great-circle distance in metres.
+pub fn haversine_distance(a: &Point2<f64>, b: &Point2<f64>) -> f64 {
+    EARTH_RADIUS * central_angle(a, b)
+}
+
+/// Initial bearing in degrees `[0, 360)` when travelling from `a` to `b`
+/// along a great circle.
+pub fn initial_bearing(a: &Point2<f64>, b: &Point2<f64>) -> f64 {
+    let (lon1, lat1) = radians(a);
+    let (lon2, lat2) = radians(b);
+    let dlon = lon2 - lon1;
+    let y = dlon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+/// The point reached after travelling `distance` metres from `start` on the
+/// initial `bearing` (degrees).
+pub fn destination(start: &Point2<f64>, bearing: f64, distance: f64) -> Point2<f64> {
+    let (lon1, lat1) = radians(start);
+    let theta = bearing.to_radians();
+    let delta = distance / EARTH_RADIUS;
+    let lat2 = (lat1.sin() * delta.cos() + lat1.cos() * delta.sin() * theta.cos()).asin();
+    let lon2 = lon1
+        + (theta.sin() * delta.sin() * lat1.cos()).atan2(delta.cos() - lat1.sin() * lat2.sin());
+    Point2::new(wrap_longitude(lon2.to_degrees()), lat2.to_degrees())
+}
+
+/// Signed distance in metres from `point` to the great circle through
+/// `path_start` and `path_end`; negative to the left of the path.
+pub fn cross_track_distance(
+    point: &Point2<f64>,
+    path_start: &Point2<f64>,
+    path_end: &Point2<f64>,
+) -> f64 {
+    let d13 = central_angle(path_start, point);
+    let t13 = initial_bearing(path_start, point).to_radians();
+    let t12 = initial_bearing(path_start, path_end).to_radians();
+    (d13.sin() * (t13 - t12).sin()).asin() * EARTH_RADIUS
+}
+
+fn wrap_longitude(lon: f64) -> f64 {
+    (lon + 540.0).rem_euclid(360.0) - 180.0
+}
+
+/// The point a fraction `t` of the way from `a` to `b` along the great
+/// circle; `t` outside [0, 1] continues round it. Between antipodes,
+/// where every great circle is shortest, the path heads north.
+pub fn slerp_geo(a: &Point2<f64>, b: &Point2<f64>, t: f64) -> Point2<f64> {
+    let delta = central_angle(a, b);
+    if delta == 0.0 {
+        return Point2::new(a.x(), a.y());
+    }
+    if delta.sin() < 1e-12 {
+        return destination(a, 0.0, t * delta * EARTH_RADIUS);
+    }
+    let (lon1, lat1) = radians(a);
+    let (lon2, lat2) = radians(b);
+    let wa = ((1.0 - t) * delta).sin() / delta.sin();
+    let wb = (t * delta).sin() / delta.sin();
+    let x = wa * lat1.cos() * lon1.cos() + wb * lat2.cos() * lon2.cos();
+    let y = wa * lat1.cos() * lon1.sin() + wb * lat2.cos() * lon2.sin();
+    let z = wa * lat1.sin() + wb * lat2.sin();
+    Point2::new(y.atan2(x).to_degrees(), z.atan2(x.hypot(y)).to_degrees())
+}
+
+/// The point halfway along the great circle from `a` to `b`.
+pub fn midpoint_geo(a: &Point2<f64>, b: &Point2<f64>) -> Point2<f64> {
+    slerp_geo(a, b, 0.5)
+}
+
+/// `n` points evenly spaced along the great circle from `a` to `b`, both
+/// ends included, for drawing the path.
+pub fn great_circle_points(a: &Point2<f64>, b: &Point2<f64>, n: usize) -> Vec<Point2<f64>> {
+    sample(n, |t| slerp_geo(a, b, t))
+}
+
+fn sample(n: usize, at: impl Fn(f64) -> Point2<f64>) -> Vec<Point2<f64>> {
+    let last = n.saturating_sub(1).max(1) as f64;
+    (0..n).map(|i| at(i as f64 / last)).collect()
+}
+
+// Mercator stretched latitude.
+fn psi(lat: f64) -> f64 {
+    (std::f64::consts::FRAC_PI_4 + lat / 2.0).tan().ln()
+}
+
+// Latitude change, shortest longitude change (both radians) and the ratio of
+// latitude to stretched latitude change between `a` and `b`.
+fn rhumb_deltas(a: &Point2<f64>, b: &Point2<f64>) -> (f64, f64, f64) {
+    let (lon1, lat1) = radians(a);
+    let (lon2, lat2) = radians(b);
+    let dlat = lat2 - lat1;
+    let dlon = wrap_longitude((lon2 - lon1).to_degrees()).to_radians();
+    let dpsi = psi(lat2) - psi(lat1);
+    // Due east or west the ratio tends to cos(latitude).
+    let q = if dpsi.abs() > 1e-12 {
+        dlat / dpsi
+    } else {
+        lat1.cos()
+    };
+    (dlat, dlon, q)
+}
+
+/// Length in metres of the rhumb line from `a` to `b`, the shorter way
+/// round in longitude.
+pub fn rhumb_distance(a: &Point2<f64>, b: &Point2<f64>) -> f64 {
+    let (dlat, dlon, q) = rhumb_deltas(a, b);
+    dlat.hypot(q * dlon) * EARTH_RADIUS
+}
+
+/// The constant bearing in degrees `[0, 360)` of the rhumb line from `a` to
+/// `b`.
+pub fn rhumb_bearing(a: &Point2<f64>, b: &Point2<f64>) -> f64 {
+    let (_, lat1) = radians(a);
+    let (_, lat2) = radians(b);
+    let (_, dlon, _) = rhumb_deltas(a, b);
+    dlon.atan2(psi(lat2) - psi(lat1))
+        .to_degrees()
+        .rem_euclid(360.0)
+}
+
+/// The point a fraction `t` of the distance from `a` to `b` along the
+/// rhumb line.
+pub fn rhumb_interpolate(a: &Point2<f64>, b: &Point2<f64>, t: f64) -> Point2<f64> {
+    let (dlat, dlon, _) = rhumb_deltas(a, b);
+    let (lon1, lat1) = radians(a);
+    // Distance along a rhumb line is proportional to the latitude change,
+    // unless it runs along a parallel.
+    let lat = lat1 + t * dlat;
+    let dpsi = psi(lat1 + dlat) - psi(lat1);
+    let lon = if dpsi.abs() > 1e-12 {
+        lon1 + dlon * (psi(lat) - psi(lat1)) / dpsi
+    } else {
+        lon1 + t * dlon
+    };
+    Point2::new(wrap_longitude(lon.to_degrees()), lat.to_degrees())
+}
+
+/// The point halfway along the rhumb line from `a` to `b`.
+pub fn rhumb_midpoint(a: &Point2<f64>, b: &Point2<f64>) -> Point2<f64> {
+    rhumb_interpolate(a, b, 0.5)
+}
+
+/// `n` points evenly spaced along the rhumb line from `a` to `b`, both ends
+/// included.
+pub fn rhumb_points(a: &Point2<f64>, b: &Point2<f64>, n: usize) -> Vec<Point2<f64>> {
+    sample(n, |t| rhumb_interpolate(a, b, t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tol: f64) -> bool {
+        (a - b).abs() < tol
+    }
+
+    #[test]
+    fn distance() {
+        // One degree of latitude is about 111.2 km.
+        let a = Point2::new(0.0, 0.0);
+        let b = Point2::new(0.0, 1.0);
+        assert!(close(haversine_distance(&a, &b), 111_195.0, 1.0));
+    }
+
+    #[test]
+    fn bearing() {
+        let origin = Point2::new(0.0, 0.0);
+        assert!(close(
+            initial_bearing(&origin, &Point2::new(0.0, 1.0)),
+            0.0,
+            1e-9
+        ));
+        assert!(close(
+            initial_bearing(&origin, &Point2::new(1.0, 0.0)),
+            90.0,
+            1e-9
+        ));
+        assert!(close(
+            initial_bearing(&origin, &Point2::new(-1.0, 0.0)),
+            270.0,
+            1e-9
+        ));
+    }
+
+    #[test]
+    fn destination_round_trip() {
+        let start = Point2::new(-0.1278, 51.5074);
+        let end = Point2::new(2.3522, 48.8566);
+        let bearing = initial_bearing(&start, &end);
+        let dist = haversine_distance(&start, &end);
+        let reached = destination(&start, bearing, dist);
+        assert!(close(reached.x(), end.x(), 1e-6));
+        assert!(close(reached.y(), end.y(), 1e-6));
+    }
+
+    #[test]
+    fn cross_track() {
+        // A path along the equator; a point one degree north is to the left.
+        let a = Point2::new(0.0, 0.0);
+        let b = Point2::new(10.0, 0.0);
+        let north = Point2::new(5.0, 1.0);
+        let south = Point2::new(5.0, -1.0);
+        let d = cross_track_distance(&north, &a, &b);
+        assert!(close(d, -111_195.0, 1.0));
+        assert!(close(cross_track_distance(&south, &a, &b), -d, 1e-6));
+    }
+
+    #[test]
+    fn great_circle_interpolation() {
+        let london = Point2::new(-0.1278, 51.5074);
+        let tokyo = Point2::new(139.6917, 35.6895);
+        let total = haversine_distance(&london, &tokyo);
+        for t in [0.0, 0.25, 0.5, 1.0] {
+            let p = slerp_geo(&london, &tokyo, t);
+            assert!(close(haversine_distance(&london, &p), t * total, 1e-3));
+            assert!(close(
+                haversine_distance(&p, &tokyo),
+                (1.0 - t) * total,
+                1e-3
+            ));
+        }
+        // The great circle goes far north of either end.
+        assert!(midpoint_geo(&london, &tokyo).y() > 60.0);
+        let path = great_circle_points(&london, &tokyo, 5);
+        assert_eq!(path.len(), 5);
+        assert!(close(path[4].x(), tokyo.x(), 1e-9) && close(path[4].y(), tokyo.y(), 1e-9));
+        let pole = slerp_geo(&Point2::new(0.0, 0.0), &Point2::new(180.0, 0.0), 0.5);
+        assert!(close(pole.y(), 90.0, 1e-9));
+    }
+
+    #[test]
+    fn rhumb_lines() {
+        let a = Point2::new(-10.0, 20.0);
+        let b = Point2::new(30.0, 50.0);
+        let bearing = rhumb_bearing(&a, &b);
+        let total = rhumb_distance(&a, &b);
+        assert!(total > haversine_distance(&a, &b));
+        for p in rhumb_points(&a, &b, 6) {
+            // Every point is on the same constant-bearing line.
+            if haversine_distance(&a, &p) > 1.0 {
+                assert!(close(rhumb_bearing(&a, &p), bearing, 1e-9));
+            }
+        }
+        let m = rhumb_midpoint(&a, &b);
+        assert!(close(m.y(), 35.0, 1e-9));
+        assert!(close(rhumb_distance(&a, &m), total / 2.0, 1e-6));
+        // Along a parallel, and the short way across the antimeridian.
+        let e = Point2::new(170.0, 10.0);
+        let w = Point2::new(-170.0, 10.0);
+        assert!(close(rhumb_bearing(&e, &w), 90.0, 1e-9));
+        let mid = rhumb_midpoint(&e, &w);
+        assert!(close(mid.x().abs(), 180.0, 1e-9) && close(mid.y(), 10.0, 1e-9));
+        assert!(close(
+            rhumb_distance(&e, &w),
+            20.0 * 111_195.0 * 10f64.to_radians().cos(),
+            5.0
+        ));
+    }
+}
@@ -0,0 +1,182 @@
+// Geohash keys for geographic points.
+//
+// A geohash interleaves bits of longitude and latitude, longitude first,
+// each bit halving the remaining range, and spells them five at a time in
+// base 32. Each extra character shrinks the cell about 32 times, so a
+// prefix is the enclosing cell and nearby points usually share prefixes,
+// which makes the hashes good keys for range scans in a database. Points
+// are `(longitude, latitude)` in degrees, as in `geo`.
+
+use crate::Point2;
+
+const ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Longest supported hash; 12 characters is a cell of a few centimetres.
+pub const MAX_PRECISION: usize = 12;
+
+/// A step to one of the eight cells around a geohash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+    ];
+
+    // Steps east and north, in cells.
+    fn offset(self) -> (f64, f64) {
+        match self {
+            Direction::North => (0.0, 1.0),
+            Direction::NorthEast => (1.0, 1.0),
+            Direction::East => (1.0, 0.0),
+            Direction::SouthEast => (1.0, -1.0),
+            Direction::South => (0.0, -1.0),
+            Direction::SouthWest => (-1.0, -1.0),
+            Direction::West => (-1.0, 0.0),
+            Direction::NorthWest => (-1.0, 1.0),
+        }
+    }
+}
+
+/// The `precision`-character geohash of the cell containing `p`. Panics
+/// unless `1 <= precision <= MAX_PRECISION`.
+pub fn encode(p: &Point2<f64>, precision: usize) -> String {
+    assert!(
+        (1..=MAX_PRECISION).contains(&precision),
+        "geohash precision must be between 1 and {MAX_PRECISION}"
+    );
+    let mut lon = (-180.0, 180.0);
+    let mut lat = (-90.0, 90.0);
+    let (x, y) = (p.x(), p.y());
+    let mut hash = String::with_capacity(precision);
+    let mut even = true;
+    for _ in 0..precision {
+        let mut index = 0;
+        for _ in 0..5 {
+            let (range, v) = if even { (&mut lon, x) } else { (&mut lat, y) };
+            let mid = (range.0 + range.1) / 2.0;
+            index <<= 1;
+            if v >= mid {
+                index |= 1;
+                range.0 = mid;
+            } else {
+                range.1 = mid;
+            }
+            even = !even;
+        }
+        hash.push(ALPHABET[index] as char);
+    }
+    hash
+}
+
+/// South-west and north-east corners of the cell, or `None` if `hash` is
+/// empty or has a character outside the geohash alphabet. Upper case is
+/// accepted.
+pub fn decode_bounds(hash: &str) -> Option<(Point2<f64>, Point2<f64>)> {
+    if hash.is_empty() {
+        return None;
+    }
+    let mut lon = (-180.0, 180.0);
+    let mut lat = (-90.0, 90.0);
+    let mut even = true;
+    for c in hash.bytes() {
+        let c = c.to_ascii_lowercase();
+        let index = ALPHABET.iter().position(|&a| a == c)?;
+        for bit in (0..5).rev() {
+            let range = if even { &mut lon } else { &mut lat };
+            let mid = (range.0 + range.1) / 2.0;
+            if index >> bit & 1 == 1 {
+                range.0 = mid;
+            } else {
+                range.1 = mid;
+            }
+            even = !even;
+        }
+    }
+    Some((Point2::new(lon.0, lat.0), Point2::new(lon.1, lat.1)))
+}
+
+/// Centre of the cell; see [`decode_bounds`].
+pub fn decode(hash: &str) -> Option<Point2<f64>> {
+    let (sw, ne) = decode_bounds(hash)?;
+    Some(Point2::new(
+        (sw.x() + ne.x()) / 2.0,
+        (sw.y() + ne.y()) / 2.0,
+    ))
+}
+
+/// The adjacent cell of the same precision, wrapping around the
+/// antimeridian. `None` past a pole, or if `hash` is invalid.
+pub fn neighbor(hash: &str, direction: Direction) -> Option<String> {
+    let (sw, ne) = decode_bounds(hash)?;
+    let (dx, dy) = direction.offset();
+    let lon = (sw.x() + ne.x()) / 2.0 + dx * (ne.x() - sw.x());
+    let lat = (sw.y() + ne.y()) / 2.0 + dy * (ne.y() - sw.y());
+    if !(-90.0..=90.0).contains(&lat) {
+        return None;
+    }
+    let lon = (lon + 180.0).rem_euclid(360.0) - 180.0;
+    Some(encode(&Point2::new(lon, lat), hash.len()))
+}
+
+/// The cells around `hash`, in `Direction::ALL` order, skipping any past a
+/// pole. Empty if `hash` is invalid.
+pub fn neighbors(hash: &str) -> Vec<String> {
+    Direction::ALL
+        .iter()
+        .filter_map(|&d| neighbor(hash, d))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_and_decodes() {
+        // Jutland, the example from the original geohash announcement.
+        let p = Point2::new(10.40744, 57.64911);
+        assert_eq!(encode(&p, 11), "u4pruydqqvj");
+        assert_eq!(encode(&p, 5), "u4pru");
+        let (sw, ne) = decode_bounds("u4pruydqqvj").unwrap();
+        assert!(sw.x() <= p.x() && p.x() <= ne.x() && sw.y() <= p.y() && p.y() <= ne.y());
+        let c = decode("U4PRU").unwrap();
+        assert_eq!(encode(&c, 5), "u4pru");
+        assert!(decode("u4pa").is_none() && decode("").is_none());
+        assert!(std::panic::catch_unwind(|| encode(&p, 0)).is_err());
+    }
+
+    #[test]
+    fn neighbours() {
+        let (sw, ne) = decode_bounds("u4pru").unwrap();
+        let (w, h) = (ne.x() - sw.x(), ne.y() - sw.y());
+        let c = decode("u4pru").unwrap();
+        let n = decode(&neighbor("u4pru", Direction::North).unwrap()).unwrap();
+        assert!((n.x() - c.x()).abs() < 1e-12 && (n.y() - c.y() - h).abs() < 1e-12);
+        let se = decode(&neighbor("u4pru", Direction::SouthEast).unwrap()).unwrap();
+        assert!((se.x() - c.x() - w).abs() < 1e-12 && (c.y() - se.y() - h).abs() < 1e-12);
+        let around = neighbors("u4pru");
+        assert_eq!(around.len(), 8);
+        assert!(around.iter().all(|h| h.len() == 5 && h != "u4pru"));
+        // Across the antimeridian, and nothing north of the pole.
+        assert_eq!(neighbor("2", Direction::West).unwrap(), "r");
+        assert!(neighbor("b", Direction::North).is_none());
+        assert_eq!(neighbors("zzz").len(), 5);
+    }
+}
@@ -0,0 +1,106 @@
+// Conversions to and from glam, behind the `glam` feature.
+//
+// glam's `Vec2`, `Vec3` and `Vec4` are `f32` vectors of a fixed size, so
+// only `f32` points of two to four dimensions map onto them. Going from a
+// vector to a point always works; going back from a plain `Point` checks the
+// dimension, while `Point2` / `Point3` / `Point4` and array-backed points
+// carry it in their type and convert infallibly.
+
+use glam::{Vec2, Vec3, Vec4};
+
+use crate::storage::ArrayStorage;
+use crate::{Error, Point, Point2, Point3, Point4, Storage};
+
+macro_rules! glam_vec {
+    ($vec:ident, $fixed:ident, $dim:literal) => {
+        impl From<$vec> for Point<f32> {
+            fn from(v: $vec) -> Self {
+                Point::new(v.to_array().to_vec())
+            }
+        }
+
+        impl From<$vec> for Point<f32, ArrayStorage<f32, $dim>> {
+            fn from(v: $vec) -> Self {
+                Point::from_array(v.to_array())
+            }
+        }
+
+        impl From<$vec> for $fixed<f32> {
+            fn from(v: $vec) -> Self {
+                $fixed::try_from(Point::from(v)).expect("dimension matches")
+            }
+        }
+
+        impl From<Point<f32, ArrayStorage<f32, $dim>>> for $vec {
+            fn from(p: Point<f32, ArrayStorage<f32, $dim>>) -> Self {
+                $vec::from_array(p.into_storage().0)
+            }
+        }
+
+        impl From<&$fixed<f32>> for $vec {
+            fn from(p: &$fixed<f32>) -> Self {
+                $vec::from_slice(p.as_slice())
+            }
+        }
+
+        /// Fails with `Error::DimensionMismatch` unless the point has the
+        /// vector's dimension.
+        impl<S: Storage<f32>> TryFrom<&Point<f32, S>> for $vec {
+            type Error = Error;
+
+            fn try_from(p: &Point<f32, S>) -> Result<Self, Error> {
+                let coords: [f32; $dim] =
+                    p.as_slice()
+                        .try_into()
+                        .map_err(|_| Error::DimensionMismatch {
+                            expected: $dim,
+                            found: p.as_slice().len(),
+                        })?;
+                Ok($vec::from_array(coords))
+            }
+        }
+    };
+}
+
+glam_vec!(Vec2, Point2, 2);
+glam_vec!(Vec3, Point3, 3);
+glam_vec!(Vec4, Point4, 4);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let v = Vec3::new(1.0, -2.0, 0.5);
+        let p: Point<f32> = v.into();
+        assert_eq!(p.data(), [1.0, -2.0, 0.5]);
+        assert_eq!(Vec3::try_from(&p).unwrap(), v);
+
+        let fixed: Point3<f32> = v.into();
+        assert_eq!(fixed.y(), -2.0);
+        assert_eq!(Vec3::from(&fixed), v);
+
+        let a: Point<f32, ArrayStorage<f32, 4>> = Vec4::ONE.into();
+        assert_eq!(Vec4::from(a), Vec4::ONE);
+        assert_eq!(Vec2::from(&Point2::from(Vec2::X)), Vec2::X);
+    }
+
+    #[test]
+    fn other_dimensions_are_errors() {
+        let p = Point::new(vec![1.0f32, 2.0, 3.0, 4.0, 5.0]);
+        for found in [Vec2::try_from(&p).err(), Vec3::try_from(&p).err()] {
+            assert!(matches!(
+                found,
+                Some(Error::DimensionMismatch { found: 5, .. })
+            ));
+        }
+        assert_eq!(
+            Vec4::try_from(&Point::new(vec![1.0f32])).unwrap_err(),
+            Error::DimensionMismatch {
+                expected: 4,
+                found: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,175 @@
+// Real spherical harmonics on the unit sphere.
+//
+// `sh_eval` gives every orthonormal real harmonic up to band `l_max` at a
+// point, `(l_max + 1)^2` values indexed `l (l + 1) + m` for
+// `-l <= m <= l`. The associated Legendre functions come from the stable
+// three-term recurrence and include the Condon-Shortley phase, following
+// Sloan's "Stupid Spherical Harmonics Tricks", so `Y_1^1 = -sqrt(3/4pi) x`.
+// `sh_project` estimates the coefficients of a function from samples spread
+// uniformly over the sphere; `sh_reconstruct` sums them back up.
+
+use std::f64::consts::PI;
+
+use crate::Point3;
+
+/// Position of `Y_l^m` in the output of [`sh_eval`]. Panics unless
+/// `|m| <= l`.
+pub fn sh_index(l: usize, m: i64) -> usize {
+    assert!(m.unsigned_abs() as usize <= l, "need |m| <= l");
+    ((l * (l + 1)) as i64 + m) as usize
+}
+
+// P_l^m(x) for every 0 <= m <= l <= l_max, at `l (l + 1) / 2 + m`.
+fn legendre(l_max: usize, x: f64) -> Vec<f64> {
+    let at = |l: usize, m: usize| l * (l + 1) / 2 + m;
+    let mut p = vec![0.0; (l_max + 1) * (l_max + 2) / 2];
+    let s = ((1.0 - x) * (1.0 + x)).max(0.0).sqrt();
+    let mut pmm = 1.0;
+    for m in 0..=l_max {
+        if m > 0 {
+            pmm *= -((2 * m - 1) as f64) * s;
+        }
+        p[at(m, m)] = pmm;
+        if m < l_max {
+            p[at(m + 1, m)] = x * (2 * m + 1) as f64 * pmm;
+        }
+        for l in m + 2..=l_max {
+            p[at(l, m)] = ((2 * l - 1) as f64 * x * p[at(l - 1, m)]
+                - (l + m - 1) as f64 * p[at(l - 2, m)])
+                / (l - m) as f64;
+        }
+    }
+    p
+}
+
+// sqrt((2l + 1) / 4pi * (l - m)! / (l + m)!), without overflowing.
+fn norm(l: usize, m: usize) -> f64 {
+    let ratio: f64 = (l - m + 1..=l + m).map(|k| 1.0 / k as f64).product();
+    ((2 * l + 1) as f64 / (4.0 * PI) * ratio).sqrt()
+}
+
+/// All real harmonics up to band `l_max` at the direction of `p`, which
+/// needn't be unit length. Panics on the zero vector.
+pub fn sh_eval(l_max: usize, p: &Point3<f64>) -> Vec<f64> {
+    let len = p.dist();
+    assert!(len > 0.0, "the zero vector has no direction");
+    let (x, y, z) = (p.x() / len, p.y() / len, p.z() / len);
+    let phi = y.atan2(x);
+    let legendre = legendre(l_max, z);
+    let mut out = vec![0.0; (l_max + 1) * (l_max + 1)];
+    for l in 0..=l_max {
+        let base = l * (l + 1);
+        out[base] = norm(l, 0) * legendre[l * (l + 1) / 2];
+        for m in 1..=l {
+            let k = std::f64::consts::SQRT_2 * norm(l, m) * legendre[l * (l + 1) / 2 + m];
+            let (sin, cos) = (m as f64 * phi).sin_cos();
+            out[base + m] = k * cos;
+            out[base - m] = k * sin;
+        }
+    }
+    out
+}
+
+/// Coefficients up to band `l_max` of the function sampled as
+/// `(direction, value)` pairs, by Monte Carlo integration: the samples must
+/// be spread uniformly over the sphere. Empty input gives all zeros.
+pub fn sh_project(samples: &[(Point3<f64>, f64)], l_max: usize) -> Vec<f64> {
+    let mut coeffs = vec![0.0; (l_max + 1) * (l_max + 1)];
+    if samples.is_empty() {
+        return coeffs;
+    }
+    for (p, f) in samples {
+        for (c, y) in coeffs.iter_mut().zip(sh_eval(l_max, p)) {
+            *c += f * y;
+        }
+    }
+    let weight = 4.0 * PI / samples.len() as f64;
+    coeffs.iter_mut().for_each(|c| *c *= weight);
+    coeffs
+}
+
+/// The function with SH coefficients `coeffs` at the direction of `p`.
+/// Panics unless the number of coefficients is a square, `(l_max + 1)^2`.
+pub fn sh_reconstruct(coeffs: &[f64], p: &Point3<f64>) -> f64 {
+    let bands = coeffs.len().isqrt();
+    assert!(
+        bands > 0 && bands * bands == coeffs.len(),
+        "need (l_max + 1)^2 coefficients"
+    );
+    coeffs
+        .iter()
+        .zip(sh_eval(bands - 1, p))
+        .map(|(c, y)| c * y)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Near-uniform points on the sphere (Fibonacci lattice).
+    fn lattice(n: usize) -> Vec<Point3<f64>> {
+        let golden = PI * (3.0 - 5f64.sqrt());
+        (0..n)
+            .map(|i| {
+                let z = 1.0 - (2 * i + 1) as f64 / n as f64;
+                let r = (1.0 - z * z).sqrt();
+                let phi = golden * i as f64;
+                Point3::new(r * phi.cos(), r * phi.sin(), z)
+            })
+            .collect()
+    }
+
+    fn close(a: f64, b: f64, tol: f64) -> bool {
+        (a - b).abs() < tol
+    }
+
+    #[test]
+    fn low_bands_in_closed_form() {
+        let p = Point3::new(0.48, -0.6, 0.64);
+        let y = sh_eval(2, &Point3::new(1.2, -1.5, 1.6));
+        let c0 = 0.5 / PI.sqrt();
+        let c1 = (3.0 / (4.0 * PI)).sqrt();
+        assert!(close(y[sh_index(0, 0)], c0, 1e-12));
+        assert!(close(y[sh_index(1, -1)], -c1 * p.y(), 1e-12));
+        assert!(close(y[sh_index(1, 0)], c1 * p.z(), 1e-12));
+        assert!(close(y[sh_index(1, 1)], -c1 * p.x(), 1e-12));
+        let c2 = 0.5 * (15.0 / PI).sqrt();
+        assert!(close(y[sh_index(2, -2)], c2 * p.x() * p.y(), 1e-12));
+        assert!(close(
+            y[sh_index(2, 2)],
+            c2 / 2.0 * (p.x() * p.x() - p.y() * p.y()),
+            1e-12
+        ));
+        let c20 = 0.25 * (5.0 / PI).sqrt();
+        assert!(close(
+            y[sh_index(2, 0)],
+            c20 * (3.0 * p.z() * p.z() - 1.0),
+            1e-12
+        ));
+    }
+
+    #[test]
+    fn orthonormal_and_projectable() {
+        let points = lattice(20_000);
+        let l_max = 4;
+        let n = (l_max + 1) * (l_max + 1);
+        let w = 4.0 * PI / points.len() as f64;
+        let values: Vec<Vec<f64>> = points.iter().map(|p| sh_eval(l_max, p)).collect();
+        for i in 0..n {
+            for j in 0..n {
+                let dot: f64 = values.iter().map(|y| y[i] * y[j]).sum::<f64>() * w;
+                assert!(close(dot, if i == j { 1.0 } else { 0.0 }, 1e-3), "{i} {j}");
+            }
+        }
+
+        // A band-limited function comes back from its projection.
+        let f = |p: &Point3<f64>| 1.0 + p.x() * p.z() - 0.5 * p.y();
+        let samples: Vec<_> = points.iter().map(|p| (p.clone(), f(p))).collect();
+        let coeffs = sh_project(&samples, 2);
+        for p in lattice(50) {
+            assert!(close(sh_reconstruct(&coeffs, &p), f(&p), 1e-3));
+        }
+        assert!(sh_project(&[], 1).iter().all(|&c| c == 0.0));
+    }
+}
@@ -0,0 +1,237 @@
+// Hexagonal grids in axial coordinates.
+//
+// A `Hex` is the pair `(q, r)`; the third cube coordinate `s = -q - r` is
+// implied, so the three always sum to zero. Distance is half the L1 norm of
+// the cube difference. `HexLayout` places cells in the plane with pointy or
+// flat tops and bins plane points back into cells by cube rounding.
+
+use std::ops::{Add, Mul, Sub};
+
+use crate::{Point, Point2};
+
+/// A hex cell in axial coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Hex {
+    pub q: i32,
+    pub r: i32,
+}
+
+impl Hex {
+    /// The six unit steps, counter-clockwise from `+q`.
+    pub const DIRECTIONS: [Hex; 6] = [
+        Hex::new(1, 0),
+        Hex::new(1, -1),
+        Hex::new(0, -1),
+        Hex::new(-1, 0),
+        Hex::new(-1, 1),
+        Hex::new(0, 1),
+    ];
+
+    pub const fn new(q: i32, r: i32) -> Self {
+        Hex { q, r }
+    }
+
+    /// The cell with cube coordinates `(q, r, s)`, or `None` unless they sum
+    /// to zero.
+    pub fn from_cube(q: i32, r: i32, s: i32) -> Option<Self> {
+        (q + r + s == 0).then_some(Hex { q, r })
+    }
+
+    pub fn s(&self) -> i32 {
+        -self.q - self.r
+    }
+
+    /// Cube coordinates `(q, r, s)` as a point.
+    pub fn to_cube(&self) -> Point<i32> {
+        Point::new(vec![self.q, self.r, self.s()])
+    }
+
+    /// Steps between the two cells.
+    pub fn distance(&self, other: &Hex) -> u32 {
+        let d = *self - *other;
+        (d.q.unsigned_abs() + d.r.unsigned_abs() + d.s().unsigned_abs()) / 2
+    }
+
+    pub fn neighbors(&self) -> impl Iterator<Item = Hex> + '_ {
+        Hex::DIRECTIONS.iter().map(move |&d| *self + d)
+    }
+
+    /// The cells exactly `radius` steps away, going round from the
+    /// `DIRECTIONS[4]` side; just the cell itself for radius 0.
+    pub fn ring(&self, radius: u32) -> Vec<Hex> {
+        if radius == 0 {
+            return vec![*self];
+        }
+        let mut out = Vec::with_capacity(6 * radius as usize);
+        let mut h = *self + Hex::DIRECTIONS[4] * radius as i32;
+        for d in Hex::DIRECTIONS {
+            for _ in 0..radius {
+                out.push(h);
+                h = h + d;
+            }
+        }
+        out
+    }
+
+    /// The cells within `radius` steps, ring by ring outwards from this one.
+    pub fn spiral(&self, radius: u32) -> Vec<Hex> {
+        (0..=radius).flat_map(|k| self.ring(k)).collect()
+    }
+
+    // The nearest cell to fractional axial coordinates.
+    fn round(q: f64, r: f64) -> Hex {
+        let s = -q - r;
+        let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        }
+        Hex::new(rq as i32, rr as i32)
+    }
+}
+
+impl Add for Hex {
+    type Output = Hex;
+
+    fn add(self, o: Hex) -> Hex {
+        Hex::new(self.q + o.q, self.r + o.r)
+    }
+}
+
+impl Sub for Hex {
+    type Output = Hex;
+
+    fn sub(self, o: Hex) -> Hex {
+        Hex::new(self.q - o.q, self.r - o.r)
+    }
+}
+
+impl Mul<i32> for Hex {
+    type Output = Hex;
+
+    fn mul(self, k: i32) -> Hex {
+        Hex::new(self.q * k, self.r * k)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexOrientation {
+    /// A vertex at the top; rows of cells are horizontal.
+    Pointy,
+    /// An edge at the top; columns of cells are vertical.
+    Flat,
+}
+
+/// Where the cells of a hex grid sit in the plane.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HexLayout {
+    pub orientation: HexOrientation,
+    /// Centre to corner distance.
+    pub size: f64,
+    /// Centre of `Hex::new(0, 0)`.
+    pub origin: (f64, f64),
+}
+
+impl HexLayout {
+    pub fn new(orientation: HexOrientation, size: f64) -> Self {
+        HexLayout {
+            orientation,
+            size,
+            origin: (0.0, 0.0),
+        }
+    }
+
+    /// Centre of `hex` in the plane.
+    pub fn to_point(&self, hex: Hex) -> Point2<f64> {
+        let (q, r) = (hex.q as f64, hex.r as f64);
+        let root3 = 3f64.sqrt();
+        let (x, y) = match self.orientation {
+            HexOrientation::Pointy => (root3 * q + root3 / 2.0 * r, 1.5 * r),
+            HexOrientation::Flat => (1.5 * q, root3 / 2.0 * q + root3 * r),
+        };
+        Point2::new(self.origin.0 + self.size * x, self.origin.1 + self.size * y)
+    }
+
+    /// The cell containing `p`, for binning points into hexes.
+    pub fn to_hex<T>(&self, p: &Point2<T>) -> Hex
+    where
+        T: Into<f64> + Copy,
+    {
+        let x = (p.x().into() - self.origin.0) / self.size;
+        let y = (p.y().into() - self.origin.1) / self.size;
+        let root3 = 3f64.sqrt();
+        match self.orientation {
+            HexOrientation::Pointy => Hex::round(root3 / 3.0 * x - y / 3.0, 2.0 / 3.0 * y),
+            HexOrientation::Flat => Hex::round(2.0 / 3.0 * x, -x / 3.0 + root3 / 3.0 * y),
+        }
+    }
+
+    /// The six corners of `hex`, counter-clockwise.
+    pub fn corners(&self, hex: Hex) -> [Point2<f64>; 6] {
+        let c = self.to_point(hex);
+        let start = match self.orientation {
+            HexOrientation::Pointy => 30f64,
+            HexOrientation::Flat => 0.0,
+        };
+        std::array::from_fn(|i| {
+            let a = (start + 60.0 * i as f64).to_radians();
+            Point2::new(c.x() + self.size * a.cos(), c.y() + self.size * a.sin())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cube_coordinates_and_distance() {
+        let a = Hex::new(1, -3);
+        assert_eq!(a.s(), 2);
+        assert_eq!(a.to_cube().data(), [1, -3, 2]);
+        assert_eq!(Hex::from_cube(1, -3, 2), Some(a));
+        assert_eq!(Hex::from_cube(1, 1, 1), None);
+        assert_eq!(a.distance(&Hex::new(0, 0)), 3);
+        assert_eq!(Hex::new(-2, 4).distance(&a), 7);
+        assert!(a.neighbors().all(|n| n.distance(&a) == 1));
+    }
+
+    #[test]
+    fn rings_and_spirals() {
+        let c = Hex::new(2, -1);
+        assert_eq!(c.ring(0), [c]);
+        for k in 1..4 {
+            let ring = c.ring(k);
+            assert_eq!(ring.len(), 6 * k as usize);
+            assert!(ring.iter().all(|h| h.distance(&c) == k));
+        }
+        let mut spiral = c.spiral(3);
+        assert_eq!(spiral.len(), 37);
+        assert_eq!(spiral[..7], *c.spiral(1));
+        spiral.sort();
+        spiral.dedup();
+        assert_eq!(spiral.len(), 37);
+    }
+
+    #[test]
+    fn layout_round_trips() {
+        for orientation in [HexOrientation::Pointy, HexOrientation::Flat] {
+            let mut layout = HexLayout::new(orientation, 2.0);
+            layout.origin = (5.0, -1.0);
+            for h in Hex::new(0, 0).spiral(3) {
+                let c = layout.to_point(h);
+                assert_eq!(layout.to_hex(&c), h);
+                // Points near the centre bin into the same cell.
+                assert_eq!(layout.to_hex(&Point2::new(c.x() + 0.9, c.y() - 0.9)), h);
+                for corner in layout.corners(h) {
+                    assert!((corner.distance_to(&c) - 2.0).abs() < 1e-12);
+                }
+            }
+        }
+        let pointy = HexLayout::new(HexOrientation::Pointy, 1.0);
+        let p = pointy.to_point(Hex::new(1, 0));
+        assert!((p.x() - 3f64.sqrt()).abs() < 1e-12 && p.y() == 0.0);
+    }
+}
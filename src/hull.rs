@@ -0,0 +1,463 @@
+// Convex hulls in any dimension.
+//
+// In 2D this is quickhull: split the points by the line between the
+// leftmost and rightmost, and recursively keep the point farthest outside
+// each edge. In 3D it is quickhull again, on facets: every point outside
+// the hull so far is filed under one facet it sees, and the farthest point
+// of some facet is added next. Adding a point removes the facets it sees
+// and cones the horizon, the ridges between seen and unseen facets, to it;
+// the points filed under removed facets are refiled or, if they see none of
+// the new facets, dropped as inside. In four and more dimensions points are
+// simply added in input order, which needs no bookkeeping beyond the
+// facets.
+//
+// Facets are simplices, so a square face of a cube comes out as two
+// triangles. Points within a small tolerance of a facet (relative to the
+// coordinates' magnitude) count as inside, so coplanar and duplicate points
+// never become extra vertices.
+
+use std::collections::HashMap;
+
+use crate::linalg::{Matrix, dot};
+use crate::polytope::{HPolytope, HalfSpace};
+use crate::{Point, Storage};
+
+// Distances below this, times the coordinates' scale, count as zero.
+const EPS: f64 = 1e-10;
+
+/// One face of a hull, a simplex on its boundary.
+#[derive(Debug, Clone)]
+pub struct Facet {
+    /// Indices of the facet's corners among the input points; in 2D an edge
+    /// of the counter-clockwise boundary, in 3D and above positively
+    /// oriented with the outward normal (counter-clockwise seen from
+    /// outside in 3D).
+    pub vertices: Vec<usize>,
+    /// The facet's supporting half-space, with a unit outward normal.
+    pub plane: HalfSpace,
+}
+
+#[derive(Debug, Clone)]
+pub struct Hull {
+    /// Indices of the extreme points; counter-clockwise in 2D, ascending
+    /// otherwise.
+    pub vertices: Vec<usize>,
+    pub facets: Vec<Facet>,
+}
+
+impl Hull {
+    /// The hull as an intersection of half-spaces.
+    pub fn to_polytope(&self) -> HPolytope {
+        let dim = self.facets[0].plane.normal.dim();
+        let planes = self.facets.iter().map(|f| f.plane.clone()).collect();
+        HPolytope::from_halfspaces(dim, planes).expect("facet normals share a dimension")
+    }
+}
+
+/// The convex hull of `points`; `None` if they don't span their space (too
+/// few, or all on a line in 2D, a plane in 3D, and so on). Panics if they
+/// don't share a dimension.
+pub fn convex_hull<T, S>(points: &[Point<T, S>]) -> Option<Hull>
+where
+    T: Into<f64> + Copy,
+    S: Storage<T>,
+{
+    let dim = points.first()?.as_slice().len();
+    let pts: Vec<Vec<f64>> = points
+        .iter()
+        .map(|p| {
+            assert_eq!(p.as_slice().len(), dim, "points must share a dimension");
+            p.as_slice().iter().map(|&v| v.into()).collect()
+        })
+        .collect();
+    let scale = pts.iter().flatten().fold(0.0f64, |m, v| m.max(v.abs()));
+    let eps = EPS * scale;
+    match dim {
+        0 => None,
+        1 => hull_1d(&pts),
+        2 => hull_2d(&pts, eps),
+        _ => hull_nd(&pts, eps, dim == 3),
+    }
+}
+
+fn hull_1d(pts: &[Vec<f64>]) -> Option<Hull> {
+    let by = |a: &usize, b: &usize| pts[*a][0].total_cmp(&pts[*b][0]);
+    let lo = (0..pts.len()).min_by(by)?;
+    let hi = (0..pts.len()).min_by(|a, b| by(b, a))?;
+    if pts[lo][0] == pts[hi][0] {
+        return None;
+    }
+    let facet = |i: usize, sign: f64| Facet {
+        vertices: vec![i],
+        plane: HalfSpace::new(Point::new(vec![sign]), sign * pts[i][0]),
+    };
+    Some(Hull {
+        vertices: vec![lo, hi],
+        facets: vec![facet(lo, -1.0), facet(hi, 1.0)],
+    })
+}
+
+// Twice the signed area of the triangle (a, b, p): positive when p is left
+// of a -> b.
+fn cross(a: &[f64], b: &[f64], p: &[f64]) -> f64 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+fn hull_2d(pts: &[Vec<f64>], eps: f64) -> Option<Hull> {
+    let lex = |a: &usize, b: &usize| {
+        pts[*a][0]
+            .total_cmp(&pts[*b][0])
+            .then(pts[*a][1].total_cmp(&pts[*b][1]))
+    };
+    let a = (0..pts.len()).min_by(lex)?;
+    // The first of any duplicates, like `a`.
+    let b = (0..pts.len()).min_by(|x, y| lex(y, x))?;
+    let all: Vec<usize> = (0..pts.len()).collect();
+    // The chain from `a` to `b` through the points right of a -> b, which
+    // runs counter-clockwise.
+    fn chain(pts: &[Vec<f64>], a: usize, b: usize, set: &[usize], eps: f64, out: &mut Vec<usize>) {
+        let right: Vec<usize> = set
+            .iter()
+            .copied()
+            .filter(|&i| cross(&pts[a], &pts[b], &pts[i]) < -eps)
+            .collect();
+        let Some(&c) = right.iter().min_by(|&&i, &&j| {
+            cross(&pts[a], &pts[b], &pts[i]).total_cmp(&cross(&pts[a], &pts[b], &pts[j]))
+        }) else {
+            return;
+        };
+        chain(pts, a, c, &right, eps, out);
+        out.push(c);
+        chain(pts, c, b, &right, eps, out);
+    }
+    let mut ring = vec![a];
+    chain(pts, a, b, &all, eps, &mut ring);
+    ring.push(b);
+    chain(pts, b, a, &all, eps, &mut ring);
+    if ring.len() < 3 {
+        return None;
+    }
+    let facets = (0..ring.len())
+        .map(|k| {
+            let (p, q) = (ring[k], ring[(k + 1) % ring.len()]);
+            let (dx, dy) = (pts[q][0] - pts[p][0], pts[q][1] - pts[p][1]);
+            let len = dx.hypot(dy);
+            let normal = vec![dy / len, -dx / len];
+            Facet {
+                vertices: vec![p, q],
+                plane: HalfSpace::new(Point::new(normal.clone()), dot(&normal, &pts[p])),
+            }
+        })
+        .collect();
+    Some(Hull {
+        vertices: ring,
+        facets,
+    })
+}
+
+struct Working {
+    vertices: Vec<usize>,
+    normal: Vec<f64>,
+    offset: f64,
+    // Points outside this facet and filed under it, for quickhull.
+    outside: Vec<usize>,
+}
+
+impl Working {
+    fn height(&self, p: &[f64]) -> f64 {
+        dot(&self.normal, p) - self.offset
+    }
+}
+
+// The facet through `vertices`, facing away from `interior`; `None` if they
+// don't span a hyperplane.
+fn facet(pts: &[Vec<f64>], vertices: Vec<usize>, interior: &[f64]) -> Option<Working> {
+    let dim = interior.len();
+    let base = &pts[vertices[0]];
+    let rows: Vec<Vec<f64>> = vertices[1..]
+        .iter()
+        .map(|&v| pts[v].iter().zip(base).map(|(a, b)| a - b).collect())
+        .collect();
+    // Generalised cross product: cofactors along a last, free row.
+    let mut normal: Vec<f64> = (0..dim)
+        .map(|i| {
+            let minor: Vec<Vec<f64>> = rows
+                .iter()
+                .map(|r| [&r[..i], &r[i + 1..]].concat())
+                .collect();
+            let sign = if (dim - 1 + i).is_multiple_of(2) {
+                1.0
+            } else {
+                -1.0
+            };
+            sign * Matrix::from_rows(&minor).determinant()
+        })
+        .collect();
+    let len = normal.iter().map(|v| v * v).sum::<f64>().sqrt();
+    if len == 0.0 {
+        return None;
+    }
+    normal.iter_mut().for_each(|v| *v /= len);
+    let mut f = Working {
+        offset: dot(&normal, base),
+        normal,
+        vertices,
+        outside: Vec::new(),
+    };
+    // The cofactor normal makes the vertices positively oriented with it,
+    // so flipping it to face outwards also swaps two vertices.
+    if f.height(interior) > 0.0 {
+        f.normal.iter_mut().for_each(|v| *v = -*v);
+        f.offset = -f.offset;
+        f.vertices.swap(0, 1);
+    }
+    Some(f)
+}
+
+// Indices of `dim + 1` affinely independent points, greedily far apart.
+fn initial_simplex(pts: &[Vec<f64>], eps: f64) -> Option<Vec<usize>> {
+    let dim = pts[0].len();
+    let first = (0..pts.len()).min_by(|&a, &b| pts[a][0].total_cmp(&pts[b][0]))?;
+    let mut chosen = vec![first];
+    let mut basis: Vec<Vec<f64>> = Vec::new();
+    let residual = |basis: &[Vec<f64>], p: &[f64]| -> Vec<f64> {
+        let mut r: Vec<f64> = p.iter().zip(&pts[first]).map(|(a, b)| a - b).collect();
+        for e in basis {
+            let k = dot(&r, e);
+            r.iter_mut().zip(e).for_each(|(v, e)| *v -= k * e);
+        }
+        r
+    };
+    while chosen.len() <= dim {
+        let (best, r) = (0..pts.len())
+            .map(|i| (i, residual(&basis, &pts[i])))
+            .max_by(|a, b| dot(&a.1, &a.1).total_cmp(&dot(&b.1, &b.1)))?;
+        let len = dot(&r, &r).sqrt();
+        if len <= eps.max(f64::MIN_POSITIVE) {
+            return None;
+        }
+        basis.push(r.iter().map(|v| v / len).collect());
+        chosen.push(best);
+    }
+    Some(chosen)
+}
+
+fn hull_nd(pts: &[Vec<f64>], eps: f64, quickhull: bool) -> Option<Hull> {
+    let dim = pts[0].len();
+    let simplex = initial_simplex(pts, eps)?;
+    let mut interior = vec![0.0; dim];
+    for &i in &simplex {
+        interior
+            .iter_mut()
+            .zip(&pts[i])
+            .for_each(|(c, v)| *c += v / (dim + 1) as f64);
+    }
+    let mut facets: Vec<Working> = Vec::new();
+    for skip in 0..=dim {
+        let verts: Vec<usize> = simplex
+            .iter()
+            .enumerate()
+            .filter(|&(k, _)| k != skip)
+            .map(|(_, &v)| v)
+            .collect();
+        facets.push(facet(pts, verts, &interior)?);
+    }
+    let rest = (0..pts.len()).filter(|i| !simplex.contains(i));
+    if quickhull {
+        file(pts, &mut facets, rest, eps);
+        while let Some(f) = facets.iter().position(|f| !f.outside.is_empty()) {
+            let p = *facets[f]
+                .outside
+                .iter()
+                .max_by(|&&a, &&b| {
+                    facets[f]
+                        .height(&pts[a])
+                        .total_cmp(&facets[f].height(&pts[b]))
+                })
+                .expect("outside set is not empty");
+            let (orphans, first_new) = add_point(pts, &mut facets, p, &interior, eps)?;
+            file(
+                pts,
+                &mut facets[first_new..],
+                orphans.into_iter().filter(|&i| i != p),
+                eps,
+            );
+        }
+    } else {
+        for p in rest {
+            if facets.iter().any(|f| f.height(&pts[p]) > eps) {
+                add_point(pts, &mut facets, p, &interior, eps)?;
+            }
+        }
+    }
+    let mut vertices: Vec<usize> = facets.iter().flat_map(|f| f.vertices.clone()).collect();
+    vertices.sort_unstable();
+    vertices.dedup();
+    let facets = facets
+        .into_iter()
+        .map(|f| Facet {
+            vertices: f.vertices,
+            plane: HalfSpace::new(Point::new(f.normal), f.offset),
+        })
+        .collect();
+    Some(Hull { vertices, facets })
+}
+
+// Files each of `points` under the first of `facets` it is outside of.
+fn file(
+    pts: &[Vec<f64>],
+    facets: &mut [Working],
+    points: impl IntoIterator<Item = usize>,
+    eps: f64,
+) {
+    for i in points {
+        if let Some(f) = facets.iter_mut().find(|f| f.height(&pts[i]) > eps) {
+            f.outside.push(i);
+        }
+    }
+}
+
+// Adds point `p` to the hull, replacing the facets it sees. Returns the
+// points filed under the removed facets and the index of the first new
+// facet, which all come last.
+fn add_point(
+    pts: &[Vec<f64>],
+    facets: &mut Vec<Working>,
+    p: usize,
+    interior: &[f64],
+    eps: f64,
+) -> Option<(Vec<usize>, usize)> {
+    let (visible, kept): (Vec<Working>, Vec<Working>) =
+        facets.drain(..).partition(|f| f.height(&pts[p]) > eps);
+    *facets = kept;
+    // A ridge of exactly one visible facet borders an unseen one.
+    let mut ridges: HashMap<Vec<usize>, usize> = HashMap::new();
+    for f in &visible {
+        for skip in 0..f.vertices.len() {
+            let mut ridge: Vec<usize> = f.vertices.clone();
+            ridge.remove(skip);
+            ridge.sort_unstable();
+            *ridges.entry(ridge).or_default() += 1;
+        }
+    }
+    let first_new = facets.len();
+    let mut horizon: Vec<Vec<usize>> = ridges
+        .into_iter()
+        .filter(|&(_, n)| n == 1)
+        .map(|(r, _)| r)
+        .collect();
+    horizon.sort_unstable();
+    for mut ridge in horizon {
+        ridge.push(p);
+        facets.push(facet(pts, ridge, interior)?);
+    }
+    let orphans = visible.into_iter().flat_map(|f| f.outside).collect();
+    Some((orphans, first_new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn random_points(dim: usize, n: usize, rng: &mut Rng) -> Vec<Point<f64>> {
+        (0..n)
+            .map(|_| Point::new((0..dim).map(|_| rng.normal()).collect()))
+            .collect()
+    }
+
+    // Every point inside every facet, every vertex on some facet.
+    fn check(points: &[Point<f64>], hull: &Hull) {
+        for p in points {
+            for f in &hull.facets {
+                assert!(f.plane.signed_distance(p) <= 1e-9);
+            }
+        }
+        for &v in &hull.vertices {
+            assert!(hull.facets.iter().any(|f| f.vertices.contains(&v)));
+        }
+    }
+
+    #[test]
+    fn polygons() {
+        let pts: Vec<Point<f64>> = [
+            [0.0, 0.0],
+            [2.0, 0.0],
+            [1.0, 1.0],
+            [2.0, 2.0],
+            [0.0, 2.0],
+            [1.0, 0.0],
+            [2.0, 2.0],
+        ]
+        .iter()
+        .map(|p| Point::new(p.to_vec()))
+        .collect();
+        let hull = convex_hull(&pts).unwrap();
+        assert_eq!(hull.vertices, vec![0, 1, 3, 4]);
+        assert_eq!(hull.facets[0].vertices, vec![0, 1]);
+        assert_eq!(hull.facets[0].plane.normal.data(), [0.0, -1.0]);
+        check(&pts, &hull);
+
+        let line: Vec<Point<i32>> = (0..5).map(|i| Point::new(vec![i, 2 * i])).collect();
+        assert!(convex_hull(&line).is_none());
+        let ends = convex_hull(&[Point::new(vec![3.0]), Point::new(vec![-1.0])]).unwrap();
+        assert_eq!(ends.vertices, vec![1, 0]);
+    }
+
+    #[test]
+    fn cube_and_random_3d() {
+        let mut pts: Vec<Point<f64>> = (0..8)
+            .map(|i| Point::new((0..3).map(|b| ((i >> b) & 1) as f64).collect()))
+            .collect();
+        let mut rng = Rng::seed_from_u64(276);
+        pts.extend((0..50).map(|_| Point::new((0..3).map(|_| rng.next_f64()).collect())));
+        pts.push(Point::new(vec![0.5, 0.0, 0.5]));
+        let hull = convex_hull(&pts).unwrap();
+        assert_eq!(hull.vertices, (0..8).collect::<Vec<_>>());
+        assert_eq!(hull.facets.len(), 12);
+        check(&pts, &hull);
+        // Counter-clockwise from outside.
+        for f in &hull.facets {
+            let [a, b, c] = [0, 1, 2].map(|k| Point::new(pts[f.vertices[k]].data().to_vec()));
+            let n = (&b - &a).cross(&(&c - &a));
+            assert!(n.dot(&f.plane.normal) > 0.0);
+        }
+        assert!(
+            hull.to_polytope()
+                .contains(&Point::new(vec![0.5, 0.5, 0.5]))
+        );
+        assert!(
+            !hull
+                .to_polytope()
+                .contains(&Point::new(vec![1.5, 0.5, 0.5]))
+        );
+
+        let sphere = random_points(3, 300, &mut rng);
+        let hull = convex_hull(&sphere).unwrap();
+        check(&sphere, &hull);
+        // Euler's formula for a triangulated sphere.
+        assert_eq!(hull.facets.len(), 2 * hull.vertices.len() - 4);
+        let flat: Vec<Point<f64>> = random_points(2, 10, &mut rng)
+            .iter()
+            .map(|p| Point::new(vec![p[0], p[1], 1.0]))
+            .collect();
+        assert!(convex_hull(&flat).is_none());
+    }
+
+    #[test]
+    fn higher_dimensions() {
+        let mut pts: Vec<Point<f64>> = (0..16)
+            .map(|i| Point::new((0..4).map(|b| ((i >> b) & 1) as f64 * 2.0 - 1.0).collect()))
+            .collect();
+        let mut rng = Rng::seed_from_u64(277);
+        pts.extend(
+            (0..40).map(|_| Point::new((0..4).map(|_| rng.next_f64() * 1.8 - 0.9).collect())),
+        );
+        let hull = convex_hull(&pts).unwrap();
+        assert_eq!(hull.vertices, (0..16).collect::<Vec<_>>());
+        check(&pts, &hull);
+
+        let blob = random_points(5, 80, &mut rng);
+        check(&blob, &convex_hull(&blob).unwrap());
+    }
+}
@@ -0,0 +1,661 @@
+// Common interface of the nearest-neighbour indices.
+//
+// Coordinate indices implement `SpatialIndex`, whose queries take `&[f64]` so
+// a `&Point<f64>` can be passed directly. Indices that only need distances
+// between items implement `MetricIndex` over any `MetricItem`, with points
+// under the Euclidean distance as the default. Results are sorted by
+// distance, ties broken by index.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::Point;
+
+/// One query result: the index of a stored point and its distance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Neighbor {
+    pub index: usize,
+    pub distance: f64,
+}
+
+impl Neighbor {
+    fn cmp_key(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+impl Eq for Neighbor {}
+
+impl PartialOrd for Neighbor {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Neighbor {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_key(other)
+    }
+}
+
+pub trait SpatialIndex {
+    /// Dimension of the stored points.
+    fn dim(&self) -> usize;
+
+    /// Number of stored points.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The `k` stored points closest to `query`, nearest first.
+    fn k_nearest(&self, query: &[f64], k: usize) -> Vec<Neighbor> {
+        self.k_nearest_where(query, k, &|_| true)
+    }
+
+    /// Like `k_nearest`, but only points whose index passes `keep` are
+    /// candidates. The predicate is checked during the search, so a filter
+    /// that rejects most points still returns `k` of them when it can.
+    fn k_nearest_where(
+        &self,
+        query: &[f64],
+        k: usize,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor>;
+
+    /// All stored points within `radius` of `query`, nearest first.
+    fn within_radius(&self, query: &[f64], radius: f64) -> Vec<Neighbor> {
+        self.within_radius_where(query, radius, &|_| true)
+    }
+
+    /// Like `within_radius`, restricted to the points whose index passes
+    /// `keep`.
+    fn within_radius_where(
+        &self,
+        query: &[f64],
+        radius: f64,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor>;
+
+    /// The `k` stored points farthest from `query`, farthest first.
+    fn k_farthest(&self, query: &[f64], k: usize) -> Vec<Neighbor> {
+        self.k_farthest_where(query, k, &|_| true)
+    }
+
+    /// Like `k_farthest`, restricted to the points whose index passes `keep`.
+    fn k_farthest_where(
+        &self,
+        query: &[f64],
+        k: usize,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor>;
+
+    fn nearest(&self, query: &[f64]) -> Option<Neighbor> {
+        self.k_nearest(query, 1).into_iter().next()
+    }
+
+    fn nearest_where(&self, query: &[f64], keep: &dyn Fn(usize) -> bool) -> Option<Neighbor> {
+        self.k_nearest_where(query, 1, keep).into_iter().next()
+    }
+
+    fn farthest(&self, query: &[f64]) -> Option<Neighbor> {
+        self.k_farthest(query, 1).into_iter().next()
+    }
+
+    /// Runs `k_nearest` for every query, collecting the results into one
+    /// flat buffer.
+    fn query_batch(&self, queries: &[Point<f64>], k: usize) -> BatchResult {
+        BatchResult::collect(
+            queries.iter().map(|q| self.k_nearest(q, k)),
+            queries.len(),
+            k,
+        )
+    }
+
+    /// [`SpatialIndex::query_batch`] with the queries spread over all cores.
+    #[cfg(feature = "parallel")]
+    fn par_query_batch(&self, queries: &[Point<f64>], k: usize) -> BatchResult
+    where
+        Self: Sync,
+    {
+        use rayon::prelude::*;
+        let results: Vec<Vec<Neighbor>> =
+            queries.par_iter().map(|q| self.k_nearest(q, k)).collect();
+        BatchResult::collect(results, queries.len(), k)
+    }
+}
+
+/// Anything with a distance to other values of its type. The distance must
+/// be a metric (symmetric, zero only between equal items, and obeying the
+/// triangle inequality) for the metric indices to return exact results.
+pub trait MetricItem {
+    fn distance(&self, other: &Self) -> f64;
+}
+
+/// Panics if the dimensions differ.
+impl<T> MetricItem for Point<T>
+where
+    T: Into<f64> + Copy,
+{
+    fn distance(&self, other: &Self) -> f64 {
+        assert_eq!(self.dim(), other.dim(), "points must share a dimension");
+        self.p
+            .iter()
+            .zip(other.p.iter())
+            .map(|(&a, &b)| {
+                let d = a.into() - b.into();
+                d * d
+            })
+            .sum::<f64>()
+            .sqrt()
+    }
+}
+
+/// Nearest-neighbour queries over items that only provide a distance.
+pub trait MetricIndex<I: MetricItem = Point<f64>> {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The `k` items closest to `query`, nearest first.
+    fn k_nearest(&self, query: &I, k: usize) -> Vec<Neighbor> {
+        self.k_nearest_where(query, k, &|_| true)
+    }
+
+    /// Like `k_nearest`, but only items whose index passes `keep` are
+    /// candidates; the predicate is checked during the search.
+    fn k_nearest_where(&self, query: &I, k: usize, keep: &dyn Fn(usize) -> bool) -> Vec<Neighbor>;
+
+    /// All items within `radius` of `query`, nearest first.
+    fn within_radius(&self, query: &I, radius: f64) -> Vec<Neighbor> {
+        self.within_radius_where(query, radius, &|_| true)
+    }
+
+    /// Like `within_radius`, restricted to the items whose index passes
+    /// `keep`.
+    fn within_radius_where(
+        &self,
+        query: &I,
+        radius: f64,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor>;
+
+    fn nearest(&self, query: &I) -> Option<Neighbor> {
+        self.k_nearest(query, 1).into_iter().next()
+    }
+}
+
+/// A coordinate index answering Euclidean point queries through
+/// `MetricIndex`, so it can go where a metric index is expected. A wrapper
+/// rather than an impl on every `SpatialIndex`, which would make the
+/// index's own query methods ambiguous wherever both traits are in scope.
+#[derive(Debug, Clone)]
+pub struct Euclidean<S>(pub S);
+
+impl<S: SpatialIndex> MetricIndex<Point<f64>> for Euclidean<S> {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn k_nearest_where(
+        &self,
+        query: &Point<f64>,
+        k: usize,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor> {
+        self.0.k_nearest_where(query, k, keep)
+    }
+
+    fn within_radius_where(
+        &self,
+        query: &Point<f64>,
+        radius: f64,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor> {
+        self.0.within_radius_where(query, radius, keep)
+    }
+}
+
+/// The exact baseline: compares the query with every item.
+#[derive(Debug, Clone)]
+pub struct LinearScan<I> {
+    pub(crate) items: Vec<I>,
+}
+
+impl<I: MetricItem> LinearScan<I> {
+    pub fn new(items: Vec<I>) -> Self {
+        LinearScan { items }
+    }
+
+    pub fn items(&self) -> &[I] {
+        &self.items
+    }
+}
+
+impl<I: MetricItem> MetricIndex<I> for LinearScan<I> {
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn k_nearest_where(&self, query: &I, k: usize, keep: &dyn Fn(usize) -> bool) -> Vec<Neighbor> {
+        let mut best = KBest::new(k);
+        for (i, item) in self.items.iter().enumerate() {
+            if keep(i) {
+                best.push(i, item.distance(query));
+            }
+        }
+        best.into_neighbors()
+    }
+
+    fn within_radius_where(
+        &self,
+        query: &I,
+        radius: f64,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor> {
+        let mut out = RadiusResults::new(radius);
+        for (i, item) in self.items.iter().enumerate() {
+            if keep(i) {
+                out.push(i, item.distance(query));
+            }
+        }
+        out.into_neighbors()
+    }
+}
+
+/// Shape and size of a tree index, for diagnosing degenerate builds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexStats {
+    pub points: usize,
+    pub nodes: usize,
+    pub leaves: usize,
+    /// Longest root-to-leaf path, counting the root as depth 0.
+    pub depth: usize,
+    pub min_leaf: usize,
+    pub max_leaf: usize,
+    /// Mean leaf occupancy divided by the leaf capacity.
+    pub fill_factor: f64,
+    /// Rough heap plus inline size in bytes.
+    pub memory_bytes: usize,
+}
+
+impl IndexStats {
+    // From the (depth, size) of every leaf.
+    pub(crate) fn from_leaves(
+        points: usize,
+        nodes: usize,
+        leaves: &[(usize, usize)],
+        capacity: usize,
+        memory_bytes: usize,
+    ) -> Self {
+        let n = leaves.len().max(1) as f64;
+        let mean = leaves.iter().map(|l| l.1).sum::<usize>() as f64 / n;
+        IndexStats {
+            points,
+            nodes,
+            leaves: leaves.len(),
+            depth: leaves.iter().map(|l| l.0).max().unwrap_or(0),
+            min_leaf: leaves.iter().map(|l| l.1).min().unwrap_or(0),
+            max_leaf: leaves.iter().map(|l| l.1).max().unwrap_or(0),
+            fill_factor: mean / capacity.max(1) as f64,
+            memory_bytes,
+        }
+    }
+}
+
+/// Results of [`SpatialIndex::query_batch`] in one flat buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchResult {
+    neighbors: Vec<Neighbor>,
+    // Query `i` owns `neighbors[offsets[i]..offsets[i + 1]]`.
+    offsets: Vec<usize>,
+}
+
+impl BatchResult {
+    fn collect(results: impl IntoIterator<Item = Vec<Neighbor>>, queries: usize, k: usize) -> Self {
+        let mut batch = BatchResult {
+            neighbors: Vec::with_capacity(queries * k),
+            offsets: Vec::with_capacity(queries + 1),
+        };
+        batch.offsets.push(0);
+        for r in results {
+            batch.neighbors.extend(r);
+            batch.offsets.push(batch.neighbors.len());
+        }
+        batch
+    }
+
+    /// Number of queries.
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The neighbours found for query `i`, nearest first.
+    pub fn get(&self, i: usize) -> &[Neighbor] {
+        &self.neighbors[self.offsets[i]..self.offsets[i + 1]]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &[Neighbor]> {
+        self.offsets.windows(2).map(|w| &self.neighbors[w[0]..w[1]])
+    }
+
+    /// All results back to back, in query order.
+    pub fn as_flat(&self) -> &[Neighbor] {
+        &self.neighbors
+    }
+}
+
+// Heap entry ordered by distance, then item.
+#[derive(Debug, Clone)]
+struct Entry<T> {
+    distance: f64,
+    item: T,
+}
+
+impl<T: Ord> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Ord> Eq for Entry<T> {}
+
+impl<T: Ord> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then_with(|| self.item.cmp(&other.item))
+    }
+}
+
+/// The `k` closest candidates seen so far, kept in a bounded max-heap.
+///
+/// Ties on distance are broken by the smaller item, so results don't depend
+/// on the order candidates arrive in.
+#[derive(Debug, Clone)]
+pub struct KBest<T = usize> {
+    k: usize,
+    heap: BinaryHeap<Entry<T>>,
+}
+
+impl<T: Ord> KBest<T> {
+    pub fn new(k: usize) -> Self {
+        KBest {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Offers a candidate; it is kept if it is among the `k` best so far.
+    pub fn push(&mut self, item: T, distance: f64) {
+        let e = Entry { distance, item };
+        if self.heap.len() < self.k {
+            self.heap.push(e);
+        } else if let Some(top) = self.heap.peek()
+            && e < *top
+        {
+            self.heap.pop();
+            self.heap.push(e);
+        }
+    }
+
+    /// Distance a candidate must beat to get in: infinite until `k`
+    /// candidates have been seen.
+    pub fn worst(&self) -> f64 {
+        if self.heap.len() < self.k {
+            f64::INFINITY
+        } else {
+            self.heap.peek().map_or(f64::NEG_INFINITY, |e| e.distance)
+        }
+    }
+
+    /// Combines with results gathered elsewhere, e.g. from another shard or
+    /// thread, keeping the `k` best of both.
+    pub fn merge(&mut self, other: KBest<T>) {
+        for e in other.heap {
+            self.push(e.item, e.distance);
+        }
+    }
+
+    /// The kept candidates, nearest first.
+    pub fn into_sorted_vec(self) -> Vec<(T, f64)> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|e| (e.item, e.distance))
+            .collect()
+    }
+}
+
+impl KBest<usize> {
+    pub fn into_neighbors(self) -> Vec<Neighbor> {
+        self.into_sorted_vec()
+            .into_iter()
+            .map(|(index, distance)| Neighbor { index, distance })
+            .collect()
+    }
+}
+
+impl<T: Ord> Extend<(T, f64)> for KBest<T> {
+    fn extend<It: IntoIterator<Item = (T, f64)>>(&mut self, iter: It) {
+        for (item, distance) in iter {
+            self.push(item, distance);
+        }
+    }
+}
+
+/// Candidates within a fixed radius, for range queries.
+#[derive(Debug, Clone)]
+pub struct RadiusResults<T = usize> {
+    radius: f64,
+    items: Vec<Entry<T>>,
+}
+
+impl<T: Ord> RadiusResults<T> {
+    pub fn new(radius: f64) -> Self {
+        RadiusResults {
+            radius,
+            items: Vec::new(),
+        }
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Keeps the candidate if it lies within the radius.
+    pub fn push(&mut self, item: T, distance: f64) {
+        if distance <= self.radius {
+            self.items.push(Entry { distance, item });
+        }
+    }
+
+    /// Adds results gathered elsewhere, keeping those within this radius.
+    pub fn merge(&mut self, other: RadiusResults<T>) {
+        for e in other.items {
+            self.push(e.item, e.distance);
+        }
+    }
+
+    /// The candidates, nearest first.
+    pub fn into_sorted_vec(mut self) -> Vec<(T, f64)> {
+        self.items.sort_unstable();
+        self.items
+            .into_iter()
+            .map(|e| (e.item, e.distance))
+            .collect()
+    }
+}
+
+impl RadiusResults<usize> {
+    pub fn into_neighbors(self) -> Vec<Neighbor> {
+        self.into_sorted_vec()
+            .into_iter()
+            .map(|(index, distance)| Neighbor { index, distance })
+            .collect()
+    }
+}
+
+impl<T: Ord> Extend<(T, f64)> for RadiusResults<T> {
+    fn extend<It: IntoIterator<Item = (T, f64)>>(&mut self, iter: It) {
+        for (item, distance) in iter {
+            self.push(item, distance);
+        }
+    }
+}
+
+#[cfg(not(feature = "simd"))]
+pub(crate) fn squared_distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+#[cfg(feature = "simd")]
+pub(crate) fn squared_distance(a: &[f64], b: &[f64]) -> f64 {
+    <f64 as crate::simd::SimdFloat>::squared_distance(a, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kdtree::KdTree;
+
+    // Words under the Levenshtein distance.
+    struct Word(&'static str);
+
+    impl MetricItem for Word {
+        fn distance(&self, other: &Self) -> f64 {
+            let (a, b): (Vec<char>, Vec<char>) =
+                (self.0.chars().collect(), other.0.chars().collect());
+            let mut row: Vec<usize> = (0..=b.len()).collect();
+            for i in 1..=a.len() {
+                let mut diag = row[0];
+                row[0] = i;
+                for j in 1..=b.len() {
+                    let next = (row[j] + 1)
+                        .min(row[j - 1] + 1)
+                        .min(diag + usize::from(a[i - 1] != b[j - 1]));
+                    diag = row[j];
+                    row[j] = next;
+                }
+            }
+            row[b.len()] as f64
+        }
+    }
+
+    #[test]
+    fn linear_scan_with_edit_distance() {
+        let words = LinearScan::new(vec![
+            Word("kitten"),
+            Word("sitting"),
+            Word("mitten"),
+            Word("bat"),
+        ]);
+        let hits = words.k_nearest(&Word("fitten"), 2);
+        assert_eq!(hits.iter().map(|n| n.index).collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(hits[0].distance, 1.0);
+        let close = words.within_radius(&Word("sitten"), 2.0);
+        assert_eq!(
+            close.iter().map(|n| n.index).collect::<Vec<_>>(),
+            vec![0, 2, 1]
+        );
+    }
+
+    #[test]
+    fn points_are_metric_items() {
+        let pts: Vec<Point<f64>> = (0..20).map(|i| Point::new(vec![i as f64, 0.0])).collect();
+        let scan = LinearScan::new(pts.clone());
+        let tree = Euclidean(KdTree::new(&pts));
+        let q = Point::new(vec![4.4, 1.0]);
+        // Both answer through the same trait, and with it in scope the
+        // tree's own methods still resolve.
+        fn three<M: MetricIndex>(m: &M, q: &Point<f64>) -> Vec<Neighbor> {
+            m.k_nearest(q, 3)
+        }
+        assert_eq!(three(&scan, &q), three(&tree, &q));
+        assert_eq!(tree.0.k_nearest(&q, 3), three(&scan, &q));
+        assert!(
+            std::panic::catch_unwind(|| {
+                Point::new(vec![0.0, 0.0]).distance(&Point::new(vec![1.0]))
+            })
+            .is_err()
+        );
+        assert_eq!(
+            Point::new(vec![0, 0]).distance(&Point::new(vec![3, 4])),
+            5.0
+        );
+    }
+
+    #[test]
+    fn k_best() {
+        let mut best = KBest::new(2);
+        assert_eq!(best.worst(), f64::INFINITY);
+        for (i, d) in [(0, 3.0), (1, 1.0), (2, 2.0), (3, 1.0)] {
+            best.push(i, d);
+        }
+        assert_eq!(best.worst(), 1.0);
+        let v = best.into_neighbors();
+        assert_eq!(v.iter().map(|n| n.index).collect::<Vec<_>>(), vec![1, 3]);
+        assert!(KBest::<usize>::new(0).into_sorted_vec().is_empty());
+    }
+
+    #[test]
+    fn k_best_merge() {
+        let mut a: KBest<&str> = KBest::new(3);
+        a.extend([("x", 5.0), ("y", 1.0)]);
+        let mut b = KBest::new(3);
+        b.extend([("z", 2.0), ("w", 0.5), ("v", 9.0)]);
+        a.merge(b);
+        assert_eq!(a.len(), 3);
+        assert_eq!(
+            a.into_sorted_vec(),
+            vec![("w", 0.5), ("y", 1.0), ("z", 2.0)]
+        );
+    }
+
+    #[test]
+    fn radius_results() {
+        let mut a = RadiusResults::new(2.0);
+        a.extend([(4, 1.5), (1, 3.0), (0, 1.5)]);
+        let mut b = RadiusResults::new(10.0);
+        b.extend([(7, 0.1), (8, 5.0)]);
+        a.merge(b);
+        assert_eq!(a.len(), 3);
+        let n = a.into_neighbors();
+        assert_eq!(n.iter().map(|n| n.index).collect::<Vec<_>>(), vec![7, 0, 4]);
+    }
+}
@@ -0,0 +1,273 @@
+// A point cloud that keeps its indices in step with its edits.
+//
+// `IndexedCloud` owns both the cloud and an index built from it, and the only
+// way to edit the cloud is through the wrapper. Each edit is offered to the
+// index as a `Change`; an index that can fold it in stays live, any other is
+// dropped and rebuilt on the next query. Either way a query never sees a
+// stale index.
+
+use crate::cloud::Change;
+use crate::cover_tree::CoverTree;
+use crate::index::LinearScan;
+use crate::kdtree::KdTree;
+use crate::vptree::VpTree;
+use crate::{Error, Point, PointCloud};
+
+/// An index that can be derived from a cloud.
+pub trait DerivedIndex: Sized {
+    /// Builds the index over the cloud's current points; results refer to
+    /// positions in the cloud.
+    fn build(cloud: &PointCloud<f64>) -> Self;
+
+    /// Folds one edit, already applied to the cloud, into the index. Returns
+    /// false if the index can't, in which case it will be rebuilt.
+    fn update(&mut self, change: &Change<'_, f64>) -> bool {
+        let _ = change;
+        false
+    }
+}
+
+fn points(cloud: &PointCloud<f64>) -> Vec<Point<f64>> {
+    (0..cloud.len()).filter_map(|i| cloud.point(i)).collect()
+}
+
+impl DerivedIndex for KdTree {
+    fn build(cloud: &PointCloud<f64>) -> Self {
+        KdTree::from_parts(cloud.dim(), (0..cloud.len()).collect(), cloud.as_flat())
+    }
+}
+
+impl DerivedIndex for VpTree<Point<f64>> {
+    fn build(cloud: &PointCloud<f64>) -> Self {
+        VpTree::new(points(cloud))
+    }
+}
+
+impl DerivedIndex for CoverTree<Point<f64>> {
+    fn build(cloud: &PointCloud<f64>) -> Self {
+        CoverTree::new(points(cloud))
+    }
+
+    // Appends go straight in; anything that renumbers or moves a point
+    // needs a rebuild.
+    fn update(&mut self, change: &Change<'_, f64>) -> bool {
+        match *change {
+            Change::Inserted { index, coords } if index == self.items().len() => {
+                self.insert(Point::from(coords));
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl DerivedIndex for LinearScan<Point<f64>> {
+    fn build(cloud: &PointCloud<f64>) -> Self {
+        LinearScan::new(points(cloud))
+    }
+
+    fn update(&mut self, change: &Change<'_, f64>) -> bool {
+        match *change {
+            Change::Inserted { index, coords } => self.items.insert(index, Point::from(coords)),
+            Change::Removed { index, .. } => {
+                self.items.remove(index);
+            }
+            Change::Modified { index, new, .. } => self.items[index] = Point::from(new),
+        }
+        true
+    }
+}
+
+// Several indices over one cloud. A pair that can't take an edit is rebuilt
+// as a whole.
+impl<A: DerivedIndex, B: DerivedIndex> DerivedIndex for (A, B) {
+    fn build(cloud: &PointCloud<f64>) -> Self {
+        (A::build(cloud), B::build(cloud))
+    }
+
+    fn update(&mut self, change: &Change<'_, f64>) -> bool {
+        self.0.update(change) && self.1.update(change)
+    }
+}
+
+impl<A: DerivedIndex, B: DerivedIndex, C: DerivedIndex> DerivedIndex for (A, B, C) {
+    fn build(cloud: &PointCloud<f64>) -> Self {
+        (A::build(cloud), B::build(cloud), C::build(cloud))
+    }
+
+    fn update(&mut self, change: &Change<'_, f64>) -> bool {
+        self.0.update(change) && self.1.update(change) && self.2.update(change)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexedCloud<I> {
+    cloud: PointCloud<f64>,
+    index: Option<I>,
+    rebuilds: usize,
+}
+
+impl<I: DerivedIndex> IndexedCloud<I> {
+    /// Wraps `cloud`; the index is built on the first query.
+    pub fn new(cloud: PointCloud<f64>) -> Self {
+        IndexedCloud {
+            cloud,
+            index: None,
+            rebuilds: 0,
+        }
+    }
+
+    pub fn cloud(&self) -> &PointCloud<f64> {
+        &self.cloud
+    }
+
+    pub fn into_cloud(self) -> PointCloud<f64> {
+        self.cloud
+    }
+
+    /// The index, rebuilt first if an edit invalidated it.
+    pub fn index(&mut self) -> &I {
+        if self.index.is_none() {
+            self.rebuilds += 1;
+        }
+        self.index.get_or_insert_with(|| I::build(&self.cloud))
+    }
+
+    /// Whether the next `index()` call can answer without a rebuild.
+    pub fn is_fresh(&self) -> bool {
+        self.index.is_some()
+    }
+
+    /// How many times the index has been built so far.
+    pub fn rebuilds(&self) -> usize {
+        self.rebuilds
+    }
+
+    /// Appends a point and returns its index.
+    pub fn push(&mut self, coords: &[f64]) -> Result<usize, Error> {
+        let index = self.cloud.push(coords)?;
+        self.sync(&Change::Inserted { index, coords });
+        Ok(index)
+    }
+
+    /// Inserts a point at `i`, shifting later points up. Panics if `i` is past
+    /// the end.
+    pub fn insert(&mut self, i: usize, coords: &[f64]) -> Result<(), Error> {
+        self.cloud.insert(i, coords)?;
+        self.sync(&Change::Inserted { index: i, coords });
+        Ok(())
+    }
+
+    /// Removes point `i`, shifting later points down.
+    pub fn remove(&mut self, i: usize) -> Option<Point<f64>> {
+        let p = self.cloud.remove(i)?;
+        self.sync(&Change::Removed {
+            index: i,
+            coords: &p,
+        });
+        Some(p)
+    }
+
+    /// Overwrites point `i`; false if there is no such point.
+    pub fn set(&mut self, i: usize, coords: &[f64]) -> Result<bool, Error> {
+        let Some(old) = self.cloud.point(i) else {
+            return Ok(false);
+        };
+        self.cloud.set(i, coords)?;
+        self.sync(&Change::Modified {
+            index: i,
+            old: &old,
+            new: coords,
+        });
+        Ok(true)
+    }
+
+    /// Edits point `i` in place with `f`; false if there is no such point.
+    pub fn update<F: FnOnce(&mut [f64])>(&mut self, i: usize, f: F) -> bool {
+        let Some(mut p) = self.cloud.point(i) else {
+            return false;
+        };
+        f(&mut p);
+        self.set(i, &p).expect("dimension is unchanged")
+    }
+
+    fn sync(&mut self, change: &Change<'_, f64>) {
+        if let Some(index) = &mut self.index
+            && !index.update(change)
+        {
+            self.index = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::{MetricIndex, SpatialIndex};
+    use crate::rng::Rng;
+
+    fn random_cloud(rng: &mut Rng, n: usize) -> PointCloud<f64> {
+        let flat: Vec<f64> = (0..n * 2).map(|_| rng.range_f64(0.0, 10.0)).collect();
+        PointCloud::from_flat_vec(flat, 2).unwrap()
+    }
+
+    #[test]
+    fn never_stale() {
+        let mut rng = Rng::seed_from_u64(39);
+        let mut ic: IndexedCloud<(KdTree, LinearScan<Point<f64>>)> =
+            IndexedCloud::new(random_cloud(&mut rng, 100));
+        for step in 0..40 {
+            match step % 4 {
+                0 => {
+                    ic.push(&[rng.range_f64(0.0, 10.0), rng.range_f64(0.0, 10.0)])
+                        .unwrap();
+                }
+                1 => {
+                    ic.remove(step);
+                }
+                2 => {
+                    ic.update(step, |p| p[0] += 1.0);
+                }
+                _ => {
+                    ic.insert(3, &[5.0, 5.0]).unwrap();
+                }
+            }
+            let q = [rng.range_f64(0.0, 10.0), rng.range_f64(0.0, 10.0)];
+            let (kd, scan) = ic.index();
+            assert_eq!(
+                SpatialIndex::k_nearest(kd, &q, 4),
+                scan.k_nearest(&Point::from(&q[..]), 4)
+            );
+        }
+        assert_eq!(ic.cloud().len(), 110);
+    }
+
+    #[test]
+    fn incremental_updates_skip_rebuilds() {
+        let mut rng = Rng::seed_from_u64(40);
+        let mut ic: IndexedCloud<CoverTree<Point<f64>>> =
+            IndexedCloud::new(random_cloud(&mut rng, 50));
+        assert!(!ic.is_fresh());
+        ic.index();
+        for _ in 0..10 {
+            ic.push(&[rng.range_f64(0.0, 10.0), rng.range_f64(0.0, 10.0)])
+                .unwrap();
+        }
+        assert!(ic.is_fresh());
+        assert_eq!(ic.index().len(), 60);
+        assert_eq!(ic.rebuilds(), 1);
+
+        assert_eq!(ic.set(0, &[1.0, 1.0]), Ok(true));
+        assert!(!ic.is_fresh());
+        assert_eq!(
+            ic.index()
+                .nearest(&Point::new(vec![1.0, 1.1]))
+                .unwrap()
+                .index,
+            0
+        );
+        assert_eq!(ic.rebuilds(), 2);
+        assert_eq!(ic.set(99, &[1.0, 1.0]), Ok(false));
+        assert!(ic.set(0, &[1.0]).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+// Interpolation between two points.
+//
+// `lerp` moves along the straight line, `slerp` along the great circle
+// between two unit vectors at constant angular speed, which is what
+// animating a direction wants. Both accept any `t`; values outside [0, 1]
+// extrapolate.
+
+use crate::{Point, Storage};
+
+impl<T, S: Storage<T>> Point<T, S>
+where
+    T: Into<f64> + Copy,
+{
+    /// `self + t (other - self)`: `self` at 0, `other` at 1. Panics if the
+    /// dimensions differ, as do the others here.
+    pub fn lerp<O: Storage<T>>(&self, other: &Point<T, O>, t: f64) -> Point<f64> {
+        assert_eq!(self.dim(), other.dim(), "points must share a dimension");
+        Point::new(
+            self.iter()
+                .zip(other.iter())
+                .map(|(&a, &b)| {
+                    let (a, b) = (a.into(), b.into());
+                    a + t * (b - a)
+                })
+                .collect(),
+        )
+    }
+
+    /// The point halfway to `other`.
+    pub fn midpoint<O: Storage<T>>(&self, other: &Point<T, O>) -> Point<f64> {
+        self.lerp(other, 0.5)
+    }
+
+    /// Spherical interpolation between two unit vectors; both are normalised
+    /// first, so the result is a unit vector. Nearly parallel vectors fall
+    /// back to a normalised `lerp`, and opposite ones turn through an
+    /// arbitrary perpendicular, since every great circle joins them. Panics
+    /// on a zero vector.
+    pub fn slerp<O: Storage<T>>(&self, other: &Point<T, O>, t: f64) -> Point<f64> {
+        assert_eq!(self.dim(), other.dim(), "points must share a dimension");
+        let a = self.normalize();
+        let b = other.normalize();
+        let cos = a.dot(&b).clamp(-1.0, 1.0);
+        if cos > 1.0 - 1e-9 {
+            return a.lerp(&b, t).normalize();
+        }
+        // The unit vector perpendicular to `a` in the plane of rotation.
+        let (perp, angle) = if cos < -1.0 + 1e-9 {
+            (perpendicular(&a), std::f64::consts::PI)
+        } else {
+            (b.add_scaled(&a, -cos).normalize(), cos.acos())
+        };
+        let theta = angle * t;
+        Point::linear_combination(&[(theta.cos(), &a), (theta.sin(), &perp)])
+    }
+}
+
+// A unit vector perpendicular to the unit vector `a`, from the axis it
+// leans on least. One-dimensional space has none; `a` itself is returned.
+fn perpendicular(a: &Point<f64>) -> Point<f64> {
+    let axis = (0..a.dim())
+        .min_by(|&i, &j| a[i].abs().total_cmp(&a[j].abs()))
+        .expect("points have a dimension");
+    let mut e = vec![0.0; a.dim()];
+    e[axis] = 1.0;
+    let e = Point::new(e).add_scaled(a, -a[axis]);
+    e.try_normalize().unwrap_or_else(|_| a.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Point;
+
+    fn close(a: &Point<f64>, b: &[f64]) -> bool {
+        a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-12)
+    }
+
+    #[test]
+    fn lerp_and_midpoint() {
+        let a = Point::new(vec![0, 10, -4]);
+        let b = Point::from_array([10, 20, 4]);
+        assert_eq!(a.lerp(&b, 0.0).data(), [0.0, 10.0, -4.0]);
+        assert_eq!(a.lerp(&b, 0.25).data(), [2.5, 12.5, -2.0]);
+        assert_eq!(a.lerp(&b, 2.0).data(), [20.0, 30.0, 12.0]);
+        assert_eq!(a.midpoint(&b).data(), [5.0, 15.0, 0.0]);
+    }
+
+    #[test]
+    fn slerp_keeps_unit_length() {
+        let x = Point::new(vec![1.0, 0.0]);
+        let y = Point::new(vec![0.0, 3.0]);
+        let h = 0.5f64.sqrt();
+        assert!(close(&x.slerp(&y, 0.5), &[h, h]));
+        assert!(close(&x.slerp(&y, 1.0), &[0.0, 1.0]));
+        let third = x.slerp(&y, 1.0 / 3.0);
+        assert!(close(&third, &[(30f64).to_radians().cos(), 0.5]));
+
+        assert!(close(&x.slerp(&x, 0.7), &[1.0, 0.0]));
+        // Opposite vectors pass through a perpendicular.
+        let z = Point::new(vec![0.0, 0.0, 1.0]);
+        let mid = z.slerp(&Point::new(vec![0.0, 0.0, -1.0]), 0.5);
+        assert!(mid[2].abs() < 1e-12 && (mid.dist() - 1.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,269 @@
+// Spatial joins between two point sets, and all-nearest-neighbours within
+// one.
+//
+// Both sets go into k-d trees and the trees are walked together: a pair of
+// nodes whose bounding boxes are too far apart is dropped with all the point
+// pairs under it, so large joins cost far less than one query per point.
+
+use crate::boxtree::BoxTree;
+use crate::index::{KBest, Neighbor, squared_distance};
+use crate::{Error, Point};
+
+/// Point `a` of the first set and point `b` of the second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pair {
+    pub a: usize,
+    pub b: usize,
+    pub distance: f64,
+}
+
+fn build_pair<T>(a: &[Point<T>], b: &[Point<T>]) -> Result<Option<(BoxTree, BoxTree)>, Error>
+where
+    T: Into<f64> + Copy,
+{
+    let (Some(first), false) = (a.first(), b.is_empty()) else {
+        return Ok(None);
+    };
+    let dim = first.dim();
+    Ok(Some((BoxTree::build(a, dim)?, BoxTree::build(b, dim)?)))
+}
+
+/// All pairs of a point in `a` and a point in `b` at most `radius` apart,
+/// ordered by `a` then `b`.
+pub fn spatial_join<T>(a: &[Point<T>], b: &[Point<T>], radius: f64) -> Result<Vec<Pair>, Error>
+where
+    T: Into<f64> + Copy,
+{
+    let mut out = Vec::new();
+    if let Some((ta, tb)) = build_pair(a, b)? {
+        join_radius(&ta, 0, &tb, 0, radius * radius, &mut out);
+    }
+    out.sort_unstable_by_key(|p| (p.a, p.b));
+    Ok(out)
+}
+
+fn join_radius(ta: &BoxTree, na: usize, tb: &BoxTree, nb: usize, r2: f64, out: &mut Vec<Pair>) {
+    if ta.min_sq(na, tb, nb) > r2 {
+        return;
+    }
+    // Split the bigger node, or the only one that can be split.
+    let (ca, cb) = (ta.kd.children(na), tb.kd.children(nb));
+    let split_a = ca.is_some() && (cb.is_none() || ta.size(na) >= tb.size(nb));
+    if let (true, Some((l, r))) = (split_a, ca) {
+        join_radius(ta, l, tb, nb, r2, out);
+        join_radius(ta, r, tb, nb, r2, out);
+    } else if let Some((l, r)) = cb {
+        join_radius(ta, na, tb, l, r2, out);
+        join_radius(ta, na, tb, r, r2, out);
+    } else {
+        let (a0, a1) = ta.slots[na];
+        let (b0, b1) = tb.slots[nb];
+        for sa in a0..a1 {
+            for sb in b0..b1 {
+                let d2 = squared_distance(ta.kd.point(sa), tb.kd.point(sb));
+                if d2 <= r2 {
+                    out.push(Pair {
+                        a: ta.kd.id(sa),
+                        b: tb.kd.id(sb),
+                        distance: d2.sqrt(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// The `k` nearest points of `b` to each point of `a`, nearest first;
+/// entry `i` belongs to `a[i]`.
+pub fn nearest_join<T>(
+    a: &[Point<T>],
+    b: &[Point<T>],
+    k: usize,
+) -> Result<Vec<Vec<Neighbor>>, Error>
+where
+    T: Into<f64> + Copy,
+{
+    let Some((ta, tb)) = build_pair(a, b)? else {
+        return Ok(vec![Vec::new(); a.len()]);
+    };
+    Ok(KnnJoin::new(&ta, &tb, a.len(), k, false).run())
+}
+
+/// The `k` nearest other points of every point in `points`, nearest first;
+/// entry `i` belongs to `points[i]` and never lists `i` itself. Duplicates
+/// of a point do count as its neighbours, at distance zero.
+pub fn all_k_nearest<T>(points: &[Point<T>], k: usize) -> Result<Vec<Vec<Neighbor>>, Error>
+where
+    T: Into<f64> + Copy,
+{
+    let Some(first) = points.first() else {
+        return Ok(Vec::new());
+    };
+    let tree = BoxTree::build(points, first.dim())?;
+    Ok(KnnJoin::new(&tree, &tree, points.len(), k, true).run())
+}
+
+// Dual-tree k-nearest search of the reference tree for every query point.
+// `bound[nq]` is at least the largest squared k-th best distance of any query
+// under `nq`: a reference node farther than that can't help any of them.
+struct KnnJoin<'a> {
+    tq: &'a BoxTree,
+    tr: &'a BoxTree,
+    best: Vec<KBest>,
+    bound: Vec<f64>,
+    // Both trees are the same set; a point is not its own neighbour.
+    same: bool,
+}
+
+impl<'a> KnnJoin<'a> {
+    fn new(tq: &'a BoxTree, tr: &'a BoxTree, queries: usize, k: usize, same: bool) -> Self {
+        KnnJoin {
+            tq,
+            tr,
+            best: (0..queries).map(|_| KBest::new(k)).collect(),
+            bound: vec![f64::INFINITY; tq.kd.node_count()],
+            same,
+        }
+    }
+
+    fn run(mut self) -> Vec<Vec<Neighbor>> {
+        if self.best.first().is_some_and(|b| b.k() > 0) {
+            self.visit(0, 0);
+        }
+        self.best
+            .into_iter()
+            .map(|b| {
+                b.into_neighbors()
+                    .into_iter()
+                    .map(|n| Neighbor {
+                        index: n.index,
+                        distance: n.distance.sqrt(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn visit(&mut self, nq: usize, nr: usize) {
+        let (tq, tr) = (self.tq, self.tr);
+        if tq.min_sq(nq, tr, nr) > self.bound[nq] {
+            return;
+        }
+        let (cq, cr) = (tq.kd.children(nq), tr.kd.children(nr));
+        let split_q = cq.is_some() && (cr.is_none() || tq.size(nq) >= tr.size(nr));
+        if let (true, Some((l, r))) = (split_q, cq) {
+            self.visit(l, nr);
+            self.visit(r, nr);
+            self.bound[nq] = self.bound[l].max(self.bound[r]);
+        } else if let Some((l, r)) = cr {
+            // Nearer reference child first so the bound tightens sooner.
+            let (near, far) = if tq.min_sq(nq, tr, l) <= tq.min_sq(nq, tr, r) {
+                (l, r)
+            } else {
+                (r, l)
+            };
+            self.visit(nq, near);
+            self.visit(nq, far);
+        } else {
+            let (q0, q1) = tq.slots[nq];
+            let (r0, r1) = tr.slots[nr];
+            let mut worst = 0.0f64;
+            for sq in q0..q1 {
+                let qid = tq.kd.id(sq);
+                let kb = &mut self.best[qid];
+                let q = tq.kd.point(sq);
+                for sr in r0..r1 {
+                    let rid = tr.kd.id(sr);
+                    if !(self.same && rid == qid) {
+                        kb.push(rid, squared_distance(q, tr.kd.point(sr)));
+                    }
+                }
+                worst = worst.max(kb.worst());
+            }
+            self.bound[nq] = worst;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::SpatialIndex;
+    use crate::kdtree::KdTree;
+    use crate::rng::Rng;
+
+    fn random(rng: &mut Rng, n: usize) -> Vec<Point<f64>> {
+        (0..n)
+            .map(|_| Point::new((0..3).map(|_| rng.range_f64(0.0, 10.0)).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn radius_matches_brute_force() {
+        let mut rng = Rng::seed_from_u64(40);
+        let a = random(&mut rng, 300);
+        let b = random(&mut rng, 200);
+        let got = spatial_join(&a, &b, 1.5).unwrap();
+        let mut want = Vec::new();
+        for (i, p) in a.iter().enumerate() {
+            for (j, q) in b.iter().enumerate() {
+                let d = squared_distance(p, q).sqrt();
+                if d <= 1.5 {
+                    want.push((i, j));
+                }
+            }
+        }
+        assert!(!want.is_empty());
+        assert_eq!(got.iter().map(|p| (p.a, p.b)).collect::<Vec<_>>(), want);
+    }
+
+    #[test]
+    fn nearest_matches_queries() {
+        let mut rng = Rng::seed_from_u64(41);
+        let a = random(&mut rng, 250);
+        let b = random(&mut rng, 400);
+        let got = nearest_join(&a, &b, 4).unwrap();
+        let tree = KdTree::new(&b);
+        for (p, hits) in a.iter().zip(&got) {
+            assert_eq!(hits, &tree.k_nearest(p, 4));
+        }
+    }
+
+    #[test]
+    fn all_k_nearest_excludes_self() {
+        let mut rng = Rng::seed_from_u64(42);
+        let mut pts = random(&mut rng, 500);
+        pts.push(pts[7].clone());
+        let got = all_k_nearest(&pts, 3).unwrap();
+        let tree = KdTree::new(&pts);
+        for (i, (p, hits)) in pts.iter().zip(&got).enumerate() {
+            let want: Vec<Neighbor> = tree
+                .k_nearest(p, 4)
+                .into_iter()
+                .filter(|n| n.index != i)
+                .take(3)
+                .collect();
+            assert_eq!(hits, &want);
+        }
+        assert_eq!(got[7][0].index, 500);
+        assert_eq!(got[500][0].index, 7);
+        assert_eq!(got[7][0].distance, 0.0);
+        assert!(all_k_nearest::<f64>(&[], 2).unwrap().is_empty());
+        assert!(all_k_nearest(&pts[..1], 2).unwrap()[0].is_empty());
+    }
+
+    #[test]
+    fn edge_cases() {
+        let a = vec![Point::new(vec![0.0, 0.0])];
+        assert!(spatial_join(&a, &[], 1.0).unwrap().is_empty());
+        assert_eq!(nearest_join(&a, &[], 3).unwrap(), vec![Vec::new()]);
+        assert!(nearest_join(&a, &a, 0).unwrap()[0].is_empty());
+        assert_eq!(
+            spatial_join(&a, &[Point::new(vec![1.0])], 1.0),
+            Err(Error::DimensionMismatch {
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+}
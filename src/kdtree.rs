@@ -0,0 +1,1058 @@
+// k-d trees: a static one and a dynamic forest of them.
+//
+// `KdTree` splits at the median of the axis with the largest spread and keeps
+// the coordinates in one flat buffer in tree order. `KdForest` supports
+// inserts and deletes with the logarithmic method: it keeps at most one tree
+// of each size 2^i and merges equal-sized trees on insert, so an insert costs
+// O(log^2 n) amortised. Deleted points are skipped by queries and the whole
+// forest is rebuilt once they outnumber the live ones.
+
+#[cfg(feature = "io")]
+use std::io::{self, Read, Write};
+
+use crate::index::{IndexStats, KBest, Neighbor, SpatialIndex, squared_distance};
+#[cfg(feature = "io")]
+use crate::persist::{self, invalid};
+use crate::{Error, Point};
+
+const LEAF_SIZE: usize = 8;
+#[cfg(feature = "io")]
+const MAGIC: &[u8; 4] = b"NDKD";
+#[cfg(feature = "io")]
+const VERSION: u32 = 1;
+// The largest dimension `load` accepts for a tree with no points.
+#[cfg(feature = "io")]
+const MAX_EMPTY_DIM: usize = 1 << 16;
+
+#[derive(Debug, Clone)]
+enum Node {
+    Leaf {
+        lo: usize,
+        hi: usize,
+    },
+    Split {
+        axis: usize,
+        value: f64,
+        left: usize,
+        right: usize,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct KdTree {
+    dim: usize,
+    coords: Vec<f64>,
+    ids: Vec<usize>,
+    nodes: Vec<Node>,
+    // Bounding box of all points, lower corner then upper.
+    bounds: Vec<f64>,
+}
+
+impl KdTree {
+    /// Builds a tree over `points`; results refer to positions in `points`.
+    /// Panics if the points don't share a dimension.
+    pub fn new<T>(points: &[Point<T>]) -> Self
+    where
+        T: Into<f64> + Copy,
+    {
+        let dim = points.first().map_or(0, |p| p.dim());
+        let mut flat = Vec::with_capacity(points.len() * dim);
+        for p in points {
+            assert_eq!(p.dim(), dim, "points must share a dimension");
+            flat.extend(p.p.iter().map(|&v| v.into()));
+        }
+        KdTree::from_parts(dim, (0..points.len()).collect(), &flat)
+    }
+
+    // `flat` holds the coordinates of `ids[i]` at `i * dim`.
+    pub(crate) fn from_parts(dim: usize, ids: Vec<usize>, flat: &[f64]) -> Self {
+        let mut perm: Vec<usize> = (0..ids.len()).collect();
+        let mut nodes = Vec::new();
+        build(&mut nodes, flat, dim, &mut perm, 0);
+        let mut coords = Vec::with_capacity(flat.len());
+        for &i in &perm {
+            coords.extend_from_slice(&flat[i * dim..(i + 1) * dim]);
+        }
+        KdTree {
+            bounds: bounds(&coords, dim),
+            dim,
+            coords,
+            ids: perm.iter().map(|&i| ids[i]).collect(),
+            nodes,
+        }
+    }
+
+    pub fn stats(&self) -> IndexStats {
+        let mut leaves = Vec::new();
+        self.collect_leaves(0, 0, &mut leaves);
+        IndexStats::from_leaves(
+            self.ids.len(),
+            self.nodes.len(),
+            &leaves,
+            LEAF_SIZE,
+            self.memory_bytes(),
+        )
+    }
+
+    fn memory_bytes(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.coords.capacity() * std::mem::size_of::<f64>()
+            + self.ids.capacity() * std::mem::size_of::<usize>()
+            + self.nodes.capacity() * std::mem::size_of::<Node>()
+            + self.bounds.capacity() * std::mem::size_of::<f64>()
+    }
+
+    fn collect_leaves(&self, node: usize, depth: usize, out: &mut Vec<(usize, usize)>) {
+        match self.nodes[node] {
+            Node::Leaf { lo, hi } => out.push((depth, hi - lo)),
+            Node::Split { left, right, .. } => {
+                self.collect_leaves(left, depth + 1, out);
+                self.collect_leaves(right, depth + 1, out);
+            }
+        }
+    }
+
+    /// Checks the tree's invariants: every node is reached exactly once, the
+    /// leaves cover every point once, and each split separates its subtrees.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.coords.len() != self.ids.len() * self.dim {
+            return Err(Error::InvalidIndex("coordinate buffer length"));
+        }
+        let mut seen_nodes = vec![false; self.nodes.len()];
+        let mut seen_slots = vec![false; self.ids.len()];
+        self.validate_in(0, &mut seen_nodes, &mut seen_slots)?;
+        if !seen_nodes.iter().all(|&s| s) {
+            return Err(Error::InvalidIndex("unreachable node"));
+        }
+        if !seen_slots.iter().all(|&s| s) {
+            return Err(Error::InvalidIndex("point in no leaf"));
+        }
+        Ok(())
+    }
+
+    // Returns the slot range under `node`.
+    fn validate_in(
+        &self,
+        node: usize,
+        seen_nodes: &mut [bool],
+        seen_slots: &mut [bool],
+    ) -> Result<(usize, usize), Error> {
+        if node >= seen_nodes.len() || std::mem::replace(&mut seen_nodes[node], true) {
+            return Err(Error::InvalidIndex("node reached twice or out of range"));
+        }
+        match self.nodes[node] {
+            Node::Leaf { lo, hi } => {
+                if lo > hi || hi > seen_slots.len() {
+                    return Err(Error::InvalidIndex("leaf range out of bounds"));
+                }
+                for s in &mut seen_slots[lo..hi] {
+                    if std::mem::replace(s, true) {
+                        return Err(Error::InvalidIndex("point in two leaves"));
+                    }
+                }
+                Ok((lo, hi))
+            }
+            Node::Split {
+                axis,
+                value,
+                left,
+                right,
+            } => {
+                if axis >= self.dim {
+                    return Err(Error::InvalidIndex("split axis out of range"));
+                }
+                let (llo, lhi) = self.validate_in(left, seen_nodes, seen_slots)?;
+                let (rlo, rhi) = self.validate_in(right, seen_nodes, seen_slots)?;
+                let left_ok = (llo..lhi).all(|s| self.point(s)[axis] <= value);
+                let right_ok = (rlo..rhi).all(|s| self.point(s)[axis] >= value);
+                if !left_ok || !right_ok {
+                    return Err(Error::InvalidIndex("point on the wrong side of a split"));
+                }
+                Ok((llo.min(rlo), lhi.max(rhi)))
+            }
+        }
+    }
+
+    pub(crate) fn point(&self, slot: usize) -> &[f64] {
+        &self.coords[slot * self.dim..(slot + 1) * self.dim]
+    }
+
+    // Tree structure for the dual-tree algorithms in `join`. Node 0 is the
+    // root and the points under any node occupy a contiguous run of slots.
+    pub(crate) fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub(crate) fn children(&self, node: usize) -> Option<(usize, usize)> {
+        match self.nodes[node] {
+            Node::Leaf { .. } => None,
+            Node::Split { left, right, .. } => Some((left, right)),
+        }
+    }
+
+    pub(crate) fn leaf_slots(&self, node: usize) -> Option<(usize, usize)> {
+        match self.nodes[node] {
+            Node::Leaf { lo, hi } => Some((lo, hi)),
+            Node::Split { .. } => None,
+        }
+    }
+
+    pub(crate) fn id(&self, slot: usize) -> usize {
+        self.ids[slot]
+    }
+
+    // Squared distances go into `best`; `keep` filters ids.
+    pub(crate) fn search_k(&self, q: &[f64], best: &mut KBest, keep: &dyn Fn(usize) -> bool) {
+        if !self.ids.is_empty() {
+            self.search_k_in(0, q, best, keep);
+        }
+    }
+
+    fn search_k_in(&self, node: usize, q: &[f64], best: &mut KBest, keep: &dyn Fn(usize) -> bool) {
+        match self.nodes[node] {
+            Node::Leaf { lo, hi } => {
+                for slot in lo..hi {
+                    let id = self.ids[slot];
+                    if keep(id) {
+                        best.push(id, squared_distance(self.point(slot), q));
+                    }
+                }
+            }
+            Node::Split {
+                axis,
+                value,
+                left,
+                right,
+            } => {
+                let diff = q[axis] - value;
+                let (near, far) = if diff < 0.0 {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+                self.search_k_in(near, q, best, keep);
+                if diff * diff <= best.worst() {
+                    self.search_k_in(far, q, best, keep);
+                }
+            }
+        }
+    }
+
+    // Like `search_k` for the farthest points: negated squared distances go
+    // into `best`. A node is skipped when even its box's farthest corner is
+    // nearer than the current k-th farthest point.
+    pub(crate) fn search_far(&self, q: &[f64], best: &mut KBest, keep: &dyn Fn(usize) -> bool) {
+        if !self.ids.is_empty() {
+            let (lo, hi) = self.bounds.split_at(self.dim);
+            let (mut lo, mut hi) = (lo.to_vec(), hi.to_vec());
+            self.search_far_in(0, q, &mut lo, &mut hi, best, keep);
+        }
+    }
+
+    fn search_far_in(
+        &self,
+        node: usize,
+        q: &[f64],
+        lo: &mut [f64],
+        hi: &mut [f64],
+        best: &mut KBest,
+        keep: &dyn Fn(usize) -> bool,
+    ) {
+        let reach: f64 = q
+            .iter()
+            .zip(lo.iter().zip(hi.iter()))
+            .map(|(v, (l, h))| {
+                let d = (v - l).abs().max((v - h).abs());
+                d * d
+            })
+            .sum();
+        if -reach > best.worst() {
+            return;
+        }
+        match self.nodes[node] {
+            Node::Leaf { lo: s, hi: e } => {
+                for slot in s..e {
+                    let id = self.ids[slot];
+                    if keep(id) {
+                        best.push(id, -squared_distance(self.point(slot), q));
+                    }
+                }
+            }
+            Node::Split {
+                axis,
+                value,
+                left,
+                right,
+            } => {
+                // The half away from the query first.
+                let sides = if q[axis] < value {
+                    [(right, false), (left, true)]
+                } else {
+                    [(left, true), (right, false)]
+                };
+                for (child, is_left) in sides {
+                    let edge = if is_left {
+                        &mut hi[axis]
+                    } else {
+                        &mut lo[axis]
+                    };
+                    let saved = std::mem::replace(edge, value);
+                    self.search_far_in(child, q, lo, hi, best, keep);
+                    if is_left {
+                        hi[axis] = saved;
+                    } else {
+                        lo[axis] = saved;
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn search_radius(
+        &self,
+        q: &[f64],
+        radius: f64,
+        out: &mut Vec<Neighbor>,
+        keep: &dyn Fn(usize) -> bool,
+    ) {
+        if !self.ids.is_empty() {
+            self.search_radius_in(0, q, radius, out, keep);
+        }
+    }
+
+    fn search_radius_in(
+        &self,
+        node: usize,
+        q: &[f64],
+        radius: f64,
+        out: &mut Vec<Neighbor>,
+        keep: &dyn Fn(usize) -> bool,
+    ) {
+        match self.nodes[node] {
+            Node::Leaf { lo, hi } => {
+                for slot in lo..hi {
+                    let id = self.ids[slot];
+                    let d = squared_distance(self.point(slot), q).sqrt();
+                    if d <= radius && keep(id) {
+                        out.push(Neighbor {
+                            index: id,
+                            distance: d,
+                        });
+                    }
+                }
+            }
+            Node::Split {
+                axis,
+                value,
+                left,
+                right,
+            } => {
+                let diff = q[axis] - value;
+                if diff <= radius {
+                    self.search_radius_in(left, q, radius, out, keep);
+                }
+                if diff >= -radius {
+                    self.search_radius_in(right, q, radius, out, keep);
+                }
+            }
+        }
+    }
+}
+
+fn build(nodes: &mut Vec<Node>, flat: &[f64], dim: usize, perm: &mut [usize], lo: usize) -> usize {
+    let node = nodes.len();
+    let leaf = Node::Leaf {
+        lo,
+        hi: lo + perm.len(),
+    };
+    if perm.len() <= LEAF_SIZE {
+        nodes.push(leaf);
+        return node;
+    }
+    let coord = |i: usize, axis: usize| flat[i * dim + axis];
+    // Every axis's spread in one pass over the points.
+    let mut min = vec![f64::INFINITY; dim];
+    let mut max = vec![f64::NEG_INFINITY; dim];
+    for &i in perm.iter() {
+        for (axis, &v) in flat[i * dim..(i + 1) * dim].iter().enumerate() {
+            min[axis] = min[axis].min(v);
+            max[axis] = max[axis].max(v);
+        }
+    }
+    let spread: Vec<f64> = max.iter().zip(&min).map(|(hi, lo)| hi - lo).collect();
+    let axis = (0..dim).max_by(|&a, &b| spread[a].total_cmp(&spread[b]));
+    let Some(axis) = axis.filter(|&a| spread[a] > 0.0) else {
+        // All points coincide.
+        nodes.push(leaf);
+        return node;
+    };
+    let mid = perm.len() / 2;
+    perm.select_nth_unstable_by(mid, |&a, &b| coord(a, axis).total_cmp(&coord(b, axis)));
+    let value = coord(perm[mid], axis);
+    nodes.push(leaf);
+    let (l, r) = perm.split_at_mut(mid);
+    let left = build(nodes, flat, dim, l, lo);
+    let right = build(nodes, flat, dim, r, lo + mid);
+    nodes[node] = Node::Split {
+        axis,
+        value,
+        left,
+        right,
+    };
+    node
+}
+
+// Lower corner then upper corner of the box around `coords`.
+fn bounds(coords: &[f64], dim: usize) -> Vec<f64> {
+    let mut b = vec![f64::INFINITY; dim];
+    b.extend(vec![f64::NEG_INFINITY; dim]);
+    for p in coords.chunks_exact(dim.max(1)) {
+        for (k, &v) in p.iter().enumerate() {
+            b[k] = b[k].min(v);
+            b[dim + k] = b[dim + k].max(v);
+        }
+    }
+    b
+}
+
+fn finish_far(best: KBest) -> Vec<Neighbor> {
+    best.into_neighbors()
+        .into_iter()
+        .map(|n| Neighbor {
+            index: n.index,
+            distance: (-n.distance).sqrt(),
+        })
+        .collect()
+}
+
+fn finish(best: KBest) -> Vec<Neighbor> {
+    best.into_neighbors()
+        .into_iter()
+        .map(|n| Neighbor {
+            index: n.index,
+            distance: n.distance.sqrt(),
+        })
+        .collect()
+}
+
+fn sort_neighbors(mut v: Vec<Neighbor>) -> Vec<Neighbor> {
+    v.sort_unstable();
+    v
+}
+
+#[cfg(feature = "io")]
+impl KdTree {
+    /// Writes the tree in a versioned binary format, so it can be loaded
+    /// without rebuilding.
+    pub fn save<W: Write>(&self, mut w: W) -> io::Result<()> {
+        persist::write_header(&mut w, MAGIC, VERSION)?;
+        persist::write_usize(&mut w, self.dim)?;
+        persist::write_f64s(&mut w, &self.coords)?;
+        persist::write_usizes(&mut w, &self.ids)?;
+        persist::write_usize(&mut w, self.nodes.len())?;
+        for node in &self.nodes {
+            match *node {
+                Node::Leaf { lo, hi } => {
+                    persist::write_u8(&mut w, 0)?;
+                    persist::write_usize(&mut w, lo)?;
+                    persist::write_usize(&mut w, hi)?;
+                }
+                Node::Split {
+                    axis,
+                    value,
+                    left,
+                    right,
+                } => {
+                    persist::write_u8(&mut w, 1)?;
+                    persist::write_usize(&mut w, axis)?;
+                    persist::write_f64(&mut w, value)?;
+                    persist::write_usize(&mut w, left)?;
+                    persist::write_usize(&mut w, right)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a tree written by [`KdTree::save`]. Fails with
+    /// `ErrorKind::InvalidData` on a foreign, newer or inconsistent file.
+    pub fn load<R: Read>(mut r: R) -> io::Result<Self> {
+        persist::read_header(&mut r, MAGIC, VERSION)?;
+        let dim = persist::read_usize(&mut r)?;
+        let coords = persist::read_f64s(&mut r)?;
+        let ids = persist::read_usizes(&mut r)?;
+        let count = persist::read_usize(&mut r)?;
+        let mut nodes = Vec::with_capacity(count.min(1 << 16));
+        for _ in 0..count {
+            let node = match persist::read_u8(&mut r)? {
+                0 => Node::Leaf {
+                    lo: persist::read_usize(&mut r)?,
+                    hi: persist::read_usize(&mut r)?,
+                },
+                1 => Node::Split {
+                    axis: persist::read_usize(&mut r)?,
+                    value: persist::read_f64(&mut r)?,
+                    left: persist::read_usize(&mut r)?,
+                    right: persist::read_usize(&mut r)?,
+                },
+                _ => return Err(invalid("unknown node tag")),
+            };
+            nodes.push(node);
+        }
+        // An empty tree has no coordinates to vouch for its dimension, so
+        // that is capped too before the bounds are allocated from it.
+        let consistent = ids.len().checked_mul(dim) == Some(coords.len())
+            && dim <= coords.len().max(MAX_EMPTY_DIM)
+            && !nodes.is_empty();
+        if !consistent {
+            return Err(invalid("inconsistent k-d tree"));
+        }
+        let tree = KdTree {
+            bounds: bounds(&coords, dim),
+            dim,
+            coords,
+            ids,
+            nodes,
+        };
+        // Queries recurse through the nodes, so a cycle or shared child in
+        // a corrupt file must be caught here.
+        tree.validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(tree)
+    }
+}
+
+impl SpatialIndex for KdTree {
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    fn k_nearest_where(
+        &self,
+        query: &[f64],
+        k: usize,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor> {
+        let mut best = KBest::new(k);
+        self.search_k(query, &mut best, keep);
+        finish(best)
+    }
+
+    fn within_radius_where(
+        &self,
+        query: &[f64],
+        radius: f64,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor> {
+        let mut out = Vec::new();
+        self.search_radius(query, radius, &mut out, keep);
+        sort_neighbors(out)
+    }
+
+    fn k_farthest_where(
+        &self,
+        query: &[f64],
+        k: usize,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor> {
+        let mut best = KBest::new(k);
+        self.search_far(query, &mut best, keep);
+        finish_far(best)
+    }
+}
+
+/// A k-d tree index supporting interleaved inserts, deletes and queries.
+#[derive(Debug, Clone)]
+pub struct KdForest {
+    dim: usize,
+    levels: Vec<Option<KdTree>>,
+    coords: Vec<f64>,
+    alive: Vec<bool>,
+    live: usize,
+    // Deleted points still stored in some tree.
+    dead: usize,
+}
+
+impl KdForest {
+    pub fn new(dim: usize) -> Self {
+        KdForest {
+            dim,
+            levels: Vec::new(),
+            coords: Vec::new(),
+            alive: Vec::new(),
+            live: 0,
+            dead: 0,
+        }
+    }
+
+    /// Adds a point and returns its id. Ids are never reused.
+    pub fn insert<T>(&mut self, p: &Point<T>) -> Result<usize, Error>
+    where
+        T: Into<f64> + Copy,
+    {
+        if p.dim() != self.dim {
+            return Err(Error::DimensionMismatch {
+                expected: self.dim,
+                found: p.dim(),
+            });
+        }
+        let id = self.alive.len();
+        self.coords.extend(p.p.iter().map(|&v| v.into()));
+        self.alive.push(true);
+        self.live += 1;
+
+        let mut carry = vec![id];
+        let mut level = 0;
+        loop {
+            if level == self.levels.len() {
+                self.levels.push(None);
+            }
+            match self.levels[level].take() {
+                None => {
+                    self.levels[level] = Some(self.build_tree(carry));
+                    break;
+                }
+                Some(tree) => {
+                    for id in tree.ids {
+                        if self.alive[id] {
+                            carry.push(id);
+                        } else {
+                            self.dead -= 1;
+                        }
+                    }
+                    level += 1;
+                }
+            }
+        }
+        Ok(id)
+    }
+
+    /// Deletes point `id`. Returns false if it wasn't present.
+    pub fn remove(&mut self, id: usize) -> bool {
+        if !self.alive.get(id).copied().unwrap_or(false) {
+            return false;
+        }
+        self.alive[id] = false;
+        self.live -= 1;
+        self.dead += 1;
+        if self.dead > self.live {
+            self.rebuild();
+        }
+        true
+    }
+
+    /// Coordinates of point `id` if it is present.
+    pub fn get(&self, id: usize) -> Option<&[f64]> {
+        if *self.alive.get(id)? {
+            Some(&self.coords[id * self.dim..(id + 1) * self.dim])
+        } else {
+            None
+        }
+    }
+
+    /// Number of static trees currently making up the forest.
+    pub fn tree_count(&self) -> usize {
+        self.levels.iter().flatten().count()
+    }
+
+    /// Statistics summed over the trees; `depth` is the deepest tree's.
+    pub fn stats(&self) -> IndexStats {
+        let mut leaves = Vec::new();
+        let mut nodes = 0;
+        let mut memory = std::mem::size_of::<Self>()
+            + self.coords.capacity() * std::mem::size_of::<f64>()
+            + self.alive.capacity()
+            + self.levels.capacity() * std::mem::size_of::<Option<KdTree>>();
+        for tree in self.levels.iter().flatten() {
+            tree.collect_leaves(0, 0, &mut leaves);
+            nodes += tree.nodes.len();
+            memory += tree.memory_bytes() - std::mem::size_of::<KdTree>();
+        }
+        IndexStats::from_leaves(self.live, nodes, &leaves, LEAF_SIZE, memory)
+    }
+
+    /// Validates every tree and the live and deleted counts.
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut stored = 0;
+        let mut dead = 0;
+        for tree in self.levels.iter().flatten() {
+            tree.validate()?;
+            if tree.dim != self.dim {
+                return Err(Error::InvalidIndex("tree dimension"));
+            }
+            for &id in &tree.ids {
+                match self.alive.get(id) {
+                    Some(true) => stored += 1,
+                    Some(false) => dead += 1,
+                    None => return Err(Error::InvalidIndex("unknown id")),
+                }
+            }
+        }
+        if stored != self.live || dead != self.dead {
+            return Err(Error::InvalidIndex("live or deleted count"));
+        }
+        Ok(())
+    }
+
+    fn build_tree(&self, ids: Vec<usize>) -> KdTree {
+        let mut flat = Vec::with_capacity(ids.len() * self.dim);
+        for &id in &ids {
+            flat.extend_from_slice(&self.coords[id * self.dim..(id + 1) * self.dim]);
+        }
+        KdTree::from_parts(self.dim, ids, &flat)
+    }
+
+    // Redistributes the live points into trees following the binary digits
+    // of their count.
+    fn rebuild(&mut self) {
+        let mut ids: Vec<usize> = (0..self.alive.len()).filter(|&i| self.alive[i]).collect();
+        self.levels.clear();
+        self.dead = 0;
+        let mut level = 0;
+        while !ids.is_empty() {
+            let size = 1usize << level;
+            if self.live & size != 0 {
+                let rest = ids.split_off(size);
+                let tree = self.build_tree(ids);
+                ids = rest;
+                self.levels.push(Some(tree));
+            } else {
+                self.levels.push(None);
+            }
+            level += 1;
+        }
+    }
+}
+
+impl SpatialIndex for KdForest {
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    fn len(&self) -> usize {
+        self.live
+    }
+
+    fn k_nearest_where(
+        &self,
+        query: &[f64],
+        k: usize,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor> {
+        let mut best = KBest::new(k);
+        let keep = |id: usize| self.alive[id] && keep(id);
+        for tree in self.levels.iter().flatten() {
+            tree.search_k(query, &mut best, &keep);
+        }
+        finish(best)
+    }
+
+    fn within_radius_where(
+        &self,
+        query: &[f64],
+        radius: f64,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor> {
+        let mut out = Vec::new();
+        let keep = |id: usize| self.alive[id] && keep(id);
+        for tree in self.levels.iter().flatten() {
+            tree.search_radius(query, radius, &mut out, &keep);
+        }
+        sort_neighbors(out)
+    }
+
+    fn k_farthest_where(
+        &self,
+        query: &[f64],
+        k: usize,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor> {
+        let mut best = KBest::new(k);
+        let keep = |id: usize| self.alive[id] && keep(id);
+        for tree in self.levels.iter().flatten() {
+            tree.search_far(query, &mut best, &keep);
+        }
+        finish_far(best)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn random_points(rng: &mut Rng, n: usize, dim: usize) -> Vec<Point<f64>> {
+        (0..n)
+            .map(|_| Point::new((0..dim).map(|_| rng.range_f64(-10.0, 10.0)).collect()))
+            .collect()
+    }
+
+    fn brute(pts: &[(usize, &Point<f64>)], q: &[f64], k: usize) -> Vec<usize> {
+        let mut all: Vec<Neighbor> = pts
+            .iter()
+            .map(|&(i, p)| Neighbor {
+                index: i,
+                distance: squared_distance(p, q).sqrt(),
+            })
+            .collect();
+        all.sort();
+        all.iter().take(k).map(|n| n.index).collect()
+    }
+
+    #[test]
+    fn kd_tree_queries() {
+        let mut rng = Rng::seed_from_u64(11);
+        let pts = random_points(&mut rng, 300, 3);
+        let tree = KdTree::new(&pts);
+        assert_eq!(tree.len(), 300);
+        let indexed: Vec<(usize, &Point<f64>)> = pts.iter().enumerate().collect();
+        for q in random_points(&mut rng, 20, 3) {
+            let got: Vec<usize> = tree.k_nearest(&q, 5).iter().map(|n| n.index).collect();
+            assert_eq!(got, brute(&indexed, &q, 5));
+            let within: Vec<usize> = tree
+                .within_radius(&q, 4.0)
+                .iter()
+                .map(|n| n.index)
+                .collect();
+            let expected: Vec<usize> = brute(&indexed, &q, 300)
+                .into_iter()
+                .filter(|&i| (&pts[i] - &q).dist() <= 4.0)
+                .collect();
+            assert_eq!(within, expected);
+        }
+        let first = tree.nearest(&pts[42]).unwrap();
+        assert_eq!((first.index, first.distance), (42, 0.0));
+    }
+
+    #[test]
+    fn predicates_and_farthest() {
+        let mut rng = Rng::seed_from_u64(12);
+        let pts = random_points(&mut rng, 400, 2);
+        let tree = KdTree::new(&pts);
+        let mut forest = KdForest::new(2);
+        for p in &pts {
+            forest.insert(p).unwrap();
+        }
+        forest.remove(3);
+        let keep = |i: usize| i % 2 == 1;
+        let odd: Vec<(usize, &Point<f64>)> = pts.iter().enumerate().filter(|e| keep(e.0)).collect();
+        let ids = |v: Vec<Neighbor>| v.iter().map(|n| n.index).collect::<Vec<_>>();
+        for q in random_points(&mut rng, 10, 2) {
+            let order = brute(&odd, &q, odd.len());
+            let live: Vec<usize> = order.iter().copied().filter(|&i| i != 3).collect();
+            assert_eq!(ids(tree.k_nearest_where(&q, 4, &keep)), order[..4]);
+            assert_eq!(ids(forest.k_nearest_where(&q, 4, &keep)), live[..4]);
+            let within = tree.within_radius_where(&q, 3.0, &keep);
+            assert_eq!(
+                ids(within),
+                order
+                    .iter()
+                    .copied()
+                    .filter(|&i| squared_distance(&pts[i], &q) <= 9.0)
+                    .collect::<Vec<_>>()
+            );
+
+            let far = tree.k_farthest_where(&q, 3, &keep);
+            assert_eq!(
+                ids(far.clone()),
+                order.iter().rev().take(3).copied().collect::<Vec<_>>()
+            );
+            assert_eq!(
+                far[0].distance,
+                squared_distance(&pts[far[0].index], &q).sqrt()
+            );
+            assert_eq!(
+                ids(forest.k_farthest_where(&q, 3, &keep)),
+                live.iter().rev().take(3).copied().collect::<Vec<_>>()
+            );
+        }
+        assert!(tree.nearest_where(&pts[0], &|_| false).is_none());
+        assert!(tree.farthest(&pts[0]).unwrap().distance > 0.0);
+        assert!(KdTree::new::<f64>(&[]).farthest(&[0.0]).is_none());
+    }
+
+    #[test]
+    fn kd_tree_degenerate() {
+        let empty = KdTree::new::<f64>(&[]);
+        assert!(empty.nearest(&[0.0]).is_none());
+        let same = vec![Point::new(vec![1.0, 1.0]); 40];
+        let tree = KdTree::new(&same);
+        assert_eq!(tree.k_nearest(&[0.0, 0.0], 3).len(), 3);
+        assert_eq!(tree.within_radius(&[1.0, 1.0], 0.0).len(), 40);
+    }
+
+    #[test]
+    fn stats_and_validate() {
+        let mut rng = Rng::seed_from_u64(15);
+        let pts = random_points(&mut rng, 100, 2);
+        let tree = KdTree::new(&pts);
+        let stats = tree.stats();
+        assert_eq!(stats.points, 100);
+        assert_eq!(stats.leaves * 2 - 1, stats.nodes);
+        assert!(stats.max_leaf <= LEAF_SIZE && stats.min_leaf >= 1);
+        assert!(stats.depth >= 3 && stats.depth <= 5);
+        assert!(stats.fill_factor > 0.5 && stats.fill_factor <= 1.0);
+        assert!(stats.memory_bytes > 100 * 2 * 8);
+        assert_eq!(tree.validate(), Ok(()));
+
+        let mut broken = tree.clone();
+        broken.coords[0] = 1e9;
+        broken.coords[1] = 1e9;
+        assert!(broken.validate().is_err());
+
+        // All-equal points can't be split: one big leaf.
+        let same = KdTree::new(&vec![Point::new(vec![0.0]); 30]);
+        assert_eq!(same.stats().leaves, 1);
+        assert_eq!(same.stats().max_leaf, 30);
+
+        let mut forest = KdForest::new(2);
+        for p in &pts {
+            forest.insert(p).unwrap();
+        }
+        forest.remove(5);
+        assert_eq!(forest.validate(), Ok(()));
+        assert_eq!(forest.stats().points, 99);
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn save_and_load() {
+        let mut rng = Rng::seed_from_u64(14);
+        let pts = random_points(&mut rng, 100, 3);
+        let tree = KdTree::new(&pts);
+        let mut buf = Vec::new();
+        tree.save(&mut buf).unwrap();
+        let loaded = KdTree::load(&buf[..]).unwrap();
+        assert_eq!(loaded.len(), 100);
+        for q in random_points(&mut rng, 10, 3) {
+            assert_eq!(loaded.k_nearest(&q, 4), tree.k_nearest(&q, 4));
+        }
+
+        let err = KdTree::load(&buf[..buf.len() - 3]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let mut bad = buf.clone();
+        bad[4] = 9;
+        assert_eq!(
+            KdTree::load(&bad[..]).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        // A split whose left child is itself loads as an error, not a tree
+        // that queries recurse through forever.
+        let cyclic = KdTree {
+            dim: 1,
+            coords: vec![0.0, 1.0],
+            ids: vec![0, 1],
+            nodes: vec![
+                Node::Split {
+                    axis: 0,
+                    value: 0.5,
+                    left: 0,
+                    right: 1,
+                },
+                Node::Leaf { lo: 0, hi: 2 },
+            ],
+            bounds: bounds(&[0.0, 1.0], 1),
+        };
+        let mut buf = Vec::new();
+        cyclic.save(&mut buf).unwrap();
+        assert_eq!(
+            KdTree::load(&buf[..]).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn query_batch() {
+        let mut rng = Rng::seed_from_u64(13);
+        let pts = random_points(&mut rng, 500, 2);
+        let tree = KdTree::new(&pts);
+        let queries = random_points(&mut rng, 300, 2);
+        let batch = tree.query_batch(&queries, 3);
+        assert_eq!(batch.len(), 300);
+        assert_eq!(batch.as_flat().len(), 900);
+        for (q, got) in queries.iter().zip(batch.iter()) {
+            assert_eq!(got, &tree.k_nearest(q, 3)[..]);
+        }
+        assert_eq!(batch.get(7), &tree.k_nearest(&queries[7], 3)[..]);
+
+        let small = KdTree::new(&pts[..2]);
+        let batch = small.query_batch(&queries[..5], 3);
+        assert!(batch.iter().all(|r| r.len() == 2));
+        assert!(tree.query_batch(&[], 3).is_empty());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn par_query_batch() {
+        let mut rng = Rng::seed_from_u64(14);
+        let pts = random_points(&mut rng, 500, 3);
+        let tree = KdTree::new(&pts);
+        let queries = random_points(&mut rng, 300, 3);
+        assert_eq!(
+            tree.par_query_batch(&queries, 4),
+            tree.query_batch(&queries, 4)
+        );
+        assert!(tree.par_query_batch(&[], 4).is_empty());
+    }
+
+    #[test]
+    fn forest_insert_remove() {
+        let mut rng = Rng::seed_from_u64(12);
+        let pts = random_points(&mut rng, 200, 2);
+        let mut forest = KdForest::new(2);
+        for p in &pts {
+            forest.insert(p).unwrap();
+        }
+        // 200 = 0b11001000: one tree per set bit.
+        assert_eq!(forest.tree_count(), 3);
+        for id in (0..200).step_by(3) {
+            assert!(forest.remove(id));
+        }
+        assert!(!forest.remove(0));
+        assert_eq!(forest.len(), 200 - 67);
+        assert!(forest.get(3).is_none());
+        assert_eq!(forest.get(4), Some(pts[4].data()));
+
+        let live: Vec<(usize, &Point<f64>)> =
+            pts.iter().enumerate().filter(|(i, _)| i % 3 != 0).collect();
+        for q in random_points(&mut rng, 20, 2) {
+            let got: Vec<usize> = forest.k_nearest(&q, 4).iter().map(|n| n.index).collect();
+            assert_eq!(got, brute(&live, &q, 4));
+            assert_eq!(
+                forest.within_radius(&q, 3.0).len(),
+                live.iter().filter(|(_, p)| (*p - &q).dist() <= 3.0).count()
+            );
+        }
+    }
+
+    #[test]
+    fn forest_rebuilds_after_many_deletes() {
+        let mut forest = KdForest::new(1);
+        for i in 0..64 {
+            forest.insert(&Point::new(vec![i as f64])).unwrap();
+        }
+        for id in 0..40 {
+            forest.remove(id);
+        }
+        // The 33rd delete rebuilt the 31 live points into trees of 1, 2, 4, 8
+        // and 16; later deletes only mark points.
+        assert_eq!(forest.len(), 24);
+        assert_eq!(forest.tree_count(), 5);
+        assert_eq!(forest.nearest(&[0.0]).unwrap().index, 40);
+        let id = forest.insert(&Point::new(vec![0.5])).unwrap();
+        assert_eq!(id, 64);
+        assert_eq!(forest.nearest(&[0.0]).unwrap().index, 64);
+        assert_eq!(
+            forest.insert(&Point::new(vec![1.0, 2.0])),
+            Err(Error::DimensionMismatch {
+                expected: 1,
+                found: 2
+            })
+        );
+    }
+}
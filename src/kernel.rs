@@ -0,0 +1,237 @@
+// Kernel sums over a point set, for density maps and heatmaps.
+//
+// A query sums `K(|q - p| / h)` over all points `p`. Each node of the
+// underlying k-d tree bounds the kernel over its points by its box's nearest
+// and farthest distances to `q`; when those bounds are close enough the node
+// contributes their midpoint times its point count and the query moves on.
+// The allowed error is split over the points in proportion to their weight,
+// so the returned bound never exceeds the tolerance.
+
+use crate::Point;
+use crate::boxtree::BoxTree;
+use crate::index::squared_distance;
+
+/// Kernel profiles, unnormalised: both are 1 at distance zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kernel {
+    /// `exp(-d^2 / 2h^2)`.
+    Gaussian,
+    /// `max(0, 1 - d^2 / h^2)`; points beyond the bandwidth add nothing.
+    Epanechnikov,
+}
+
+impl Kernel {
+    // `u2` is the squared distance over the squared bandwidth.
+    fn eval(self, u2: f64) -> f64 {
+        match self {
+            Kernel::Gaussian => (-0.5 * u2).exp(),
+            Kernel::Epanechnikov => (1.0 - u2).max(0.0),
+        }
+    }
+}
+
+/// A kernel sum and a bound on how far it can be from the exact sum.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KernelEstimate {
+    pub value: f64,
+    pub error: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct KernelTree {
+    tree: BoxTree,
+    kernel: Kernel,
+    tolerance: f64,
+    // Point weights in slot order, `None` when all are one.
+    weights: Option<Vec<f64>>,
+    // Total weight under each node.
+    mass: Vec<f64>,
+}
+
+impl KernelTree {
+    /// Builds the tree. Sums are exact until a tolerance is set. Panics if the
+    /// points don't share a dimension.
+    pub fn new<T>(points: &[Point<T>], kernel: Kernel) -> Self
+    where
+        T: Into<f64> + Copy,
+    {
+        let dim = points.first().map_or(0, |p| p.dim());
+        let tree = BoxTree::build(points, dim).expect("points must share a dimension");
+        let mass = (0..tree.kd.node_count())
+            .map(|node| tree.size(node) as f64)
+            .collect();
+        KernelTree {
+            tree,
+            kernel,
+            tolerance: 0.0,
+            weights: None,
+            mass,
+        }
+    }
+
+    /// Like `new`, but point `i` contributes `weights[i]` times its kernel
+    /// value. Panics if there isn't one weight per point or a weight is
+    /// negative.
+    pub fn with_weights<T>(points: &[Point<T>], weights: &[f64], kernel: Kernel) -> Self
+    where
+        T: Into<f64> + Copy,
+    {
+        assert_eq!(weights.len(), points.len(), "one weight per point");
+        assert!(
+            weights.iter().all(|&w| w >= 0.0),
+            "weights must be non-negative"
+        );
+        let mut kt = KernelTree::new(points, kernel);
+        let slot_weights: Vec<f64> = (0..points.len())
+            .map(|s| weights[kt.tree.kd.id(s)])
+            .collect();
+        for node in 0..kt.mass.len() {
+            let (lo, hi) = kt.tree.slots[node];
+            kt.mass[node] = slot_weights[lo..hi].iter().sum();
+        }
+        kt.weights = Some(slot_weights);
+        kt
+    }
+
+    /// Allows each sum to be off by up to `tolerance` in absolute terms,
+    /// which lets distant or tightly packed nodes be summed without visiting
+    /// their points.
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance.max(0.0);
+        self
+    }
+
+    pub fn kernel(&self) -> Kernel {
+        self.kernel
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.size(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Weighted sum of the kernel at `bandwidth` between `query` and every
+    /// point.
+    pub fn kernel_sum(&self, query: &[f64], bandwidth: f64) -> KernelEstimate {
+        let mut est = KernelEstimate {
+            value: 0.0,
+            error: 0.0,
+        };
+        if self.mass[0] > 0.0 {
+            let per_point = self.tolerance / self.mass[0];
+            let inv_h2 = 1.0 / (bandwidth * bandwidth);
+            self.sum_in(0, query, inv_h2, per_point, &mut est);
+        }
+        est
+    }
+
+    fn sum_in(
+        &self,
+        node: usize,
+        q: &[f64],
+        inv_h2: f64,
+        per_point: f64,
+        est: &mut KernelEstimate,
+    ) {
+        let hi = self.kernel.eval(self.tree.min_sq_to(node, q) * inv_h2);
+        if hi == 0.0 {
+            return;
+        }
+        let lo = self.kernel.eval(self.tree.max_sq_to(node, q) * inv_h2);
+        let n = self.mass[node];
+        if (hi - lo) / 2.0 <= per_point {
+            est.value += n * (hi + lo) / 2.0;
+            est.error += n * (hi - lo) / 2.0;
+            return;
+        }
+        match self.tree.kd.children(node) {
+            Some((l, r)) => {
+                self.sum_in(l, q, inv_h2, per_point, est);
+                self.sum_in(r, q, inv_h2, per_point, est);
+            }
+            None => {
+                let (s, e) = self.tree.slots[node];
+                for slot in s..e {
+                    let d2 = squared_distance(self.tree.kd.point(slot), q);
+                    let w = self.weights.as_ref().map_or(1.0, |w| w[slot]);
+                    est.value += w * self.kernel.eval(d2 * inv_h2);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn brute(pts: &[Point<f64>], kernel: Kernel, q: &[f64], h: f64) -> f64 {
+        pts.iter()
+            .map(|p| kernel.eval(squared_distance(p, q) / (h * h)))
+            .sum()
+    }
+
+    #[test]
+    fn within_tolerance() {
+        let mut rng = Rng::seed_from_u64(43);
+        let pts: Vec<Point<f64>> = (0..2000)
+            .map(|_| Point::new((0..2).map(|_| rng.range_f64(0.0, 10.0)).collect()))
+            .collect();
+        for kernel in [Kernel::Gaussian, Kernel::Epanechnikov] {
+            let exact = KernelTree::new(&pts, kernel);
+            let approx = KernelTree::new(&pts, kernel).tolerance(0.5);
+            for _ in 0..10 {
+                let q = [rng.range_f64(-2.0, 12.0), rng.range_f64(-2.0, 12.0)];
+                let want = brute(&pts, kernel, &q, 1.5);
+                let e = exact.kernel_sum(&q, 1.5);
+                assert!((e.value - want).abs() < 1e-9);
+                let a = approx.kernel_sum(&q, 1.5);
+                assert!(a.error <= 0.5 + 1e-12);
+                assert!((a.value - want).abs() <= a.error + 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn weights() {
+        let mut rng = Rng::seed_from_u64(44);
+        let pts: Vec<Point<f64>> = (0..500)
+            .map(|_| Point::new((0..2).map(|_| rng.range_f64(0.0, 10.0)).collect()))
+            .collect();
+        let w: Vec<f64> = (0..500).map(|i| (i % 5) as f64).collect();
+        let tree = KernelTree::with_weights(&pts, &w, Kernel::Gaussian).tolerance(0.1);
+        let q = [5.0, 5.0];
+        let want: f64 = pts
+            .iter()
+            .zip(&w)
+            .map(|(p, w)| w * Kernel::Gaussian.eval(squared_distance(p, &q)))
+            .sum();
+        let got = tree.kernel_sum(&q, 1.0);
+        assert!(got.error <= 0.1 + 1e-12);
+        assert!((got.value - want).abs() <= got.error + 1e-9);
+        let zero = KernelTree::with_weights(&pts, &[0.0; 500], Kernel::Gaussian);
+        assert_eq!(zero.kernel_sum(&q, 1.0).value, 0.0);
+    }
+
+    #[test]
+    fn epanechnikov_support() {
+        let pts = vec![
+            Point::new(vec![0.0]),
+            Point::new(vec![0.5]),
+            Point::new(vec![3.0]),
+        ];
+        let tree = KernelTree::new(&pts, Kernel::Epanechnikov);
+        assert_eq!(tree.kernel_sum(&[0.0], 1.0).value, 1.75);
+        assert_eq!(tree.kernel_sum(&[10.0], 1.0).value, 0.0);
+        assert_eq!(
+            KernelTree::new::<f64>(&[], Kernel::Gaussian)
+                .kernel_sum(&[0.0], 1.0)
+                .value,
+            0.0
+        );
+    }
+}
@@ -0,0 +1,270 @@
+// Serial kinematic chains: robot arms, skeletons and other linkages.
+//
+// A `Chain` is a list of links, each a joint followed by a rigid offset to
+// the next joint, both expressed in the frame left by the links before it.
+// A revolute joint turns by its parameter (radians) in a coordinate plane,
+// a prismatic one slides by its parameter along a coordinate axis, so a
+// chain works in any dimension. `positions` gives the base and every joint
+// end for a set of parameters, and `jacobian` how the end effector moves
+// per unit change of each parameter, by central differences. `solve` runs
+// damped least squares (Levenberg-Marquardt) steps on the Jacobian to reach
+// a target. The damping keeps steps bounded near singular poses, where the
+// plain pseudo-inverse would send the joints flying: it grows whenever a
+// step fails to bring the end effector closer and shrinks when one does.
+
+use crate::linalg::Matrix;
+use crate::transform::Transform;
+use crate::{Point, Storage};
+
+// Parameter step of the central differences.
+const STEP: f64 = 1e-6;
+// Starting damping of the least-squares steps in `solve`, and its bounds.
+const DAMPING: f64 = 1e-2;
+const MIN_DAMPING: f64 = 1e-9;
+const MAX_DAMPING: f64 = 1e9;
+
+/// How a link moves with its parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Joint {
+    /// Turns from the first axis of `plane` towards the second.
+    Revolute { plane: [usize; 2] },
+    /// Slides along coordinate `axis`.
+    Prismatic { axis: usize },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    pub joint: Joint,
+    /// From this joint to the next, in the frame after the joint moves.
+    pub offset: Vec<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chain {
+    dim: usize,
+    links: Vec<Link>,
+}
+
+impl Chain {
+    /// An empty chain rooted at the origin. Panics if `dim` is zero.
+    pub fn new(dim: usize) -> Self {
+        assert!(dim > 0, "dimension must be at least 1");
+        Chain {
+            dim,
+            links: Vec::new(),
+        }
+    }
+
+    /// Appends a link. Panics if the joint names an axis the chain doesn't
+    /// have, a revolute joint repeats an axis, or the offset has the wrong
+    /// dimension.
+    pub fn then(mut self, joint: Joint, offset: &[f64]) -> Self {
+        let axes_ok = match joint {
+            Joint::Revolute { plane: [i, j] } => i != j && i < self.dim && j < self.dim,
+            Joint::Prismatic { axis } => axis < self.dim,
+        };
+        assert!(axes_ok, "joint axes must be distinct axes of the chain");
+        assert_eq!(offset.len(), self.dim, "offset has the wrong dimension");
+        self.links.push(Link {
+            joint,
+            offset: offset.to_vec(),
+        });
+        self
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// The number of links, which is also the number of parameters.
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+
+    /// The frame at the end of every link, from the base outwards. Panics
+    /// unless there is one parameter per link.
+    pub fn frames(&self, params: &[f64]) -> Vec<Transform> {
+        assert_eq!(params.len(), self.len(), "one parameter per link");
+        let mut frame = Transform::identity(self.dim);
+        self.links
+            .iter()
+            .zip(params)
+            .map(|(link, &q)| {
+                let motion = match link.joint {
+                    Joint::Revolute { plane: [i, j] } => {
+                        Transform::rotation_plane(self.dim, i, j, q)
+                    }
+                    Joint::Prismatic { axis } => {
+                        let mut slide = vec![0.0; self.dim];
+                        slide[axis] = q;
+                        Transform::translation(&slide)
+                    }
+                };
+                let local = Transform::translation(&link.offset).then(&motion);
+                frame = local.then(&frame);
+                frame.clone()
+            })
+            .collect()
+    }
+
+    /// The base followed by the end of every link: `len() + 1` points.
+    pub fn positions(&self, params: &[f64]) -> Vec<Point<f64>> {
+        let base = Point::new(vec![0.0; self.dim]);
+        std::iter::once(base)
+            .chain(
+                self.frames(params)
+                    .iter()
+                    .map(|f| Point::new(f.offset().to_vec())),
+            )
+            .collect()
+    }
+
+    /// Where the last link ends; the origin for an empty chain.
+    pub fn end_effector(&self, params: &[f64]) -> Point<f64> {
+        let end = self.frames(params).pop();
+        Point::new(end.map_or(vec![0.0; self.dim], |f| f.offset().to_vec()))
+    }
+
+    /// The `dim() x len()` Jacobian of the end effector, row by row: entry
+    /// `(i, k)` is the rate of change of coordinate `i` with parameter `k`.
+    pub fn jacobian(&self, params: &[f64]) -> Vec<Vec<f64>> {
+        let mut jac = vec![vec![0.0; self.len()]; self.dim];
+        let mut q = params.to_vec();
+        for k in 0..self.len() {
+            q[k] = params[k] + STEP;
+            let ahead = self.end_effector(&q);
+            q[k] = params[k] - STEP;
+            let behind = self.end_effector(&q);
+            q[k] = params[k];
+            for (i, row) in jac.iter_mut().enumerate() {
+                row[k] = (ahead[i] - behind[i]) / (2.0 * STEP);
+            }
+        }
+        jac
+    }
+
+    /// Parameters that bring the end effector to `target`, starting from
+    /// `start`, stopping once within `tolerance`, after `max_iter` steps or
+    /// when no step gets closer. Out-of-reach targets end at the nearest
+    /// pose found. Panics if the
+    /// target's dimension differs or `start` has the wrong length.
+    pub fn solve<S: Storage<f64>>(
+        &self,
+        start: &[f64],
+        target: &Point<f64, S>,
+        tolerance: f64,
+        max_iter: usize,
+    ) -> Vec<f64> {
+        assert_eq!(target.dim(), self.dim, "target has the wrong dimension");
+        let residual = |q: &[f64]| -> Vec<f64> {
+            let end = self.end_effector(q);
+            target
+                .as_slice()
+                .iter()
+                .zip(end.iter())
+                .map(|(t, e)| t - e)
+                .collect()
+        };
+        let norm = |e: &[f64]| e.iter().map(|v| v * v).sum::<f64>().sqrt();
+        let mut q = start.to_vec();
+        let mut error = residual(&q);
+        let mut damping = DAMPING;
+        for _ in 0..max_iter {
+            if norm(&error) <= tolerance || damping > MAX_DAMPING {
+                break;
+            }
+            // dq = J^T (J J^T + damping I)^-1 error
+            let jac = Matrix::from_rows(&self.jacobian(&q));
+            let mut jjt = &jac * &jac.transpose();
+            for i in 0..self.dim {
+                jjt[(i, i)] += damping;
+            }
+            let Some(y) = jjt.solve(&error) else {
+                break;
+            };
+            let dq = jac.transpose().mul_vec(&y);
+            let trial: Vec<f64> = q.iter().zip(dq).map(|(q, d)| q + d).collect();
+            let trial_error = residual(&trial);
+            if norm(&trial_error) < norm(&error) {
+                (q, error) = (trial, trial_error);
+                damping = (damping / 2.0).max(MIN_DAMPING);
+            } else {
+                damping *= 4.0;
+            }
+        }
+        q
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    // A planar arm with links of length 2 and 1.
+    fn arm() -> Chain {
+        Chain::new(2)
+            .then(Joint::Revolute { plane: [0, 1] }, &[2.0, 0.0])
+            .then(Joint::Revolute { plane: [0, 1] }, &[1.0, 0.0])
+    }
+
+    fn close(a: &[f64], b: &[f64], tol: f64) -> bool {
+        a.iter().zip(b).all(|(x, y)| (x - y).abs() < tol)
+    }
+
+    #[test]
+    fn forward_kinematics() {
+        let arm = arm();
+        let pos = arm.positions(&[FRAC_PI_2, -FRAC_PI_2]);
+        assert_eq!(pos.len(), 3);
+        assert!(close(pos[1].data(), &[0.0, 2.0], 1e-12));
+        assert!(close(pos[2].data(), &[1.0, 2.0], 1e-12));
+
+        let slider = Chain::new(3)
+            .then(Joint::Prismatic { axis: 2 }, &[0.0, 0.0, 0.0])
+            .then(Joint::Revolute { plane: [0, 1] }, &[1.0, 0.0, 0.0]);
+        assert!(close(
+            slider.end_effector(&[4.0, FRAC_PI_2]).data(),
+            &[0.0, 1.0, 4.0],
+            1e-12
+        ));
+        assert_eq!(Chain::new(2).end_effector(&[]).data(), [0.0, 0.0]);
+        assert!(
+            std::panic::catch_unwind(
+                || Chain::new(2).then(Joint::Prismatic { axis: 2 }, &[0.0, 0.0])
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn jacobian_matches_the_analytic_one() {
+        let (a, b) = (0.3, 0.9);
+        let jac = arm().jacobian(&[a, b]);
+        let (s1, c1, s12, c12) = (a.sin(), a.cos(), (a + b).sin(), (a + b).cos());
+        let want = [[-2.0 * s1 - s12, -s12], [2.0 * c1 + c12, c12]];
+        for (row, want) in jac.iter().zip(want) {
+            assert!(close(row, &want, 1e-8));
+        }
+    }
+
+    #[test]
+    fn solves_for_reachable_targets() {
+        let arm = arm();
+        let target = Point::new(vec![1.5, 1.5]);
+        let q = arm.solve(&[0.1, 0.1], &target, 1e-10, 100);
+        assert!(arm.end_effector(&q).distance_to(&target) < 1e-10);
+        // Too far: ends stretched out towards it.
+        let far = Point::new(vec![0.0, 10.0]);
+        let q = arm.solve(&[0.1, 0.1], &far, 1e-10, 200);
+        assert!(close(arm.end_effector(&q).data(), &[0.0, 3.0], 1e-3));
+    }
+}
@@ -0,0 +1,266 @@
+// Point clouds with one label per point, as clustering and segmentation
+// produce.
+//
+// `LabeledCloud` owns the cloud and the label array and only edits them
+// together, so a label can't drift onto the wrong point. Labels can be any
+// `Eq + Hash` type: cluster ids, class names, `Option`s for noise.
+//
+// The text format is one point per line, coordinates then label, separated
+// by whitespace, the usual `x y z label` layout of segmentation datasets.
+
+use std::collections::HashMap;
+#[cfg(feature = "io")]
+use std::fmt::Display;
+use std::hash::Hash;
+#[cfg(feature = "io")]
+use std::io::{self, BufRead, Write};
+#[cfg(feature = "io")]
+use std::str::FromStr;
+
+#[cfg(feature = "io")]
+use crate::persist::invalid;
+use crate::{Error, Point, PointCloud};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabeledCloud<T, L = u32> {
+    cloud: PointCloud<T>,
+    labels: Vec<L>,
+}
+
+impl<T, L> LabeledCloud<T, L>
+where
+    T: Copy,
+    L: Clone + Eq + Hash,
+{
+    /// Pairs `cloud` with its labels. Panics unless there is one label per
+    /// point.
+    pub fn new(cloud: PointCloud<T>, labels: Vec<L>) -> Self {
+        assert_eq!(labels.len(), cloud.len(), "one label per point");
+        LabeledCloud { cloud, labels }
+    }
+
+    /// Every point of `cloud` gets `label`.
+    pub fn uniform(cloud: PointCloud<T>, label: L) -> Self {
+        let labels = vec![label; cloud.len()];
+        LabeledCloud { cloud, labels }
+    }
+
+    pub fn cloud(&self) -> &PointCloud<T> {
+        &self.cloud
+    }
+
+    pub fn labels(&self) -> &[L] {
+        &self.labels
+    }
+
+    pub fn into_parts(self) -> (PointCloud<T>, Vec<L>) {
+        (self.cloud, self.labels)
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    pub fn label(&self, i: usize) -> Option<&L> {
+        self.labels.get(i)
+    }
+
+    /// Changes the label of point `i`; false if there is no such point.
+    pub fn set_label(&mut self, i: usize, label: L) -> bool {
+        match self.labels.get_mut(i) {
+            Some(l) => {
+                *l = label;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Appends a labelled point and returns its index.
+    pub fn push(&mut self, coords: &[T], label: L) -> Result<usize, Error> {
+        let i = self.cloud.push(coords)?;
+        self.labels.push(label);
+        Ok(i)
+    }
+
+    /// Removes point `i` and its label, shifting later points down.
+    pub fn remove(&mut self, i: usize) -> Option<(Point<T>, L)> {
+        let p = self.cloud.remove(i)?;
+        Some((p, self.labels.remove(i)))
+    }
+
+    /// Maps every label through `f`, e.g. to merge clusters or to turn class
+    /// names into ids.
+    pub fn relabel<M, F>(self, f: F) -> LabeledCloud<T, M>
+    where
+        M: Clone + Eq + Hash,
+        F: FnMut(&L) -> M,
+    {
+        LabeledCloud {
+            labels: self.labels.iter().map(f).collect(),
+            cloud: self.cloud,
+        }
+    }
+
+    /// The distinct labels in order of first appearance.
+    pub fn distinct(&self) -> Vec<L> {
+        self.label_counts().into_iter().map(|(l, _)| l).collect()
+    }
+
+    /// How many points carry each label, in order of first appearance.
+    pub fn label_counts(&self) -> Vec<(L, usize)> {
+        let mut slot: HashMap<&L, usize> = HashMap::new();
+        let mut counts: Vec<(L, usize)> = Vec::new();
+        for l in &self.labels {
+            let i = *slot.entry(l).or_insert_with(|| {
+                counts.push((l.clone(), 0));
+                counts.len() - 1
+            });
+            counts[i].1 += 1;
+        }
+        counts
+    }
+
+    /// One cloud per label, in order of first appearance; points keep their
+    /// relative order.
+    pub fn split_by_label(&self) -> Vec<(L, PointCloud<T>)> {
+        let mut slot: HashMap<&L, usize> = HashMap::new();
+        let mut parts: Vec<(L, Vec<T>)> = Vec::new();
+        for (p, l) in self.cloud.iter().zip(&self.labels) {
+            let i = *slot.entry(l).or_insert_with(|| {
+                parts.push((l.clone(), Vec::new()));
+                parts.len() - 1
+            });
+            parts[i].1.extend_from_slice(p);
+        }
+        let dim = self.cloud.dim();
+        parts
+            .into_iter()
+            .map(|(l, flat)| {
+                let cloud = PointCloud::from_flat_vec(flat, dim).expect("dimension is positive");
+                (l, cloud)
+            })
+            .collect()
+    }
+
+    /// Indices of the points labelled `label`.
+    pub fn indices_of(&self, label: &L) -> Vec<usize> {
+        (0..self.labels.len())
+            .filter(|&i| self.labels[i] == *label)
+            .collect()
+    }
+}
+
+#[cfg(feature = "io")]
+impl<T, L> LabeledCloud<T, L>
+where
+    T: Copy + Display,
+    L: Clone + Eq + Hash + Display,
+{
+    /// Writes one `coords... label` line per point. Fails with
+    /// `ErrorKind::InvalidInput` if a label is empty or contains whitespace,
+    /// since it couldn't be read back.
+    pub fn write_text<W: Write>(&self, mut w: W) -> io::Result<()> {
+        for (p, l) in self.cloud.iter().zip(&self.labels) {
+            let label = l.to_string();
+            if label.is_empty() || label.contains(char::is_whitespace) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "label is empty or contains whitespace",
+                ));
+            }
+            for v in p {
+                write!(w, "{v} ")?;
+            }
+            writeln!(w, "{label}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "io")]
+impl<T, L> LabeledCloud<T, L>
+where
+    T: Copy + FromStr,
+    L: Clone + Eq + Hash + FromStr,
+{
+    /// Reads the format written by `write_text`: `dim` coordinates and a
+    /// label per line. Blank lines and lines starting with `#` are skipped.
+    /// Fails with `ErrorKind::InvalidData` on a malformed line.
+    pub fn read_text<R: BufRead>(r: R, dim: usize) -> io::Result<Self> {
+        let mut cloud = PointCloud::new(dim).map_err(|_| invalid("zero dimension"))?;
+        let mut labels = Vec::new();
+        let mut coords = Vec::with_capacity(dim);
+        for line in r.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != dim + 1 {
+                return Err(invalid("wrong number of fields"));
+            }
+            coords.clear();
+            for f in &fields[..dim] {
+                coords.push(f.parse().map_err(|_| invalid("bad coordinate"))?);
+            }
+            labels.push(fields[dim].parse().map_err(|_| invalid("bad label"))?);
+            cloud.push(&coords).expect("coordinate count checked");
+        }
+        Ok(LabeledCloud { cloud, labels })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> LabeledCloud<f64, &'static str> {
+        let cloud = PointCloud::from_flat(&[0.0, 0.0, 1.0, 0.0, 5.0, 5.0, 2.0, 0.0], 2).unwrap();
+        LabeledCloud::new(cloud, vec!["road", "road", "tree", "road"])
+    }
+
+    #[test]
+    fn counts_and_splits() {
+        let mut lc = sample();
+        assert_eq!(lc.label_counts(), vec![("road", 3), ("tree", 1)]);
+        assert_eq!(lc.indices_of(&"road"), vec![0, 1, 3]);
+        let parts = lc.split_by_label();
+        assert_eq!(parts[0].1.as_flat(), &[0.0, 0.0, 1.0, 0.0, 2.0, 0.0]);
+        assert_eq!(parts[1].0, "tree");
+
+        assert_eq!(lc.push(&[9.0, 9.0], "car"), Ok(4));
+        assert!(lc.push(&[1.0], "car").is_err());
+        assert_eq!(lc.remove(2).map(|(_, l)| l), Some("tree"));
+        assert!(lc.set_label(0, "car"));
+        assert_eq!(lc.distinct(), vec!["car", "road"]);
+        let ids = lc.relabel(|l| u32::from(*l == "road"));
+        assert_eq!(ids.labels(), &[0, 1, 1, 0]);
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn text_round_trip() {
+        let lc = sample().relabel(|l| l.to_string());
+        let mut buf = Vec::new();
+        lc.write_text(&mut buf).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&buf).lines().nth(2),
+            Some("5 5 tree")
+        );
+        let back = LabeledCloud::<f64, String>::read_text(&buf[..], 2).unwrap();
+        assert_eq!(back, lc);
+
+        let bad = LabeledCloud::uniform(PointCloud::from_flat(&[1.0], 1).unwrap(), "a b");
+        assert!(bad.write_text(Vec::new()).is_err());
+        let err = LabeledCloud::<f64, u32>::read_text(&b"# x y c\n1 2 x\n"[..], 2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let ok = LabeledCloud::<f64, u32>::read_text(&b"\n1 2 7\n"[..], 2).unwrap();
+        assert_eq!(ok.labels(), &[7]);
+    }
+}
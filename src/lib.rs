@@ -1,8 +1,166 @@
-use std::ops::{Add, Div, Mul, Sub};
-
-#[derive(Debug, Clone)]
-pub struct Point<T> {
-    p: Vec<T>,
+#[cfg(feature = "geometry")]
+pub mod aabb;
+#[cfg(feature = "index")]
+pub mod aggregate;
+#[cfg(feature = "transform")]
+pub mod arap;
+#[cfg(feature = "transform")]
+pub mod augment;
+#[cfg(feature = "index")]
+pub mod ball_tree;
+#[cfg(feature = "index")]
+mod boxtree;
+#[cfg(feature = "index")]
+pub mod bsp;
+#[cfg(feature = "geometry")]
+pub mod center;
+pub mod cloud;
+#[cfg(feature = "io")]
+pub mod cloud_io;
+#[cfg(feature = "cluster")]
+pub mod cluster;
+mod convert;
+#[cfg(feature = "index")]
+pub mod cover_tree;
+#[cfg(feature = "transform")]
+pub mod deform;
+#[cfg(feature = "geometry")]
+pub mod delaunay;
+pub mod dims;
+pub mod directional;
+#[cfg(feature = "geometry")]
+pub mod distance_field;
+mod error;
+#[cfg(feature = "cluster")]
+pub mod facility;
+#[cfg(feature = "geometry")]
+pub mod frustum;
+#[cfg(feature = "geometry")]
+pub mod geo;
+#[cfg(feature = "geometry")]
+pub mod geohash;
+#[cfg(feature = "glam")]
+mod glam_convert;
+#[cfg(feature = "geometry")]
+pub mod harmonics;
+#[cfg(feature = "geometry")]
+pub mod hex;
+#[cfg(feature = "geometry")]
+pub mod hull;
+#[cfg(feature = "index")]
+pub mod index;
+#[cfg(feature = "index")]
+pub mod indexed;
+pub mod interpolate;
+#[cfg(feature = "index")]
+pub mod join;
+#[cfg(feature = "index")]
+pub mod kdtree;
+#[cfg(feature = "index")]
+pub mod kernel;
+#[cfg(feature = "transform")]
+pub mod kinematics;
+#[cfg(feature = "cluster")]
+pub mod labels;
+#[cfg(any(feature = "geometry", feature = "transform"))]
+#[cfg_attr(not(all(feature = "index", feature = "transform")), allow(dead_code))]
+mod linalg;
+#[cfg(feature = "geometry")]
+pub mod lp;
+#[cfg(feature = "geometry")]
+pub mod map_projection;
+pub mod metrics;
+#[cfg(feature = "transform")]
+pub mod morph;
+#[cfg(feature = "nalgebra")]
+mod nalgebra_convert;
+#[cfg(feature = "ndarray")]
+mod ndarray_convert;
+#[cfg(feature = "geometry")]
+pub mod optics;
+#[cfg(feature = "cluster")]
+pub mod partition;
+#[cfg(feature = "io")]
+#[cfg_attr(not(feature = "index"), allow(dead_code))]
+mod persist;
+#[cfg(feature = "geometry")]
+pub mod planning;
+#[cfg(feature = "geometry")]
+pub mod polyline;
+#[cfg(feature = "geometry")]
+pub mod polytope;
+#[cfg(feature = "geometry")]
+pub mod potential;
+#[cfg(feature = "transform")]
+pub mod procrustes;
+#[cfg(feature = "transform")]
+pub mod projection;
+#[cfg(feature = "index")]
+pub mod range_tree;
+#[cfg(feature = "transform")]
+pub mod reduce;
+pub mod rng;
+#[cfg(feature = "transform")]
+pub mod rotation;
+#[cfg(feature = "transform")]
+pub mod se3;
+#[cfg(feature = "geometry")]
+pub mod separation;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "transform")]
+pub mod shape_matching;
+#[cfg(feature = "transform")]
+pub mod shape_model;
+#[cfg(feature = "cluster")]
+pub mod shard;
+pub mod shared;
+#[cfg(feature = "simd")]
+pub mod simd;
+#[cfg(feature = "index")]
+pub mod similarity;
+#[cfg(feature = "transform")]
+pub mod skeleton;
+mod smallbuf;
+#[cfg(feature = "transform")]
+pub mod so3;
+#[cfg(feature = "geometry")]
+pub mod sphere_cell;
+pub mod stats;
+pub mod storage;
+#[cfg(feature = "transform")]
+pub mod tps;
+#[cfg(feature = "transform")]
+pub mod transform;
+#[cfg(feature = "geometry")]
+pub mod tsp;
+pub mod versioned;
+#[cfg(feature = "transform")]
+pub mod viewport;
+#[cfg(feature = "index")]
+pub mod vptree;
+#[cfg(feature = "geometry")]
+pub mod weighted;
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ops::{
+    Add, AddAssign, Deref, DerefMut, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub,
+    SubAssign,
+};
+use std::slice::SliceIndex;
+
+pub use cloud::PointCloud;
+pub use dims::{Point2, Point3, Point4, PointN};
+pub use error::Error;
+pub use shared::SharedStorage;
+pub use storage::{ArrayStorage, BuildStorage, SliceStorage, Storage, StorageMut, VecStorage};
+
+#[derive(Clone, Copy)]
+pub struct Point<T, S = VecStorage<T>> {
+    p: S,
+    coords: PhantomData<T>,
 }
 
 impl<T> Point<T>
@@ -10,225 +168,469 @@ where
     T: Into<f64> + Copy, // Ensures T can be converted to f64
 {
     pub fn new(p: Vec<T>) -> Self {
-        Point { p }
+        Point::from_storage(p.into())
+    }
+
+    /// The cross product of two 3D points. Panics unless both are 3D; see
+    /// also `Point3::cross`.
+    pub fn cross(&self, other: &Point<T>) -> Point<T>
+    where
+        T: Mul<Output = T> + Sub<Output = T>,
+    {
+        assert!(
+            self.dim() == 3 && other.dim() == 3,
+            "cross product needs two 3D points"
+        );
+        let (a, b) = (&self.p, &other.p);
+        Point::from([
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ])
+    }
+
+    pub fn from_vec(p: Vec<T>) -> Self {
+        Point::new(p)
+    }
+
+    /// Decomposes the point into a `Vec`'s raw pointer, length and capacity,
+    /// e.g. to hand the coordinates across an FFI boundary.
+    ///
+    /// The memory must be released with [`Point::from_raw_parts`].
+    pub fn into_raw_parts(self) -> (*mut T, usize, usize) {
+        let mut v = ManuallyDrop::new(self.into_vec());
+        (v.as_mut_ptr(), v.len(), v.capacity())
+    }
+
+    /// Rebuilds a point from the parts returned by [`Point::into_raw_parts`].
+    ///
+    /// # Safety
+    ///
+    /// The parts must come from `into_raw_parts` (or otherwise satisfy the
+    /// requirements of [`Vec::from_raw_parts`]) and must not be used again.
+    pub unsafe fn from_raw_parts(ptr: *mut T, len: usize, capacity: usize) -> Self {
+        Point::new(unsafe { Vec::from_raw_parts(ptr, len, capacity) })
     }
+}
 
+impl<T, S: Storage<T>> Point<T, S>
+where
+    T: Into<f64> + Copy,
+{
     pub fn dim(&self) -> usize {
-        self.p.len()
+        self.as_slice().len()
     }
 
     pub fn dist(&self) -> f64 {
-        self.p.iter().map(|&x| x.into().powi(2)).sum::<f64>().sqrt()
+        self.iter().map(|&x| x.into().powi(2)).sum::<f64>().sqrt()
+    }
+
+    /// The inner product. Panics if the dimensions differ.
+    pub fn dot<O: Storage<T>>(&self, other: &Point<T, O>) -> f64 {
+        assert_eq!(self.dim(), other.dim(), "points must share a dimension");
+        self.iter()
+            .zip(other.iter())
+            .map(|(&a, &b)| a.into() * b.into())
+            .sum()
     }
 
     pub fn apply<F: Fn(&[T]) -> f64>(&self, func: F) -> f64 {
-        func(&self.p)
+        func(self.as_slice())
     }
+}
 
+// Coordinate access, for any coordinate type and storage.
+impl<T, S: Storage<T>> Point<T, S> {
     pub fn data(&self) -> &[T] {
-        &self.p
+        self.p.as_slice()
     }
-}
 
-// Implementing Add, Sub, Mul for Point<T>
-impl<T> Add<&Point<T>> for &Point<T>
-where
-    T: Add<Output = T> + Copy,
-{
-    type Output = Point<T>;
+    pub fn as_slice(&self) -> &[T] {
+        self.p.as_slice()
+    }
 
-    fn add(self, other: &Point<T>) -> Self::Output {
-        let p = self
-            .p
-            .iter()
-            .zip(other.p.iter())
-            .map(|(&a, &b)| a + b)
-            .collect();
-        Point { p }
+    pub fn as_mut_slice(&mut self) -> &mut [T]
+    where
+        S: StorageMut<T>,
+    {
+        self.p.as_mut_slice()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T>
+    where
+        S: StorageMut<T>,
+    {
+        self.as_mut_slice().iter_mut()
     }
 }
 
-impl<T> Sub<&Point<T>> for &Point<T>
-where
-    T: Sub<Output = T> + Copy,
-{
-    type Output = Point<T>;
+impl<T: Copy> Point<T> {
+    pub fn into_vec(self) -> Vec<T> {
+        self.p.into_vec()
+    }
+}
 
-    fn sub(self, other: &Point<T>) -> Self::Output {
-        let p = self
-            .p
-            .iter()
-            .zip(other.p.iter())
-            .map(|(&a, &b)| a - b)
-            .collect();
-        Point { p }
+impl<T: fmt::Debug, S: Storage<T>> fmt::Debug for Point<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Point")
+            .field("p", &self.as_slice())
+            .finish()
     }
 }
 
-impl<T> Mul<&Point<T>> for &Point<T>
-where
-    T: Mul<Output = T> + Copy,
-{
-    type Output = Point<T>;
+impl<T, S: Storage<T>, I: SliceIndex<[T]>> Index<I> for Point<T, S> {
+    type Output = I::Output;
 
-    fn mul(self, other: &Point<T>) -> Self::Output {
-        let p = self
-            .p
-            .iter()
-            .zip(other.p.iter())
-            .map(|(&a, &b)| a * b)
-            .collect();
-        Point { p }
+    fn index(&self, i: I) -> &I::Output {
+        &self.as_slice()[i]
     }
 }
 
-// Implementing scalar operations
-impl<T> Add<T> for &Point<T>
-where
-    T: Add<Output = T> + Copy,
-{
-    type Output = Point<T>;
+impl<T, S: StorageMut<T>, I: SliceIndex<[T]>> IndexMut<I> for Point<T, S> {
+    fn index_mut(&mut self, i: I) -> &mut I::Output {
+        &mut self.as_mut_slice()[i]
+    }
+}
+
+impl<'a, T, S: Storage<T>> IntoIterator for &'a Point<T, S> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
 
-    fn add(self, scalar: T) -> Self::Output {
-        let p = self.p.iter().map(|&a| a + scalar).collect();
-        Point { p }
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
 }
 
-impl<T> Sub<T> for &Point<T>
-where
-    T: Sub<Output = T> + Copy,
-{
-    type Output = Point<T>;
+impl<'a, T, S: StorageMut<T>> IntoIterator for &'a mut Point<T, S> {
+    type Item = &'a mut T;
+    type IntoIter = std::slice::IterMut<'a, T>;
 
-    fn sub(self, scalar: T) -> Self::Output {
-        let p = self.p.iter().map(|&a| a - scalar).collect();
-        Point { p }
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
     }
 }
 
-impl<T> Mul<T> for &Point<T>
-where
-    T: Mul<Output = T> + Copy,
-{
-    type Output = Point<T>;
+impl<T: Copy> IntoIterator for Point<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
 
-    fn mul(self, scalar: T) -> Self::Output {
-        let p = self.p.iter().map(|&a| a * scalar).collect();
-        Point { p }
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_vec().into_iter()
     }
 }
 
-impl<T> Div<T> for &Point<T>
-where
-    T: Div<Output = T> + Copy,
-{
-    type Output = Point<T>;
+impl<T, S: Storage<T>> Deref for Point<T, S> {
+    type Target = [T];
 
-    fn div(self, scalar: T) -> Self::Output {
-        let p = self.p.iter().map(|&a| a / scalar).collect();
-        Point { p }
+    fn deref(&self) -> &[T] {
+        self.as_slice()
     }
 }
 
-// Ownership operations
-impl<T> Add<Point<T>> for Point<T>
-where
-    T: Add<Output = T> + Copy,
-{
-    type Output = Point<T>;
+impl<T, S: StorageMut<T>> DerefMut for Point<T, S> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T, S: Storage<T>> AsRef<[T]> for Point<T, S> {
+    fn as_ref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
 
-    fn add(self, other: Point<T>) -> Self::Output {
-        let p = self
-            .p
-            .iter()
-            .zip(other.p.iter())
-            .map(|(&a, &b)| a + b)
-            .collect();
-        Point { p }
+impl<T, S: StorageMut<T>> AsMut<[T]> for Point<T, S> {
+    fn as_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
     }
 }
 
-impl<T> Sub<Point<T>> for Point<T>
+// Coordinate-wise `f`; panics naming `op` if the dimensions differ.
+fn elementwise<T: Copy, S: BuildStorage<T>>(
+    a: &[T],
+    b: &[T],
+    op: &str,
+    f: impl Fn(T, T) -> T,
+) -> Point<T, S::Output> {
+    try_elementwise::<T, S>(a, b, f).unwrap_or_else(|e| panic!("cannot {op} points: {e}"))
+}
+
+fn try_elementwise<T: Copy, S: BuildStorage<T>>(
+    a: &[T],
+    b: &[T],
+    f: impl Fn(T, T) -> T,
+) -> Result<Point<T, S::Output>, Error> {
+    if a.len() != b.len() {
+        return Err(Error::DimensionMismatch {
+            expected: a.len(),
+            found: b.len(),
+        });
+    }
+    Ok(Point::from_storage(S::build(
+        a.iter().zip(b).map(|(&x, &y)| f(x, y)),
+    )))
+}
+
+fn map<T: Copy, S: BuildStorage<T>>(a: &[T], f: impl Fn(T) -> T) -> Point<T, S::Output> {
+    Point::from_storage(S::build(a.iter().map(|&x| f(x))))
+}
+
+impl<T, S> Point<T, S>
 where
-    T: Sub<Output = T> + Copy,
+    T: Copy,
+    S: BuildStorage<T>,
 {
-    type Output = Point<T>;
-
-    fn sub(self, other: Point<T>) -> Self::Output {
-        let p = self
-            .p
-            .iter()
-            .zip(other.p.iter())
-            .map(|(&a, &b)| a - b)
-            .collect();
-        Point { p }
+    /// `self + other`, or `Error::DimensionMismatch` if the dimensions
+    /// differ. The `+` operator panics in that case.
+    pub fn try_add<O: Storage<T>>(&self, other: &Point<T, O>) -> Result<Point<T, S::Output>, Error>
+    where
+        T: Add<Output = T>,
+    {
+        try_elementwise::<T, S>(self.as_slice(), other.as_slice(), |a, b| a + b)
+    }
+
+    /// `self - other`, or `Error::DimensionMismatch` if the dimensions
+    /// differ.
+    pub fn try_sub<O: Storage<T>>(&self, other: &Point<T, O>) -> Result<Point<T, S::Output>, Error>
+    where
+        T: Sub<Output = T>,
+    {
+        try_elementwise::<T, S>(self.as_slice(), other.as_slice(), |a, b| a - b)
+    }
+
+    /// The coordinate-wise product, or `Error::DimensionMismatch` if the
+    /// dimensions differ.
+    pub fn try_mul<O: Storage<T>>(&self, other: &Point<T, O>) -> Result<Point<T, S::Output>, Error>
+    where
+        T: Mul<Output = T>,
+    {
+        try_elementwise::<T, S>(self.as_slice(), other.as_slice(), |a, b| a * b)
+    }
+
+    /// `self + other * k` in one pass, without the temporary point that
+    /// `&a + &(&b * k)` builds. Panics if the dimensions differ.
+    pub fn add_scaled<O: Storage<T>>(&self, other: &Point<T, O>, k: T) -> Point<T, S::Output>
+    where
+        T: Add<Output = T> + Mul<Output = T>,
+    {
+        elementwise::<T, S>(self.as_slice(), other.as_slice(), "add", |a, b| a + b * k)
+    }
+
+    /// The sum of `k * p` over `terms`, built in one pass: `a + 2b - c` is
+    /// `[(1, &a), (2, &b), (-1, &c)]`. Panics if `terms` is empty or the
+    /// dimensions differ.
+    pub fn linear_combination(terms: &[(T, &Self)]) -> Point<T, S::Output>
+    where
+        T: Add<Output = T> + Mul<Output = T>,
+    {
+        let ((k0, first), rest) = terms
+            .split_first()
+            .expect("linear combination needs at least one term");
+        if let Some((_, p)) = rest.iter().find(|(_, p)| p.len() != first.len()) {
+            let err = Error::DimensionMismatch {
+                expected: first.len(),
+                found: p.len(),
+            };
+            panic!("cannot combine points: {err}");
+        }
+        let coords = (0..first.len()).map(|i| {
+            rest.iter()
+                .fold(first[i] * *k0, |acc, (k, q)| acc + q[i] * *k)
+        });
+        Point::from_storage(S::build(coords))
     }
 }
 
-impl<T> Mul<Point<T>> for Point<T>
+// The arithmetic operators, for every storage and across storages. Results
+// go in the left operand's `BuildStorage::Output`.
+macro_rules! binary_ops {
+    ($($trait:ident $method:ident $op:tt $name:literal),+) => {
+        $(
+            impl<T, S, O> $trait<&Point<T, O>> for &Point<T, S>
+            where
+                T: $trait<Output = T> + Copy,
+                S: BuildStorage<T>,
+                O: Storage<T>,
+            {
+                type Output = Point<T, S::Output>;
+
+                fn $method(self, other: &Point<T, O>) -> Self::Output {
+                    elementwise::<T, S>(self.as_slice(), other.as_slice(), $name, |a, b| a $op b)
+                }
+            }
+
+            impl<T, S, O> $trait<Point<T, O>> for Point<T, S>
+            where
+                T: $trait<Output = T> + Copy,
+                S: BuildStorage<T>,
+                O: Storage<T>,
+            {
+                type Output = Point<T, S::Output>;
+
+                fn $method(self, other: Point<T, O>) -> Self::Output {
+                    &self $op &other
+                }
+            }
+        )+
+    };
+}
+
+binary_ops!(Add add + "add", Sub sub - "sub", Mul mul * "mul");
+
+macro_rules! scalar_ops {
+    ($($trait:ident $method:ident $op:tt),+) => {
+        $(
+            impl<T, S> $trait<T> for &Point<T, S>
+            where
+                T: $trait<Output = T> + Copy,
+                S: BuildStorage<T>,
+            {
+                type Output = Point<T, S::Output>;
+
+                fn $method(self, scalar: T) -> Self::Output {
+                    map::<T, S>(self.as_slice(), |a| a $op scalar)
+                }
+            }
+
+            impl<T, S> $trait<T> for Point<T, S>
+            where
+                T: $trait<Output = T> + Copy,
+                S: BuildStorage<T>,
+            {
+                type Output = Point<T, S::Output>;
+
+                fn $method(self, scalar: T) -> Self::Output {
+                    &self $op scalar
+                }
+            }
+        )+
+    };
+}
+
+scalar_ops!(Add add +, Sub sub -, Mul mul *, Div div /);
+
+impl<T, S> Neg for &Point<T, S>
 where
-    T: Mul<Output = T> + Copy,
+    T: Neg<Output = T> + Copy,
+    S: BuildStorage<T>,
 {
-    type Output = Point<T>;
+    type Output = Point<T, S::Output>;
 
-    fn mul(self, other: Point<T>) -> Self::Output {
-        let p = self
-            .p
-            .iter()
-            .zip(other.p.iter())
-            .map(|(&a, &b)| a * b)
-            .collect();
-        Point { p }
+    fn neg(self) -> Self::Output {
+        map::<T, S>(self.as_slice(), |a| -a)
     }
 }
 
-// Ownership operations with scalar
-impl<T> Add<T> for Point<T>
+impl<T, S> Neg for Point<T, S>
 where
-    T: Add<Output = T> + Copy,
+    T: Neg<Output = T> + Copy,
+    S: BuildStorage<T>,
 {
-    type Output = Point<T>;
+    type Output = Point<T, S::Output>;
 
-    fn add(self, scalar: T) -> Self::Output {
-        let p = self.p.iter().map(|&a| a + scalar).collect();
-        Point { p }
+    fn neg(self) -> Self::Output {
+        -&self
     }
 }
 
-impl<T> Sub<T> for Point<T>
+// In-place operations, which reuse the point's storage
+macro_rules! assign_ops {
+    ($($trait:ident $method:ident $op:tt $name:literal),+) => {
+        $(
+            impl<T, S, O> $trait<&Point<T, O>> for Point<T, S>
+            where
+                T: $trait + Copy,
+                S: StorageMut<T>,
+                O: Storage<T>,
+            {
+                fn $method(&mut self, other: &Point<T, O>) {
+                    assert_eq!(
+                        self.len(),
+                        other.len(),
+                        concat!("cannot ", $name, " points of different dimensions")
+                    );
+                    self.iter_mut().zip(other.iter()).for_each(|(a, &b)| *a $op b);
+                }
+            }
+
+            impl<T, S, O> $trait<Point<T, O>> for Point<T, S>
+            where
+                T: $trait + Copy,
+                S: StorageMut<T>,
+                O: Storage<T>,
+            {
+                fn $method(&mut self, other: Point<T, O>) {
+                    *self $op &other;
+                }
+            }
+
+            impl<T, S> $trait<T> for Point<T, S>
+            where
+                T: $trait + Copy,
+                S: StorageMut<T>,
+            {
+                fn $method(&mut self, scalar: T) {
+                    self.iter_mut().for_each(|a| *a $op scalar);
+                }
+            }
+        )+
+    };
+}
+
+assign_ops!(AddAssign add_assign += "add", SubAssign sub_assign -= "subtract", MulAssign mul_assign *= "multiply");
+
+impl<T, S> DivAssign<T> for Point<T, S>
 where
-    T: Sub<Output = T> + Copy,
+    T: DivAssign + Copy,
+    S: StorageMut<T>,
 {
-    type Output = Point<T>;
-
-    fn sub(self, scalar: T) -> Self::Output {
-        let p = self.p.iter().map(|&a| a - scalar).collect();
-        Point { p }
+    fn div_assign(&mut self, scalar: T) {
+        self.iter_mut().for_each(|a| *a /= scalar);
     }
 }
 
-impl<T> Mul<T> for Point<T>
+impl<T, S, O> PartialEq<Point<T, O>> for Point<T, S>
 where
-    T: Mul<Output = T> + Copy,
+    T: PartialEq,
+    S: Storage<T>,
+    O: Storage<T>,
 {
-    type Output = Point<T>;
-
-    fn mul(self, scalar: T) -> Self::Output {
-        let p = self.p.iter().map(|&a| a * scalar).collect();
-        Point { p }
+    fn eq(&self, other: &Point<T, O>) -> bool {
+        self.as_slice() == other.as_slice()
     }
 }
 
-impl<T> Div<T> for Point<T>
+// Fused in-place updates for iterative solvers; any mutable storage works.
+impl<T, S> Point<T, S>
 where
-    T: Div<Output = T> + Copy,
+    T: Add<Output = T> + Mul<Output = T> + Copy,
+    S: StorageMut<T>,
 {
-    type Output = Point<T>;
-
-    fn div(self, scalar: T) -> Self::Output {
-        let p = self.p.iter().map(|&a| a / scalar).collect();
-        Point { p }
+    /// `self += alpha * x`. Panics if the dimensions differ.
+    pub fn axpy<O: Storage<T>>(&mut self, alpha: T, x: &Point<T, O>) {
+        assert_eq!(
+            self.len(),
+            x.len(),
+            "cannot add points of different dimensions"
+        );
+        self.iter_mut()
+            .zip(x.iter())
+            .for_each(|(s, &v)| *s = *s + alpha * v);
+    }
+
+    /// `self += a * b` coordinate-wise. Panics if the dimensions differ.
+    pub fn fma<A: Storage<T>, B: Storage<T>>(&mut self, a: &Point<T, A>, b: &Point<T, B>) {
+        assert!(
+            self.len() == a.len() && a.len() == b.len(),
+            "cannot add points of different dimensions"
+        );
+        self.iter_mut()
+            .zip(a.iter().zip(b.iter()))
+            .for_each(|(s, (&x, &y))| *s = *s + x * y);
     }
 }
 
@@ -248,6 +650,15 @@ mod tests {
         dbg!(iv);
     }
 
+    #[test]
+    fn inline_storage() {
+        let p = Point::new(vec![1.0, 2.0, 3.0]);
+        let q = &p + &p;
+        assert!(p.p.is_inline());
+        assert!(q.p.is_inline());
+        assert!(!Point::new(vec![0; 5]).p.is_inline());
+    }
+
     #[test]
     fn add() {
         let iv1 = Point::new(vec![1, 2, 3]);
@@ -385,4 +796,126 @@ mod tests {
         let data = iv1.data();
         assert_eq!(data, &[1, 2, 3]);
     }
+
+    #[test]
+    fn slices() {
+        let mut iv1 = Point::from_vec(vec![1, 2, 3]);
+        iv1.as_mut_slice()[0] = 7;
+        assert_eq!(iv1.as_slice(), &[7, 2, 3]);
+        assert_eq!(iv1.as_ref(), &[7, 2, 3]);
+        assert_eq!(iv1.len(), 3);
+        assert_eq!(iv1.iter().max(), Some(&7));
+        assert_eq!(iv1.into_vec(), vec![7, 2, 3]);
+    }
+
+    #[test]
+    fn indexing_and_iteration() {
+        let mut p = Point::new(vec![1, 2, 3]);
+        p[1] = 5;
+        assert_eq!(p[1], 5);
+        assert_eq!(&p[1..], &[5, 3]);
+        p.iter_mut().for_each(|v| *v *= 2);
+        for v in &mut p {
+            *v += 1;
+        }
+        assert_eq!((&p).into_iter().sum::<i32>(), 21);
+        assert_eq!(p.into_iter().collect::<Vec<_>>(), vec![3, 11, 7]);
+        // Accessors don't need numeric coordinates.
+        let mut names = Point::from_storage(VecStorage::from_slice(&["x", "y"]));
+        names[0] = "u";
+        assert_eq!(names.as_slice(), &["u", "y"]);
+    }
+
+    #[test]
+    fn raw_parts() {
+        let iv1 = Point::new(vec![1, 2, 3, 4, 5, 6]);
+        let (ptr, len, cap) = iv1.into_raw_parts();
+        assert_eq!(len, 6);
+        let iv2 = unsafe { Point::from_raw_parts(ptr, len, cap) };
+        assert_eq!(iv2.data(), &[1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn dot_and_cross() {
+        let a = Point::new(vec![1, 2, 3]);
+        let b = Point::new(vec![4, -5, 6]);
+        assert_eq!(a.dot(&b), 12.0);
+        assert_eq!(a.cross(&b).data(), &[27, 6, -13]);
+        assert_eq!(a.cross(&b).dot(&a), 0.0);
+        assert_eq!(
+            Point::new(vec![0.5, 2.0]).dot(&Point::new(vec![2.0, 0.25])),
+            1.5
+        );
+    }
+
+    #[test]
+    fn mismatched_dimensions() {
+        let a = Point::new(vec![1, 2, 3]);
+        let b = Point::new(vec![1, 2]);
+        let err = Error::DimensionMismatch {
+            expected: 3,
+            found: 2,
+        };
+        assert_eq!(a.try_add(&b).unwrap_err(), err);
+        assert_eq!(a.try_sub(&b).unwrap_err(), err);
+        assert_eq!(a.try_mul(&b).unwrap_err(), err);
+        assert_eq!(a.try_add(&a).unwrap().data(), &[2, 4, 6]);
+        let panic = std::panic::catch_unwind(|| &a + &b).unwrap_err();
+        assert_eq!(
+            panic.downcast_ref::<String>().unwrap(),
+            "cannot add points: expected dimension 3, found 2"
+        );
+    }
+
+    #[test]
+    fn fused() {
+        let a = Point::new(vec![1.0, 2.0]);
+        let b = Point::new(vec![0.5, -1.0]);
+        let c = Point::new(vec![3.0, 3.0]);
+        assert_eq!(a.add_scaled(&b, 2.0).data(), &[2.0, 0.0]);
+        let chained = &(&a + &(&b * 2.0)) - &c;
+        let fused = Point::linear_combination(&[(1.0, &a), (2.0, &b), (-1.0, &c)]);
+        assert_eq!(fused.data(), chained.data());
+        let short = Point::new(vec![1.0]);
+        assert!(std::panic::catch_unwind(|| a.add_scaled(&short, 1.0)).is_err());
+        assert!(
+            std::panic::catch_unwind(|| Point::linear_combination(&[(1.0, &a), (1.0, &short)]))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn axpy_and_fma() {
+        let mut y = Point::new(vec![1.0, 1.0, 1.0]);
+        let x = Point::new(vec![1.0, 2.0, 3.0]);
+        y.axpy(2.0, &x);
+        assert_eq!(y.data(), &[3.0, 5.0, 7.0]);
+        y.fma(&x, &Point::from_array([1.0, 0.0, -1.0]));
+        assert_eq!(y.data(), &[4.0, 5.0, 4.0]);
+        let mut a = Point::from_array([1, 2]);
+        a.axpy(3, &Point::view(&[1, 1]));
+        assert_eq!(a.as_slice(), &[4, 5]);
+        assert!(std::panic::catch_unwind(move || a.axpy(1, &Point::new(vec![1]))).is_err());
+    }
+
+    #[test]
+    fn assign_ops() {
+        let mut p = Point::new(vec![1.0, 2.0, 3.0]);
+        let q = Point::new(vec![1.0, 1.0, 2.0]);
+        p += &q;
+        assert_eq!(p.data(), &[2.0, 3.0, 5.0]);
+        p -= q.clone();
+        p *= &q;
+        assert_eq!(p.data(), &[1.0, 2.0, 6.0]);
+        p += 1.0;
+        p -= 0.5;
+        p *= 4.0;
+        p /= 2.0;
+        assert_eq!(p.data(), &[3.0, 5.0, 13.0]);
+        let caught = std::panic::catch_unwind(|| {
+            let mut p = Point::new(vec![1, 2]);
+            p += Point::new(vec![1]);
+        });
+        assert!(caught.is_err());
+    }
 }
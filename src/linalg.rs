@@ -0,0 +1,417 @@
+// Dense linear algebra used by the geometric algorithms.
+//
+// Small, row-major, `f64` only. Nothing here is tuned for large matrices.
+
+use std::ops::{Index, IndexMut, Mul};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Matrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<f64>,
+}
+
+impl Matrix {
+    pub(crate) fn zeros(rows: usize, cols: usize) -> Self {
+        Matrix {
+            rows,
+            cols,
+            data: vec![0.0; rows * cols],
+        }
+    }
+
+    pub(crate) fn identity(n: usize) -> Self {
+        let mut m = Matrix::zeros(n, n);
+        for i in 0..n {
+            m[(i, i)] = 1.0;
+        }
+        m
+    }
+
+    pub(crate) fn from_rows(rows: &[Vec<f64>]) -> Self {
+        let cols = rows.first().map_or(0, |r| r.len());
+        let mut data = Vec::with_capacity(rows.len() * cols);
+        for r in rows {
+            assert_eq!(r.len(), cols, "ragged rows");
+            data.extend_from_slice(r);
+        }
+        Matrix {
+            rows: rows.len(),
+            cols,
+            data,
+        }
+    }
+
+    pub(crate) fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub(crate) fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub(crate) fn row(&self, i: usize) -> &[f64] {
+        &self.data[i * self.cols..(i + 1) * self.cols]
+    }
+
+    pub(crate) fn to_rows(&self) -> Vec<Vec<f64>> {
+        (0..self.rows).map(|i| self.row(i).to_vec()).collect()
+    }
+
+    pub(crate) fn transpose(&self) -> Matrix {
+        let mut t = Matrix::zeros(self.cols, self.rows);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                t[(j, i)] = self[(i, j)];
+            }
+        }
+        t
+    }
+
+    pub(crate) fn mul_vec(&self, v: &[f64]) -> Vec<f64> {
+        (0..self.rows).map(|i| dot(self.row(i), v)).collect()
+    }
+
+    /// Determinant of a square matrix by elimination with partial pivoting.
+    pub(crate) fn determinant(&self) -> f64 {
+        let n = self.rows;
+        assert_eq!(n, self.cols, "determinant of a non-square matrix");
+        let mut a = self.clone();
+        let mut det = 1.0;
+        for k in 0..n {
+            let p = (k..n)
+                .max_by(|&i, &j| a[(i, k)].abs().total_cmp(&a[(j, k)].abs()))
+                .unwrap();
+            if a[(p, k)] == 0.0 {
+                return 0.0;
+            }
+            if p != k {
+                for j in 0..n {
+                    a.data.swap(p * n + j, k * n + j);
+                }
+                det = -det;
+            }
+            det *= a[(k, k)];
+            for i in k + 1..n {
+                let f = a[(i, k)] / a[(k, k)];
+                for j in k..n {
+                    let v = a[(k, j)];
+                    a[(i, j)] -= f * v;
+                }
+            }
+        }
+        det
+    }
+
+    pub(crate) fn column(&self, j: usize) -> Vec<f64> {
+        (0..self.rows).map(|i| self[(i, j)]).collect()
+    }
+
+    /// Solves `self * x = b` by Gaussian elimination with partial pivoting.
+    /// Returns `None` for singular (or non-square) systems.
+    pub(crate) fn solve(&self, b: &[f64]) -> Option<Vec<f64>> {
+        let n = self.rows;
+        if self.cols != n || b.len() != n {
+            return None;
+        }
+        let mut a = self.clone();
+        let mut x = b.to_vec();
+        let scale = self
+            .data
+            .iter()
+            .fold(0.0f64, |m, v| m.max(v.abs()))
+            .max(1.0);
+        for k in 0..n {
+            let p = (k..n).max_by(|&i, &j| a[(i, k)].abs().total_cmp(&a[(j, k)].abs()))?;
+            if a[(p, k)].abs() <= 1e-12 * scale {
+                return None;
+            }
+            if p != k {
+                for j in 0..n {
+                    a.data.swap(p * n + j, k * n + j);
+                }
+                x.swap(p, k);
+            }
+            for i in k + 1..n {
+                let f = a[(i, k)] / a[(k, k)];
+                if f != 0.0 {
+                    for j in k..n {
+                        a[(i, j)] -= f * a[(k, j)];
+                    }
+                    x[i] -= f * x[k];
+                }
+            }
+        }
+        for k in (0..n).rev() {
+            let s: f64 = (k + 1..n).map(|j| a[(k, j)] * x[j]).sum();
+            x[k] = (x[k] - s) / a[(k, k)];
+        }
+        Some(x)
+    }
+
+    pub(crate) fn inverse(&self) -> Option<Matrix> {
+        let n = self.rows;
+        let mut inv = Matrix::zeros(n, n);
+        for j in 0..n {
+            let mut e = vec![0.0; n];
+            e[j] = 1.0;
+            let col = self.solve(&e)?;
+            for i in 0..n {
+                inv[(i, j)] = col[i];
+            }
+        }
+        Some(inv)
+    }
+
+    /// Rank by Gaussian elimination with tolerance `tol`.
+    pub(crate) fn rank(&self, tol: f64) -> usize {
+        let mut a = self.clone();
+        let mut rank = 0;
+        for col in 0..a.cols {
+            if rank == a.rows {
+                break;
+            }
+            let p = (rank..a.rows)
+                .max_by(|&i, &j| a[(i, col)].abs().total_cmp(&a[(j, col)].abs()))
+                .unwrap();
+            if a[(p, col)].abs() <= tol {
+                continue;
+            }
+            for j in 0..a.cols {
+                a.data.swap(p * a.cols + j, rank * a.cols + j);
+            }
+            for i in rank + 1..a.rows {
+                let f = a[(i, col)] / a[(rank, col)];
+                for j in col..a.cols {
+                    let v = a[(rank, j)];
+                    a[(i, j)] -= f * v;
+                }
+            }
+            rank += 1;
+        }
+        rank
+    }
+
+    /// Eigenvalues of a symmetric matrix, descending, and the matching unit
+    /// eigenvectors as columns, by cyclic Jacobi rotations.
+    pub(crate) fn symmetric_eigen(&self) -> (Vec<f64>, Matrix) {
+        let n = self.rows;
+        let mut a = self.clone();
+        let mut v = Matrix::identity(n);
+        let total: f64 = a.data.iter().map(|x| x * x).sum();
+        for _ in 0..100 {
+            let off: f64 = (0..n)
+                .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+                .map(|(i, j)| a[(i, j)] * a[(i, j)])
+                .sum();
+            if off <= 1e-30 * total {
+                break;
+            }
+            for p in 0..n {
+                for q in p + 1..n {
+                    if a[(p, q)] == 0.0 {
+                        continue;
+                    }
+                    // The rotation in the (p, q) plane that zeroes a[p][q].
+                    let theta = (a[(q, q)] - a[(p, p)]) / (2.0 * a[(p, q)]);
+                    let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                    let c = 1.0 / (t * t + 1.0).sqrt();
+                    let s = t * c;
+                    for k in 0..n {
+                        let (x, y) = (a[(k, p)], a[(k, q)]);
+                        a[(k, p)] = c * x - s * y;
+                        a[(k, q)] = s * x + c * y;
+                    }
+                    for k in 0..n {
+                        let (x, y) = (a[(p, k)], a[(q, k)]);
+                        a[(p, k)] = c * x - s * y;
+                        a[(q, k)] = s * x + c * y;
+                    }
+                    for k in 0..n {
+                        let (x, y) = (v[(k, p)], v[(k, q)]);
+                        v[(k, p)] = c * x - s * y;
+                        v[(k, q)] = s * x + c * y;
+                    }
+                }
+            }
+        }
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&i, &j| a[(j, j)].total_cmp(&a[(i, i)]));
+        let mut vectors = Matrix::zeros(n, n);
+        for (col, &k) in order.iter().enumerate() {
+            for i in 0..n {
+                vectors[(i, col)] = v[(i, k)];
+            }
+        }
+        (order.iter().map(|&k| a[(k, k)]).collect(), vectors)
+    }
+
+    /// The rotation `r` (orthogonal, determinant one) maximising
+    /// `trace(r^T self)`. With `self = sum q p^T` over centred pairs it is
+    /// the rotation best taking every `p` to its `q` (Kabsch), from the
+    /// singular value decomposition `self = u s v^T`. Square matrices only.
+    pub(crate) fn nearest_rotation(&self) -> Matrix {
+        let n = self.rows;
+        let (values, v) = (&self.transpose() * self).symmetric_eigen();
+        let largest = values[0].max(0.0).sqrt();
+        // Columns of u: self v_i / s_i, orthonormalised, with the basis
+        // filling in wherever s_i vanishes.
+        let mut u: Vec<Vec<f64>> = Vec::with_capacity(n);
+        let mut candidates = (0..n).map(|i| {
+            let s = values[i].max(0.0).sqrt();
+            (s > 1e-8 * largest).then(|| self.mul_vec(&v.column(i)))
+        });
+        let mut basis = 0;
+        while u.len() < n {
+            let mut c = match candidates.next() {
+                Some(Some(c)) => c,
+                _ => {
+                    let mut e = vec![0.0; n];
+                    e[basis] = 1.0;
+                    basis += 1;
+                    e
+                }
+            };
+            for w in &u {
+                let k = dot(&c, w);
+                c.iter_mut().zip(w).for_each(|(x, w)| *x -= k * w);
+            }
+            let len = norm(&c);
+            if len > 1e-8 {
+                u.push(c.iter().map(|x| x / len).collect());
+            }
+        }
+        let mut u = Matrix::from_rows(&u).transpose();
+        // A reflection flips the direction of least singular value.
+        if u.determinant() * v.determinant() < 0.0 {
+            for i in 0..n {
+                u[(i, n - 1)] = -u[(i, n - 1)];
+            }
+        }
+        &u * &v.transpose()
+    }
+}
+
+impl Index<(usize, usize)> for Matrix {
+    type Output = f64;
+
+    fn index(&self, (i, j): (usize, usize)) -> &f64 {
+        &self.data[i * self.cols + j]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Matrix {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut f64 {
+        &mut self.data[i * self.cols + j]
+    }
+}
+
+impl Mul<&Matrix> for &Matrix {
+    type Output = Matrix;
+
+    fn mul(self, other: &Matrix) -> Matrix {
+        assert_eq!(self.cols, other.rows, "matrix dimensions don't match");
+        let mut out = Matrix::zeros(self.rows, other.cols);
+        for i in 0..self.rows {
+            for k in 0..self.cols {
+                let a = self[(i, k)];
+                if a != 0.0 {
+                    for j in 0..other.cols {
+                        out[(i, j)] += a * other[(k, j)];
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+pub(crate) fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+pub(crate) fn norm(a: &[f64]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solve_and_inverse() {
+        let a = Matrix::from_rows(&[vec![2.0, 1.0], vec![1.0, 3.0]]);
+        let x = a.solve(&[3.0, 5.0]).unwrap();
+        assert!((x[0] - 0.8).abs() < 1e-12 && (x[1] - 1.4).abs() < 1e-12);
+
+        let inv = a.inverse().unwrap();
+        let id = &a * &inv;
+        for i in 0..2 {
+            for j in 0..2 {
+                let e = if i == j { 1.0 } else { 0.0 };
+                assert!((id[(i, j)] - e).abs() < 1e-12);
+            }
+        }
+
+        let singular = Matrix::from_rows(&[vec![1.0, 2.0], vec![2.0, 4.0]]);
+        assert!(singular.solve(&[1.0, 2.0]).is_none());
+        assert_eq!(singular.rank(1e-9), 1);
+    }
+
+    #[test]
+    fn products() {
+        let a = Matrix::from_rows(&[vec![1.0, 2.0, 3.0]]);
+        let b = Matrix::from_rows(&[vec![1.0], vec![1.0], vec![1.0]]);
+        assert_eq!((&a * &b)[(0, 0)], 6.0);
+        assert_eq!(b.column(0), vec![1.0, 1.0, 1.0]);
+        assert_eq!(Matrix::from_rows(&[a.column(2)]).rank(1e-9), 1);
+        assert_eq!(norm(&[3.0, 4.0]), 5.0);
+        assert_eq!(a.transpose().transpose(), a);
+        assert_eq!(a.transpose().column(0), vec![1.0, 2.0, 3.0]);
+        assert_eq!(a.mul_vec(&[1.0, 0.0, 1.0]), vec![4.0]);
+    }
+
+    #[test]
+    fn determinant() {
+        let a = Matrix::from_rows(&[vec![0.0, 2.0], vec![3.0, 1.0]]);
+        assert_eq!(a.determinant(), -6.0);
+        assert_eq!(Matrix::identity(3).determinant(), 1.0);
+        assert_eq!(Matrix::zeros(2, 2).determinant(), 0.0);
+    }
+
+    #[test]
+    fn eigen_and_rotation() {
+        let a = Matrix::from_rows(&[
+            vec![2.0, 1.0, 0.0],
+            vec![1.0, 2.0, 0.0],
+            vec![0.0, 0.0, 5.0],
+        ]);
+        let (values, vectors) = a.symmetric_eigen();
+        for (k, e) in [5.0, 3.0, 1.0].iter().enumerate() {
+            assert!((values[k] - e).abs() < 1e-12);
+            let x = vectors.column(k);
+            let ax = a.mul_vec(&x);
+            assert!(ax.iter().zip(&x).all(|(y, x)| (y - e * x).abs() < 1e-12));
+        }
+
+        // A rotation scaled unevenly still has that rotation as its nearest.
+        let (s, c) = 0.7f64.sin_cos();
+        let r = Matrix::from_rows(&[vec![c, -s, 0.0], vec![s, c, 0.0], vec![0.0, 0.0, 1.0]]);
+        let m = &r
+            * &Matrix::from_rows(&[
+                vec![3.0, 0.0, 0.0],
+                vec![0.0, 1.0, 0.0],
+                vec![0.0, 0.0, 0.0],
+            ]);
+        let nearest = m.nearest_rotation();
+        for i in 0..3 {
+            for j in 0..3 {
+                assert!((nearest[(i, j)] - r[(i, j)]).abs() < 1e-9);
+            }
+        }
+        // A reflection gives the nearest proper rotation instead.
+        let mirror = Matrix::from_rows(&[vec![1.0, 0.0], vec![0.0, -0.5]]);
+        assert!((mirror.nearest_rotation().determinant() - 1.0).abs() < 1e-12);
+        assert_eq!(Matrix::zeros(2, 2).nearest_rotation(), Matrix::identity(2));
+    }
+}
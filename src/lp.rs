@@ -0,0 +1,251 @@
+// A small dense linear-programming solver.
+//
+// Two-phase simplex on a full tableau with Bland's rule, which never cycles.
+// It is meant for the handful of variables and constraints that geometric
+// queries produce, not for large sparse problems.
+
+const EPS: f64 = 1e-9;
+
+/// Outcome of [`maximize`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lp {
+    Optimal { x: Vec<f64>, value: f64 },
+    Infeasible,
+    Unbounded,
+}
+
+struct Tableau {
+    rows: Vec<Vec<f64>>,
+    obj: Vec<f64>,
+    basis: Vec<usize>,
+}
+
+impl Tableau {
+    fn rhs(&self, i: usize) -> f64 {
+        *self.rows[i].last().unwrap()
+    }
+
+    fn pivot(&mut self, row: usize, col: usize) {
+        let p = self.rows[row][col];
+        for v in self.rows[row].iter_mut() {
+            *v /= p;
+        }
+        let pivot_row = self.rows[row].clone();
+        for (i, r) in self.rows.iter_mut().enumerate() {
+            let f = r[col];
+            if i != row && f != 0.0 {
+                for (v, pv) in r.iter_mut().zip(&pivot_row) {
+                    *v -= f * pv;
+                }
+            }
+        }
+        let f = self.obj[col];
+        if f != 0.0 {
+            for (v, pv) in self.obj.iter_mut().zip(&pivot_row) {
+                *v -= f * pv;
+            }
+        }
+        self.basis[row] = col;
+    }
+
+    // Runs simplex iterations on columns `0..cols`. Returns false if unbounded.
+    fn optimize(&mut self, cols: usize) -> bool {
+        loop {
+            let Some(col) = (0..cols).find(|&j| self.obj[j] < -EPS) else {
+                return true;
+            };
+            let mut best: Option<(usize, f64)> = None;
+            for i in 0..self.rows.len() {
+                let a = self.rows[i][col];
+                if a > EPS {
+                    let ratio = self.rhs(i) / a;
+                    let better = match best {
+                        None => true,
+                        Some((b, r)) => {
+                            ratio < r - EPS || (ratio < r + EPS && self.basis[i] < self.basis[b])
+                        }
+                    };
+                    if better {
+                        best = Some((i, ratio));
+                    }
+                }
+            }
+            match best {
+                Some((row, _)) => self.pivot(row, col),
+                None => return false,
+            }
+        }
+    }
+
+    // Objective row for maximising `c` over the current basis.
+    fn set_objective(&mut self, c: &[f64]) {
+        let width = self.rows.first().map_or(c.len() + 1, |r| r.len());
+        let mut obj = vec![0.0; width];
+        for (j, o) in obj.iter_mut().enumerate().take(c.len()) {
+            *o = -c[j];
+        }
+        for (i, &b) in self.basis.iter().enumerate() {
+            let cb = c.get(b).copied().unwrap_or(0.0);
+            if cb != 0.0 {
+                for (o, v) in obj.iter_mut().zip(&self.rows[i]) {
+                    *o += cb * v;
+                }
+            }
+        }
+        self.obj = obj;
+    }
+}
+
+/// Maximises `c . x` subject to `a x <= b` with `x` unrestricted in sign.
+pub fn maximize(c: &[f64], a: &[Vec<f64>], b: &[f64]) -> Lp {
+    let n = c.len();
+    let m = a.len();
+    // Columns: x+ (n), x- (n), slacks (m), artificials (one per negative row).
+    let negative: Vec<usize> = (0..m).filter(|&i| b[i] < 0.0).collect();
+    let structural = 2 * n + m;
+    let width = structural + negative.len() + 1;
+
+    let mut rows = Vec::with_capacity(m);
+    let mut basis = Vec::with_capacity(m);
+    for i in 0..m {
+        let mut row = vec![0.0; width];
+        let sign = if b[i] < 0.0 { -1.0 } else { 1.0 };
+        for j in 0..n {
+            row[j] = sign * a[i][j];
+            row[n + j] = -sign * a[i][j];
+        }
+        row[2 * n + i] = sign;
+        row[width - 1] = sign * b[i];
+        if let Some(k) = negative.iter().position(|&r| r == i) {
+            row[structural + k] = 1.0;
+            basis.push(structural + k);
+        } else {
+            basis.push(2 * n + i);
+        }
+        rows.push(row);
+    }
+    let mut t = Tableau {
+        rows,
+        obj: Vec::new(),
+        basis,
+    };
+
+    if !negative.is_empty() {
+        // Phase 1: drive the artificials to zero.
+        let mut c1 = vec![0.0; structural + negative.len()];
+        for v in c1.iter_mut().skip(structural) {
+            *v = -1.0;
+        }
+        t.set_objective(&c1);
+        t.optimize(structural + negative.len());
+        if *t.obj.last().unwrap() < -EPS {
+            return Lp::Infeasible;
+        }
+        // Pivot remaining (zero) artificials out of the basis, dropping
+        // rows that turn out to be redundant.
+        let mut i = 0;
+        while i < t.rows.len() {
+            if t.basis[i] >= structural {
+                match (0..structural).find(|&j| t.rows[i][j].abs() > EPS) {
+                    Some(j) => t.pivot(i, j),
+                    None => {
+                        t.rows.remove(i);
+                        t.basis.remove(i);
+                        continue;
+                    }
+                }
+            }
+            i += 1;
+        }
+        for r in t.rows.iter_mut() {
+            r.drain(structural..width - 1);
+        }
+    }
+
+    let mut c2 = vec![0.0; structural];
+    c2[..n].copy_from_slice(c);
+    for j in 0..n {
+        c2[n + j] = -c[j];
+    }
+    t.set_objective(&c2);
+    if !t.optimize(structural) {
+        return Lp::Unbounded;
+    }
+
+    let mut y = vec![0.0; structural];
+    for (i, &bi) in t.basis.iter().enumerate() {
+        y[bi] = t.rhs(i);
+    }
+    let x: Vec<f64> = (0..n).map(|j| y[j] - y[n + j]).collect();
+    let value = c.iter().zip(&x).map(|(a, b)| a * b).sum();
+    Lp::Optimal { x, value }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounded() {
+        // max x + y s.t. x + 2y <= 4, 3x + y <= 6, x, y >= 0
+        let a = vec![
+            vec![1.0, 2.0],
+            vec![3.0, 1.0],
+            vec![-1.0, 0.0],
+            vec![0.0, -1.0],
+        ];
+        let b = vec![4.0, 6.0, 0.0, 0.0];
+        match maximize(&[1.0, 1.0], &a, &b) {
+            Lp::Optimal { x, value } => {
+                assert!((value - 2.8).abs() < 1e-9);
+                assert!((x[0] - 1.6).abs() < 1e-9 && (x[1] - 1.2).abs() < 1e-9);
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn negative_rhs_and_free_variables() {
+        // max -x s.t. x >= 2 (i.e. -x <= -2), x <= 5
+        let a = vec![vec![-1.0], vec![1.0]];
+        let b = vec![-2.0, 5.0];
+        match maximize(&[-1.0], &a, &b) {
+            Lp::Optimal { x, value } => {
+                assert!((x[0] - 2.0).abs() < 1e-9);
+                assert!((value + 2.0).abs() < 1e-9);
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+        // Unrestricted sign: max -x s.t. x >= -3.
+        match maximize(&[-1.0], &[vec![-1.0]], &[3.0]) {
+            Lp::Optimal { x, .. } => assert!((x[0] + 3.0).abs() < 1e-9),
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn infeasible_and_unbounded() {
+        // x <= 1 and x >= 2
+        let a = vec![vec![1.0], vec![-1.0]];
+        assert_eq!(maximize(&[1.0], &a, &[1.0, -2.0]), Lp::Infeasible);
+        // max x s.t. -x <= 0
+        assert_eq!(maximize(&[1.0], &[vec![-1.0]], &[0.0]), Lp::Unbounded);
+    }
+
+    #[test]
+    fn degenerate() {
+        // Many constraints through the optimum.
+        let a = vec![
+            vec![1.0, 0.0],
+            vec![0.0, 1.0],
+            vec![1.0, 1.0],
+            vec![2.0, 1.0],
+            vec![1.0, 2.0],
+        ];
+        let b = vec![1.0, 1.0, 2.0, 3.0, 3.0];
+        match maximize(&[1.0, 1.0], &a, &b) {
+            Lp::Optimal { value, .. } => assert!((value - 2.0).abs() < 1e-9),
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,254 @@
+// Map projections of geographic points on the WGS 84 ellipsoid.
+//
+// Points are `(longitude, latitude)` in degrees as in `geo`; projected
+// points are `(easting, northing)` in metres. UTM uses the transverse
+// Mercator series of Snyder's "Map Projections: A Working Manual", good to
+// well under a millimetre inside a zone. Web Mercator (EPSG:3857) is the
+// spherical Mercator of web map tiles, on a sphere of the WGS 84 equatorial
+// radius.
+
+use std::f64::consts::FRAC_PI_4;
+
+use crate::Point2;
+
+/// WGS 84 semi-major axis in metres.
+pub const WGS84_A: f64 = 6_378_137.0;
+/// WGS 84 flattening.
+pub const WGS84_F: f64 = 1.0 / 298.257_223_563;
+/// Web Mercator's latitude limit, where the map becomes square.
+pub const WEB_MERCATOR_MAX_LATITUDE: f64 = 85.051_128_779_806_59;
+
+const K0: f64 = 0.9996;
+const FALSE_EASTING: f64 = 500_000.0;
+const FALSE_NORTHING: f64 = 10_000_000.0;
+
+/// A UTM zone: a 6 degree band of longitude, north or south of the equator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UtmZone {
+    /// 1 to 60, eastwards from 180°W.
+    pub number: u8,
+    pub north: bool,
+}
+
+impl UtmZone {
+    /// The zone of `p`, with the usual exceptions around Norway and
+    /// Svalbard; `None` outside UTM's latitudes, 80°S to 84°N.
+    pub fn of(p: &Point2<f64>) -> Option<UtmZone> {
+        let (lon, lat) = (p.x(), p.y());
+        if !(-80.0..=84.0).contains(&lat) {
+            return None;
+        }
+        let lon = (lon + 540.0).rem_euclid(360.0) - 180.0;
+        let mut number = ((lon + 180.0) / 6.0).floor() as u8 % 60 + 1;
+        if (56.0..64.0).contains(&lat) && (3.0..12.0).contains(&lon) {
+            number = 32;
+        }
+        if lat >= 72.0 {
+            number = match lon {
+                l if (0.0..9.0).contains(&l) => 31,
+                l if (9.0..21.0).contains(&l) => 33,
+                l if (21.0..33.0).contains(&l) => 35,
+                l if (33.0..42.0).contains(&l) => 37,
+                _ => number,
+            };
+        }
+        Some(UtmZone {
+            number,
+            north: lat >= 0.0,
+        })
+    }
+
+    /// The latitude band letter, `C` to `X` without `I` and `O`, of the
+    /// point's latitude; `None` outside 80°S to 84°N.
+    pub fn band(latitude: f64) -> Option<char> {
+        const BANDS: &[u8; 20] = b"CDEFGHJKLMNPQRSTUVWX";
+        if !(-80.0..=84.0).contains(&latitude) {
+            return None;
+        }
+        let i = (((latitude + 80.0) / 8.0) as usize).min(19);
+        Some(BANDS[i] as char)
+    }
+
+    /// Longitude of the zone's central meridian, in degrees.
+    pub fn central_meridian(&self) -> f64 {
+        self.number as f64 * 6.0 - 183.0
+    }
+}
+
+fn eccentricity2() -> f64 {
+    WGS84_F * (2.0 - WGS84_F)
+}
+
+// Meridian arc length from the equator to latitude `phi` (radians).
+fn meridian_arc(phi: f64) -> f64 {
+    let e2 = eccentricity2();
+    let (e4, e6) = (e2 * e2, e2 * e2 * e2);
+    WGS84_A
+        * ((1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * phi
+            - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * phi).sin()
+            + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * phi).sin()
+            - (35.0 * e6 / 3072.0) * (6.0 * phi).sin())
+}
+
+/// `p` in UTM coordinates in its own zone; `None` outside 80°S to 84°N.
+pub fn to_utm(p: &Point2<f64>) -> Option<(Point2<f64>, UtmZone)> {
+    let zone = UtmZone::of(p)?;
+    Some((to_utm_zone(p, zone), zone))
+}
+
+/// `p` in UTM coordinates of `zone`, which needn't be its own; accuracy
+/// falls off a few degrees past the zone's edges.
+pub fn to_utm_zone(p: &Point2<f64>, zone: UtmZone) -> Point2<f64> {
+    let e2 = eccentricity2();
+    let ep2 = e2 / (1.0 - e2);
+    let phi = p.y().to_radians();
+    let dlon = (p.x() - zone.central_meridian() + 540.0).rem_euclid(360.0) - 180.0;
+    let (sin, cos, tan) = (phi.sin(), phi.cos(), phi.tan());
+    let n = WGS84_A / (1.0 - e2 * sin * sin).sqrt();
+    let t = tan * tan;
+    let c = ep2 * cos * cos;
+    let a = cos * dlon.to_radians();
+    let easting = K0
+        * n
+        * (a + (1.0 - t + c) * a.powi(3) / 6.0
+            + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * a.powi(5) / 120.0)
+        + FALSE_EASTING;
+    let mut northing = K0
+        * (meridian_arc(phi)
+            + n * tan
+                * (a * a / 2.0
+                    + (5.0 - t + 9.0 * c + 4.0 * c * c) * a.powi(4) / 24.0
+                    + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * a.powi(6) / 720.0));
+    if !zone.north {
+        northing += FALSE_NORTHING;
+    }
+    Point2::new(easting, northing)
+}
+
+/// The geographic point at UTM coordinates `p` in `zone`.
+pub fn from_utm(p: &Point2<f64>, zone: UtmZone) -> Point2<f64> {
+    let e2 = eccentricity2();
+    let ep2 = e2 / (1.0 - e2);
+    let (e4, e6) = (e2 * e2, e2 * e2 * e2);
+    let northing = if zone.north {
+        p.y()
+    } else {
+        p.y() - FALSE_NORTHING
+    };
+    let m = northing / K0;
+    let mu = m / (WGS84_A * (1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0));
+    let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+    let phi1 = mu
+        + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+        + (21.0 * e1 * e1 / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+        + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+        + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+    let (sin, cos, tan) = (phi1.sin(), phi1.cos(), phi1.tan());
+    let c1 = ep2 * cos * cos;
+    let t1 = tan * tan;
+    let w = 1.0 - e2 * sin * sin;
+    let n1 = WGS84_A / w.sqrt();
+    let r1 = WGS84_A * (1.0 - e2) / w.powf(1.5);
+    let d = (p.x() - FALSE_EASTING) / (n1 * K0);
+    let phi = phi1
+        - (n1 * tan / r1)
+            * (d * d / 2.0
+                - (5.0 + 3.0 * t1 + 10.0 * c1 - 4.0 * c1 * c1 - 9.0 * ep2) * d.powi(4) / 24.0
+                + (61.0 + 90.0 * t1 + 298.0 * c1 + 45.0 * t1 * t1 - 252.0 * ep2 - 3.0 * c1 * c1)
+                    * d.powi(6)
+                    / 720.0);
+    let dlon = (d - (1.0 + 2.0 * t1 + c1) * d.powi(3) / 6.0
+        + (5.0 - 2.0 * c1 + 28.0 * t1 - 3.0 * c1 * c1 + 8.0 * ep2 + 24.0 * t1 * t1) * d.powi(5)
+            / 120.0)
+        / cos;
+    let lon = zone.central_meridian() + dlon.to_degrees();
+    Point2::new((lon + 540.0).rem_euclid(360.0) - 180.0, phi.to_degrees())
+}
+
+/// `p` in Web Mercator metres. Latitudes beyond
+/// `WEB_MERCATOR_MAX_LATITUDE` are clamped to it, as map tiles are.
+pub fn to_web_mercator(p: &Point2<f64>) -> Point2<f64> {
+    let lat = p
+        .y()
+        .clamp(-WEB_MERCATOR_MAX_LATITUDE, WEB_MERCATOR_MAX_LATITUDE)
+        .to_radians();
+    Point2::new(
+        WGS84_A * p.x().to_radians(),
+        WGS84_A * (FRAC_PI_4 + lat / 2.0).tan().ln(),
+    )
+}
+
+/// The geographic point at Web Mercator coordinates `p`.
+pub fn from_web_mercator(p: &Point2<f64>) -> Point2<f64> {
+    let lat = 2.0 * (p.y() / WGS84_A).exp().atan() - std::f64::consts::FRAC_PI_2;
+    Point2::new((p.x() / WGS84_A).to_degrees(), lat.to_degrees())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tol: f64) -> bool {
+        (a - b).abs() < tol
+    }
+
+    #[test]
+    fn utm_reference_points() {
+        // On the central meridian of zone 31 at the equator.
+        let (p, zone) = to_utm(&Point2::new(3.0, 0.0)).unwrap();
+        assert_eq!(
+            zone,
+            UtmZone {
+                number: 31,
+                north: true
+            }
+        );
+        assert!(close(p.x(), 500_000.0, 1e-6) && close(p.y(), 0.0, 1e-6));
+        // The origin sits 3 degrees west of that meridian.
+        let (p, _) = to_utm(&Point2::new(0.0, 0.0)).unwrap();
+        assert!(close(p.x(), 166_021.443, 1e-2) && close(p.y(), 0.0, 1e-6));
+        // Southern points get the false northing.
+        let (p, zone) = to_utm(&Point2::new(151.2093, -33.8688)).unwrap();
+        assert_eq!(
+            zone,
+            UtmZone {
+                number: 56,
+                north: false
+            }
+        );
+        assert!(close(p.x(), 334_368.6, 1.0) && close(p.y(), 6_250_948.3, 1.0));
+        assert!(to_utm(&Point2::new(0.0, 85.0)).is_none());
+    }
+
+    #[test]
+    fn utm_round_trip_and_zones() {
+        for &(lon, lat) in &[
+            (-122.4194, 37.7749),
+            (18.07, 59.33),
+            (-70.0, -55.0),
+            (179.9, 10.0),
+        ] {
+            let g = Point2::new(lon, lat);
+            let (p, zone) = to_utm(&g).unwrap();
+            let back = from_utm(&p, zone);
+            assert!(close(back.x(), lon, 1e-8) && close(back.y(), lat, 1e-8));
+        }
+        assert_eq!(UtmZone::of(&Point2::new(5.0, 60.0)).unwrap().number, 32);
+        assert_eq!(UtmZone::of(&Point2::new(10.0, 78.0)).unwrap().number, 33);
+        assert_eq!(UtmZone::of(&Point2::new(180.0, 0.0)).unwrap().number, 1);
+        assert_eq!(UtmZone::band(48.8), Some('U'));
+        assert_eq!(UtmZone::band(84.0), Some('X'));
+        assert_eq!(UtmZone::band(-81.0), None);
+    }
+
+    #[test]
+    fn web_mercator() {
+        let p = to_web_mercator(&Point2::new(180.0, 0.0));
+        assert!(close(p.x(), 20_037_508.342_789_244, 1e-6) && close(p.y(), 0.0, 1e-6));
+        let corner = to_web_mercator(&Point2::new(-180.0, 90.0));
+        assert!(close(corner.y(), -corner.x(), 1e-6));
+        let g = Point2::new(-0.1278, 51.5074);
+        let back = from_web_mercator(&to_web_mercator(&g));
+        assert!(close(back.x(), g.x(), 1e-12) && close(back.y(), g.y(), 1e-12));
+    }
+}
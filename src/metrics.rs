@@ -0,0 +1,155 @@
+// Norms of a point and distances and similarities between two points.
+//
+// The pairwise functions compare points of the same dimension and panic
+// otherwise, like `Point::dot`. The Lp norm and the Minkowski distance
+// generalise the others: order 1 is Manhattan, order 2 Euclidean and order
+// infinity Chebyshev.
+
+use crate::{Error, Point, Storage};
+
+impl<T, S: Storage<T>> Point<T, S>
+where
+    T: Into<f64> + Copy,
+{
+    // Absolute coordinate differences. Panics if the dimensions differ.
+    fn gaps<'a, O: Storage<T>>(&'a self, other: &'a Point<T, O>) -> impl Iterator<Item = f64> + 'a {
+        assert_eq!(self.dim(), other.dim(), "points must share a dimension");
+        self.iter()
+            .zip(other.iter())
+            .map(|(&a, &b)| (a.into() - b.into()).abs())
+    }
+
+    /// Sum of the absolute coordinates.
+    pub fn norm_l1(&self) -> f64 {
+        self.iter().map(|&v| v.into().abs()).sum()
+    }
+
+    /// Euclidean length; the same as [`Point::dist`].
+    pub fn norm_l2(&self) -> f64 {
+        self.dist()
+    }
+
+    /// Largest absolute coordinate.
+    pub fn norm_inf(&self) -> f64 {
+        self.iter().map(|&v| v.into().abs()).fold(0.0, f64::max)
+    }
+
+    /// The Lp norm of order `p`, which may be `f64::INFINITY`. Panics unless
+    /// `p >= 1`, below which it isn't a norm.
+    pub fn norm_lp(&self, p: f64) -> f64 {
+        assert!(p >= 1.0, "norm order must be at least 1");
+        match p {
+            1.0 => self.norm_l1(),
+            2.0 => self.norm_l2(),
+            f64::INFINITY => self.norm_inf(),
+            _ => self
+                .iter()
+                .map(|&v| v.into().abs().powf(p))
+                .sum::<f64>()
+                .powf(p.recip()),
+        }
+    }
+
+    /// The point scaled to unit Euclidean length, or `Error::ZeroLength`
+    /// for the zero vector.
+    pub fn try_normalize(&self) -> Result<Point<f64>, Error> {
+        let len = self.norm_l2();
+        if len == 0.0 {
+            return Err(Error::ZeroLength);
+        }
+        Ok(Point::new(self.iter().map(|&v| v.into() / len).collect()))
+    }
+
+    /// The point scaled to unit Euclidean length. Panics on the zero vector;
+    /// see [`Point::try_normalize`].
+    pub fn normalize(&self) -> Point<f64> {
+        self.try_normalize().unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Euclidean distance to `other`.
+    pub fn distance_to<O: Storage<T>>(&self, other: &Point<T, O>) -> f64 {
+        self.gaps(other).map(|d| d * d).sum::<f64>().sqrt()
+    }
+
+    /// Sum of the absolute coordinate differences (L1, taxicab).
+    pub fn manhattan_distance<O: Storage<T>>(&self, other: &Point<T, O>) -> f64 {
+        self.gaps(other).sum()
+    }
+
+    /// Largest absolute coordinate difference (L∞).
+    pub fn chebyshev_distance<O: Storage<T>>(&self, other: &Point<T, O>) -> f64 {
+        self.gaps(other).fold(0.0, f64::max)
+    }
+
+    /// The Lp distance of order `p`, which may be `f64::INFINITY`. Panics
+    /// unless `p >= 1`, below which it isn't a metric.
+    pub fn minkowski_distance<O: Storage<T>>(&self, other: &Point<T, O>, p: f64) -> f64 {
+        assert!(p >= 1.0, "minkowski order must be at least 1");
+        match p {
+            1.0 => self.manhattan_distance(other),
+            2.0 => self.distance_to(other),
+            f64::INFINITY => self.chebyshev_distance(other),
+            _ => self
+                .gaps(other)
+                .map(|d| d.powf(p))
+                .sum::<f64>()
+                .powf(p.recip()),
+        }
+    }
+
+    /// Cosine of the angle between the two points as vectors, in `[-1, 1]`.
+    /// `None` if either is the zero vector.
+    pub fn cosine_similarity<O: Storage<T>>(&self, other: &Point<T, O>) -> Option<f64> {
+        let norms = self.dist() * other.dist();
+        if norms == 0.0 {
+            return None;
+        }
+        Some((self.dot(other) / norms).clamp(-1.0, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        let a = Point::new(vec![1, 2, 3]);
+        let b = Point::new(vec![4, -2, 3]);
+        assert_eq!(a.distance_to(&b), 5.0);
+        assert_eq!(a.manhattan_distance(&b), 7.0);
+        assert_eq!(a.chebyshev_distance(&b), 4.0);
+        assert_eq!(a.minkowski_distance(&b, 2.0), 5.0);
+        assert_eq!(a.minkowski_distance(&b, f64::INFINITY), 4.0);
+        let cubic = (27.0f64 + 64.0).cbrt();
+        assert!((a.minkowski_distance(&b, 3.0) - cubic).abs() < 1e-12);
+        assert_eq!(a.distance_to(&a), 0.0);
+        assert!(std::panic::catch_unwind(|| a.manhattan_distance(&Point::new(vec![1]))).is_err());
+    }
+
+    #[test]
+    fn norms() {
+        let v = Point::new(vec![3, -4, 0]);
+        assert_eq!((v.norm_l1(), v.norm_l2(), v.norm_inf()), (7.0, 5.0, 4.0));
+        assert_eq!(v.norm_lp(1.0), 7.0);
+        assert_eq!(v.norm_lp(f64::INFINITY), 4.0);
+        assert!((v.norm_lp(3.0) - 91f64.cbrt()).abs() < 1e-12);
+        assert_eq!(v.normalize().data(), &[0.6, -0.8, 0.0]);
+        let zero = Point::new(vec![0.0, 0.0]);
+        assert_eq!(zero.try_normalize().unwrap_err(), Error::ZeroLength);
+        assert_eq!(zero.norm_inf(), 0.0);
+    }
+
+    #[test]
+    fn cosine() {
+        let x = Point::new(vec![2.0, 0.0]);
+        assert_eq!(x.cosine_similarity(&Point::new(vec![0.0, 5.0])), Some(0.0));
+        assert_eq!(
+            x.cosine_similarity(&Point::new(vec![-1.0, 0.0])),
+            Some(-1.0)
+        );
+        let diag = x.cosine_similarity(&Point::new(vec![3.0, 3.0])).unwrap();
+        assert!((diag - 0.5f64.sqrt()).abs() < 1e-12);
+        assert_eq!(x.cosine_similarity(&Point::new(vec![0.0, 0.0])), None);
+    }
+}
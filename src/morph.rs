@@ -0,0 +1,198 @@
+// Morphing between point sets.
+//
+// `morph` blends two clouds point by point: the i-th point of the result is
+// a fraction `t` of the way from the i-th point of `a` to the i-th of `b`.
+// That only looks like a shape changing if point i means the same thing in
+// both clouds. When it doesn't, `correspondence` pairs the points to
+// minimise the summed squared distances they move, solved exactly with the
+// Hungarian algorithm (Kuhn-Munkres, with Jonker-Volgenant style
+// potentials) in O(n^3) time, so the straight-line paths of the morph don't
+// cross needlessly.
+
+use crate::PointCloud;
+
+/// The cloud a fraction `t` of the way from `a` to `b`, pairing points by
+/// index; `t` outside [0, 1] extrapolates. Panics unless the clouds have
+/// the same size and dimension.
+pub fn morph(a: &PointCloud<f64>, b: &PointCloud<f64>, t: f64) -> PointCloud<f64> {
+    check(a, b);
+    let data = a
+        .as_flat()
+        .iter()
+        .zip(b.as_flat())
+        .map(|(x, y)| x + t * (y - x))
+        .collect();
+    PointCloud::from_flat_vec(data, a.dim()).expect("dimension unchanged")
+}
+
+/// For every point of `a`, the index of its partner in `b` under the
+/// pairing that minimises the summed squared distances. Panics unless the
+/// clouds have the same size and dimension.
+pub fn correspondence(a: &PointCloud<f64>, b: &PointCloud<f64>) -> Vec<usize> {
+    check(a, b);
+    let cost: Vec<Vec<f64>> = a
+        .iter()
+        .map(|p| {
+            b.iter()
+                .map(|q| p.iter().zip(q).map(|(x, y)| (x - y) * (x - y)).sum())
+                .collect()
+        })
+        .collect();
+    hungarian(&cost)
+}
+
+/// `b` reordered so its i-th point is the partner of `a`'s i-th point, as
+/// found by [`correspondence`]. Align once and [`morph`] many times to
+/// render a sequence of frames.
+pub fn align(a: &PointCloud<f64>, b: &PointCloud<f64>) -> PointCloud<f64> {
+    let mut data = Vec::with_capacity(b.as_flat().len());
+    for j in correspondence(a, b) {
+        data.extend_from_slice(b.get(j).expect("index from the assignment"));
+    }
+    PointCloud::from_flat_vec(data, b.dim()).expect("dimension unchanged")
+}
+
+/// [`morph`] after pairing the points with [`correspondence`].
+pub fn morph_matched(a: &PointCloud<f64>, b: &PointCloud<f64>, t: f64) -> PointCloud<f64> {
+    morph(a, &align(a, b), t)
+}
+
+fn check(a: &PointCloud<f64>, b: &PointCloud<f64>) {
+    assert_eq!(a.dim(), b.dim(), "cloud dimensions differ");
+    assert_eq!(
+        a.len(),
+        b.len(),
+        "clouds must have the same number of points"
+    );
+}
+
+// Minimum-cost perfect matching of a square cost matrix: the column
+// assigned to every row.
+fn hungarian(cost: &[Vec<f64>]) -> Vec<usize> {
+    let n = cost.len();
+    // Rows and columns are numbered from 1; column 0 is a virtual start.
+    let mut u = vec![0.0; n + 1];
+    let mut v = vec![0.0; n + 1];
+    let mut row_of = vec![0usize; n + 1];
+    let mut way = vec![0usize; n + 1];
+    for row in 1..=n {
+        row_of[0] = row;
+        let mut col = 0;
+        let mut min = vec![f64::INFINITY; n + 1];
+        let mut used = vec![false; n + 1];
+        // Grow a shortest augmenting path in reduced costs.
+        loop {
+            used[col] = true;
+            let r = row_of[col];
+            let (mut delta, mut next) = (f64::INFINITY, 0);
+            for j in 1..=n {
+                if used[j] {
+                    continue;
+                }
+                let reduced = cost[r - 1][j - 1] - u[r] - v[j];
+                if reduced < min[j] {
+                    min[j] = reduced;
+                    way[j] = col;
+                }
+                if min[j] < delta {
+                    delta = min[j];
+                    next = j;
+                }
+            }
+            for j in 0..=n {
+                if used[j] {
+                    u[row_of[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min[j] -= delta;
+                }
+            }
+            col = next;
+            if row_of[col] == 0 {
+                break;
+            }
+        }
+        // Flip the path.
+        while col != 0 {
+            let prev = way[col];
+            row_of[col] = row_of[prev];
+            col = prev;
+        }
+    }
+    let mut assignment = vec![0; n];
+    for j in 1..=n {
+        assignment[row_of[j] - 1] = j - 1;
+    }
+    assignment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    #[test]
+    fn blends_by_index() {
+        let a = PointCloud::from_flat(&[0.0, 0.0, 2.0, 0.0], 2).unwrap();
+        let b = PointCloud::from_flat(&[0.0, 4.0, 2.0, 2.0], 2).unwrap();
+        assert_eq!(morph(&a, &b, 0.5).as_flat(), &[0.0, 2.0, 2.0, 1.0]);
+        assert_eq!(morph(&a, &b, 0.0), a);
+        assert_eq!(morph(&a, &b, 1.0), b);
+        assert!(
+            std::panic::catch_unwind(|| {
+                let short = PointCloud::from_flat(&[0.0, 0.0], 2).unwrap();
+                morph(&PointCloud::from_flat(&[0.0; 4], 2).unwrap(), &short, 0.5)
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn finds_the_optimal_pairing() {
+        // A shuffled, slightly moved copy pairs back up with its original.
+        let mut rng = Rng::seed_from_u64(2762);
+        let n = 40;
+        let a_flat: Vec<f64> = (0..2 * n).map(|_| 10.0 * rng.next_f64()).collect();
+        let a = PointCloud::from_flat(&a_flat, 2).unwrap();
+        let perm: Vec<usize> = (0..n).map(|i| (i * 7 + 3) % n).collect();
+        let mut b_flat = Vec::new();
+        for &i in &perm {
+            b_flat.extend(a.get(i).unwrap().iter().map(|v| v + 1e-3));
+        }
+        let b = PointCloud::from_flat(&b_flat, 2).unwrap();
+        let pairs = correspondence(&a, &b);
+        for (j, &i) in perm.iter().enumerate() {
+            assert_eq!(pairs[i], j);
+        }
+        let aligned = align(&a, &b);
+        let mid = morph_matched(&a, &b, 0.5);
+        for ((m, p), q) in mid.as_flat().iter().zip(a.as_flat()).zip(aligned.as_flat()) {
+            assert!((m - (p + q) / 2.0).abs() < 1e-12 && (q - p - 1e-3).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn solves_small_assignments() {
+        // On an outer product, pairing small with large is cheapest; in the
+        // second matrix the two cheapest entries (1 and 0) share a column.
+        let cost = vec![
+            vec![1.0, 2.0, 3.0],
+            vec![2.0, 4.0, 6.0],
+            vec![3.0, 6.0, 9.0],
+        ];
+        let assignment = hungarian(&cost);
+        let total: f64 = assignment
+            .iter()
+            .enumerate()
+            .map(|(i, &j)| cost[i][j])
+            .sum();
+        assert_eq!(total, 10.0);
+        let cost = vec![
+            vec![4.0, 1.0, 3.0],
+            vec![2.0, 0.0, 5.0],
+            vec![3.0, 2.0, 2.0],
+        ];
+        assert_eq!(hungarian(&cost), vec![1, 0, 2]);
+        assert!(hungarian(&[]).is_empty());
+    }
+}
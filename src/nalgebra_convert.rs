@@ -0,0 +1,109 @@
+// Conversions to and from nalgebra, behind the `nalgebra` feature.
+//
+// A `DVector` and a heap-backed `Point` both keep their coordinates in a
+// `Vec`, so moving between them by value hands the buffer over instead of
+// copying it (points of up to four dimensions live inline and are copied).
+// nalgebra's fixed-size `Point<T, D>` maps to a point of dimension `D`; the
+// direction into it checks the dimension. To borrow a `DVector` as a point,
+// use `Point::view(v.as_slice())`.
+
+use ::nalgebra as na;
+
+use crate::storage::{ArrayStorage, VecStorage};
+use crate::{Error, Point, Storage};
+
+impl<T: na::Scalar + Copy> From<na::DVector<T>> for Point<T> {
+    fn from(v: na::DVector<T>) -> Self {
+        let coords: Vec<T> = v.data.into();
+        Point::from_storage(coords.into())
+    }
+}
+
+impl<T: na::Scalar + Copy> From<Point<T>> for na::DVector<T> {
+    fn from(p: Point<T>) -> Self {
+        na::DVector::from_vec(p.into_storage().into_vec())
+    }
+}
+
+impl<T: na::Scalar + Copy, S: Storage<T>> From<&Point<T, S>> for na::DVector<T> {
+    fn from(p: &Point<T, S>) -> Self {
+        na::DVector::from_column_slice(p.as_slice())
+    }
+}
+
+impl<T: na::Scalar + Copy, const D: usize> From<na::Point<T, D>> for Point<T> {
+    fn from(p: na::Point<T, D>) -> Self {
+        Point::from_storage(VecStorage::from_slice(p.coords.as_slice()))
+    }
+}
+
+impl<T: na::Scalar + Copy, const D: usize> From<na::Point<T, D>> for Point<T, ArrayStorage<T, D>> {
+    fn from(p: na::Point<T, D>) -> Self {
+        Point::from_array(p.coords.into())
+    }
+}
+
+impl<T: na::Scalar + Copy, const D: usize> From<Point<T, ArrayStorage<T, D>>> for na::Point<T, D> {
+    fn from(p: Point<T, ArrayStorage<T, D>>) -> Self {
+        na::Point::from(p.into_storage().0)
+    }
+}
+
+/// Fails with `Error::DimensionMismatch` unless the point has `D`
+/// coordinates.
+impl<T: na::Scalar + Copy, S: Storage<T>, const D: usize> TryFrom<&Point<T, S>>
+    for na::Point<T, D>
+{
+    type Error = Error;
+
+    fn try_from(p: &Point<T, S>) -> Result<Self, Error> {
+        let coords: [T; D] = p
+            .as_slice()
+            .try_into()
+            .map_err(|_| Error::DimensionMismatch {
+                expected: D,
+                found: p.as_slice().len(),
+            })?;
+        Ok(na::Point::from(coords))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dvectors_share_the_buffer() {
+        let v = na::DVector::from_vec(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let ptr = v.as_slice().as_ptr();
+        let p: Point<f64> = v.into();
+        assert_eq!(p.as_slice().as_ptr(), ptr);
+        assert_eq!(p.dim(), 6);
+        let back: na::DVector<f64> = p.into();
+        assert_eq!(back.as_slice().as_ptr(), ptr);
+        assert_eq!(
+            back.norm(),
+            Point::new(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).dist()
+        );
+
+        let small = Point::new(vec![1, 2]);
+        assert_eq!(na::DVector::from(&small), na::DVector::from_vec(vec![1, 2]));
+    }
+
+    #[test]
+    fn fixed_points() {
+        let np = na::Point3::new(1.0, 2.0, 3.0);
+        let p: Point<f64> = np.into();
+        assert_eq!(p.data(), [1.0, 2.0, 3.0]);
+        assert_eq!(na::Point3::try_from(&p).unwrap(), np);
+        assert_eq!(
+            na::Point2::<f64>::try_from(&p).unwrap_err(),
+            Error::DimensionMismatch {
+                expected: 2,
+                found: 3
+            }
+        );
+        let a: Point<f64, ArrayStorage<f64, 3>> = np.into();
+        assert_eq!(na::Point3::from(a), np);
+    }
+}
@@ -0,0 +1,83 @@
+// Conversions to and from ndarray, behind the `ndarray` feature.
+//
+// A point is a one-dimensional array and a cloud a two-dimensional one
+// with a row per point, the layout ML code expects for an `n x dim` batch.
+// Arrays of any memory order or strides are accepted; `view_array2` lends a
+// cloud's buffer to ndarray without copying.
+
+use ::ndarray::{Array1, Array2, ArrayBase, ArrayView2, Data, Ix1, Ix2};
+
+use crate::{Error, Point, PointCloud, Storage};
+
+impl<T: Copy> Point<T> {
+    /// A point with the elements of `a`.
+    pub fn from_array1<D: Data<Elem = T>>(a: &ArrayBase<D, Ix1>) -> Self {
+        a.iter().copied().collect::<Vec<T>>().into()
+    }
+}
+
+impl<T: Copy, S: Storage<T>> Point<T, S> {
+    pub fn to_array1(&self) -> Array1<T> {
+        Array1::from(self.as_slice().to_vec())
+    }
+}
+
+impl<T: Copy> PointCloud<T> {
+    /// The cloud as an `n_points x dim` array.
+    pub fn to_array2(&self) -> Array2<T> {
+        Array2::from_shape_vec((self.len(), self.dim()), self.as_flat().to_vec())
+            .expect("the buffer holds len * dim coordinates")
+    }
+
+    /// The cloud's buffer seen as an `n_points x dim` array, without copying.
+    pub fn view_array2(&self) -> ArrayView2<'_, T> {
+        ArrayView2::from_shape((self.len(), self.dim()), self.as_flat())
+            .expect("the buffer holds len * dim coordinates")
+    }
+
+    /// A cloud with a point per row of `a`; `Error::ZeroDimension` if it has
+    /// no columns.
+    pub fn from_array2<D: Data<Elem = T>>(a: &ArrayBase<D, Ix2>) -> Result<Self, Error> {
+        PointCloud::from_flat_vec(a.iter().copied().collect(), a.ncols())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::ndarray::{Array2, ShapeBuilder, array, s};
+
+    use super::*;
+
+    #[test]
+    fn points_are_1d_arrays() {
+        let a = array![1.0, 2.0, 3.0];
+        let p = Point::from_array1(&a);
+        assert_eq!(p.data(), [1.0, 2.0, 3.0]);
+        assert_eq!(p.to_array1(), a);
+        // Any strides, e.g. a column of a matrix.
+        let m = array![[1, 2], [3, 4], [5, 6]];
+        assert_eq!(Point::from_array1(&m.column(1)).data(), [2, 4, 6]);
+        assert_eq!(Point::from_array([7, 8]).to_array1(), array![7, 8]);
+    }
+
+    #[test]
+    fn clouds_are_2d_arrays() {
+        let c = PointCloud::from_flat(&[1, 2, 3, 4, 5, 6], 3).unwrap();
+        let a = c.to_array2();
+        assert_eq!(a, array![[1, 2, 3], [4, 5, 6]]);
+        assert_eq!(c.view_array2(), a);
+        assert_eq!(c.view_array2().as_ptr(), c.as_flat().as_ptr());
+        assert_eq!(PointCloud::from_array2(&a).unwrap(), c);
+
+        // Column-major arrays and slices come out in row order too.
+        let f = Array2::from_shape_vec((2, 3).f(), vec![1, 4, 2, 5, 3, 6]).unwrap();
+        assert_eq!(PointCloud::from_array2(&f).unwrap(), c);
+        let sub = PointCloud::from_array2(&a.slice(s![.., 1..])).unwrap();
+        assert_eq!(sub.as_flat(), [2, 3, 5, 6]);
+        let empty = Array2::<i32>::zeros((2, 0));
+        assert_eq!(
+            PointCloud::from_array2(&empty).unwrap_err(),
+            Error::ZeroDimension
+        );
+    }
+}
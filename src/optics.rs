@@ -0,0 +1,91 @@
+// Reflection and refraction of ray directions, for simple optics.
+//
+// A direction is any point, read as a vector; the surface normal needn't be
+// unit length and may face either side of the surface. Both functions work
+// in any dimension and return unit directions.
+
+use crate::{Point, Storage};
+
+impl<T, S: Storage<T>> Point<T, S>
+where
+    T: Into<f64> + Copy,
+{
+    // The unit direction and unit normal, the normal flipped to face
+    // against the direction, and the cosine between them (at most 0).
+    // Panics on mismatched dimensions or a zero vector.
+    fn against<O: Storage<T>>(&self, normal: &Point<T, O>) -> (Point<f64>, Point<f64>, f64) {
+        assert_eq!(self.dim(), normal.dim(), "points must share a dimension");
+        let d = self.normalize();
+        let mut n = normal.normalize();
+        let mut cos = d.dot(&n);
+        if cos > 0.0 {
+            n.iter_mut().for_each(|v| *v = -*v);
+            cos = -cos;
+        }
+        (d, n, cos)
+    }
+
+    /// This direction mirrored off a surface with the given normal.
+    pub fn reflect<O: Storage<T>>(&self, normal: &Point<T, O>) -> Point<f64> {
+        let (d, n, cos) = self.against(normal);
+        d.add_scaled(&n, -2.0 * cos)
+    }
+
+    /// This direction bent through a surface with the given normal, where
+    /// `eta` is the ratio of refractive indices, from the incoming side over
+    /// the outgoing. `None` on total internal reflection, when the ray
+    /// can't leave and [`Point::reflect`] gives its path instead.
+    pub fn refract<O: Storage<T>>(&self, normal: &Point<T, O>, eta: f64) -> Option<Point<f64>> {
+        let (d, n, cos) = self.against(normal);
+        let k = 1.0 - eta * eta * (1.0 - cos * cos);
+        if k < 0.0 {
+            return None;
+        }
+        Some(Point::linear_combination(&[
+            (eta, &d),
+            (-(eta * cos + k.sqrt()), &n),
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Point;
+
+    fn close(a: &Point<f64>, b: &[f64]) -> bool {
+        a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-12)
+    }
+
+    #[test]
+    fn reflect_mirrors_across_the_normal() {
+        let d = Point::new(vec![1.0, -1.0]);
+        let h = 0.5f64.sqrt();
+        assert!(close(&d.reflect(&Point::new(vec![0.0, 2.0])), &[h, h]));
+        // The normal's side doesn't matter.
+        assert!(close(&d.reflect(&Point::new(vec![0.0, -1.0])), &[h, h]));
+        let up = Point::new(vec![0.0, 0.0, 3.0]);
+        assert!(close(
+            &up.reflect(&Point::new(vec![0.0, 0.0, 1.0])),
+            &[0.0, 0.0, -1.0]
+        ));
+    }
+
+    #[test]
+    fn refract_follows_snell() {
+        let n = Point::new(vec![0.0, 1.0]);
+        // Straight through at normal incidence, whatever the indices.
+        let down = Point::new(vec![0.0, -1.0]);
+        assert!(close(&down.refract(&n, 1.5).unwrap(), &[0.0, -1.0]));
+        // Equal indices don't bend the ray.
+        let d = Point::new(vec![1.0, -1.0]);
+        let h = 0.5f64.sqrt();
+        assert!(close(&d.refract(&n, 1.0).unwrap(), &[h, -h]));
+        // sin θ₂ = η sin θ₁.
+        let eta = 1.0 / 1.33;
+        let t = d.refract(&n, eta).unwrap();
+        assert!((t[0] - eta * h).abs() < 1e-12 && t[1] < 0.0);
+        assert!((t.dist() - 1.0).abs() < 1e-12);
+        // Glass to air past the critical angle reflects back.
+        assert!(d.refract(&n, 1.5).is_none());
+    }
+}
@@ -0,0 +1,137 @@
+// Splitting a point set into spatially coherent buckets of equal size.
+//
+// The set is cut at a rank along its widest axis, the rank chosen so both
+// sides get their share of the buckets, and each side is cut again until
+// every bucket stands alone. Bucket sizes differ by at most one, so the
+// buckets make even shares of work for threads or machines while keeping
+// nearby points together.
+
+use std::ops::Range;
+
+use crate::Point;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Partition {
+    /// Point indices grouped by bucket.
+    pub order: Vec<usize>,
+    /// Bucket `b` is `order[ranges[b].clone()]`.
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl Partition {
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The point indices in bucket `b`.
+    pub fn bucket(&self, b: usize) -> &[usize] {
+        &self.order[self.ranges[b].clone()]
+    }
+
+    /// Bucket of every point, indexed by point.
+    pub fn assignment(&self) -> Vec<usize> {
+        let mut out = vec![0; self.order.len()];
+        for (b, r) in self.ranges.iter().enumerate() {
+            for &i in &self.order[r.clone()] {
+                out[i] = b;
+            }
+        }
+        out
+    }
+}
+
+/// Splits `points` into `n_buckets` buckets by recursive median cuts. With
+/// more buckets than points the extra buckets are empty. Panics if
+/// `n_buckets` is zero or the points don't share a dimension.
+pub fn partition_balanced<T>(points: &[Point<T>], n_buckets: usize) -> Partition
+where
+    T: Into<f64> + Copy,
+{
+    assert!(n_buckets > 0, "need at least one bucket");
+    let dim = points.first().map_or(0, |p| p.dim());
+    assert!(
+        points.iter().all(|p| p.dim() == dim),
+        "points must share a dimension"
+    );
+    let coord = |i: usize, k: usize| points[i][k].into();
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    let mut ranges = Vec::with_capacity(n_buckets);
+    split(&mut order, 0, n_buckets, dim, &coord, &mut ranges);
+    Partition { order, ranges }
+}
+
+fn split(
+    ids: &mut [usize],
+    start: usize,
+    buckets: usize,
+    dim: usize,
+    coord: &dyn Fn(usize, usize) -> f64,
+    out: &mut Vec<Range<usize>>,
+) {
+    if buckets == 1 {
+        out.push(start..start + ids.len());
+        return;
+    }
+    let left_buckets = buckets / 2;
+    let cut = ids.len() * left_buckets / buckets;
+    if cut > 0 && cut < ids.len() {
+        let spread = |k: usize| {
+            let (lo, hi) = ids
+                .iter()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), &i| {
+                    (a.min(coord(i, k)), b.max(coord(i, k)))
+                });
+            hi - lo
+        };
+        if let Some(axis) = (0..dim).max_by(|&a, &b| spread(a).total_cmp(&spread(b))) {
+            ids.select_nth_unstable_by(cut, |&a, &b| coord(a, axis).total_cmp(&coord(b, axis)));
+        }
+    }
+    let (left, right) = ids.split_at_mut(cut);
+    split(left, start, left_buckets, dim, coord, out);
+    split(right, start + cut, buckets - left_buckets, dim, coord, out);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    #[test]
+    fn balanced_and_coherent() {
+        let mut rng = Rng::seed_from_u64(55);
+        let pts: Vec<Point<f64>> = (0..1000)
+            .map(|_| Point::new(vec![rng.range_f64(0.0, 100.0), rng.range_f64(0.0, 1.0)]))
+            .collect();
+        let part = partition_balanced(&pts, 7);
+        assert_eq!(part.len(), 7);
+        let sizes: Vec<usize> = part.ranges.iter().map(|r| r.len()).collect();
+        assert!(sizes.iter().all(|&s| s == 142 || s == 143));
+        let mut seen = part.order.clone();
+        seen.sort_unstable();
+        assert_eq!(seen, (0..1000).collect::<Vec<_>>());
+        // The long axis is cut into slabs that don't overlap.
+        let span = |b: usize| {
+            let xs = part.bucket(b).iter().map(|&i| pts[i][0]);
+            let lo = xs.clone().fold(f64::INFINITY, f64::min);
+            (lo, xs.fold(f64::NEG_INFINITY, f64::max))
+        };
+        for b in 1..7 {
+            assert!(span(b - 1).1 <= span(b).0);
+        }
+        assert_eq!(part.assignment()[part.bucket(3)[0]], 3);
+    }
+
+    #[test]
+    fn more_buckets_than_points() {
+        let pts = vec![Point::new(vec![1.0]), Point::new(vec![0.0])];
+        let part = partition_balanced(&pts, 4);
+        assert_eq!(part.len(), 4);
+        assert_eq!(part.ranges.iter().filter(|r| r.is_empty()).count(), 2);
+        assert!(partition_balanced::<f64>(&[], 3).order.is_empty());
+    }
+}
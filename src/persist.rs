@@ -0,0 +1,116 @@
+// Helpers for the binary index formats.
+//
+// Every file starts with a four-byte magic naming the index type and a `u32`
+// format version. All numbers are little-endian; `usize` is stored as `u64`.
+
+use std::io::{self, Read, Write};
+
+pub(crate) fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub(crate) fn write_header<W: Write>(w: &mut W, magic: &[u8; 4], version: u32) -> io::Result<()> {
+    w.write_all(magic)?;
+    w.write_all(&version.to_le_bytes())
+}
+
+/// Checks the magic and returns the version, rejecting versions newer than
+/// `latest`.
+pub(crate) fn read_header<R: Read>(r: &mut R, magic: &[u8; 4], latest: u32) -> io::Result<u32> {
+    let mut m = [0u8; 4];
+    r.read_exact(&mut m)?;
+    if &m != magic {
+        return Err(invalid("wrong file type"));
+    }
+    let mut v = [0u8; 4];
+    r.read_exact(&mut v)?;
+    let version = u32::from_le_bytes(v);
+    if version == 0 || version > latest {
+        return Err(invalid("unsupported format version"));
+    }
+    Ok(version)
+}
+
+pub(crate) fn write_usize<W: Write>(w: &mut W, v: usize) -> io::Result<()> {
+    w.write_all(&(v as u64).to_le_bytes())
+}
+
+pub(crate) fn read_usize<R: Read>(r: &mut R) -> io::Result<usize> {
+    let mut b = [0u8; 8];
+    r.read_exact(&mut b)?;
+    usize::try_from(u64::from_le_bytes(b)).map_err(|_| invalid("length out of range"))
+}
+
+pub(crate) fn write_f64<W: Write>(w: &mut W, v: f64) -> io::Result<()> {
+    w.write_all(&v.to_le_bytes())
+}
+
+pub(crate) fn read_f64<R: Read>(r: &mut R) -> io::Result<f64> {
+    let mut b = [0u8; 8];
+    r.read_exact(&mut b)?;
+    Ok(f64::from_le_bytes(b))
+}
+
+pub(crate) fn write_u8<W: Write>(w: &mut W, v: u8) -> io::Result<()> {
+    w.write_all(&[v])
+}
+
+pub(crate) fn read_u8<R: Read>(r: &mut R) -> io::Result<u8> {
+    let mut b = [0u8; 1];
+    r.read_exact(&mut b)?;
+    Ok(b[0])
+}
+
+pub(crate) fn write_f64s<W: Write>(w: &mut W, v: &[f64]) -> io::Result<()> {
+    write_usize(w, v.len())?;
+    v.iter().try_for_each(|&x| write_f64(w, x))
+}
+
+pub(crate) fn read_f64s<R: Read>(r: &mut R) -> io::Result<Vec<f64>> {
+    let n = read_usize(r)?;
+    // Don't trust the length for the allocation; a corrupt file would abort.
+    let mut v = Vec::with_capacity(n.min(1 << 16));
+    for _ in 0..n {
+        v.push(read_f64(r)?);
+    }
+    Ok(v)
+}
+
+pub(crate) fn write_usizes<W: Write>(w: &mut W, v: &[usize]) -> io::Result<()> {
+    write_usize(w, v.len())?;
+    v.iter().try_for_each(|&x| write_usize(w, x))
+}
+
+pub(crate) fn read_usizes<R: Read>(r: &mut R) -> io::Result<Vec<usize>> {
+    let n = read_usize(r)?;
+    let mut v = Vec::with_capacity(n.min(1 << 16));
+    for _ in 0..n {
+        v.push(read_usize(r)?);
+    }
+    Ok(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header() {
+        let mut buf = Vec::new();
+        write_header(&mut buf, b"TEST", 2).unwrap();
+        assert_eq!(read_header(&mut &buf[..], b"TEST", 2).unwrap(), 2);
+        assert!(read_header(&mut &buf[..], b"TEST", 1).is_err());
+        assert!(read_header(&mut &buf[..], b"NOPE", 2).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut buf = Vec::new();
+        write_f64s(&mut buf, &[1.5, -2.0]).unwrap();
+        write_usizes(&mut buf, &[7, 0]).unwrap();
+        let mut r = &buf[..];
+        assert_eq!(read_f64s(&mut r).unwrap(), vec![1.5, -2.0]);
+        assert_eq!(read_usizes(&mut r).unwrap(), vec![7, 0]);
+        assert!(read_u8(&mut r).is_err());
+    }
+}
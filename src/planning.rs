@@ -0,0 +1,629 @@
+// Path planning over point spaces.
+//
+// `astar` searches an n-dimensional occupancy grid; `rrt` and `rrt_star`
+// sample a continuous box and ask a user predicate whether a straight move
+// between two points is collision free. All planners return the path as a
+// polyline of points from start to goal.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::Point;
+use crate::rng::Rng;
+
+/// An n-dimensional grid of free and blocked cells, stored row-major with the
+/// last axis varying fastest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OccupancyGrid {
+    shape: Vec<usize>,
+    blocked: Vec<bool>,
+}
+
+/// Which neighbouring cells a grid path may step to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Only cells sharing a face (4 neighbours in 2D).
+    Axis,
+    /// Every cell touching the current one (8 neighbours in 2D).
+    Full,
+}
+
+impl OccupancyGrid {
+    /// A grid of the given shape with every cell free.
+    pub fn new(shape: Vec<usize>) -> Self {
+        let len = shape.iter().product();
+        OccupancyGrid {
+            shape,
+            blocked: vec![false; len],
+        }
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn set_blocked(&mut self, cell: &[usize], blocked: bool) {
+        let i = self.index(cell).expect("cell outside the grid");
+        self.blocked[i] = blocked;
+    }
+
+    pub fn is_blocked(&self, cell: &[usize]) -> bool {
+        self.index(cell).is_none_or(|i| self.blocked[i])
+    }
+
+    fn index(&self, cell: &[usize]) -> Option<usize> {
+        if cell.len() != self.shape.len() {
+            return None;
+        }
+        let mut i = 0;
+        for (&c, &s) in cell.iter().zip(&self.shape) {
+            if c >= s {
+                return None;
+            }
+            i = i * s + c;
+        }
+        Some(i)
+    }
+
+    fn cell(&self, mut index: usize) -> Vec<usize> {
+        let mut cell = vec![0; self.shape.len()];
+        for (c, &s) in cell.iter_mut().zip(&self.shape).rev() {
+            *c = index % s;
+            index /= s;
+        }
+        cell
+    }
+
+    fn neighbors(&self, cell: &[usize], connectivity: Connectivity) -> Vec<(Vec<usize>, f64)> {
+        let dim = self.shape.len();
+        let mut out = Vec::new();
+        // Enumerate offsets in {-1, 0, 1}^dim.
+        for code in 0..3usize.pow(dim as u32) {
+            let mut offset = Vec::with_capacity(dim);
+            let mut c = code;
+            for _ in 0..dim {
+                offset.push(c % 3);
+                c /= 3;
+            }
+            let moved = offset.iter().filter(|&&o| o != 1).count();
+            if moved == 0 || (connectivity == Connectivity::Axis && moved > 1) {
+                continue;
+            }
+            let next: Option<Vec<usize>> = cell
+                .iter()
+                .zip(&offset)
+                .map(|(&x, &o)| (x + o).checked_sub(1))
+                .collect();
+            if let Some(next) = next
+                && !self.is_blocked(&next)
+            {
+                out.push((next, (moved as f64).sqrt()));
+            }
+        }
+        out
+    }
+}
+
+struct Open {
+    f: f64,
+    index: usize,
+}
+
+impl PartialEq for Open {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Open {}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .f
+            .total_cmp(&self.f)
+            .then(other.index.cmp(&self.index))
+    }
+}
+
+fn cell_distance(a: &[usize], b: &[usize]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(&x, &y)| (x as f64 - y as f64).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// Shortest grid path from `start` to `goal` as a polyline of cell
+/// coordinates, or `None` if the goal can't be reached.
+pub fn astar(
+    grid: &OccupancyGrid,
+    start: &[usize],
+    goal: &[usize],
+    connectivity: Connectivity,
+) -> Option<Vec<Point<f64>>> {
+    if grid.is_blocked(start) || grid.is_blocked(goal) {
+        return None;
+    }
+    let start_i = grid.index(start)?;
+    let goal_i = grid.index(goal)?;
+
+    let mut g = vec![f64::INFINITY; grid.blocked.len()];
+    let mut parent = vec![usize::MAX; grid.blocked.len()];
+    let mut open = BinaryHeap::new();
+    g[start_i] = 0.0;
+    open.push(Open {
+        f: cell_distance(start, goal),
+        index: start_i,
+    });
+
+    while let Some(Open { f, index }) = open.pop() {
+        if index == goal_i {
+            break;
+        }
+        let cell = grid.cell(index);
+        if f > g[index] + cell_distance(&cell, goal) {
+            continue;
+        }
+        for (next, cost) in grid.neighbors(&cell, connectivity) {
+            let ni = grid.index(&next).expect("neighbour inside the grid");
+            let tentative = g[index] + cost;
+            if tentative < g[ni] {
+                g[ni] = tentative;
+                parent[ni] = index;
+                open.push(Open {
+                    f: tentative + cell_distance(&next, goal),
+                    index: ni,
+                });
+            }
+        }
+    }
+
+    if g[goal_i].is_infinite() {
+        return None;
+    }
+    let mut path = vec![goal_i];
+    while *path.last().unwrap() != start_i {
+        path.push(parent[*path.last().unwrap()]);
+    }
+    path.reverse();
+    Some(
+        path.into_iter()
+            .map(|i| Point::new(grid.cell(i).into_iter().map(|c| c as f64).collect()))
+            .collect(),
+    )
+}
+
+/// Settings shared by `rrt` and `rrt_star`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RrtConfig {
+    /// Longest edge added to the tree.
+    pub step: f64,
+    /// How close to the goal a node must get to finish.
+    pub goal_tolerance: f64,
+    pub max_iterations: usize,
+    /// Probability of sampling the goal itself instead of a random point.
+    pub goal_bias: f64,
+    /// Neighbourhood searched for cheaper parents and rewiring (RRT* only).
+    pub rewire_radius: f64,
+}
+
+impl Default for RrtConfig {
+    fn default() -> Self {
+        RrtConfig {
+            step: 0.5,
+            goal_tolerance: 0.5,
+            max_iterations: 5000,
+            goal_bias: 0.05,
+            rewire_radius: 1.5,
+        }
+    }
+}
+
+fn distance(a: &Point<f64>, b: &Point<f64>) -> f64 {
+    (a - b).dist()
+}
+
+fn steer(from: &Point<f64>, to: &Point<f64>, step: f64) -> Point<f64> {
+    let d = distance(from, to);
+    if d <= step {
+        to.clone()
+    } else {
+        from + &(&(to - from) * (step / d))
+    }
+}
+
+struct Tree {
+    nodes: Vec<Point<f64>>,
+    parent: Vec<usize>,
+    children: Vec<Vec<usize>>,
+    cost: Vec<f64>,
+}
+
+impl Tree {
+    fn nearest(&self, p: &Point<f64>) -> usize {
+        (0..self.nodes.len())
+            .min_by(|&a, &b| distance(&self.nodes[a], p).total_cmp(&distance(&self.nodes[b], p)))
+            .unwrap()
+    }
+
+    // Reparents every neighbour that is cheaper to reach through `id`, and
+    // passes the saving on to its subtree.
+    fn rewire<F>(&mut self, id: usize, neighbors: &[usize], is_free: &F)
+    where
+        F: Fn(&Point<f64>, &Point<f64>) -> bool,
+    {
+        for &i in neighbors {
+            let c = self.cost[id] + distance(&self.nodes[id], &self.nodes[i]);
+            if c < self.cost[i] && is_free(&self.nodes[id], &self.nodes[i]) {
+                let delta = self.cost[i] - c;
+                let old = std::mem::replace(&mut self.parent[i], id);
+                self.children[old].retain(|&k| k != i);
+                self.children[id].push(i);
+                let mut stack = vec![i];
+                while let Some(j) = stack.pop() {
+                    self.cost[j] -= delta;
+                    stack.extend_from_slice(&self.children[j]);
+                }
+            }
+        }
+    }
+
+    // The cost of a path through node `i` that ends with a free straight
+    // move to the goal, if `i` is close enough for one.
+    fn goal_cost<F>(&self, i: usize, goal: &Point<f64>, tolerance: f64, is_free: &F) -> Option<f64>
+    where
+        F: Fn(&Point<f64>, &Point<f64>) -> bool,
+    {
+        let d = distance(&self.nodes[i], goal);
+        (d <= tolerance && is_free(&self.nodes[i], goal)).then(|| self.cost[i] + d)
+    }
+
+    // The node with the cheapest path to the goal, with costs as they stand
+    // after every rewire.
+    fn best_to_goal<F>(&self, goal: &Point<f64>, tolerance: f64, is_free: &F) -> Option<usize>
+    where
+        F: Fn(&Point<f64>, &Point<f64>) -> bool,
+    {
+        (0..self.nodes.len())
+            .filter_map(|i| Some((i, self.goal_cost(i, goal, tolerance, is_free)?)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    fn path_to(&self, mut i: usize) -> Vec<Point<f64>> {
+        let mut path = vec![self.nodes[i].clone()];
+        while i != 0 {
+            i = self.parent[i];
+            path.push(self.nodes[i].clone());
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// Rapidly-exploring random tree inside the box `[lower, upper]`.
+///
+/// `is_free(a, b)` must report whether the straight move from `a` to `b` is
+/// collision free.
+pub fn rrt<F>(
+    start: &Point<f64>,
+    goal: &Point<f64>,
+    bounds: (&Point<f64>, &Point<f64>),
+    is_free: F,
+    config: &RrtConfig,
+    rng: &mut Rng,
+) -> Option<Vec<Point<f64>>>
+where
+    F: Fn(&Point<f64>, &Point<f64>) -> bool,
+{
+    plan(start, goal, bounds, is_free, config, rng, false)
+}
+
+/// RRT* inside the box `[lower, upper]`: like `rrt`, but new nodes pick the
+/// cheapest parent in `rewire_radius` and rewire their neighbours, so the path
+/// keeps improving until `max_iterations` is spent.
+pub fn rrt_star<F>(
+    start: &Point<f64>,
+    goal: &Point<f64>,
+    bounds: (&Point<f64>, &Point<f64>),
+    is_free: F,
+    config: &RrtConfig,
+    rng: &mut Rng,
+) -> Option<Vec<Point<f64>>>
+where
+    F: Fn(&Point<f64>, &Point<f64>) -> bool,
+{
+    plan(start, goal, bounds, is_free, config, rng, true)
+}
+
+fn plan<F>(
+    start: &Point<f64>,
+    goal: &Point<f64>,
+    (lower, upper): (&Point<f64>, &Point<f64>),
+    is_free: F,
+    config: &RrtConfig,
+    rng: &mut Rng,
+    optimize: bool,
+) -> Option<Vec<Point<f64>>>
+where
+    F: Fn(&Point<f64>, &Point<f64>) -> bool,
+{
+    let mut tree = Tree {
+        nodes: vec![start.clone()],
+        parent: vec![0],
+        children: vec![Vec::new()],
+        cost: vec![0.0],
+    };
+    let mut best: Option<usize> = None;
+
+    for _ in 0..config.max_iterations {
+        let sample = if rng.next_f64() < config.goal_bias {
+            goal.clone()
+        } else {
+            Point::new(
+                lower
+                    .p
+                    .iter()
+                    .zip(upper.p.iter())
+                    .map(|(&lo, &hi)| rng.range_f64(lo, hi))
+                    .collect(),
+            )
+        };
+        let near = tree.nearest(&sample);
+        let new = steer(&tree.nodes[near], &sample, config.step);
+        if !is_free(&tree.nodes[near], &new) {
+            continue;
+        }
+
+        let mut parent = near;
+        let mut cost = tree.cost[near] + distance(&tree.nodes[near], &new);
+        let neighbors: Vec<usize> = if optimize {
+            (0..tree.nodes.len())
+                .filter(|&i| distance(&tree.nodes[i], &new) <= config.rewire_radius)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        for &i in &neighbors {
+            let c = tree.cost[i] + distance(&tree.nodes[i], &new);
+            if c < cost && is_free(&tree.nodes[i], &new) {
+                parent = i;
+                cost = c;
+            }
+        }
+
+        let id = tree.nodes.len();
+        tree.nodes.push(new);
+        tree.parent.push(parent);
+        tree.children.push(Vec::new());
+        tree.children[parent].push(id);
+        tree.cost.push(cost);
+
+        tree.rewire(id, &neighbors, &is_free);
+
+        // Plain RRT stops at the first node that reaches the goal; RRT* keeps
+        // refining and picks the cheapest one once the budget is spent.
+        if !optimize
+            && tree
+                .goal_cost(id, goal, config.goal_tolerance, &is_free)
+                .is_some()
+        {
+            best = Some(id);
+            break;
+        }
+    }
+    if optimize {
+        best = tree.best_to_goal(goal, config.goal_tolerance, &is_free);
+    }
+
+    best.map(|b| {
+        let mut path = tree.path_to(b);
+        if distance(path.last().unwrap(), goal) > 0.0 {
+            path.push(goal.clone());
+        }
+        path
+    })
+}
+
+/// Total length of a polyline.
+pub fn path_length(path: &[Point<f64>]) -> f64 {
+    path.windows(2).map(|w| distance(&w[0], &w[1])).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wall_grid() -> OccupancyGrid {
+        // A 5x5 grid with a wall at x = 2 except for y = 4.
+        let mut grid = OccupancyGrid::new(vec![5, 5]);
+        for y in 0..4 {
+            grid.set_blocked(&[2, y], true);
+        }
+        grid
+    }
+
+    #[test]
+    fn astar_axis() {
+        let grid = wall_grid();
+        let path = astar(&grid, &[0, 0], &[4, 0], Connectivity::Axis).unwrap();
+        assert_eq!(path.first().unwrap().data(), &[0.0, 0.0]);
+        assert_eq!(path.last().unwrap().data(), &[4.0, 0.0]);
+        // Up 4, across 4, down 4.
+        assert_eq!(path.len(), 13);
+        assert!(
+            path.iter()
+                .all(|p| !(p.data()[0] == 2.0 && p.data()[1] < 4.0))
+        );
+    }
+
+    #[test]
+    fn astar_full() {
+        let grid = wall_grid();
+        let path = astar(&grid, &[0, 0], &[4, 0], Connectivity::Full).unwrap();
+        // Four diagonal and four straight moves over the gap at y = 4.
+        assert!((path_length(&path) - (4.0 + 4.0 * 2f64.sqrt())).abs() < 1e-9);
+    }
+
+    #[test]
+    fn astar_unreachable() {
+        let mut grid = wall_grid();
+        grid.set_blocked(&[2, 4], true);
+        assert!(astar(&grid, &[0, 0], &[4, 0], Connectivity::Full).is_none());
+        assert!(astar(&grid, &[0, 0], &[2, 0], Connectivity::Full).is_none());
+    }
+
+    #[test]
+    fn astar_3d() {
+        let grid = OccupancyGrid::new(vec![3, 3, 3]);
+        let path = astar(&grid, &[0, 0, 0], &[2, 2, 2], Connectivity::Full).unwrap();
+        assert_eq!(path.len(), 3);
+    }
+
+    // A wall x in [4, 6], y < 7 inside a 10 x 10 box.
+    fn free(a: &Point<f64>, b: &Point<f64>) -> bool {
+        (0..=20).all(|i| {
+            let t = i as f64 / 20.0;
+            let x = a.data()[0] + t * (b.data()[0] - a.data()[0]);
+            let y = a.data()[1] + t * (b.data()[1] - a.data()[1]);
+            !((4.0..=6.0).contains(&x) && y < 7.0)
+        })
+    }
+
+    #[test]
+    fn rrt_finds_path() {
+        let start = Point::new(vec![1.0, 1.0]);
+        let goal = Point::new(vec![9.0, 1.0]);
+        let lower = Point::new(vec![0.0, 0.0]);
+        let upper = Point::new(vec![10.0, 10.0]);
+        let config = RrtConfig::default();
+        let path = rrt(
+            &start,
+            &goal,
+            (&lower, &upper),
+            free,
+            &config,
+            &mut Rng::seed_from_u64(1),
+        )
+        .unwrap();
+        assert_eq!(path.first().unwrap().data(), start.data());
+        assert_eq!(path.last().unwrap().data(), goal.data());
+        assert!(path.windows(2).all(|w| free(&w[0], &w[1])));
+
+        // Same seed, same path.
+        let again = rrt(
+            &start,
+            &goal,
+            (&lower, &upper),
+            free,
+            &config,
+            &mut Rng::seed_from_u64(1),
+        )
+        .unwrap();
+        assert_eq!(path.len(), again.len());
+    }
+
+    #[test]
+    fn rrt_star_is_shorter() {
+        let start = Point::new(vec![1.0, 1.0]);
+        let goal = Point::new(vec![9.0, 1.0]);
+        let lower = Point::new(vec![0.0, 0.0]);
+        let upper = Point::new(vec![10.0, 10.0]);
+        let config = RrtConfig {
+            max_iterations: 1500,
+            ..RrtConfig::default()
+        };
+        let quick = rrt(
+            &start,
+            &goal,
+            (&lower, &upper),
+            free,
+            &config,
+            &mut Rng::seed_from_u64(2),
+        )
+        .unwrap();
+        let star = rrt_star(
+            &start,
+            &goal,
+            (&lower, &upper),
+            free,
+            &config,
+            &mut Rng::seed_from_u64(2),
+        )
+        .unwrap();
+        assert!(star.windows(2).all(|w| free(&w[0], &w[1])));
+        assert!(path_length(&star) <= path_length(&quick));
+        // The optimum goes over the wall at y = 7: about 2 * sqrt(3^2 + 6^2) + 2.
+        assert!(path_length(&star) < 18.0);
+    }
+
+    #[test]
+    fn final_move_to_goal_is_free() {
+        // The goal sits just past the wall, so nodes on the near side are
+        // within tolerance but can't see it.
+        let start = Point::new(vec![1.0, 1.0]);
+        let goal = Point::new(vec![6.5, 1.0]);
+        let lower = Point::new(vec![0.0, 0.0]);
+        let upper = Point::new(vec![10.0, 10.0]);
+        let config = RrtConfig {
+            goal_tolerance: 3.0,
+            max_iterations: 1500,
+            ..RrtConfig::default()
+        };
+        for seed in 0..5 {
+            for optimize in [false, true] {
+                let mut rng = Rng::seed_from_u64(seed);
+                let path = plan(
+                    &start,
+                    &goal,
+                    (&lower, &upper),
+                    free,
+                    &config,
+                    &mut rng,
+                    optimize,
+                )
+                .unwrap();
+                assert_eq!(path.last().unwrap().data(), goal.data());
+                assert!(path.windows(2).all(|w| free(&w[0], &w[1])));
+            }
+        }
+    }
+
+    #[test]
+    fn rewired_costs_pick_the_goal_node() {
+        let p = |x: f64, y: f64| Point::new(vec![x, y]);
+        let open = |_: &Point<f64>, _: &Point<f64>| true;
+        // Node 2 reaches the goal through a detour over node 1, node 3
+        // directly but from further away.
+        let mut tree = Tree {
+            nodes: vec![p(0.0, 0.0), p(0.0, 4.0), p(4.0, 0.0), p(3.7, 0.3)],
+            parent: vec![0, 0, 1, 0],
+            children: vec![vec![1, 3], vec![2], vec![], vec![]],
+            cost: vec![0.0, 4.0, 4.0 + 32f64.sqrt(), 0.0],
+        };
+        tree.cost[3] = distance(&tree.nodes[0], &tree.nodes[3]);
+        let goal = p(4.5, 0.0);
+        assert_eq!(tree.best_to_goal(&goal, 1.0, &open), Some(3));
+
+        // A new node on the straight line rewires node 2. Its cost stays above
+        // node 3's, but not once the final legs are counted.
+        tree.nodes.push(p(2.0, 0.0));
+        tree.parent.push(0);
+        tree.children.push(Vec::new());
+        tree.children[0].push(4);
+        tree.cost.push(2.0);
+        tree.rewire(4, &[2], &open);
+        assert_eq!(tree.parent[2], 4);
+        assert!((tree.cost[2] - 4.0).abs() < 1e-12);
+        assert!(tree.cost[2] > tree.cost[3]);
+        assert_eq!(tree.best_to_goal(&goal, 1.0, &open), Some(2));
+        assert_eq!(tree.goal_cost(2, &goal, 1.0, &open), Some(4.5));
+    }
+}
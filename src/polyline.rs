@@ -0,0 +1,483 @@
+// Polyline simplification.
+//
+// Both algorithms return the indices of the vertices to keep, in order, always
+// including the first and last vertex. They work in any dimension; topology
+// preservation (no new self-intersections) is only enforced for 2D lines.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::Point;
+
+/// When to stop simplifying.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    /// Douglas-Peucker: the largest allowed distance from a dropped vertex to
+    /// the simplified line. Visvalingam-Whyatt: the smallest effective area a
+    /// kept vertex may have.
+    Tolerance(f64),
+    /// Keep this many vertices (at least 2). Topology preservation may need
+    /// to keep more.
+    Points(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Simplify {
+    pub target: Target,
+    pub preserve_topology: bool,
+}
+
+impl Simplify {
+    pub fn new(target: Target) -> Self {
+        Simplify {
+            target,
+            preserve_topology: false,
+        }
+    }
+
+    pub fn preserve_topology(mut self, preserve: bool) -> Self {
+        self.preserve_topology = preserve;
+        self
+    }
+
+    pub fn douglas_peucker<T>(&self, line: &[Point<T>]) -> Vec<usize>
+    where
+        T: Into<f64> + Copy,
+    {
+        let coords = to_f64(line);
+        let n = coords.len();
+        if n <= 2 {
+            return (0..n).collect();
+        }
+
+        let mut keep = vec![false; n];
+        keep[0] = true;
+        keep[n - 1] = true;
+        let mut kept = 2;
+        let mut heap = BinaryHeap::new();
+        push_segment(&coords, 0, n - 1, &mut heap);
+
+        while let Some(seg) = heap.pop() {
+            let done = match self.target {
+                Target::Tolerance(tol) => seg.dist <= tol,
+                Target::Points(max) => kept >= max,
+            };
+            if done {
+                break;
+            }
+            keep[seg.split] = true;
+            kept += 1;
+            push_segment(&coords, seg.start, seg.split, &mut heap);
+            push_segment(&coords, seg.split, seg.end, &mut heap);
+        }
+
+        if self.preserve_topology && is_2d(&coords) {
+            // Split any output segment that crosses another until the result
+            // is as simple as the input allows.
+            loop {
+                let idx: Vec<usize> = (0..n).filter(|&i| keep[i]).collect();
+                let mut changed = false;
+                for s in crossing_segments(&coords, &idx) {
+                    let (a, b) = (idx[s], idx[s + 1]);
+                    if let Some((split, _)) = farthest(&coords, a, b) {
+                        keep[split] = true;
+                        changed = true;
+                    }
+                }
+                if !changed {
+                    break;
+                }
+            }
+        }
+
+        (0..n).filter(|&i| keep[i]).collect()
+    }
+
+    pub fn visvalingam<T>(&self, line: &[Point<T>]) -> Vec<usize>
+    where
+        T: Into<f64> + Copy,
+    {
+        let coords = to_f64(line);
+        let n = coords.len();
+        if n <= 2 {
+            return (0..n).collect();
+        }
+        let topology = self.preserve_topology && is_2d(&coords);
+
+        let mut prev: Vec<usize> = (0..n).map(|i| i.wrapping_sub(1)).collect();
+        let mut next: Vec<usize> = (1..=n).collect();
+        let mut alive = vec![true; n];
+        let mut version = vec![0usize; n];
+        let mut remaining = n;
+        let mut heap = BinaryHeap::new();
+        for i in 1..n - 1 {
+            heap.push(Vertex {
+                area: triangle_area(&coords[i - 1], &coords[i], &coords[i + 1]),
+                index: i,
+                version: 0,
+            });
+        }
+        // Vertices whose removal would create a crossing, retried once a
+        // neighbour changes.
+        let mut blocked: Vec<Vertex> = Vec::new();
+
+        while let Some(v) = heap.pop() {
+            if !alive[v.index] || v.version != version[v.index] {
+                continue;
+            }
+            let done = match self.target {
+                Target::Tolerance(tol) => v.area >= tol,
+                Target::Points(max) => remaining <= max.max(2),
+            };
+            if done {
+                break;
+            }
+            let (p, q) = (prev[v.index], next[v.index]);
+            if topology && creates_crossing(&coords, &next, p, q) {
+                blocked.push(v);
+                continue;
+            }
+
+            alive[v.index] = false;
+            remaining -= 1;
+            next[p] = q;
+            prev[q] = p;
+            for &nb in &[p, q] {
+                if nb == 0 || nb == n - 1 {
+                    continue;
+                }
+                version[nb] += 1;
+                // Enforce monotonic areas so a vertex never looks less
+                // important than one already removed.
+                let area =
+                    triangle_area(&coords[prev[nb]], &coords[nb], &coords[next[nb]]).max(v.area);
+                heap.push(Vertex {
+                    area,
+                    index: nb,
+                    version: version[nb],
+                });
+            }
+            for b in blocked.drain(..) {
+                if alive[b.index] && b.version == version[b.index] {
+                    heap.push(b);
+                }
+            }
+        }
+
+        (0..n).filter(|&i| alive[i]).collect()
+    }
+}
+
+/// The vertices of `line` selected by `indices`.
+pub fn select<T: Clone>(line: &[Point<T>], indices: &[usize]) -> Vec<Point<T>> {
+    indices.iter().map(|&i| line[i].clone()).collect()
+}
+
+fn to_f64<T: Into<f64> + Copy>(line: &[Point<T>]) -> Vec<Vec<f64>> {
+    line.iter()
+        .map(|p| p.p.iter().map(|&x| x.into()).collect())
+        .collect()
+}
+
+fn is_2d(coords: &[Vec<f64>]) -> bool {
+    coords.first().is_some_and(|c| c.len() == 2)
+}
+
+fn point_segment_distance(p: &[f64], a: &[f64], b: &[f64]) -> f64 {
+    let ab: Vec<f64> = a.iter().zip(b).map(|(x, y)| y - x).collect();
+    let ap: Vec<f64> = a.iter().zip(p).map(|(x, y)| y - x).collect();
+    let len2: f64 = ab.iter().map(|x| x * x).sum();
+    let t = if len2 == 0.0 {
+        0.0
+    } else {
+        (ab.iter().zip(&ap).map(|(x, y)| x * y).sum::<f64>() / len2).clamp(0.0, 1.0)
+    };
+    ap.iter()
+        .zip(&ab)
+        .map(|(p, d)| (p - t * d).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+fn triangle_area(a: &[f64], b: &[f64], c: &[f64]) -> f64 {
+    let u: Vec<f64> = a.iter().zip(b).map(|(x, y)| y - x).collect();
+    let v: Vec<f64> = a.iter().zip(c).map(|(x, y)| y - x).collect();
+    let uu: f64 = u.iter().map(|x| x * x).sum();
+    let vv: f64 = v.iter().map(|x| x * x).sum();
+    let uv: f64 = u.iter().zip(&v).map(|(x, y)| x * y).sum();
+    0.5 * (uu * vv - uv * uv).max(0.0).sqrt()
+}
+
+fn farthest(coords: &[Vec<f64>], start: usize, end: usize) -> Option<(usize, f64)> {
+    (start + 1..end)
+        .map(|i| {
+            (
+                i,
+                point_segment_distance(&coords[i], &coords[start], &coords[end]),
+            )
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+struct Segment {
+    dist: f64,
+    start: usize,
+    split: usize,
+    end: usize,
+}
+
+impl PartialEq for Segment {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Segment {}
+
+impl PartialOrd for Segment {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Segment {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.dist
+            .total_cmp(&other.dist)
+            .then(other.start.cmp(&self.start))
+    }
+}
+
+fn push_segment(coords: &[Vec<f64>], start: usize, end: usize, heap: &mut BinaryHeap<Segment>) {
+    if let Some((split, dist)) = farthest(coords, start, end) {
+        heap.push(Segment {
+            dist,
+            start,
+            split,
+            end,
+        });
+    }
+}
+
+// Min-heap entry for Visvalingam-Whyatt.
+struct Vertex {
+    area: f64,
+    index: usize,
+    version: usize,
+}
+
+impl PartialEq for Vertex {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Vertex {}
+
+impl PartialOrd for Vertex {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Vertex {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .area
+            .total_cmp(&self.area)
+            .then(other.index.cmp(&self.index))
+    }
+}
+
+fn orient(a: &[f64], b: &[f64], c: &[f64]) -> f64 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+fn on_segment(a: &[f64], b: &[f64], p: &[f64]) -> bool {
+    p[0] >= a[0].min(b[0])
+        && p[0] <= a[0].max(b[0])
+        && p[1] >= a[1].min(b[1])
+        && p[1] <= a[1].max(b[1])
+}
+
+fn strictly_inside(s: &[f64], e: &[f64], p: &[f64]) -> bool {
+    p != s && p != e && on_segment(s, e, p)
+}
+
+// Whether segments `ab` and `cd` meet anywhere other than a shared endpoint.
+fn segments_cross(a: &[f64], b: &[f64], c: &[f64], d: &[f64]) -> bool {
+    let (o1, o2) = (orient(a, b, c), orient(a, b, d));
+    let (o3, o4) = (orient(c, d, a), orient(c, d, b));
+    if a == c || a == d || b == c || b == d {
+        // Touching at the shared endpoint is fine; only a collinear overlap
+        // counts.
+        return o1 == 0.0
+            && o2 == 0.0
+            && (strictly_inside(a, b, c)
+                || strictly_inside(a, b, d)
+                || strictly_inside(c, d, a)
+                || strictly_inside(c, d, b));
+    }
+    if o1 * o2 < 0.0 && o3 * o4 < 0.0 {
+        return true;
+    }
+    (o1 == 0.0 && on_segment(a, b, c))
+        || (o2 == 0.0 && on_segment(a, b, d))
+        || (o3 == 0.0 && on_segment(c, d, a))
+        || (o4 == 0.0 && on_segment(c, d, b))
+}
+
+// Positions `s` in `idx` of output segments `idx[s]..idx[s + 1]` that cross a
+// non-adjacent output segment.
+fn crossing_segments(coords: &[Vec<f64>], idx: &[usize]) -> Vec<usize> {
+    let m = idx.len().saturating_sub(1);
+    (0..m)
+        .filter(|&s| {
+            let (a, b) = (&coords[idx[s]], &coords[idx[s + 1]]);
+            (0..m)
+                .filter(|&t| t + 1 < s || t > s + 1)
+                .any(|t| segments_cross(a, b, &coords[idx[t]], &coords[idx[t + 1]]))
+        })
+        .collect()
+}
+
+// Whether replacing the path `p -> v -> q` with the segment `pq` would cross
+// any other segment of the current line.
+fn creates_crossing(coords: &[Vec<f64>], next: &[usize], p: usize, q: usize) -> bool {
+    let last = coords.len() - 1;
+    let mut i = 0;
+    while i < last {
+        if i == p {
+            i = q;
+            continue;
+        }
+        let j = next[i];
+        if segments_cross(&coords[p], &coords[q], &coords[i], &coords[j]) {
+            return true;
+        }
+        i = j;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(coords: &[(f64, f64)]) -> Vec<Point<f64>> {
+        coords.iter().map(|&c| Point::from(c)).collect()
+    }
+
+    #[test]
+    fn douglas_peucker_tolerance() {
+        let l = line(&[
+            (0.0, 0.0),
+            (1.0, 0.1),
+            (2.0, -0.1),
+            (3.0, 5.0),
+            (4.0, 6.0),
+            (5.0, 7.0),
+        ]);
+        let kept = Simplify::new(Target::Tolerance(0.5)).douglas_peucker(&l);
+        assert_eq!(kept, vec![0, 2, 3, 5]);
+        // Collinear vertices are dropped even at zero tolerance.
+        let all = Simplify::new(Target::Tolerance(0.0)).douglas_peucker(&l);
+        assert_eq!(all, vec![0, 1, 2, 3, 5]);
+    }
+
+    #[test]
+    fn douglas_peucker_count() {
+        let l = line(&[
+            (0.0, 0.0),
+            (1.0, 0.1),
+            (2.0, -0.1),
+            (3.0, 5.0),
+            (4.0, 6.0),
+            (5.0, 7.0),
+        ]);
+        let kept = Simplify::new(Target::Points(3)).douglas_peucker(&l);
+        assert_eq!(kept, vec![0, 2, 5]);
+    }
+
+    #[test]
+    fn visvalingam() {
+        let l = line(&[(0.0, 0.0), (1.0, 0.01), (2.0, 0.0), (3.0, 3.0), (4.0, 0.0)]);
+        let kept = Simplify::new(Target::Points(3)).visvalingam(&l);
+        assert_eq!(kept, vec![0, 3, 4]);
+        let kept = Simplify::new(Target::Tolerance(0.1)).visvalingam(&l);
+        assert_eq!(kept, vec![0, 2, 3, 4]);
+    }
+
+    #[test]
+    fn three_dimensional() {
+        let l: Vec<Point<f64>> = (0..10)
+            .map(|i| Point::new(vec![i as f64, 0.0, if i == 5 { 3.0 } else { 0.0 }]))
+            .collect();
+        assert_eq!(
+            Simplify::new(Target::Tolerance(0.5)).douglas_peucker(&l),
+            vec![0, 4, 5, 6, 9]
+        );
+        assert_eq!(
+            Simplify::new(Target::Points(5)).visvalingam(&l),
+            vec![0, 4, 5, 6, 9]
+        );
+    }
+
+    // A bump on the outbound leg with the return leg reaching up into it:
+    // flattening the bump makes the shortcut cross the return leg.
+    fn hairpin() -> Vec<Point<f64>> {
+        line(&[
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (4.0, 3.0),
+            (6.0, 3.0),
+            (6.0, 0.0),
+            (10.0, 0.0),
+            (10.0, -2.0),
+            (5.0, -2.0),
+            (5.0, 2.0),
+        ])
+    }
+
+    fn simple(l: &[Point<f64>], kept: &[usize]) -> bool {
+        let coords = to_f64(l);
+        crossing_segments(&coords, kept).is_empty()
+    }
+
+    #[test]
+    fn topology_douglas_peucker() {
+        let l = hairpin();
+        let plain = Simplify::new(Target::Points(7)).douglas_peucker(&l);
+        assert!(!simple(&l, &plain));
+        let safe = Simplify::new(Target::Points(7))
+            .preserve_topology(true)
+            .douglas_peucker(&l);
+        assert!(simple(&l, &safe));
+        assert_eq!(safe, vec![0, 1, 2, 3, 4, 6, 7, 8]);
+    }
+
+    #[test]
+    fn topology_visvalingam() {
+        let l = hairpin();
+        let plain = Simplify::new(Target::Points(4)).visvalingam(&l);
+        assert!(!simple(&l, &plain));
+        let safe = Simplify::new(Target::Points(4))
+            .preserve_topology(true)
+            .visvalingam(&l);
+        assert!(simple(&l, &safe));
+        assert_eq!(safe.len(), 4);
+    }
+
+    #[test]
+    fn short_lines() {
+        let l = line(&[(0.0, 0.0), (1.0, 1.0)]);
+        assert_eq!(Simplify::new(Target::Points(1)).visvalingam(&l), vec![0, 1]);
+        assert_eq!(
+            select(&l, &[1])
+                .iter()
+                .map(|p| p.data()[0])
+                .collect::<Vec<_>>(),
+            vec![1.0]
+        );
+    }
+}
@@ -0,0 +1,588 @@
+// Convex polytopes given as intersections of half-spaces (H-representation).
+//
+// `vertices` converts to the vertex representation with the double
+// description method: the polytope is lifted to the cone
+// `{(x, t) : a.x - b t <= 0, t >= 0}` and the cone's extreme rays are built up
+// one constraint at a time. Rays with `t > 0` are the vertices.
+
+use crate::linalg::{Matrix, dot, norm};
+use crate::lp::{self, Lp};
+use crate::{Error, Point};
+
+const EPS: f64 = 1e-9;
+// Active-set steps allowed per constraint in `project`.
+const ACTIVE_SET_STEPS: usize = 100;
+
+/// The half-space `normal . x <= offset`.
+#[derive(Debug, Clone)]
+pub struct HalfSpace {
+    pub normal: Point<f64>,
+    pub offset: f64,
+}
+
+impl HalfSpace {
+    pub fn new(normal: Point<f64>, offset: f64) -> Self {
+        HalfSpace { normal, offset }
+    }
+
+    /// `normal . p - offset`, scaled to a Euclidean distance; negative inside.
+    pub fn signed_distance<T>(&self, p: &Point<T>) -> f64
+    where
+        T: Into<f64> + Copy,
+    {
+        let x: Vec<f64> = p.p.iter().map(|&v| v.into()).collect();
+        (dot(&self.normal.p, &x) - self.offset) / norm(&self.normal.p)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HPolytope {
+    dim: usize,
+    halfspaces: Vec<HalfSpace>,
+}
+
+impl HPolytope {
+    /// The whole of `dim`-dimensional space, before any constraint is added.
+    pub fn new(dim: usize) -> Self {
+        HPolytope {
+            dim,
+            halfspaces: Vec::new(),
+        }
+    }
+
+    pub fn from_halfspaces(dim: usize, halfspaces: Vec<HalfSpace>) -> Result<Self, Error> {
+        let mut poly = HPolytope::new(dim);
+        for h in halfspaces {
+            poly.add(h)?;
+        }
+        Ok(poly)
+    }
+
+    /// The axis-aligned box `[lower, upper]`.
+    pub fn cuboid(lower: &[f64], upper: &[f64]) -> Self {
+        let dim = lower.len();
+        let mut poly = HPolytope::new(dim);
+        for i in 0..dim {
+            let mut e = vec![0.0; dim];
+            e[i] = 1.0;
+            poly.halfspaces
+                .push(HalfSpace::new(Point::new(e.clone()), upper[i]));
+            e[i] = -1.0;
+            poly.halfspaces
+                .push(HalfSpace::new(Point::new(e), -lower[i]));
+        }
+        poly
+    }
+
+    /// Adds a constraint; fails if its normal has the wrong dimension or is
+    /// zero, which bounds nothing and has no distance to measure.
+    pub fn add(&mut self, halfspace: HalfSpace) -> Result<(), Error> {
+        if halfspace.normal.dim() != self.dim {
+            return Err(Error::DimensionMismatch {
+                expected: self.dim,
+                found: halfspace.normal.dim(),
+            });
+        }
+        if norm(&halfspace.normal.p) == 0.0 {
+            return Err(Error::ZeroLength);
+        }
+        self.halfspaces.push(halfspace);
+        Ok(())
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn halfspaces(&self) -> &[HalfSpace] {
+        &self.halfspaces
+    }
+
+    pub fn contains<T>(&self, p: &Point<T>) -> bool
+    where
+        T: Into<f64> + Copy,
+    {
+        p.dim() == self.dim && self.halfspaces.iter().all(|h| h.signed_distance(p) <= EPS)
+    }
+
+    /// Center and radius of the largest ball inside the polytope, or `None`
+    /// if it is empty or contains arbitrarily large balls.
+    pub fn chebyshev_center(&self) -> Option<(Point<f64>, f64)> {
+        let d = self.dim;
+        let mut a: Vec<Vec<f64>> = self
+            .halfspaces
+            .iter()
+            .map(|h| {
+                let mut row = h.normal.p.to_vec();
+                row.push(norm(&h.normal.p));
+                row
+            })
+            .collect();
+        let mut b: Vec<f64> = self.halfspaces.iter().map(|h| h.offset).collect();
+        let mut r_nonneg = vec![0.0; d + 1];
+        r_nonneg[d] = -1.0;
+        a.push(r_nonneg);
+        b.push(0.0);
+        let mut c = vec![0.0; d + 1];
+        c[d] = 1.0;
+        match lp::maximize(&c, &a, &b) {
+            Lp::Optimal { x, .. } => {
+                let r = x[d];
+                Some((Point::new(x[..d].to_vec()), r))
+            }
+            Lp::Infeasible | Lp::Unbounded => None,
+        }
+    }
+
+    /// Whether any point satisfies every constraint.
+    pub fn is_feasible(&self) -> bool {
+        !matches!(self.maximize(&vec![0.0; self.dim]), Lp::Infeasible)
+    }
+
+    /// Maximises `objective . x` over the polytope.
+    pub fn maximize(&self, objective: &[f64]) -> Lp {
+        let a: Vec<Vec<f64>> = self
+            .halfspaces
+            .iter()
+            .map(|h| h.normal.p.to_vec())
+            .collect();
+        let b: Vec<f64> = self.halfspaces.iter().map(|h| h.offset).collect();
+        lp::maximize(objective, &a, &b)
+    }
+
+    /// The point of the polytope closest to `p`, or `None` if it is empty.
+    ///
+    /// An LP gives a feasible starting point, and a primal active-set method
+    /// then solves the projection exactly (up to rounding): it heads for the
+    /// closest point on the constraints it holds as equalities, stops at the
+    /// first constraint in the way and holds that one too, and lets go of
+    /// any whose multiplier says it pushes the wrong way. Each step solves a
+    /// system no larger than the dimension. Heavily degenerate constraint
+    /// sets could make it cycle, so it is cut off after a number of steps
+    /// proportional to the constraints, returning the feasible point
+    /// reached.
+    pub fn project<T>(&self, p: &Point<T>) -> Option<Point<f64>>
+    where
+        T: Into<f64> + Copy,
+    {
+        let target: Vec<f64> = p.p.iter().map(|&v| v.into()).collect();
+        let mut x = match self.maximize(&vec![0.0; self.dim]) {
+            Lp::Optimal { x, .. } => x,
+            Lp::Infeasible | Lp::Unbounded => return None,
+        };
+        if self.contains(p) {
+            return Some(Point::new(target));
+        }
+        let mut working: Vec<usize> = Vec::new();
+        for _ in 0..ACTIVE_SET_STEPS * (self.halfspaces.len() + self.dim) {
+            let Some((goal, multipliers)) = self.project_onto(&target, &working) else {
+                // Dependent constraints, which only rounding lets in.
+                working.pop();
+                continue;
+            };
+            let step: Vec<f64> = goal.iter().zip(&x).map(|(g, x)| g - x).collect();
+            if norm(&step) <= EPS * (1.0 + norm(&x)) {
+                let most_negative = multipliers
+                    .iter()
+                    .enumerate()
+                    .min_by(|a, b| a.1.total_cmp(b.1));
+                match most_negative {
+                    Some((k, &l)) if l < -EPS => {
+                        working.remove(k);
+                        continue;
+                    }
+                    _ => return Some(Point::new(goal)),
+                }
+            }
+            // The furthest along `step` every other constraint allows.
+            let mut alpha = 1.0;
+            let mut blocking = None;
+            for (i, h) in self.halfspaces.iter().enumerate() {
+                let n = &h.normal.p;
+                let rate = dot(n, &step);
+                if working.contains(&i) || rate <= EPS * norm(n) * norm(&step) {
+                    continue;
+                }
+                let room = (h.offset - dot(n, &x)).max(0.0) / rate;
+                if room < alpha {
+                    alpha = room;
+                    blocking = Some(i);
+                }
+            }
+            x.iter_mut().zip(&step).for_each(|(x, s)| *x += alpha * s);
+            working.extend(blocking);
+        }
+        Some(Point::new(x))
+    }
+
+    // The point closest to `target` on the constraints `working`, taken as
+    // equalities, with their multipliers; `None` if the normals are
+    // dependent.
+    fn project_onto(&self, target: &[f64], working: &[usize]) -> Option<(Vec<f64>, Vec<f64>)> {
+        if working.is_empty() {
+            return Some((target.to_vec(), Vec::new()));
+        }
+        let normals: Vec<&[f64]> = working
+            .iter()
+            .map(|&i| &self.halfspaces[i].normal.p[..])
+            .collect();
+        let gram = Matrix::from_rows(
+            &normals
+                .iter()
+                .map(|a| normals.iter().map(|b| dot(a, b)).collect())
+                .collect::<Vec<_>>(),
+        );
+        let excess: Vec<f64> = working
+            .iter()
+            .zip(&normals)
+            .map(|(&i, n)| dot(n, target) - self.halfspaces[i].offset)
+            .collect();
+        let multipliers = gram.solve(&excess)?;
+        let mut goal = target.to_vec();
+        for (n, l) in normals.iter().zip(&multipliers) {
+            goal.iter_mut().zip(n.iter()).for_each(|(g, a)| *g -= l * a);
+        }
+        Some((goal, multipliers))
+    }
+
+    /// The vertices of the polytope. Unbounded polytopes return the vertices
+    /// they have (possibly none); polytopes containing a whole line have none.
+    pub fn vertices(&self) -> Vec<Point<f64>> {
+        self.extreme_rays()
+            .into_iter()
+            .filter(|r| r[self.dim] > EPS)
+            .map(|r| {
+                let t = r[self.dim];
+                Point::new(r[..self.dim].iter().map(|v| v / t).collect())
+            })
+            .collect()
+    }
+
+    /// Whether the polytope is bounded (and not empty).
+    pub fn is_bounded(&self) -> bool {
+        let rays = self.extreme_rays();
+        !rays.is_empty() && rays.iter().all(|r| r[self.dim] > EPS)
+    }
+
+    // Extreme rays of the homogenised cone in R^(dim + 1).
+    fn extreme_rays(&self) -> Vec<Vec<f64>> {
+        let d = self.dim;
+        let mut rows: Vec<Vec<f64>> = self
+            .halfspaces
+            .iter()
+            .map(|h| {
+                let mut g = h.normal.p.to_vec();
+                g.push(-h.offset);
+                let n = norm(&g);
+                g.iter().map(|v| v / n).collect()
+            })
+            .collect();
+        let mut t_nonneg = vec![0.0; d + 1];
+        t_nonneg[d] = -1.0;
+        rows.push(t_nonneg);
+        let m = rows.len();
+
+        // Start from d + 1 independent constraints: that cone is simplicial.
+        let mut chosen: Vec<usize> = Vec::new();
+        for i in 0..m {
+            let mut trial: Vec<Vec<f64>> = chosen.iter().map(|&j| rows[j].clone()).collect();
+            trial.push(rows[i].clone());
+            if Matrix::from_rows(&trial).rank(EPS) == trial.len() {
+                chosen.push(i);
+                if chosen.len() == d + 1 {
+                    break;
+                }
+            }
+        }
+        if chosen.len() < d + 1 {
+            return Vec::new();
+        }
+        let g0 = Matrix::from_rows(&chosen.iter().map(|&j| rows[j].clone()).collect::<Vec<_>>());
+        let inv = match g0.inverse() {
+            Some(inv) => inv,
+            None => return Vec::new(),
+        };
+
+        let words = m.div_ceil(64);
+        let mut rays: Vec<Ray> = (0..=d)
+            .map(|j| {
+                let y: Vec<f64> = inv.column(j).iter().map(|v| -v).collect();
+                let mut zeros = vec![0u64; words];
+                for (k, &row) in chosen.iter().enumerate() {
+                    if k != j {
+                        set_bit(&mut zeros, row);
+                    }
+                }
+                Ray::new(y, zeros)
+            })
+            .collect();
+
+        for (i, g) in rows.iter().enumerate() {
+            if chosen.contains(&i) {
+                continue;
+            }
+            let s: Vec<f64> = rays.iter().map(|r| dot(g, &r.y)).collect();
+            let pos: Vec<usize> = (0..rays.len()).filter(|&k| s[k] > EPS).collect();
+            let neg: Vec<usize> = (0..rays.len()).filter(|&k| s[k] < -EPS).collect();
+
+            let mut next: Vec<Ray> = Vec::new();
+            for &p in &pos {
+                for &n in &neg {
+                    let common: Vec<u64> = rays[p]
+                        .zeros
+                        .iter()
+                        .zip(&rays[n].zeros)
+                        .map(|(a, b)| a & b)
+                        .collect();
+                    if count(&common) + 1 < d {
+                        continue;
+                    }
+                    let adjacent = (0..rays.len())
+                        .filter(|&k| k != p && k != n)
+                        .all(|k| !subset(&common, &rays[k].zeros));
+                    if adjacent {
+                        let y: Vec<f64> = rays[n]
+                            .y
+                            .iter()
+                            .zip(&rays[p].y)
+                            .map(|(yn, yp)| s[p] * yn - s[n] * yp)
+                            .collect();
+                        let mut zeros = common;
+                        set_bit(&mut zeros, i);
+                        next.push(Ray::new(y, zeros));
+                    }
+                }
+            }
+            for (k, mut ray) in rays.into_iter().enumerate() {
+                if s[k] <= EPS {
+                    if s[k] >= -EPS {
+                        set_bit(&mut ray.zeros, i);
+                    }
+                    next.push(ray);
+                }
+            }
+            rays = next;
+        }
+        rays.into_iter().map(|r| r.y).collect()
+    }
+}
+
+struct Ray {
+    y: Vec<f64>,
+    zeros: Vec<u64>,
+}
+
+impl Ray {
+    fn new(y: Vec<f64>, zeros: Vec<u64>) -> Self {
+        let n = norm(&y);
+        Ray {
+            y: y.iter().map(|v| v / n).collect(),
+            zeros,
+        }
+    }
+}
+
+fn set_bit(bits: &mut [u64], i: usize) {
+    bits[i / 64] |= 1 << (i % 64);
+}
+
+fn count(bits: &[u64]) -> usize {
+    bits.iter().map(|w| w.count_ones() as usize).sum()
+}
+
+fn subset(a: &[u64], b: &[u64]) -> bool {
+    a.iter().zip(b).all(|(x, y)| x & !y == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut v: Vec<Point<f64>>) -> Vec<Vec<f64>> {
+        let mut out: Vec<Vec<f64>> = v
+            .iter_mut()
+            .map(|p| p.iter().map(|x| (x * 1e9).round() / 1e9).collect())
+            .collect();
+        out.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        out
+    }
+
+    #[test]
+    fn contains() {
+        let square = HPolytope::cuboid(&[0.0, 0.0], &[1.0, 1.0]);
+        assert!(square.contains(&Point::new(vec![0.5, 0.5])));
+        assert!(square.contains(&Point::new(vec![1, 0])));
+        assert!(!square.contains(&Point::new(vec![1.5, 0.5])));
+        assert!(!square.contains(&Point::new(vec![0.5])));
+    }
+
+    #[test]
+    fn add_checks_dimension() {
+        let mut p = HPolytope::new(2);
+        let err = p.add(HalfSpace::new(Point::new(vec![1.0]), 0.0));
+        assert_eq!(
+            err,
+            Err(Error::DimensionMismatch {
+                expected: 2,
+                found: 1
+            })
+        );
+        let zero = p.add(HalfSpace::new(Point::new(vec![0.0, 0.0]), 1.0));
+        assert_eq!(zero, Err(Error::ZeroLength));
+    }
+
+    #[test]
+    fn chebyshev_center() {
+        let rect = HPolytope::cuboid(&[0.0, 0.0], &[4.0, 2.0]);
+        let (_, r) = rect.chebyshev_center().unwrap();
+        assert!((r - 1.0).abs() < 1e-9);
+
+        // Right triangle with legs 3 and 4: inradius 1 at (1, 1).
+        let tri = HPolytope::from_halfspaces(
+            2,
+            vec![
+                HalfSpace::new(Point::new(vec![-1.0, 0.0]), 0.0),
+                HalfSpace::new(Point::new(vec![0.0, -1.0]), 0.0),
+                HalfSpace::new(Point::new(vec![4.0, 3.0]), 12.0),
+            ],
+        )
+        .unwrap();
+        let (c, r) = tri.chebyshev_center().unwrap();
+        assert!((r - 1.0).abs() < 1e-9);
+        assert!((c[0] - 1.0).abs() < 1e-9 && (c[1] - 1.0).abs() < 1e-9);
+
+        let mut empty = HPolytope::cuboid(&[0.0], &[1.0]);
+        empty
+            .add(HalfSpace::new(Point::new(vec![-1.0]), -2.0))
+            .unwrap();
+        assert!(empty.chebyshev_center().is_none());
+        assert!(HPolytope::new(2).chebyshev_center().is_none());
+    }
+
+    #[test]
+    fn feasibility_and_maximize() {
+        let square = HPolytope::cuboid(&[0.0, 0.0], &[1.0, 2.0]);
+        assert!(square.is_feasible());
+        match square.maximize(&[1.0, 1.0]) {
+            Lp::Optimal { value, .. } => assert!((value - 3.0).abs() < 1e-9),
+            other => panic!("unexpected {other:?}"),
+        }
+        let mut empty = square.clone();
+        empty
+            .add(HalfSpace::new(Point::new(vec![1.0, 0.0]), -1.0))
+            .unwrap();
+        assert!(!empty.is_feasible());
+        assert!(empty.project(&Point::new(vec![0.0, 0.0])).is_none());
+    }
+
+    #[test]
+    fn project() {
+        let square = HPolytope::cuboid(&[0.0, 0.0], &[1.0, 1.0]);
+        let inside = square.project(&Point::new(vec![0.25, 0.5])).unwrap();
+        assert_eq!(inside.data(), &[0.25, 0.5]);
+        let corner = square.project(&Point::new(vec![3.0, 2.0])).unwrap();
+        assert!((corner[0] - 1.0).abs() < 1e-9 && (corner[1] - 1.0).abs() < 1e-9);
+        let side = square.project(&Point::new(vec![0.5, -4.0])).unwrap();
+        assert!((side[0] - 0.5).abs() < 1e-9 && side[1].abs() < 1e-9);
+
+        // Triangle x, y >= 0, x + y <= 1.
+        let tri = HPolytope::from_halfspaces(
+            2,
+            vec![
+                HalfSpace::new(Point::new(vec![-1.0, 0.0]), 0.0),
+                HalfSpace::new(Point::new(vec![0.0, -1.0]), 0.0),
+                HalfSpace::new(Point::new(vec![1.0, 1.0]), 1.0),
+            ],
+        )
+        .unwrap();
+        let q = tri.project(&Point::new(vec![2.0, 1.0])).unwrap();
+        assert!((q[0] - 1.0).abs() < 1e-9 && q[1].abs() < 1e-9);
+        let q = tri.project(&Point::new(vec![1.0, 1.0])).unwrap();
+        assert!((q[0] - 0.5).abs() < 1e-9 && (q[1] - 0.5).abs() < 1e-9);
+
+        // A narrow wedge 0 <= y <= e x, where alternating projections
+        // crawl: the answers come out exact.
+        let e = 1e-3;
+        let wedge = HPolytope::from_halfspaces(
+            2,
+            vec![
+                HalfSpace::new(Point::new(vec![0.0, -1.0]), 0.0),
+                HalfSpace::new(Point::new(vec![-e, 1.0]), 0.0),
+            ],
+        )
+        .unwrap();
+        let apex = wedge.project(&Point::new(vec![-1.0, 1.0])).unwrap();
+        assert!(apex.iter().all(|v| v.abs() < 1e-9));
+        let q = wedge.project(&Point::new(vec![2.0, 1.0])).unwrap();
+        let t = (2.0 + e) / (1.0 + e * e);
+        assert!((q[0] - t).abs() < 1e-9 && (q[1] - e * t).abs() < 1e-9);
+    }
+
+    #[test]
+    fn vertices_of_cube() {
+        let cube = HPolytope::cuboid(&[0.0, 0.0, 0.0], &[1.0, 1.0, 1.0]);
+        let v = sorted(cube.vertices());
+        assert_eq!(v.len(), 8);
+        assert_eq!(v[0], vec![0.0, 0.0, 0.0]);
+        assert_eq!(v[7], vec![1.0, 1.0, 1.0]);
+        assert!(cube.is_bounded());
+    }
+
+    #[test]
+    fn vertices_with_redundancy() {
+        // A square with a cut corner and a redundant constraint.
+        let mut p = HPolytope::cuboid(&[0.0, 0.0], &[2.0, 2.0]);
+        p.add(HalfSpace::new(Point::new(vec![1.0, 1.0]), 3.0))
+            .unwrap();
+        p.add(HalfSpace::new(Point::new(vec![1.0, 0.0]), 5.0))
+            .unwrap();
+        let v = sorted(p.vertices());
+        assert_eq!(
+            v,
+            vec![
+                vec![0.0, 0.0],
+                vec![0.0, 2.0],
+                vec![1.0, 2.0],
+                vec![2.0, 0.0],
+                vec![2.0, 1.0]
+            ]
+        );
+    }
+
+    #[test]
+    fn unbounded() {
+        let quadrant = HPolytope::from_halfspaces(
+            2,
+            vec![
+                HalfSpace::new(Point::new(vec![-1.0, 0.0]), 0.0),
+                HalfSpace::new(Point::new(vec![0.0, -1.0]), 0.0),
+            ],
+        )
+        .unwrap();
+        assert_eq!(sorted(quadrant.vertices()), vec![vec![0.0, 0.0]]);
+        assert!(!quadrant.is_bounded());
+
+        let half_plane =
+            HPolytope::from_halfspaces(2, vec![HalfSpace::new(Point::new(vec![1.0, 0.0]), 0.0)])
+                .unwrap();
+        assert!(half_plane.vertices().is_empty());
+    }
+
+    #[test]
+    fn vertices_of_cross_polytope() {
+        // |x| + |y| + |z| <= 1 has 6 vertices.
+        let mut p = HPolytope::new(3);
+        for s in 0..8 {
+            let n: Vec<f64> = (0..3)
+                .map(|k| if s >> k & 1 == 1 { -1.0 } else { 1.0 })
+                .collect();
+            p.add(HalfSpace::new(Point::new(n), 1.0)).unwrap();
+        }
+        let v = p.vertices();
+        assert_eq!(v.len(), 6);
+        assert!(
+            v.iter()
+                .all(|x| (x.iter().map(|c| c.abs()).sum::<f64>() - 1.0).abs() < 1e-9)
+        );
+    }
+}
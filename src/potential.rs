@@ -0,0 +1,246 @@
+// Potential-field navigation.
+//
+// A quadratic well pulls towards the goal and every obstacle within
+// `influence_radius` pushes away. Following the negative gradient gives a
+// cheap path that needs no search, at the price of possibly getting stuck in
+// a local minimum, which `follow` reports.
+
+use crate::Point;
+
+#[derive(Debug, Clone)]
+pub struct PotentialField {
+    goal: Point<f64>,
+    obstacles: Vec<Point<f64>>,
+    attractive_gain: f64,
+    repulsive_gain: f64,
+    influence_radius: f64,
+}
+
+/// The result of following a field from a start point.
+#[derive(Debug, Clone)]
+pub struct FieldPath {
+    pub path: Vec<Point<f64>>,
+    /// Whether the path ended within tolerance of the goal rather than in a
+    /// local minimum or by running out of steps.
+    pub reached: bool,
+}
+
+/// Normalised descent directions sampled on a regular grid.
+#[derive(Debug, Clone)]
+pub struct FlowField {
+    pub positions: Vec<Point<f64>>,
+    pub directions: Vec<Point<f64>>,
+}
+
+fn distance(a: &Point<f64>, b: &Point<f64>) -> f64 {
+    (a - b).dist()
+}
+
+impl PotentialField {
+    pub fn new(goal: Point<f64>, obstacles: Vec<Point<f64>>) -> Self {
+        PotentialField {
+            goal,
+            obstacles,
+            attractive_gain: 1.0,
+            repulsive_gain: 1.0,
+            influence_radius: 1.0,
+        }
+    }
+
+    pub fn attractive_gain(mut self, gain: f64) -> Self {
+        self.attractive_gain = gain;
+        self
+    }
+
+    pub fn repulsive_gain(mut self, gain: f64) -> Self {
+        self.repulsive_gain = gain;
+        self
+    }
+
+    /// Obstacles farther away than this have no effect.
+    pub fn influence_radius(mut self, radius: f64) -> Self {
+        self.influence_radius = radius;
+        self
+    }
+
+    pub fn potential(&self, p: &Point<f64>) -> f64 {
+        let attract = 0.5 * self.attractive_gain * distance(p, &self.goal).powi(2);
+        let repel: f64 = self
+            .obstacles
+            .iter()
+            .map(|o| {
+                let d = distance(p, o);
+                if d >= self.influence_radius {
+                    0.0
+                } else if d == 0.0 {
+                    f64::INFINITY
+                } else {
+                    0.5 * self.repulsive_gain * (1.0 / d - 1.0 / self.influence_radius).powi(2)
+                }
+            })
+            .sum();
+        attract + repel
+    }
+
+    pub fn gradient(&self, p: &Point<f64>) -> Point<f64> {
+        let mut grad = &(p - &self.goal) * self.attractive_gain;
+        for o in &self.obstacles {
+            let d = distance(p, o);
+            if d >= self.influence_radius || d == 0.0 {
+                continue;
+            }
+            let scale = -self.repulsive_gain * (1.0 / d - 1.0 / self.influence_radius) / d.powi(3);
+            grad = &grad + &(&(p - o) * scale);
+        }
+        grad
+    }
+
+    /// Descends the field from `start` in steps of length `step` until within
+    /// `tolerance` of the goal, the gradient vanishes, or `max_steps` is hit.
+    pub fn follow(
+        &self,
+        start: &Point<f64>,
+        step: f64,
+        tolerance: f64,
+        max_steps: usize,
+    ) -> FieldPath {
+        let mut path = vec![start.clone()];
+        let mut p = start.clone();
+        for _ in 0..max_steps {
+            if distance(&p, &self.goal) <= tolerance {
+                return FieldPath {
+                    path,
+                    reached: true,
+                };
+            }
+            let grad = self.gradient(&p);
+            let norm = grad.dist();
+            if norm < 1e-12 {
+                break;
+            }
+            let next = &p - &(&grad * (step.min(distance(&p, &self.goal)) / norm));
+            // Oscillating around a point means we're stuck in a minimum.
+            let stuck = path.len() >= 2 && distance(&next, &path[path.len() - 2]) < step * 1e-3;
+            path.push(next.clone());
+            p = next;
+            if stuck {
+                break;
+            }
+        }
+        let reached = distance(&p, &self.goal) <= tolerance;
+        FieldPath { path, reached }
+    }
+
+    /// Samples normalised descent directions on a grid of `shape` points
+    /// spanning `[lower, upper]`, in row-major order.
+    pub fn flow_field(&self, lower: &Point<f64>, upper: &Point<f64>, shape: &[usize]) -> FlowField {
+        let total: usize = shape.iter().product();
+        let mut positions = Vec::with_capacity(total);
+        let mut directions = Vec::with_capacity(total);
+        for mut index in 0..total {
+            let mut coords = vec![0.0; shape.len()];
+            for (axis, &n) in shape.iter().enumerate().rev() {
+                let i = index % n;
+                index /= n;
+                let (lo, hi) = (lower.p[axis], upper.p[axis]);
+                coords[axis] = if n > 1 {
+                    lo + (hi - lo) * i as f64 / (n - 1) as f64
+                } else {
+                    lo
+                };
+            }
+            let p = Point::new(coords);
+            let grad = self.gradient(&p);
+            let norm = grad.dist();
+            let dir = if norm > 0.0 {
+                &grad * (-1.0 / norm)
+            } else {
+                &grad * 0.0
+            };
+            positions.push(p);
+            directions.push(dir);
+        }
+        FlowField {
+            positions,
+            directions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn potential_and_gradient() {
+        let field = PotentialField::new(Point::new(vec![0.0, 0.0]), vec![]);
+        let p = Point::new(vec![3.0, 4.0]);
+        assert_eq!(field.potential(&p), 12.5);
+        assert_eq!(field.gradient(&p).data(), &[3.0, 4.0]);
+    }
+
+    #[test]
+    fn gradient_matches_finite_difference() {
+        let field =
+            PotentialField::new(Point::new(vec![5.0, 0.0]), vec![Point::new(vec![2.0, 0.3])])
+                .influence_radius(2.0)
+                .repulsive_gain(3.0);
+        let p = Point::new(vec![1.5, 0.0]);
+        let grad = field.gradient(&p);
+        let h = 1e-6;
+        for axis in 0..2 {
+            let mut a = p.clone();
+            let mut b = p.clone();
+            a.as_mut_slice()[axis] += h;
+            b.as_mut_slice()[axis] -= h;
+            let fd = (field.potential(&a) - field.potential(&b)) / (2.0 * h);
+            assert!((fd - grad.data()[axis]).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn follow_around_obstacle() {
+        let field = PotentialField::new(
+            Point::new(vec![10.0, 0.0]),
+            vec![Point::new(vec![5.0, 0.5])],
+        )
+        .influence_radius(2.0)
+        .repulsive_gain(5.0);
+        let result = field.follow(&Point::new(vec![0.0, 0.0]), 0.1, 0.05, 1000);
+        assert!(result.reached);
+        let closest = result
+            .path
+            .iter()
+            .map(|p| distance(p, &Point::new(vec![5.0, 0.5])))
+            .fold(f64::INFINITY, f64::min);
+        assert!(closest > 0.5);
+    }
+
+    #[test]
+    fn follow_stuck_in_minimum() {
+        // An obstacle directly between start and goal cancels the pull.
+        let field = PotentialField::new(
+            Point::new(vec![10.0, 0.0]),
+            vec![Point::new(vec![5.0, 0.0])],
+        )
+        .influence_radius(3.0)
+        .repulsive_gain(50.0);
+        let result = field.follow(&Point::new(vec![0.0, 0.0]), 0.1, 0.05, 1000);
+        assert!(!result.reached);
+    }
+
+    #[test]
+    fn flow_field() {
+        let field = PotentialField::new(Point::new(vec![0.0, 0.0]), vec![]);
+        let flow = field.flow_field(
+            &Point::new(vec![-1.0, -1.0]),
+            &Point::new(vec![1.0, 1.0]),
+            &[3, 3],
+        );
+        assert_eq!(flow.positions.len(), 9);
+        assert_eq!(flow.positions[5].data(), &[0.0, 1.0]);
+        assert_eq!(flow.directions[5].data(), &[0.0, -1.0]);
+        // At the goal there's no direction to go.
+        assert_eq!(flow.directions[4].data(), &[0.0, 0.0]);
+    }
+}
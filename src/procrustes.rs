@@ -0,0 +1,256 @@
+// Procrustes superimposition of landmark configurations.
+//
+// A shape is a `PointCloud` of landmarks, the i-th landmark of every shape
+// marking the same anatomical or geometric feature. `superimpose` finds the
+// rigid motion (optionally with a uniform scale) that best lays one shape
+// over another in the least-squares sense.
+//
+// `generalized` aligns many shapes at once (Gower, 1975): every shape is
+// centred and scaled to unit centroid size, then rotated in turn onto the
+// running mean until the mean stops moving. Each new mean is rescaled to
+// unit size and rotated back onto the previous one, so the consensus can't
+// drift or shrink. What remains between the aligned shapes is shape
+// variation alone, ready for statistics on the landmark coordinates.
+
+use crate::PointCloud;
+use crate::linalg::Matrix;
+use crate::transform::Transform;
+
+/// The outcome of [`generalized`].
+#[derive(Debug, Clone)]
+pub struct Gpa {
+    /// Every input shape centred, scaled to unit centroid size and rotated
+    /// onto the consensus.
+    pub aligned: Vec<PointCloud<f64>>,
+    /// The consensus: the mean of the aligned shapes, at unit size.
+    pub mean: PointCloud<f64>,
+    /// The similarity transform taking each input shape to its aligned one.
+    pub transforms: Vec<Transform>,
+    pub iterations: usize,
+}
+
+/// The transform best taking `source` onto `target`, minimising the summed
+/// squared distances between matching landmarks: a rotation and
+/// translation, with a uniform scale too if `scale` is set. Panics unless
+/// the shapes have the same number of landmarks and dimension.
+pub fn superimpose(source: &PointCloud<f64>, target: &PointCloud<f64>, scale: bool) -> Transform {
+    assert_eq!(source.dim(), target.dim(), "shape dimensions differ");
+    assert_eq!(
+        source.len(),
+        target.len(),
+        "shapes must have the same landmarks"
+    );
+    let dim = source.dim();
+    let (cs, ct) = (centroid(source), centroid(target));
+    // Cross-covariance of target against source offsets.
+    let mut m = Matrix::zeros(dim, dim);
+    let mut spread = 0.0;
+    for (p, q) in source.iter().zip(target.iter()) {
+        for r in 0..dim {
+            spread += (p[r] - cs[r]) * (p[r] - cs[r]);
+            for c in 0..dim {
+                m[(r, c)] += (q[r] - ct[r]) * (p[c] - cs[c]);
+            }
+        }
+    }
+    let rotation = m.nearest_rotation();
+    // The optimal scale is trace(R^T M) / |source|^2.
+    let s = if scale && spread > 0.0 {
+        (0..dim)
+            .flat_map(|r| (0..dim).map(move |c| (r, c)))
+            .map(|(r, c)| rotation[(r, c)] * m[(r, c)])
+            .sum::<f64>()
+            / spread
+    } else {
+        1.0
+    };
+    let linear: Vec<Vec<f64>> = rotation
+        .to_rows()
+        .into_iter()
+        .map(|row| row.into_iter().map(|v| s * v).collect())
+        .collect();
+    let moved = Matrix::from_rows(&linear).mul_vec(&cs);
+    let translation: Vec<f64> = ct.iter().zip(moved).map(|(t, m)| t - m).collect();
+    Transform::new(&linear, &translation).expect("square linear part")
+}
+
+/// Generalised Procrustes analysis of `shapes`, iterating until the
+/// consensus moves less than `tolerance` (in Procrustes distance) or
+/// `max_iter` rounds have run. `None` if there are no shapes or one has all
+/// its landmarks in one place. Panics unless the shapes share their number
+/// of landmarks and dimension.
+pub fn generalized(shapes: &[PointCloud<f64>], tolerance: f64, max_iter: usize) -> Option<Gpa> {
+    let first = shapes.first()?;
+    for s in shapes {
+        assert_eq!(s.dim(), first.dim(), "shape dimensions differ");
+        assert_eq!(s.len(), first.len(), "shapes must have the same landmarks");
+    }
+    let mut aligned = shapes
+        .iter()
+        .map(normalise)
+        .collect::<Option<Vec<PointCloud<f64>>>>()?;
+    let mut mean = aligned[0].clone();
+    let mut iterations = 0;
+    while iterations < max_iter {
+        iterations += 1;
+        for shape in &mut aligned {
+            superimpose(shape, &mean, false).apply_in_place(shape);
+        }
+        let mut next = normalise(&average(&aligned))?;
+        superimpose(&next, &mean, false).apply_in_place(&mut next);
+        let moved = squared_distance(&next, &mean).sqrt();
+        mean = next;
+        if moved <= tolerance {
+            break;
+        }
+    }
+    // A last pass so the shapes sit on the final consensus.
+    for shape in &mut aligned {
+        superimpose(shape, &mean, false).apply_in_place(shape);
+    }
+    let transforms = shapes
+        .iter()
+        .zip(&aligned)
+        .map(|(s, a)| superimpose(s, a, true))
+        .collect();
+    Some(Gpa {
+        aligned,
+        mean,
+        transforms,
+        iterations,
+    })
+}
+
+fn centroid(cloud: &PointCloud<f64>) -> Vec<f64> {
+    let mut c = vec![0.0; cloud.dim()];
+    for p in cloud.iter() {
+        c.iter_mut()
+            .zip(p)
+            .for_each(|(c, v)| *c += v / cloud.len() as f64);
+    }
+    c
+}
+
+// The shape centred on the origin at unit centroid size; `None` if it has
+// no size.
+pub(crate) fn normalise(cloud: &PointCloud<f64>) -> Option<PointCloud<f64>> {
+    let c = centroid(cloud);
+    let mut data: Vec<f64> = cloud
+        .iter()
+        .flat_map(|p| p.iter().zip(&c).map(|(v, c)| v - c))
+        .collect();
+    let size = data.iter().map(|v| v * v).sum::<f64>().sqrt();
+    if size == 0.0 || !size.is_finite() {
+        return None;
+    }
+    data.iter_mut().for_each(|v| *v /= size);
+    Some(PointCloud::from_flat_vec(data, cloud.dim()).expect("dimension unchanged"))
+}
+
+fn average(shapes: &[PointCloud<f64>]) -> PointCloud<f64> {
+    let mut data = vec![0.0; shapes[0].as_flat().len()];
+    for s in shapes {
+        data.iter_mut()
+            .zip(s.as_flat())
+            .for_each(|(d, v)| *d += v / shapes.len() as f64);
+    }
+    PointCloud::from_flat_vec(data, shapes[0].dim()).expect("dimension unchanged")
+}
+
+fn squared_distance(a: &PointCloud<f64>, b: &PointCloud<f64>) -> f64 {
+    a.as_flat()
+        .iter()
+        .zip(b.as_flat())
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn triangle() -> PointCloud<f64> {
+        PointCloud::from_flat(&[0.0, 0.0, 4.0, 0.0, 0.0, 3.0], 2).unwrap()
+    }
+
+    fn moved(cloud: &PointCloud<f64>, angle: f64, scale: f64, shift: [f64; 2]) -> PointCloud<f64> {
+        let t = Transform::rotation_2d(angle)
+            .then(&Transform::scaling(&[scale, scale]))
+            .then(&Transform::translation(&shift));
+        let mut out = cloud.clone();
+        t.apply_in_place(&mut out);
+        out
+    }
+
+    #[test]
+    fn superimposes_similar_shapes() {
+        let a = triangle();
+        let b = moved(&a, 1.1, 2.5, [3.0, -1.0]);
+        let mut fitted = a.clone();
+        superimpose(&a, &b, true).apply_in_place(&mut fitted);
+        assert!(squared_distance(&fitted, &b) < 1e-20);
+        // Without scaling the fit is only as close as a rigid motion allows.
+        let rigid = superimpose(&a, &b, false);
+        assert!(rigid.is_rigid());
+        let mut fitted = a.clone();
+        rigid.apply_in_place(&mut fitted);
+        assert!(squared_distance(&fitted, &b) > 1.0);
+    }
+
+    #[test]
+    fn aligns_many_shapes_to_a_consensus() {
+        let mut rng = Rng::seed_from_u64(279);
+        let base =
+            PointCloud::from_flat(&[0.0, 0.0, 2.0, 0.0, 2.0, 1.0, 0.5, 1.5, 0.0, 1.0], 2).unwrap();
+        let shapes: Vec<PointCloud<f64>> = (0..8)
+            .map(|_| {
+                let mut noisy = base.clone();
+                for i in 0..noisy.len() {
+                    noisy.update(i, |p| p.iter_mut().for_each(|v| *v += 0.02 * rng.normal()));
+                }
+                let angle = rng.next_f64() * 6.0;
+                let scale = 0.5 + rng.next_f64() * 3.0;
+                moved(
+                    &noisy,
+                    angle,
+                    scale,
+                    [rng.normal() * 5.0, rng.normal() * 5.0],
+                )
+            })
+            .collect();
+        let gpa = generalized(&shapes, 1e-12, 100).unwrap();
+        assert!(gpa.iterations < 100);
+        // Unit-size consensus shaped like the base.
+        assert!((squared_distance(&gpa.mean, &normalise(&gpa.mean).unwrap())) < 1e-20);
+        let base_fit = {
+            let unit = normalise(&base).unwrap();
+            let mut b = unit.clone();
+            superimpose(&unit, &gpa.mean, false).apply_in_place(&mut b);
+            b
+        };
+        assert!(squared_distance(&base_fit, &gpa.mean) < 1e-3);
+        // Each transform takes its shape onto the aligned copy.
+        for ((s, a), t) in shapes.iter().zip(&gpa.aligned).zip(&gpa.transforms) {
+            let mut s = s.clone();
+            t.apply_in_place(&mut s);
+            assert!(squared_distance(&s, a) < 1e-20);
+            // Only the landmark noise is left between the shapes.
+            assert!(squared_distance(a, &gpa.mean) < 1e-2);
+        }
+    }
+
+    #[test]
+    fn degenerate_input() {
+        assert!(generalized(&[], 1e-9, 10).is_none());
+        let point = PointCloud::from_flat(&[1.0, 1.0, 1.0, 1.0, 1.0, 1.0], 2).unwrap();
+        assert!(generalized(&[triangle(), point], 1e-9, 10).is_none());
+        assert!(
+            std::panic::catch_unwind(|| {
+                let short = PointCloud::from_flat(&[0.0, 0.0, 1.0, 0.0], 2).unwrap();
+                generalized(&[triangle(), short], 1e-9, 10)
+            })
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,103 @@
+// Homogeneous coordinates and perspective projection down dimensions.
+//
+// `project` drops one axis at a time, the last, by a pinhole projection
+// from an eye on that axis: with the eye at distance `d`, the point `x`
+// lands at `x_i d / (d - x_n)` on the remaining axes, so nearer points
+// spread out and farther ones shrink towards the centre. Repeating it
+// takes a 4D or 5D shape down to 3D or 2D, the usual way to draw them.
+
+use crate::{Point, Storage};
+
+/// The eye distance `project` uses, comfortable for shapes around the unit
+/// sphere.
+pub const DEFAULT_VIEW_DISTANCE: f64 = 3.0;
+
+impl<T, S: Storage<T>> Point<T, S>
+where
+    T: Into<f64> + Copy,
+{
+    /// The point with a trailing weight of 1 appended.
+    pub fn to_homogeneous(&self) -> Point<f64> {
+        self.iter()
+            .map(|&v| v.into())
+            .chain([1.0])
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    /// Divides by the trailing weight and drops it; `None` for a point at
+    /// infinity (weight 0) or with no coordinates besides the weight.
+    pub fn from_homogeneous(&self) -> Option<Point<f64>> {
+        let (&w, rest) = self.as_slice().split_last()?;
+        let w = w.into();
+        if w == 0.0 || rest.is_empty() {
+            return None;
+        }
+        Some(
+            rest.iter()
+                .map(|&v| v.into() / w)
+                .collect::<Vec<_>>()
+                .into(),
+        )
+    }
+
+    /// Perspective projection to `target_dim` dimensions from
+    /// [`DEFAULT_VIEW_DISTANCE`]; see [`Point::project_from`].
+    pub fn project(&self, target_dim: usize) -> Option<Point<f64>> {
+        self.project_from(target_dim, DEFAULT_VIEW_DISTANCE)
+    }
+
+    /// Perspective projection to `target_dim` dimensions, dropping the last
+    /// axis each step with the eye `distance` along it. `None` if the point
+    /// reaches the eye's plane or behind it on any step. Panics unless
+    /// `1 <= target_dim <= dim`.
+    pub fn project_from(&self, target_dim: usize, distance: f64) -> Option<Point<f64>> {
+        assert!(
+            (1..=self.dim()).contains(&target_dim),
+            "can only project to a lower, non-zero dimension"
+        );
+        let mut x: Vec<f64> = self.iter().map(|&v| v.into()).collect();
+        while x.len() > target_dim {
+            let depth = distance - x.pop().expect("dimension above target");
+            if depth <= 0.0 {
+                return None;
+            }
+            let k = distance / depth;
+            x.iter_mut().for_each(|v| *v *= k);
+        }
+        Some(x.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Point;
+
+    #[test]
+    fn homogeneous_round_trip() {
+        let p = Point::new(vec![1, 2, 3]);
+        let h = p.to_homogeneous();
+        assert_eq!(h.data(), [1.0, 2.0, 3.0, 1.0]);
+        assert_eq!(h.from_homogeneous().unwrap().data(), [1.0, 2.0, 3.0]);
+        let scaled = Point::new(vec![2.0, 4.0, 2.0]);
+        assert_eq!(scaled.from_homogeneous().unwrap().data(), [1.0, 2.0]);
+        assert!(Point::new(vec![1.0, 0.0]).from_homogeneous().is_none());
+        assert!(Point::new(vec![1.0]).from_homogeneous().is_none());
+    }
+
+    #[test]
+    fn chained_perspective() {
+        // A tesseract corner: nearer in w spreads out, farther shrinks.
+        let near = Point::new(vec![1.0, 1.0, 1.0, 1.0]);
+        let far = Point::new(vec![1.0, 1.0, 1.0, -1.0]);
+        assert_eq!(near.project_from(3, 2.0).unwrap().data(), [2.0, 2.0, 2.0]);
+        assert_eq!(far.project_from(3, 2.0).unwrap().data(), [2.0 / 3.0; 3]);
+        // Down to the plane in two steps: 4D -> 3D -> 2D.
+        let flat = near.project_from(2, 3.0).unwrap();
+        assert_eq!(flat.data(), [3.0, 3.0]);
+        assert_eq!(near.project(4).unwrap().data(), near.data());
+        assert_eq!(near.project(3).unwrap().dim(), 3);
+        // At the eye's plane there is no image.
+        assert!(near.project_from(3, 1.0).is_none());
+    }
+}
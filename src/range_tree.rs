@@ -0,0 +1,203 @@
+// Multi-level range trees for orthogonal range queries on static point sets.
+//
+// The first level sorts the points by the first coordinate and keeps a
+// balanced segment tree over that order; every segment-tree node holds a range
+// tree of its points on the remaining coordinates. A box query splits into
+// O(log n) canonical nodes per level, so counting takes O(log^d n) and
+// reporting O(log^d n + k), at the price of O(n log^(d-1) n) memory.
+
+use crate::Point;
+
+#[derive(Debug, Clone)]
+struct Level {
+    axis: usize,
+    order: Vec<usize>,
+    keys: Vec<f64>,
+    // Segment tree over `order` for the next axis; `None` on the last axis.
+    tree: Option<Box<Segment>>,
+}
+
+#[derive(Debug, Clone)]
+struct Segment {
+    lo: usize,
+    hi: usize,
+    assoc: Level,
+    children: Option<(Box<Segment>, Box<Segment>)>,
+}
+
+impl Level {
+    fn build(points: &[Vec<f64>], mut order: Vec<usize>, axis: usize) -> Self {
+        order.sort_by(|&a, &b| points[a][axis].total_cmp(&points[b][axis]).then(a.cmp(&b)));
+        let keys = order.iter().map(|&i| points[i][axis]).collect();
+        let dim = points.first().map_or(0, |p| p.len());
+        let tree = (axis + 1 < dim && !order.is_empty())
+            .then(|| Box::new(Segment::build(points, &order, 0, order.len(), axis + 1)));
+        Level {
+            axis,
+            order,
+            keys,
+            tree,
+        }
+    }
+
+    fn query<F: FnMut(&[usize])>(&self, lower: &[f64], upper: &[f64], visit: &mut F) {
+        let l = self.keys.partition_point(|&k| k < lower[self.axis]);
+        let r = self.keys.partition_point(|&k| k <= upper[self.axis]);
+        if l >= r {
+            return;
+        }
+        match &self.tree {
+            None => visit(&self.order[l..r]),
+            Some(tree) => tree.query(l, r, lower, upper, visit),
+        }
+    }
+}
+
+impl Segment {
+    fn build(points: &[Vec<f64>], order: &[usize], lo: usize, hi: usize, axis: usize) -> Self {
+        let assoc = Level::build(points, order[lo..hi].to_vec(), axis);
+        let children = (hi - lo > 1).then(|| {
+            let mid = (lo + hi) / 2;
+            (
+                Box::new(Segment::build(points, order, lo, mid, axis)),
+                Box::new(Segment::build(points, order, mid, hi, axis)),
+            )
+        });
+        Segment {
+            lo,
+            hi,
+            assoc,
+            children,
+        }
+    }
+
+    fn query<F: FnMut(&[usize])>(
+        &self,
+        l: usize,
+        r: usize,
+        lower: &[f64],
+        upper: &[f64],
+        visit: &mut F,
+    ) {
+        if r <= self.lo || self.hi <= l {
+            return;
+        }
+        if l <= self.lo && self.hi <= r {
+            self.assoc.query(lower, upper, visit);
+        } else if let Some((left, right)) = &self.children {
+            left.query(l, r, lower, upper, visit);
+            right.query(l, r, lower, upper, visit);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RangeTree {
+    dim: usize,
+    root: Level,
+}
+
+impl RangeTree {
+    /// Builds the tree. Panics if the points don't share a dimension.
+    pub fn new<T>(points: &[Point<T>]) -> Self
+    where
+        T: Into<f64> + Copy,
+    {
+        let dim = points.first().map_or(0, |p| p.dim());
+        let coords: Vec<Vec<f64>> = points
+            .iter()
+            .map(|p| {
+                assert_eq!(p.dim(), dim, "points must share a dimension");
+                p.p.iter().map(|&v| v.into()).collect()
+            })
+            .collect();
+        RangeTree {
+            dim,
+            root: Level::build(&coords, (0..coords.len()).collect(), 0),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.root.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.order.is_empty()
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Number of points in the closed box `[lower, upper]`.
+    pub fn count(&self, lower: &[f64], upper: &[f64]) -> usize {
+        let mut n = 0;
+        if !self.is_empty() {
+            self.root.query(lower, upper, &mut |ids| n += ids.len());
+        }
+        n
+    }
+
+    /// Indices of the points in the closed box `[lower, upper]`, ascending.
+    pub fn report(&self, lower: &[f64], upper: &[f64]) -> Vec<usize> {
+        let mut out = Vec::new();
+        if !self.is_empty() {
+            self.root
+                .query(lower, upper, &mut |ids| out.extend_from_slice(ids));
+        }
+        out.sort_unstable();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn brute(pts: &[Point<f64>], lower: &[f64], upper: &[f64]) -> Vec<usize> {
+        (0..pts.len())
+            .filter(|&i| (0..lower.len()).all(|k| lower[k] <= pts[i][k] && pts[i][k] <= upper[k]))
+            .collect()
+    }
+
+    #[test]
+    fn one_dimension() {
+        let pts: Vec<Point<i32>> = [5, 1, 3, 3, 9]
+            .iter()
+            .map(|&x| Point::new(vec![x]))
+            .collect();
+        let tree = RangeTree::new(&pts);
+        assert_eq!(tree.count(&[3.0], &[5.0]), 3);
+        assert_eq!(tree.report(&[3.0], &[5.0]), vec![0, 2, 3]);
+        assert_eq!(tree.count(&[10.0], &[20.0]), 0);
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = Rng::seed_from_u64(7);
+        for dim in 2..=3 {
+            let pts: Vec<Point<f64>> = (0..200)
+                .map(|_| Point::new((0..dim).map(|_| rng.below(20) as f64).collect()))
+                .collect();
+            let tree = RangeTree::new(&pts);
+            assert_eq!(tree.dim(), dim);
+            for _ in 0..30 {
+                let a: Vec<f64> = (0..dim).map(|_| rng.range_f64(-1.0, 20.0)).collect();
+                let b: Vec<f64> = (0..dim).map(|_| rng.range_f64(-1.0, 20.0)).collect();
+                let lower: Vec<f64> = a.iter().zip(&b).map(|(x, y)| x.min(*y)).collect();
+                let upper: Vec<f64> = a.iter().zip(&b).map(|(x, y)| x.max(*y)).collect();
+                let expected = brute(&pts, &lower, &upper);
+                assert_eq!(tree.count(&lower, &upper), expected.len());
+                assert_eq!(tree.report(&lower, &upper), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let tree = RangeTree::new::<f64>(&[]);
+        assert!(tree.is_empty());
+        assert_eq!(tree.count(&[0.0], &[1.0]), 0);
+    }
+}
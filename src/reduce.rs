@@ -0,0 +1,338 @@
+// Dimensionality reduction by principal component analysis.
+//
+// `pca` centres the points and finds the orthogonal axes along which they
+// spread the most, the leading eigenvectors of their covariance. When only
+// a few axes are dropped the covariance is decomposed outright. When many
+// are, as when taking a 768-dimensional embedding down to 2D for a plot,
+// building and decomposing the full covariance would cost far more than the
+// answer needs, so the leading axes come from randomised subspace iteration
+// instead (Halko, Martinsson and Tropp): a few extra random directions are
+// multiplied through the data until the subspace they span settles, and the
+// axes are read off the small matrix that remains. Either way the cost of a
+// pass over the data is linear in its size.
+
+use crate::linalg::{Matrix, dot, norm};
+use crate::rng::Rng;
+use crate::{Point, PointCloud, Storage};
+
+// Extra directions carried through subspace iteration to speed it up.
+const OVERSAMPLE: usize = 10;
+const MAX_ITERATIONS: usize = 200;
+const SEED: u64 = 0x5ca1ab1e;
+
+#[derive(Debug, Clone)]
+pub struct Pca {
+    pub mean: Point<f64>,
+    /// Unit principal axes, by decreasing variance.
+    pub axes: Vec<Point<f64>>,
+    /// The variance of the points along each axis.
+    pub explained_variance: Vec<f64>,
+    /// The variance summed over all the original axes.
+    pub total_variance: f64,
+    /// The input points in the coordinates of the principal axes.
+    pub projected: PointCloud<f64>,
+}
+
+impl Pca {
+    /// The fraction of the total variance along each axis.
+    pub fn explained_variance_ratio(&self) -> Vec<f64> {
+        self.explained_variance
+            .iter()
+            .map(|v| {
+                if self.total_variance > 0.0 {
+                    v / self.total_variance
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+
+    /// The coordinates of `p` along the principal axes. Panics if its
+    /// dimension differs from the data's.
+    pub fn project<T, S>(&self, p: &Point<T, S>) -> Point<f64>
+    where
+        T: Into<f64> + Copy,
+        S: Storage<T>,
+    {
+        let p = p.as_slice();
+        assert_eq!(p.len(), self.mean.dim(), "point and data dimensions differ");
+        let centred: Vec<f64> = p
+            .iter()
+            .zip(self.mean.iter())
+            .map(|(&v, m)| v.into() - m)
+            .collect();
+        Point::new(self.axes.iter().map(|a| dot(a.data(), &centred)).collect())
+    }
+
+    /// The point with `coordinates` along the principal axes, back in the
+    /// original space. Panics unless there is one coordinate per axis.
+    pub fn reconstruct(&self, coordinates: &[f64]) -> Point<f64> {
+        assert_eq!(
+            coordinates.len(),
+            self.axes.len(),
+            "one coordinate per axis"
+        );
+        let mut p = self.mean.data().to_vec();
+        for (a, &c) in self.axes.iter().zip(coordinates) {
+            p.iter_mut().zip(a.iter()).for_each(|(p, a)| *p += c * a);
+        }
+        Point::new(p)
+    }
+}
+
+/// The principal components of `points`, keeping the `target_dim` axes of
+/// largest variance, with a fixed seed for the random start of subspace
+/// iteration; see [`pca_with_rng`]. `None` for no points. Panics unless the
+/// points share a dimension and `target_dim` is between one and that
+/// dimension.
+pub fn pca<T, P>(points: impl IntoIterator<Item = P>, target_dim: usize) -> Option<Pca>
+where
+    T: Into<f64> + Copy,
+    P: AsRef<[T]>,
+{
+    pca_with_rng(points, target_dim, &mut Rng::seed_from_u64(SEED))
+}
+
+/// Like [`pca`], drawing the random start of subspace iteration from `rng`.
+pub fn pca_with_rng<T, P>(
+    points: impl IntoIterator<Item = P>,
+    target_dim: usize,
+    rng: &mut Rng,
+) -> Option<Pca>
+where
+    T: Into<f64> + Copy,
+    P: AsRef<[T]>,
+{
+    let mut rows: Vec<Vec<f64>> = points
+        .into_iter()
+        .map(|p| p.as_ref().iter().map(|&v| v.into()).collect())
+        .collect();
+    let dim = rows.first()?.len();
+    assert!(
+        rows.iter().all(|r| r.len() == dim),
+        "points must share a dimension"
+    );
+    assert!(
+        (1..=dim).contains(&target_dim),
+        "target dimension must be between one and the data's"
+    );
+    let n = rows.len() as f64;
+    let mut mean = vec![0.0; dim];
+    for r in &rows {
+        mean.iter_mut().zip(r).for_each(|(m, v)| *m += v / n);
+    }
+    for r in &mut rows {
+        r.iter_mut().zip(&mean).for_each(|(v, m)| *v -= m);
+    }
+    let total_variance = rows.iter().map(|r| dot(r, r)).sum::<f64>() / n;
+
+    let (values, vectors) = if target_dim + OVERSAMPLE >= dim {
+        let mut cov = Matrix::zeros(dim, dim);
+        for r in &rows {
+            for i in 0..dim {
+                for j in 0..dim {
+                    cov[(i, j)] += r[i] * r[j] / n;
+                }
+            }
+        }
+        let (values, vectors) = cov.symmetric_eigen();
+        let axes = (0..dim).map(|k| vectors.column(k)).collect();
+        (values, axes)
+    } else {
+        subspace_iteration(&rows, target_dim, target_dim + OVERSAMPLE, rng)
+    };
+
+    let axes: Vec<Point<f64>> = vectors
+        .into_iter()
+        .take(target_dim)
+        .map(|mut a| {
+            // Point the largest component of every axis the positive way.
+            let big = a
+                .iter()
+                .copied()
+                .fold(0.0f64, |m, v| if v.abs() > m.abs() { v } else { m });
+            if big < 0.0 {
+                a.iter_mut().for_each(|v| *v = -*v);
+            }
+            Point::new(a)
+        })
+        .collect();
+    let projected: Vec<f64> = rows
+        .iter()
+        .flat_map(|r| axes.iter().map(move |a| dot(a.data(), r)))
+        .collect();
+    Some(Pca {
+        mean: Point::new(mean),
+        axes,
+        explained_variance: values
+            .into_iter()
+            .take(target_dim)
+            .map(|v| v.max(0.0))
+            .collect(),
+        total_variance,
+        projected: PointCloud::from_flat_vec(projected, target_dim)
+            .expect("target dimension is positive"),
+    })
+}
+
+// The leading eigenpairs of the covariance of the centred `rows`, by
+// descending eigenvalue, found without forming the covariance: `width` of
+// them, iterating until the first `wanted` eigenvalues settle.
+fn subspace_iteration(
+    rows: &[Vec<f64>],
+    wanted: usize,
+    width: usize,
+    rng: &mut Rng,
+) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let dim = rows[0].len();
+    let n = rows.len() as f64;
+    // The covariance times each of `basis`.
+    let times_cov = |basis: &[Vec<f64>]| -> Vec<Vec<f64>> {
+        let mut out = vec![vec![0.0; dim]; basis.len()];
+        for r in rows {
+            for (o, b) in out.iter_mut().zip(basis) {
+                let c = dot(r, b) / n;
+                o.iter_mut().zip(r).for_each(|(o, v)| *o += c * v);
+            }
+        }
+        out
+    };
+    let mut basis: Vec<Vec<f64>> = (0..width)
+        .map(|_| (0..dim).map(|_| rng.normal()).collect())
+        .collect();
+    orthonormalise(&mut basis);
+    let mut previous: Vec<f64> = Vec::new();
+    let mut ritz = (Vec::new(), Matrix::zeros(width, width));
+    for _ in 0..MAX_ITERATIONS {
+        let image = times_cov(&basis);
+        // Rayleigh-Ritz: the covariance restricted to the subspace.
+        let small = Matrix::from_rows(
+            &basis
+                .iter()
+                .map(|b| image.iter().map(|c| dot(b, c)).collect())
+                .collect::<Vec<_>>(),
+        );
+        ritz = small.symmetric_eigen();
+        let scale = ritz.0[0].abs().max(f64::MIN_POSITIVE);
+        let settled = previous.len() == width
+            && previous[..wanted]
+                .iter()
+                .zip(&ritz.0)
+                .all(|(a, b)| (a - b).abs() <= 1e-12 * scale);
+        if settled {
+            break;
+        }
+        previous = ritz.0.clone();
+        basis = image;
+        orthonormalise(&mut basis);
+    }
+    let (values, rotation) = ritz;
+    let vectors = (0..width)
+        .map(|k| {
+            let mut v = vec![0.0; dim];
+            for (j, b) in basis.iter().enumerate() {
+                v.iter_mut()
+                    .zip(b)
+                    .for_each(|(v, b)| *v += rotation[(j, k)] * b);
+            }
+            v
+        })
+        .collect();
+    (values, vectors)
+}
+
+// Modified Gram-Schmidt; a vector that collapses is replaced by a basis
+// direction the others miss.
+fn orthonormalise(vectors: &mut [Vec<f64>]) {
+    let dim = vectors[0].len();
+    for i in 0..vectors.len() {
+        let mut fallback = 0;
+        loop {
+            let (done, rest) = vectors.split_at_mut(i);
+            let v = &mut rest[0];
+            for u in done.iter() {
+                let k = dot(v, u);
+                v.iter_mut().zip(u).for_each(|(v, u)| *v -= k * u);
+            }
+            let len = norm(v);
+            if len > 1e-10 {
+                v.iter_mut().for_each(|x| *x /= len);
+                break;
+            }
+            *v = vec![0.0; dim];
+            v[fallback] = 1.0;
+            fallback += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_spread() {
+        // Points along (1, 1) with a little spread across it.
+        let pts: Vec<Point<f64>> = (-5..=5)
+            .flat_map(|i| {
+                let t = i as f64;
+                [
+                    Point::new(vec![t + 0.1, t - 0.1]),
+                    Point::new(vec![t - 0.1, t + 0.1]),
+                ]
+            })
+            .collect();
+        let p = pca(&pts, 1).unwrap();
+        let h = 0.5f64.sqrt();
+        assert!(p.axes[0].iter().all(|v| (v - h).abs() < 1e-12));
+        assert!(p.mean.iter().all(|v| v.abs() < 1e-12));
+        assert!((p.explained_variance[0] - 20.0).abs() < 1e-9);
+        assert!(p.explained_variance_ratio()[0] > 0.999);
+        assert_eq!((p.projected.len(), p.projected.dim()), (22, 1));
+        let back = p.reconstruct(&[2f64.sqrt() * 3.0]);
+        assert!((back[0] - 3.0).abs() < 1e-12 && (back[1] - 3.0).abs() < 1e-12);
+        assert!((p.project(&Point::new(vec![1.0, 1.0]))[0] - 2f64.sqrt()).abs() < 1e-12);
+        assert!(std::panic::catch_unwind(|| pca(&pts, 3)).is_err());
+        assert!(pca(Vec::<Vec<f64>>::new(), 1).is_none());
+    }
+
+    #[test]
+    fn high_dimensional_data() {
+        // 100-dimensional points on a noisy plane: two strong directions.
+        let mut rng = Rng::seed_from_u64(280);
+        let dim = 100;
+        let u: Vec<f64> = (0..dim)
+            .map(|i| if i % 2 == 0 { 0.1 } else { -0.1 })
+            .collect();
+        let v: Vec<f64> = (0..dim).map(|i| if i < 50 { 0.1 } else { -0.1 }).collect();
+        let pts: Vec<Vec<f64>> = (0..300)
+            .map(|_| {
+                let (a, b) = (3.0 * rng.normal(), rng.normal());
+                (0..dim)
+                    .map(|i| a * u[i] + b * v[i] + 0.01 * rng.normal())
+                    .collect()
+            })
+            .collect();
+        let fast = pca(&pts, 2).unwrap();
+        let exact = pca(&pts, dim - OVERSAMPLE).unwrap();
+        for k in 0..2 {
+            let rel = (fast.explained_variance[k] - exact.explained_variance[k]).abs()
+                / exact.explained_variance[k];
+            assert!(rel < 1e-9);
+            assert!((dot(fast.axes[k].data(), exact.axes[k].data()).abs() - 1.0).abs() < 1e-9);
+        }
+        assert!(fast.explained_variance[0] > 8.0 && fast.explained_variance[1] > 0.8);
+        assert!(fast.explained_variance_ratio().iter().sum::<f64>() > 0.99);
+        assert!((fast.total_variance - exact.total_variance).abs() < 1e-12);
+
+        // Any start converges to the same axes.
+        let other = pca_with_rng(&pts, 2, &mut Rng::seed_from_u64(7)).unwrap();
+        for k in 0..2 {
+            let rel = (other.explained_variance[k] - fast.explained_variance[k]).abs()
+                / fast.explained_variance[k];
+            assert!(rel < 1e-9);
+            assert!((dot(other.axes[k].data(), fast.axes[k].data()) - 1.0).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,422 @@
+// 3D rotations as unit quaternions, and averages of sets of rotations.
+//
+// `q` and `-q` are the same rotation, so anything that blends quaternions
+// first flips them into one hemisphere. There are two usual notions of the
+// mean of rotations. The chordal mean minimises the summed squared
+// Frobenius distances between matrices; it is the closest rotation to the
+// arithmetic mean of the matrices, which works in any dimension, and for
+// quaternions it is the dominant eigenvector of `sum w q q^T` (Markley et
+// al. 2007), which needs no sign flipping. The geodesic (Karcher) mean
+// minimises the summed squared rotation angles instead; it has no closed
+// form and is found by repeatedly averaging the rotations' offsets from the
+// current estimate in the tangent space. The two agree closely when the
+// rotations are within a few tens of degrees of each other.
+
+use std::ops::{Mul, Neg};
+
+use crate::Point3;
+use crate::linalg::Matrix;
+use crate::transform::closest_rotation;
+
+const ITERATIONS: usize = 100;
+
+/// A quaternion `w + xi + yj + zk`; unit ones stand for rotations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub const IDENTITY: Quaternion = Quaternion::new(1.0, 0.0, 0.0, 0.0);
+
+    pub const fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Quaternion { w, x, y, z }
+    }
+
+    /// Rotation by `angle` radians about `axis`, counter-clockwise looking
+    /// down the axis; `None` if the axis is zero.
+    pub fn from_axis_angle(axis: &Point3<f64>, angle: f64) -> Option<Self> {
+        let len = axis.dist();
+        if len == 0.0 {
+            return None;
+        }
+        let (s, c) = (angle / 2.0).sin_cos();
+        let k = s / len;
+        Some(Quaternion::new(c, k * axis.x(), k * axis.y(), k * axis.z()))
+    }
+
+    /// The rotation of a 3x3 rotation matrix, given row by row. Panics if it
+    /// isn't 3x3.
+    pub fn from_matrix(rows: &[Vec<f64>]) -> Self {
+        assert!(
+            rows.len() == 3 && rows.iter().all(|r| r.len() == 3),
+            "rotation matrix must be 3x3"
+        );
+        let m = |i: usize, j: usize| rows[i][j];
+        let trace = m(0, 0) + m(1, 1) + m(2, 2);
+        // Shepperd's method: divide by the largest of the four components.
+        let q = if trace > 0.0 {
+            let s = 2.0 * (1.0 + trace).sqrt();
+            Quaternion::new(
+                s / 4.0,
+                (m(2, 1) - m(1, 2)) / s,
+                (m(0, 2) - m(2, 0)) / s,
+                (m(1, 0) - m(0, 1)) / s,
+            )
+        } else if m(0, 0) >= m(1, 1) && m(0, 0) >= m(2, 2) {
+            let s = 2.0 * (1.0 + m(0, 0) - m(1, 1) - m(2, 2)).sqrt();
+            Quaternion::new(
+                (m(2, 1) - m(1, 2)) / s,
+                s / 4.0,
+                (m(0, 1) + m(1, 0)) / s,
+                (m(0, 2) + m(2, 0)) / s,
+            )
+        } else if m(1, 1) >= m(2, 2) {
+            let s = 2.0 * (1.0 + m(1, 1) - m(0, 0) - m(2, 2)).sqrt();
+            Quaternion::new(
+                (m(0, 2) - m(2, 0)) / s,
+                (m(0, 1) + m(1, 0)) / s,
+                s / 4.0,
+                (m(1, 2) + m(2, 1)) / s,
+            )
+        } else {
+            let s = 2.0 * (1.0 + m(2, 2) - m(0, 0) - m(1, 1)).sqrt();
+            Quaternion::new(
+                (m(1, 0) - m(0, 1)) / s,
+                (m(0, 2) + m(2, 0)) / s,
+                (m(1, 2) + m(2, 1)) / s,
+                s / 4.0,
+            )
+        };
+        q.canonical()
+    }
+
+    /// The rotation matrix, row by row. Assumes a unit quaternion.
+    pub fn to_matrix(&self) -> Vec<Vec<f64>> {
+        let Quaternion { w, x, y, z } = *self;
+        vec![
+            vec![
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+            ],
+            vec![
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+            ],
+            vec![
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ]
+    }
+
+    /// Unit axis and angle in [0, pi]; the x axis for the identity.
+    pub fn axis_angle(&self) -> (Point3<f64>, f64) {
+        let v = self.log();
+        let angle = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        if angle == 0.0 {
+            return (Point3::new(1.0, 0.0, 0.0), 0.0);
+        }
+        (Point3::new(v[0] / angle, v[1] / angle, v[2] / angle), angle)
+    }
+
+    pub fn norm(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    /// `None` for the zero quaternion.
+    pub fn normalize(&self) -> Option<Self> {
+        let n = self.norm();
+        (n > 0.0).then(|| self.scale(1.0 / n))
+    }
+
+    /// The inverse rotation, for unit quaternions.
+    pub fn conjugate(&self) -> Self {
+        Quaternion::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    pub fn dot(&self, other: &Quaternion) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// Rotates `p`. Assumes a unit quaternion.
+    pub fn rotate(&self, p: &Point3<f64>) -> Point3<f64> {
+        let u = [self.x, self.y, self.z];
+        let v = [p.x(), p.y(), p.z()];
+        // v + 2 u x (u x v + w v)
+        let c = cross(u, v);
+        let t = [
+            c[0] + self.w * v[0],
+            c[1] + self.w * v[1],
+            c[2] + self.w * v[2],
+        ];
+        let d = cross(u, t);
+        Point3::new(v[0] + 2.0 * d[0], v[1] + 2.0 * d[1], v[2] + 2.0 * d[2])
+    }
+
+    /// Angle in [0, pi] of the rotation taking `self` to `other`.
+    pub fn angle_to(&self, other: &Quaternion) -> f64 {
+        let d = self.conjugate() * *other;
+        2.0 * (d.x * d.x + d.y * d.y + d.z * d.z).sqrt().atan2(d.w.abs())
+    }
+
+    /// The rotation a fraction `t` of the way from `self` to `other` along
+    /// the shortest arc, at constant angular speed.
+    pub fn slerp(&self, other: &Quaternion, t: f64) -> Quaternion {
+        let (other, d) = self.same_side(other);
+        if d > 0.9995 {
+            // sin(theta) is too small to divide by; the chord is as good.
+            return self.nlerp(&other, t);
+        }
+        let theta = d.acos();
+        let a = ((1.0 - t) * theta).sin() / theta.sin();
+        let b = (t * theta).sin() / theta.sin();
+        self.scale(a) + other.scale(b)
+    }
+
+    /// Normalised linear blend along the shortest arc: cheaper than
+    /// [`Quaternion::slerp`] and on the same path, but faster in the middle.
+    pub fn nlerp(&self, other: &Quaternion, t: f64) -> Quaternion {
+        let (other, _) = self.same_side(other);
+        let q = self.scale(1.0 - t) + other.scale(t);
+        q.normalize().unwrap_or(*self)
+    }
+
+    // `other` or `-other`, whichever is nearer `self`, and the dot product.
+    fn same_side(&self, other: &Quaternion) -> (Quaternion, f64) {
+        let d = self.dot(other);
+        if d < 0.0 { (-*other, -d) } else { (*other, d) }
+    }
+
+    // The sign with `w >= 0`.
+    fn canonical(self) -> Quaternion {
+        if self.w < 0.0 { -self } else { self }
+    }
+
+    fn scale(&self, k: f64) -> Quaternion {
+        Quaternion::new(k * self.w, k * self.x, k * self.y, k * self.z)
+    }
+
+    // Rotation vector: the axis scaled by the angle, in [0, pi].
+    pub(crate) fn log(&self) -> [f64; 3] {
+        let q = self.canonical();
+        let s = (q.x * q.x + q.y * q.y + q.z * q.z).sqrt();
+        if s == 0.0 {
+            return [0.0; 3];
+        }
+        let k = 2.0 * s.atan2(q.w) / s;
+        [k * q.x, k * q.y, k * q.z]
+    }
+
+    pub(crate) fn exp(v: [f64; 3]) -> Quaternion {
+        let angle = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        if angle == 0.0 {
+            return Quaternion::IDENTITY;
+        }
+        let (s, c) = (angle / 2.0).sin_cos();
+        let k = s / angle;
+        Quaternion::new(c, k * v[0], k * v[1], k * v[2])
+    }
+}
+
+impl std::ops::Add for Quaternion {
+    type Output = Quaternion;
+
+    fn add(self, rhs: Quaternion) -> Quaternion {
+        Quaternion::new(
+            self.w + rhs.w,
+            self.x + rhs.x,
+            self.y + rhs.y,
+            self.z + rhs.z,
+        )
+    }
+}
+
+/// The Hamilton product: `a * b` rotates by `b`, then by `a`.
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, rhs: Quaternion) -> Quaternion {
+        let (a, b) = (self, rhs);
+        Quaternion::new(
+            a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+            a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+        )
+    }
+}
+
+impl Neg for Quaternion {
+    type Output = Quaternion;
+
+    fn neg(self) -> Quaternion {
+        self.scale(-1.0)
+    }
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// Weighted chordal mean of unit quaternions, with `w >= 0`; the signs of
+/// the inputs don't matter. `None` if there are none or the weights don't
+/// sum to a positive number. Ties, such as two rotations half a turn apart,
+/// are broken arbitrarily.
+pub fn average_quaternions(rotations: &[(Quaternion, f64)]) -> Option<Quaternion> {
+    if rotations.iter().map(|(_, w)| w).sum::<f64>() <= 0.0 {
+        return None;
+    }
+    let mut m = [[0.0; 4]; 4];
+    for (q, w) in rotations {
+        let v = [q.w, q.x, q.y, q.z];
+        for i in 0..4 {
+            for j in 0..4 {
+                m[i][j] += w * v[i] * v[j];
+            }
+        }
+    }
+    // Power iteration, started from the heaviest rotation, which is usually
+    // close to the answer already.
+    let start = rotations
+        .iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(q, _)| *q)?;
+    let mut v = start.normalize()?;
+    for _ in 0..ITERATIONS {
+        let a = [v.w, v.x, v.y, v.z];
+        let mv: Vec<f64> = m
+            .iter()
+            .map(|row| row.iter().zip(&a).map(|(x, y)| x * y).sum())
+            .collect();
+        let next = Quaternion::new(mv[0], mv[1], mv[2], mv[3]).normalize()?;
+        let (next, d) = v.same_side(&next);
+        v = next;
+        if d >= 1.0 - 1e-15 {
+            break;
+        }
+    }
+    Some(v.canonical())
+}
+
+/// Geodesic mean of unit quaternions: the rotation minimising the sum of
+/// squared angles to them, with `w >= 0`. `None` if there are none. Unique
+/// when they lie within a quarter turn of some rotation; beyond that it
+/// finds a local minimum.
+pub fn geodesic_mean(rotations: &[Quaternion]) -> Option<Quaternion> {
+    let weighted: Vec<_> = rotations.iter().map(|&q| (q, 1.0)).collect();
+    let mut mean = average_quaternions(&weighted)?;
+    let n = rotations.len() as f64;
+    for _ in 0..ITERATIONS {
+        let inv = mean.conjugate();
+        let mut step = [0.0; 3];
+        for q in rotations {
+            let v = (inv * *q).log();
+            step.iter_mut().zip(v).for_each(|(s, v)| *s += v / n);
+        }
+        mean = (mean * Quaternion::exp(step)).normalize()?;
+        if step.iter().all(|s| s.abs() <= 1e-15) {
+            break;
+        }
+    }
+    Some(mean.canonical())
+}
+
+/// Chordal mean of rotation matrices of any dimension, each given row by
+/// row: the rotation closest to their average. `None` if there are none or
+/// they are spread so widely that the average is singular or closest to a
+/// mirror. Panics if they aren't square matrices of one size.
+pub fn chordal_mean(rotations: &[Vec<Vec<f64>>]) -> Option<Vec<Vec<f64>>> {
+    let n = rotations.first()?.len();
+    let mut sum = Matrix::zeros(n, n);
+    for r in rotations {
+        assert!(
+            r.len() == n && r.iter().all(|row| row.len() == n),
+            "rotations must be square matrices of one size"
+        );
+        for (i, row) in r.iter().enumerate() {
+            for (j, v) in row.iter().enumerate() {
+                sum[(i, j)] += v;
+            }
+        }
+    }
+    let r = closest_rotation(&sum)?;
+    (r.determinant() > 0.0).then(|| r.to_rows())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    fn about_z(angle: f64) -> Quaternion {
+        Quaternion::from_axis_angle(&Point3::new(0.0, 0.0, 1.0), angle).unwrap()
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn quaternion_basics() {
+        let q = Quaternion::from_axis_angle(&Point3::new(1.0, 2.0, -2.0), 2.0).unwrap();
+        let (axis, angle) = q.axis_angle();
+        assert!(close(angle, 2.0) && close(axis.y(), 2.0 / 3.0));
+        let back = Quaternion::from_matrix(&q.to_matrix());
+        assert!(close(back.angle_to(&q), 0.0));
+        assert!(close(Quaternion::from_matrix(&(-q).to_matrix()).w, q.w));
+
+        let p = about_z(FRAC_PI_2).rotate(&Point3::new(1.0, 0.0, 5.0));
+        assert!(close(p.x(), 0.0) && close(p.y(), 1.0) && close(p.z(), 5.0));
+        // Composition applies the right-hand rotation first.
+        let both = about_z(0.5) * about_z(0.25);
+        assert!(close(both.angle_to(&about_z(0.75)), 0.0));
+        assert!(close((q * q.conjugate()).w, 1.0));
+        assert!(Quaternion::from_axis_angle(&Point3::new(0.0, 0.0, 0.0), 1.0).is_none());
+    }
+
+    #[test]
+    fn interpolation_takes_the_short_way() {
+        let a = about_z(0.2);
+        let b = -about_z(1.4);
+        let mid = a.slerp(&b, 0.5);
+        assert!(close(mid.angle_to(&about_z(0.8)), 0.0));
+        assert!(close(a.slerp(&b, 0.25).angle_to(&about_z(0.5)), 0.0));
+        assert!(close(a.nlerp(&b, 0.5).angle_to(&about_z(0.8)), 0.0));
+        assert!(close(a.slerp(&b, 0.0).angle_to(&a), 0.0));
+        assert!(close(a.slerp(&a, 0.3).angle_to(&a), 0.0));
+    }
+
+    #[test]
+    fn averages() {
+        let qs = [about_z(0.1), -about_z(0.3), about_z(0.8)];
+        let g = geodesic_mean(&qs).unwrap();
+        assert!(close(g.angle_to(&about_z(0.4)), 0.0));
+        assert!(g.w > 0.0);
+
+        let weighted = [(about_z(0.0), 3.0), (-about_z(0.4), 1.0)];
+        let m = average_quaternions(&weighted).unwrap();
+        // For rotations in one plane the chordal mean is the circular mean.
+        let angle = 0.4f64.sin().atan2(3.0 + 0.4f64.cos());
+        assert!(close(m.angle_to(&about_z(angle)), 0.0));
+        assert!(average_quaternions(&[]).is_none());
+        assert!(average_quaternions(&[(about_z(1.0), 0.0)]).is_none());
+
+        let mats: Vec<_> = qs.iter().map(Quaternion::to_matrix).collect();
+        let chordal = Quaternion::from_matrix(&chordal_mean(&mats).unwrap());
+        let markley = average_quaternions(&qs.map(|q| (q, 1.0))).unwrap();
+        assert!(chordal.angle_to(&markley) < 1e-9);
+        let flip = [about_z(0.0).to_matrix(), about_z(PI).to_matrix()];
+        assert!(chordal_mean(&flip).is_none());
+    }
+}
@@ -0,0 +1,299 @@
+// Rigid motions SE(3) and their Lie algebra se(3).
+//
+// A twist pairs an angular velocity `angular` (a rotation vector, see
+// `so3`) with a linear velocity `linear`; moving at that constant twist for
+// unit time sweeps out a screw motion, and `exp` gives the rigid transform
+// at the end of it:
+//
+//     R = exp(omega),  t = V v,
+//     V = I + (1 - cos θ) / θ² [omega]x + (θ - sin θ) / θ³ [omega]x²,
+//
+// with θ = |omega|. `log` inverts it, taking the rotation angle in
+// [0, pi]. Unlike blending the rotation and the translation separately,
+// scaling a twist keeps points on their helical paths, which is what
+// `interpolate` relies on. The coefficients are replaced by their Taylor
+// series near θ = 0, where the closed forms lose precision.
+//
+// By Chasles' theorem every rigid motion is such a screw: a rotation by an
+// angle about some line together with a slide along it. `screw` reads that
+// line and the two amounts off the twist; for a twist (omega, v) with
+// θ > 0 the axis runs along omega through omega x v / θ², the point on it
+// nearest the origin, and slides by omega . v / θ.
+
+use crate::linalg::Matrix;
+use crate::rotation::Quaternion;
+use crate::transform::Transform;
+use crate::{Point3, PointCloud, so3};
+
+// Below this angle the closed-form coefficients are replaced by series.
+const SMALL_ANGLE: f64 = 1e-4;
+
+/// An element of se(3): a rotation vector and a translation velocity.
+#[derive(Debug, Clone)]
+pub struct Twist {
+    pub angular: Point3<f64>,
+    pub linear: Point3<f64>,
+}
+
+impl Twist {
+    pub fn new(angular: Point3<f64>, linear: Point3<f64>) -> Self {
+        Twist { angular, linear }
+    }
+
+    /// The twist scaled by `t`: the same screw motion, a fraction `t` of
+    /// the way along.
+    pub fn scale(&self, t: f64) -> Twist {
+        Twist {
+            angular: Point3::new(
+                t * self.angular.x(),
+                t * self.angular.y(),
+                t * self.angular.z(),
+            ),
+            linear: Point3::new(
+                t * self.linear.x(),
+                t * self.linear.y(),
+                t * self.linear.z(),
+            ),
+        }
+    }
+
+    /// Moves every point of `cloud` a fraction `t` of the way along this
+    /// twist's screw motion, reporting each edit to its observers. Panics
+    /// unless the cloud is three-dimensional.
+    pub fn apply_in_place(&self, cloud: &mut PointCloud<f64>, t: f64) {
+        exp(&self.scale(t)).apply_in_place(cloud);
+    }
+}
+
+/// A rigid motion as a screw: rotation by `angle` about the line through
+/// `point` along the unit `axis`, and a slide of `distance` along it.
+#[derive(Debug, Clone)]
+pub struct Screw {
+    pub axis: Point3<f64>,
+    /// The point of the axis nearest the origin.
+    pub point: Point3<f64>,
+    /// In [0, pi].
+    pub angle: f64,
+    pub distance: f64,
+}
+
+impl Screw {
+    /// Slide per radian of rotation; infinite for a pure translation and
+    /// zero for a pure rotation.
+    pub fn pitch(&self) -> f64 {
+        if self.angle == 0.0 {
+            if self.distance == 0.0 {
+                0.0
+            } else {
+                f64::INFINITY
+            }
+        } else {
+            self.distance / self.angle
+        }
+    }
+
+    /// The twist that sweeps out this screw motion in unit time.
+    pub fn twist(&self) -> Twist {
+        let omega = &self.axis * self.angle;
+        let spin = self.point.cross(&omega);
+        Twist {
+            linear: &spin + &(&self.axis * self.distance),
+            angular: omega,
+        }
+    }
+}
+
+/// The screw motion of `transform`; `None` unless it is a rigid motion in
+/// three dimensions. For the identity the axis is the x axis.
+pub fn screw(transform: &Transform) -> Option<Screw> {
+    let Twist { angular, linear } = log(transform)?;
+    let angle = angular.dist();
+    let origin = Point3::new(0.0, 0.0, 0.0);
+    if angle == 0.0 {
+        let distance = linear.dist();
+        let axis = if distance == 0.0 {
+            Point3::new(1.0, 0.0, 0.0)
+        } else {
+            &linear * (1.0 / distance)
+        };
+        return Some(Screw {
+            axis,
+            point: origin,
+            angle,
+            distance,
+        });
+    }
+    let axis = &angular * (1.0 / angle);
+    Some(Screw {
+        point: &angular.cross(&linear) * (1.0 / (angle * angle)),
+        distance: axis.dot(&linear),
+        axis,
+        angle,
+    })
+}
+
+/// The rigid transform reached by following `twist` for unit time.
+pub fn exp(twist: &Twist) -> Transform {
+    let rotation = so3::exp(&twist.angular).to_matrix();
+    let v = left_jacobian(&twist.angular).mul_vec(&[
+        twist.linear.x(),
+        twist.linear.y(),
+        twist.linear.z(),
+    ]);
+    Transform::new(&rotation, &v).expect("3x3 rotation and 3D translation")
+}
+
+/// The twist whose [`exp`] is `transform`, rotating by at most half a turn;
+/// `None` unless it is a rigid motion in three dimensions.
+pub fn log(transform: &Transform) -> Option<Twist> {
+    if transform.dim() != 3 || !transform.is_rigid() {
+        return None;
+    }
+    let angular = so3::log(&Quaternion::from_matrix(&transform.linear()));
+    let linear = inverse_left_jacobian(&angular).mul_vec(transform.offset());
+    Some(Twist {
+        angular,
+        linear: Point3::new(linear[0], linear[1], linear[2]),
+    })
+}
+
+/// The pose a fraction `t` of the way from `from` to `to`, moving along the
+/// screw motion between them: `from * exp(t * log(from^-1 * to))`. `None`
+/// unless both are rigid motions in three dimensions.
+pub fn interpolate(from: &Transform, to: &Transform, t: f64) -> Option<Transform> {
+    if from.dim() != 3 || !from.is_rigid() {
+        return None;
+    }
+    let relative = &from.inverse()? * to;
+    Some(from * &exp(&log(&relative)?.scale(t)))
+}
+
+// V above: maps the linear velocity to the translation.
+fn left_jacobian(omega: &Point3<f64>) -> Matrix {
+    let theta = omega.dist();
+    let (a, b) = if theta < SMALL_ANGLE {
+        let t2 = theta * theta;
+        (0.5 - t2 / 24.0, 1.0 / 6.0 - t2 / 120.0)
+    } else {
+        let t2 = theta * theta;
+        (
+            (1.0 - theta.cos()) / t2,
+            (theta - theta.sin()) / (t2 * theta),
+        )
+    };
+    combine(omega, a, b)
+}
+
+// V^-1 = I - [omega]x / 2 + c [omega]x², in closed form.
+fn inverse_left_jacobian(omega: &Point3<f64>) -> Matrix {
+    let theta = omega.dist();
+    let c = if theta < SMALL_ANGLE {
+        1.0 / 12.0 + theta * theta / 720.0
+    } else {
+        let half = theta / 2.0;
+        (1.0 - half / half.tan()) / (theta * theta)
+    };
+    combine(omega, -0.5, c)
+}
+
+// I + a [omega]x + b [omega]x².
+fn combine(omega: &Point3<f64>, a: f64, b: f64) -> Matrix {
+    let k = Matrix::from_rows(&so3::hat(omega));
+    let k2 = &k * &k;
+    let mut m = Matrix::identity(3);
+    for i in 0..3 {
+        for j in 0..3 {
+            m[(i, j)] += a * k[(i, j)] + b * k2[(i, j)];
+        }
+    }
+    m
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point;
+
+    fn close(a: &[f64], b: &[f64], tol: f64) -> bool {
+        a.iter().zip(b).all(|(x, y)| (x - y).abs() < tol)
+    }
+
+    #[test]
+    fn exp_and_log_invert() {
+        for (w, v) in [
+            ([0.3, -0.2, 1.1], [1.0, 2.0, -0.5]),
+            ([0.0, 0.0, 0.0], [4.0, 0.0, 1.0]),
+            ([1e-6, 0.0, -2e-6], [0.1, 0.2, 0.3]),
+            ([0.0, 3.0, 0.0], [-1.0, 0.0, 2.0]),
+        ] {
+            let twist = Twist::new(Point3::new(w[0], w[1], w[2]), Point3::new(v[0], v[1], v[2]));
+            let t = exp(&twist);
+            assert!(t.is_rigid());
+            let back = log(&t).unwrap();
+            assert!(close(back.angular.data(), &w, 1e-9));
+            assert!(close(back.linear.data(), &v, 1e-9));
+        }
+        assert!(log(&Transform::scaling(&[2.0, 2.0, 2.0])).is_none());
+        assert!(log(&Transform::identity(2)).is_none());
+    }
+
+    #[test]
+    fn pure_twists() {
+        // A quarter turn about z, rising 1 along it: a screw about the z
+        // axis moves (1, 0, 0) to (0, 1, 1).
+        let quarter = std::f64::consts::FRAC_PI_2;
+        let screw = Twist::new(Point3::new(0.0, 0.0, quarter), Point3::new(0.0, 0.0, 1.0));
+        let p = exp(&screw).apply(&Point::new(vec![1.0, 0.0, 0.0]));
+        assert!(close(p.data(), &[0.0, 1.0, 1.0], 1e-12));
+        let slide = Twist::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 2.0, 3.0));
+        assert_eq!(exp(&slide).offset(), [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn interpolation_follows_the_screw() {
+        let from = Transform::translation(&[1.0, 0.0, 0.0]);
+        let quarter = std::f64::consts::FRAC_PI_2;
+        let to = &from
+            * &exp(&Twist::new(
+                Point3::new(0.0, 0.0, quarter),
+                Point3::new(0.0, 0.0, 2.0),
+            ));
+        let mid = interpolate(&from, &to, 0.5).unwrap();
+        let p = mid.apply(&Point::new(vec![1.0, 0.0, 0.0]));
+        let c = std::f64::consts::FRAC_1_SQRT_2;
+        assert!(close(p.data(), &[1.0 + c, c, 1.0], 1e-12));
+        let end = interpolate(&from, &to, 1.0).unwrap();
+        assert!(close(end.offset(), to.offset(), 1e-12));
+        assert!(interpolate(&Transform::scaling(&[2.0; 3]), &to, 0.5).is_none());
+    }
+
+    #[test]
+    fn screws() {
+        // Half a turn about the vertical line through (1, 2, 0), sliding 3
+        // up it.
+        let pivot = Transform::translation(&[1.0, 2.0, 0.0]);
+        let turn = &(&pivot * &Transform::rotation_plane(3, 0, 1, 2.0)) * &pivot.inverse().unwrap();
+        let t = &Transform::translation(&[0.0, 0.0, 3.0]) * &turn;
+        let s = screw(&t).unwrap();
+        assert!(close(s.axis.data(), &[0.0, 0.0, 1.0], 1e-12));
+        assert!(close(s.point.data(), &[1.0, 2.0, 0.0], 1e-12));
+        assert!(close(
+            &[s.angle, s.distance, s.pitch()],
+            &[2.0, 3.0, 1.5],
+            1e-12
+        ));
+        let again = exp(&s.twist());
+        assert!(close(again.offset(), t.offset(), 1e-12));
+
+        let slide = screw(&Transform::translation(&[0.0, 4.0, 0.0])).unwrap();
+        assert_eq!(slide.pitch(), f64::INFINITY);
+        assert!(close(slide.axis.data(), &[0.0, 1.0, 0.0], 1e-12));
+        assert_eq!(screw(&Transform::identity(3)).unwrap().pitch(), 0.0);
+        assert!(screw(&Transform::identity(2)).is_none());
+
+        let mut cloud = PointCloud::from_flat(&[2.0, 2.0, 0.0], 3).unwrap();
+        s.twist().apply_in_place(&mut cloud, 0.5);
+        let (c, d) = (1.0f64.cos(), 1.0f64.sin());
+        assert!(close(cloud.as_flat(), &[1.0 + c, 2.0 + d, 1.5], 1e-12));
+    }
+}
@@ -0,0 +1,293 @@
+// Maximum-margin separating hyperplanes between two labelled point sets.
+//
+// This is a linear support vector machine: the dual problem is solved with
+// SMO using the maximal-violating-pair working set of LIBSVM. A hard margin is
+// the limit of an unbounded penalty, so separability is checked first with the
+// LP solver; otherwise the dual would grow without bound.
+
+use crate::Point;
+use crate::linalg::{dot, norm};
+use crate::lp::{self, Lp};
+use crate::polytope::HalfSpace;
+
+const TOLERANCE: f64 = 1e-9;
+const MAX_ITERATIONS: usize = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Margin {
+    /// No point may fall inside the margin; fails if the sets overlap.
+    Hard,
+    /// Margin violations are allowed at cost `c` each (the usual SVM `C`).
+    Soft(f64),
+}
+
+#[derive(Debug, Clone)]
+pub struct Separator {
+    /// `points_a` lie in this half-space (`normal . x <= offset`), `points_b`
+    /// outside it.
+    pub plane: HalfSpace,
+    /// Distance from the plane to either margin boundary.
+    pub margin: f64,
+    /// Indices of the support vectors in `points_a`.
+    pub support_a: Vec<usize>,
+    /// Indices of the support vectors in `points_b`.
+    pub support_b: Vec<usize>,
+}
+
+/// The hyperplane separating `points_a` from `points_b` with the widest margin.
+///
+/// Returns `None` for a hard margin when the sets cannot be separated by a
+/// hyperplane. Panics if either set is empty or the dimensions differ.
+pub fn max_margin_hyperplane<T>(
+    points_a: &[Point<T>],
+    points_b: &[Point<T>],
+    margin: Margin,
+) -> Option<Separator>
+where
+    T: Into<f64> + Copy,
+{
+    assert!(
+        !points_a.is_empty() && !points_b.is_empty(),
+        "max_margin_hyperplane needs points on both sides"
+    );
+    let dim = points_a[0].dim();
+    let x: Vec<Vec<f64>> = points_a
+        .iter()
+        .chain(points_b)
+        .map(|p| {
+            assert_eq!(p.dim(), dim, "points must share a dimension");
+            p.p.iter().map(|&v| v.into()).collect()
+        })
+        .collect();
+    let na = points_a.len();
+    let y: Vec<f64> = (0..x.len())
+        .map(|i| if i < na { -1.0 } else { 1.0 })
+        .collect();
+
+    let c = match margin {
+        Margin::Hard => {
+            if !separable(&x, &y) {
+                return None;
+            }
+            f64::INFINITY
+        }
+        Margin::Soft(c) => c,
+    };
+
+    let alpha = smo(&x, &y, c)?;
+    let (w, b) = alpha.plane;
+    let (mut support_a, mut support_b) = (Vec::new(), Vec::new());
+    for (i, &a) in alpha.alpha.iter().enumerate() {
+        if a > TOLERANCE {
+            if i < na {
+                support_a.push(i);
+            } else {
+                support_b.push(i - na);
+            }
+        }
+    }
+    Some(Separator {
+        margin: 1.0 / norm(&w),
+        plane: HalfSpace::new(Point::new(w), -b),
+        support_a,
+        support_b,
+    })
+}
+
+// Whether some w, b has y_i (w . x_i + b) >= 1 for all i.
+fn separable(x: &[Vec<f64>], y: &[f64]) -> bool {
+    let dim = x[0].len();
+    let a: Vec<Vec<f64>> = x
+        .iter()
+        .zip(y)
+        .map(|(xi, &yi)| {
+            let mut row: Vec<f64> = xi.iter().map(|v| -yi * v).collect();
+            row.push(-yi);
+            row
+        })
+        .collect();
+    let b = vec![-1.0; x.len()];
+    !matches!(lp::maximize(&vec![0.0; dim + 1], &a, &b), Lp::Infeasible)
+}
+
+struct Solution {
+    alpha: Vec<f64>,
+    plane: (Vec<f64>, f64),
+}
+
+fn smo(x: &[Vec<f64>], y: &[f64], c: f64) -> Option<Solution> {
+    let n = x.len();
+    let q: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| y[i] * y[j] * dot(&x[i], &x[j])).collect())
+        .collect();
+    let mut alpha = vec![0.0; n];
+    let mut grad = vec![-1.0; n];
+    let up = |a: f64, yi: f64| (yi > 0.0 && a < c) || (yi < 0.0 && a > 0.0);
+    let low = |a: f64, yi: f64| (yi < 0.0 && a < c) || (yi > 0.0 && a > 0.0);
+
+    let mut converged = false;
+    for _ in 0..MAX_ITERATIONS {
+        let i = (0..n)
+            .filter(|&t| up(alpha[t], y[t]))
+            .max_by(|&s, &t| (-y[s] * grad[s]).total_cmp(&(-y[t] * grad[t])));
+        let j = (0..n)
+            .filter(|&t| low(alpha[t], y[t]))
+            .min_by(|&s, &t| (-y[s] * grad[s]).total_cmp(&(-y[t] * grad[t])));
+        let (Some(i), Some(j)) = (i, j) else {
+            converged = true;
+            break;
+        };
+        if -y[i] * grad[i] + y[j] * grad[j] < TOLERANCE {
+            converged = true;
+            break;
+        }
+
+        let (old_i, old_j) = (alpha[i], alpha[j]);
+        if y[i] != y[j] {
+            let quad = (q[i][i] + q[j][j] + 2.0 * q[i][j]).max(1e-12);
+            let delta = (-grad[i] - grad[j]) / quad;
+            let diff = alpha[i] - alpha[j];
+            alpha[i] += delta;
+            alpha[j] += delta;
+            if diff > 0.0 {
+                if alpha[j] < 0.0 {
+                    alpha[j] = 0.0;
+                    alpha[i] = diff;
+                }
+            } else if alpha[i] < 0.0 {
+                alpha[i] = 0.0;
+                alpha[j] = -diff;
+            }
+            if diff > 0.0 {
+                if alpha[i] > c {
+                    alpha[i] = c;
+                    alpha[j] = c - diff;
+                }
+            } else if alpha[j] > c {
+                alpha[j] = c;
+                alpha[i] = c + diff;
+            }
+        } else {
+            let quad = (q[i][i] + q[j][j] - 2.0 * q[i][j]).max(1e-12);
+            let delta = (grad[i] - grad[j]) / quad;
+            let sum = alpha[i] + alpha[j];
+            alpha[i] -= delta;
+            alpha[j] += delta;
+            if sum > c {
+                if alpha[i] > c {
+                    alpha[i] = c;
+                    alpha[j] = sum - c;
+                }
+                if alpha[j] > c {
+                    alpha[j] = c;
+                    alpha[i] = sum - c;
+                }
+            } else {
+                if alpha[j] < 0.0 {
+                    alpha[j] = 0.0;
+                    alpha[i] = sum;
+                }
+                if alpha[i] < 0.0 {
+                    alpha[i] = 0.0;
+                    alpha[j] = sum;
+                }
+            }
+        }
+        let (di, dj) = (alpha[i] - old_i, alpha[j] - old_j);
+        for t in 0..n {
+            grad[t] += q[t][i] * di + q[t][j] * dj;
+        }
+    }
+    if !converged {
+        return None;
+    }
+
+    // Bias from the free support vectors, or the middle of the feasible range.
+    let free: Vec<usize> = (0..n).filter(|&t| alpha[t] > 0.0 && alpha[t] < c).collect();
+    let rho = if free.is_empty() {
+        let (mut ub, mut lb) = (f64::INFINITY, f64::NEG_INFINITY);
+        for t in 0..n {
+            let yg = y[t] * grad[t];
+            if up(alpha[t], y[t]) {
+                ub = ub.min(yg);
+            }
+            if low(alpha[t], y[t]) {
+                lb = lb.max(yg);
+            }
+        }
+        (ub + lb) / 2.0
+    } else {
+        free.iter().map(|&t| y[t] * grad[t]).sum::<f64>() / free.len() as f64
+    };
+
+    let dim = x[0].len();
+    let mut w = vec![0.0; dim];
+    for t in 0..n {
+        for k in 0..dim {
+            w[k] += alpha[t] * y[t] * x[t][k];
+        }
+    }
+    Some(Solution {
+        alpha,
+        plane: (w, -rho),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pts(v: &[[f64; 2]]) -> Vec<Point<f64>> {
+        v.iter().map(|p| Point::new(p.to_vec())).collect()
+    }
+
+    #[test]
+    fn hard_margin() {
+        let a = pts(&[[0.0, 0.0], [0.0, 1.0], [-1.0, 0.5]]);
+        let b = pts(&[[2.0, 0.0], [2.0, 1.0], [3.0, 0.5]]);
+        let s = max_margin_hyperplane(&a, &b, Margin::Hard).unwrap();
+        // The plane is x = 1 with margin 1.
+        assert!((s.margin - 1.0).abs() < 1e-6);
+        let n = &s.plane.normal;
+        assert!((n[0] - 1.0).abs() < 1e-6 && n[1].abs() < 1e-6);
+        assert!((s.plane.offset - 1.0).abs() < 1e-6);
+        assert_eq!(s.support_a, vec![0, 1]);
+        assert_eq!(s.support_b, vec![0, 1]);
+        for p in &a {
+            assert!(s.plane.signed_distance(p) < 0.0);
+        }
+        for p in &b {
+            assert!(s.plane.signed_distance(p) > 0.0);
+        }
+    }
+
+    #[test]
+    fn hard_margin_not_separable() {
+        let a = pts(&[[0.0, 0.0], [2.0, 2.0]]);
+        let b = pts(&[[1.0, 1.0]]);
+        assert!(max_margin_hyperplane(&a, &b, Margin::Hard).is_none());
+    }
+
+    #[test]
+    fn soft_margin_tolerates_outlier() {
+        let mut a = pts(&[[0.0, 0.0], [0.0, 1.0], [0.0, 2.0]]);
+        let b = pts(&[[4.0, 0.0], [4.0, 1.0], [4.0, 2.0]]);
+        a.push(Point::new(vec![5.0, 1.0]));
+        let s = max_margin_hyperplane(&a, &b, Margin::Soft(0.1)).unwrap();
+        // The outlier is misclassified rather than wrecking the plane.
+        assert!(s.plane.signed_distance(&a[3]) > 0.0);
+        assert!(s.plane.signed_distance(&a[0]) < 0.0);
+        assert!(s.plane.signed_distance(&b[0]) > 0.0);
+        assert!(s.support_a.contains(&3));
+    }
+
+    #[test]
+    fn soft_margin_matches_hard_for_large_c() {
+        let a = pts(&[[0.0, 0.0], [1.0, 0.0]]);
+        let b = pts(&[[0.0, 3.0], [1.0, 3.0]]);
+        let hard = max_margin_hyperplane(&a, &b, Margin::Hard).unwrap();
+        let soft = max_margin_hyperplane(&a, &b, Margin::Soft(1e6)).unwrap();
+        assert!((hard.margin - 1.5).abs() < 1e-6);
+        assert!((soft.margin - hard.margin).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,117 @@
+// Serde support, behind the `serde` feature.
+//
+// Points serialize as plain sequences of coordinates, `[1.0, 2.0, 3.0]` in
+// JSON, and the fixed-dimension types check the length when read back. A
+// `PointCloud` is `{"dim": 3, "data": [...]}` with the coordinates flat, far
+// more compact than one array per point; its observers aren't saved.
+
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use crate::{Point, Point2, Point3, Point4, PointCloud, PointN, Storage};
+
+impl<T: Serialize, B: Storage<T>> Serialize for Point<T, B> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Copy> Deserialize<'de> for Point<T> {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        Vec::<T>::deserialize(d).map(Point::from)
+    }
+}
+
+macro_rules! fixed_point_serde {
+    ($($name:ident),+) => {
+        $(
+            impl<T: Serialize> Serialize for $name<T> {
+                fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                    (**self).serialize(s)
+                }
+            }
+
+            impl<'de, T: Deserialize<'de> + Copy> Deserialize<'de> for $name<T> {
+                fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                    $name::try_from(Point::deserialize(d)?).map_err(de::Error::custom)
+                }
+            }
+        )+
+    };
+}
+
+fixed_point_serde!(Point2, Point3, Point4);
+
+impl<'de, T: Deserialize<'de> + Copy, const N: usize> Deserialize<'de> for PointN<T, N> {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let v = Vec::<T>::deserialize(d)?;
+        let len = v.len();
+        let coords: [T; N] = v
+            .try_into()
+            .map_err(|_| de::Error::invalid_length(len, &format!("{N} coordinates").as_str()))?;
+        Ok(PointN::from_array(coords))
+    }
+}
+
+#[derive(Serialize)]
+struct FlatRef<'a, T> {
+    dim: usize,
+    data: &'a [T],
+}
+
+#[derive(Deserialize)]
+struct Flat<T> {
+    dim: usize,
+    data: Vec<T>,
+}
+
+impl<T: Serialize + Copy> Serialize for PointCloud<T> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        FlatRef {
+            dim: self.dim(),
+            data: self.as_flat(),
+        }
+        .serialize(s)
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Copy> Deserialize<'de> for PointCloud<T> {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let Flat { dim, data } = Flat::deserialize(d)?;
+        PointCloud::from_flat_vec(data, dim).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_are_plain_arrays() {
+        let p = Point::new(vec![1.5, -2.0, 3.0]);
+        let json = serde_json::to_string(&p).unwrap();
+        assert_eq!(json, "[1.5,-2.0,3.0]");
+        let back: Point<f64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.data(), p.data());
+
+        let q: PointN<i32, 2> = serde_json::from_str("[4,5]").unwrap();
+        assert_eq!(q.to_array(), [4, 5]);
+        assert_eq!(serde_json::to_string(&q).unwrap(), "[4,5]");
+        assert!(serde_json::from_str::<PointN<i32, 3>>("[4,5]").is_err());
+
+        let r: Point3<f64> = serde_json::from_str("[1,2,3]").unwrap();
+        assert_eq!(r.z(), 3.0);
+        assert!(serde_json::from_str::<Point2<f64>>("[1,2,3]").is_err());
+    }
+
+    #[test]
+    fn cloud_is_flat() {
+        let c = PointCloud::from_flat(&[1, 2, 3, 4], 2).unwrap();
+        let json = serde_json::to_string(&c).unwrap();
+        assert_eq!(json, r#"{"dim":2,"data":[1,2,3,4]}"#);
+        let back: PointCloud<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, c);
+        assert!(serde_json::from_str::<PointCloud<i32>>(r#"{"dim":2,"data":[1]}"#).is_err());
+    }
+}
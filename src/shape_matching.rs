@@ -0,0 +1,206 @@
+// Meshless soft bodies by shape matching (Müller et al., 2005).
+//
+// Each cluster of points remembers its rest shape. Every step, the rest
+// shape is fitted to where the cluster's points are now by the best
+// rotation and translation, and the fitted positions become goals the
+// points are pulled towards. `stiffness` one snaps straight to the goals
+// and smaller values give softer, wobblier bodies; the integration stays
+// stable for any time step. Overlapping clusters let a body bend: a point
+// in several clusters heads for the average of their goals.
+
+use crate::PointCloud;
+use crate::linalg::Matrix;
+
+#[derive(Debug, Clone)]
+pub struct ShapeMatching {
+    rest: PointCloud<f64>,
+    clusters: Vec<Vec<usize>>,
+    positions: PointCloud<f64>,
+    velocities: Vec<f64>,
+    /// How far each step pulls the points towards their goals, in [0, 1].
+    pub stiffness: f64,
+    /// Fraction of the velocity lost every step before the pull towards
+    /// the goals, in [0, 1].
+    pub damping: f64,
+}
+
+impl ShapeMatching {
+    /// A body at rest in the shape of `rest`, matched as one rigid cluster.
+    pub fn new(rest: PointCloud<f64>) -> Self {
+        let all = (0..rest.len()).collect();
+        ShapeMatching::with_clusters(rest, vec![all])
+    }
+
+    /// A body at rest in the shape of `rest`, with each cluster of point
+    /// indices matched on its own. Points in no cluster move freely.
+    /// Panics if a cluster names a point that doesn't exist.
+    pub fn with_clusters(rest: PointCloud<f64>, clusters: Vec<Vec<usize>>) -> Self {
+        assert!(
+            clusters.iter().flatten().all(|&i| i < rest.len()),
+            "cluster indices must name points of the rest shape"
+        );
+        ShapeMatching {
+            positions: rest.clone(),
+            velocities: vec![0.0; rest.as_flat().len()],
+            rest,
+            clusters,
+            stiffness: 1.0,
+            damping: 0.0,
+        }
+    }
+
+    pub fn rest(&self) -> &PointCloud<f64> {
+        &self.rest
+    }
+
+    pub fn positions(&self) -> &PointCloud<f64> {
+        &self.positions
+    }
+
+    /// The current positions, to drag or deform the body directly.
+    pub fn positions_mut(&mut self) -> &mut PointCloud<f64> {
+        &mut self.positions
+    }
+
+    /// Velocities as one flat buffer laid out like the positions.
+    pub fn velocities_mut(&mut self) -> &mut [f64] {
+        &mut self.velocities
+    }
+
+    /// Where every point would sit if each cluster were moved rigidly to
+    /// best fit its current points.
+    pub fn goals(&self) -> PointCloud<f64> {
+        let dim = self.rest.dim();
+        let mut sum = vec![0.0; self.velocities.len()];
+        let mut count = vec![0usize; self.rest.len()];
+        for cluster in &self.clusters {
+            if cluster.is_empty() {
+                continue;
+            }
+            let rest_centre = centroid(&self.rest, cluster);
+            let centre = centroid(&self.positions, cluster);
+            // Cross-covariance of current against rest offsets.
+            let mut a = Matrix::zeros(dim, dim);
+            for &i in cluster {
+                let p = self.positions.get(i).expect("index checked on creation");
+                let q = self.rest.get(i).expect("index checked on creation");
+                for r in 0..dim {
+                    for c in 0..dim {
+                        a[(r, c)] += (p[r] - centre[r]) * (q[c] - rest_centre[c]);
+                    }
+                }
+            }
+            let rotation = a.nearest_rotation();
+            for &i in cluster {
+                let q = self.rest.get(i).expect("index checked on creation");
+                let offset: Vec<f64> = q.iter().zip(&rest_centre).map(|(q, c)| q - c).collect();
+                let turned = rotation.mul_vec(&offset);
+                for k in 0..dim {
+                    sum[i * dim + k] += centre[k] + turned[k];
+                }
+                count[i] += 1;
+            }
+        }
+        let mut goals = self.positions.as_flat().to_vec();
+        for (i, &n) in count.iter().enumerate() {
+            if n > 0 {
+                for k in 0..dim {
+                    goals[i * dim + k] = sum[i * dim + k] / n as f64;
+                }
+            }
+        }
+        PointCloud::from_flat_vec(goals, dim).expect("dimension unchanged")
+    }
+
+    /// Advances the body by `dt` under the uniform `acceleration` (gravity,
+    /// say). Panics if `acceleration` doesn't match the dimension.
+    pub fn step(&mut self, dt: f64, acceleration: &[f64]) {
+        let dim = self.rest.dim();
+        assert_eq!(acceleration.len(), dim, "acceleration dimension differs");
+        let goals = self.goals();
+        let pull = self.stiffness / dt;
+        for i in 0..self.positions.len() {
+            let goal = goals.get(i).expect("one goal per point");
+            let v = &mut self.velocities[i * dim..(i + 1) * dim];
+            self.positions.update(i, |p| {
+                for k in 0..dim {
+                    v[k] = (1.0 - self.damping) * v[k]
+                        + pull * (goal[k] - p[k])
+                        + dt * acceleration[k];
+                    p[k] += dt * v[k];
+                }
+            });
+        }
+    }
+}
+
+fn centroid(cloud: &PointCloud<f64>, indices: &[usize]) -> Vec<f64> {
+    let mut c = vec![0.0; cloud.dim()];
+    for &i in indices {
+        let p = cloud.get(i).expect("index checked on creation");
+        c.iter_mut()
+            .zip(p)
+            .for_each(|(c, v)| *c += v / indices.len() as f64);
+    }
+    c
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> PointCloud<f64> {
+        PointCloud::from_flat(&[0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0], 2).unwrap()
+    }
+
+    fn side(cloud: &PointCloud<f64>, i: usize, j: usize) -> f64 {
+        let (p, q) = (cloud.get(i).unwrap(), cloud.get(j).unwrap());
+        (p[0] - q[0]).hypot(p[1] - q[1])
+    }
+
+    #[test]
+    fn rigid_motions_are_their_own_goals() {
+        let mut body = ShapeMatching::new(square());
+        let turned = PointCloud::from_flat(&[5.0, 0.0, 5.0, 1.0, 4.0, 1.0, 4.0, 0.0], 2).unwrap();
+        *body.positions_mut() = turned.clone();
+        let goals = body.goals();
+        for (g, t) in goals.as_flat().iter().zip(turned.as_flat()) {
+            assert!((g - t).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn squashed_bodies_spring_back() {
+        let mut body = ShapeMatching::new(square());
+        body.positions_mut().update(2, |p| p[1] = 0.2);
+        body.positions_mut().update(3, |p| p[1] = 0.2);
+        body.damping = 1.0;
+        body.step(0.1, &[0.0, 0.0]);
+        let p = body.positions();
+        for (i, j, len) in [(0, 1, 1.0), (1, 2, 1.0), (0, 2, 2f64.sqrt())] {
+            assert!((side(p, i, j) - len).abs() < 1e-12);
+        }
+        // Softer bodies only go part of the way.
+        let mut soft = ShapeMatching::new(square());
+        soft.stiffness = 0.5;
+        soft.positions_mut().update(2, |p| p[1] = 0.2);
+        soft.step(0.1, &[0.0, 0.0]);
+        assert!(side(soft.positions(), 1, 2) < 0.9);
+    }
+
+    #[test]
+    fn falls_under_gravity() {
+        // Two overlapping clusters; free fall keeps the shape.
+        let mut body = ShapeMatching::with_clusters(square(), vec![vec![0, 1, 2], vec![0, 2, 3]]);
+        for _ in 0..10 {
+            body.step(0.1, &[0.0, -9.8]);
+        }
+        let p = body.positions();
+        assert!((side(p, 0, 2) - 2f64.sqrt()).abs() < 1e-9);
+        assert!(p.get(0).unwrap()[1] < -4.0);
+        assert!(
+            std::panic::catch_unwind(|| ShapeMatching::with_clusters(square(), vec![vec![4]]))
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,165 @@
+// Statistical shape models (Cootes et al.'s point distribution models).
+//
+// A training set of landmark shapes is aligned by generalised Procrustes
+// analysis, and principal component analysis of the aligned landmark
+// coordinates, each shape flattened into one long vector, gives the mean
+// shape and the modes along which the shapes vary most. A new shape is then
+// the mean plus a weighted sum of modes: `synthesize` builds one from
+// weights, and `project` finds the weights that best describe a given shape.
+// Weights within about three standard deviations of zero (see
+// `standard_deviations`) give shapes like those trained on.
+
+use crate::PointCloud;
+use crate::procrustes::{generalized, normalise, superimpose};
+use crate::reduce::pca;
+
+const GPA_TOLERANCE: f64 = 1e-12;
+const GPA_ITERATIONS: usize = 100;
+
+#[derive(Debug, Clone)]
+pub struct ShapeModel {
+    /// The mean of the aligned training shapes.
+    pub mean: PointCloud<f64>,
+    /// Unit modes of variation, as landmark displacements, by decreasing
+    /// variance.
+    pub modes: Vec<PointCloud<f64>>,
+    /// The variance of the aligned training shapes along each mode.
+    pub variances: Vec<f64>,
+}
+
+impl ShapeModel {
+    /// The model of `shapes` with its `modes` largest modes of variation;
+    /// `None` if there are no shapes or one has all its landmarks in one
+    /// place. Shapes are scaled to unit centroid size, so the model
+    /// describes shape alone. Panics unless the shapes share their number
+    /// of landmarks and dimension, and `modes` is between one and the
+    /// number of coordinates in a shape.
+    pub fn fit(shapes: &[PointCloud<f64>], modes: usize) -> Option<Self> {
+        let gpa = generalized(shapes, GPA_TOLERANCE, GPA_ITERATIONS)?;
+        let dim = gpa.mean.dim();
+        let p = pca(gpa.aligned.iter().map(|s| s.as_flat()), modes)?;
+        let to_cloud = |flat: &[f64]| {
+            PointCloud::from_flat(flat, dim).expect("flattened from shapes of this dimension")
+        };
+        Some(ShapeModel {
+            mean: to_cloud(p.mean.data()),
+            modes: p.axes.iter().map(|a| to_cloud(a.data())).collect(),
+            variances: p.explained_variance,
+        })
+    }
+
+    /// The standard deviation of the training shapes along each mode.
+    pub fn standard_deviations(&self) -> Vec<f64> {
+        self.variances.iter().map(|v| v.sqrt()).collect()
+    }
+
+    /// The shape `mean + sum coefficients[k] modes[k]`. Panics unless there
+    /// is one coefficient per mode.
+    pub fn synthesize(&self, coefficients: &[f64]) -> PointCloud<f64> {
+        assert_eq!(
+            coefficients.len(),
+            self.modes.len(),
+            "one coefficient per mode"
+        );
+        let mut flat = self.mean.as_flat().to_vec();
+        for (mode, &c) in self.modes.iter().zip(coefficients) {
+            flat.iter_mut()
+                .zip(mode.as_flat())
+                .for_each(|(v, m)| *v += c * m);
+        }
+        PointCloud::from_flat_vec(flat, self.mean.dim()).expect("dimension unchanged")
+    }
+
+    /// The mode coefficients best describing `shape`, after aligning it to
+    /// the mean as the training shapes were; `None` if it has all its
+    /// landmarks in one place. Panics unless it has the model's landmarks.
+    pub fn project(&self, shape: &PointCloud<f64>) -> Option<Vec<f64>> {
+        let mut aligned = normalise(shape)?;
+        superimpose(&aligned, &self.mean, false).apply_in_place(&mut aligned);
+        Some(
+            self.modes
+                .iter()
+                .map(|mode| {
+                    aligned
+                        .as_flat()
+                        .iter()
+                        .zip(self.mean.as_flat())
+                        .zip(mode.as_flat())
+                        .map(|((a, m), d)| (a - m) * d)
+                        .sum()
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::transform::Transform;
+
+    // Rectangles whose width and height vary independently, each placed at
+    // a random rotation, scale and position.
+    fn boxes(rng: &mut Rng, count: usize) -> Vec<PointCloud<f64>> {
+        (0..count)
+            .map(|_| {
+                let (w, h) = (2.0 + 0.3 * rng.normal(), 1.0 + 0.1 * rng.normal());
+                let mut shape =
+                    PointCloud::from_flat(&[0.0, 0.0, w, 0.0, w, h, 0.0, h], 2).unwrap();
+                let s = 0.5 + rng.next_f64();
+                Transform::rotation_2d(rng.next_f64() * 6.0)
+                    .then(&Transform::scaling(&[s, s]))
+                    .then(&Transform::translation(&[rng.normal(), rng.normal()]))
+                    .apply_in_place(&mut shape);
+                shape
+            })
+            .collect()
+    }
+
+    #[test]
+    fn captures_the_modes_of_variation() {
+        let mut rng = Rng::seed_from_u64(2802);
+        let shapes = boxes(&mut rng, 40);
+        let model = ShapeModel::fit(&shapes, 2).unwrap();
+        assert_eq!(model.modes.len(), 2);
+        assert!(model.variances[0] > model.variances[1]);
+        // Only the aspect ratio varies once size is normalised away, so
+        // one mode carries nearly everything.
+        assert!(model.variances[1] < 1e-2 * model.variances[0]);
+        let mean = model.synthesize(&[0.0, 0.0]);
+        assert_eq!(mean, model.mean);
+        assert!(
+            std::panic::catch_unwind(|| {
+                let shapes = boxes(&mut Rng::seed_from_u64(1), 5);
+                ShapeModel::fit(&shapes, 2).unwrap().synthesize(&[1.0])
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn projects_and_synthesizes() {
+        let mut rng = Rng::seed_from_u64(28021);
+        let shapes = boxes(&mut rng, 30);
+        let model = ShapeModel::fit(&shapes, 2).unwrap();
+        let sd = model.standard_deviations();
+        for shape in &shapes[..5] {
+            let b = model.project(shape).unwrap();
+            assert!(b.iter().zip(&sd).all(|(b, s)| b.abs() < 4.0 * s));
+            // The rebuilt shape matches the original up to similarity.
+            let rebuilt = model.synthesize(&b);
+            let mut fitted = shape.clone();
+            superimpose(shape, &rebuilt, true).apply_in_place(&mut fitted);
+            let err: f64 = fitted
+                .as_flat()
+                .iter()
+                .zip(rebuilt.as_flat())
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum();
+            assert!(err < 1e-6);
+        }
+        let collapsed = PointCloud::from_flat(&[1.0; 8], 2).unwrap();
+        assert!(model.project(&collapsed).is_none());
+    }
+}
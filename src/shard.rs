@@ -0,0 +1,316 @@
+// Dividing a point cloud among machines and combining their answers.
+//
+// A plan cuts the cloud into spatially coherent shards, either as runs of
+// the Morton (Z-order) curve or by balanced k-d splits
+// (`partition::partition_balanced`). Planning is deterministic, so every
+// machine that plans the same cloud agrees on the shards without talking.
+// Each `ShardDescriptor` lists the global indices of its points and their
+// bounding box and travels in a small binary format; a worker extracts its
+// sub-cloud, answers queries with local indices, and the merge functions map
+// those back to global indices and combine them.
+
+#[cfg(feature = "io")]
+use std::io::{self, Read, Write};
+
+use crate::index::Neighbor;
+use crate::partition::partition_balanced;
+#[cfg(feature = "io")]
+use crate::persist::{self, invalid};
+use crate::{Point, PointCloud};
+
+#[cfg(feature = "io")]
+const MAGIC: &[u8; 4] = b"SHRD";
+#[cfg(feature = "io")]
+const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShardStrategy {
+    /// Equal runs of points sorted along the Morton curve. Cheap; shards
+    /// can be long and thin where the curve jumps.
+    Morton,
+    /// Recursive median cuts along the widest axis. Compact boxes.
+    Balanced,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardDescriptor {
+    pub id: usize,
+    /// How many shards the plan has.
+    pub count: usize,
+    /// Bounding box of the shard's points; empty for an empty shard.
+    pub lower: Vec<f64>,
+    pub upper: Vec<f64>,
+    /// Global indices of the shard's points, ascending.
+    pub indices: Vec<usize>,
+}
+
+impl ShardDescriptor {
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// The global index of the shard's `local`-th point.
+    pub fn to_global(&self, local: usize) -> usize {
+        self.indices[local]
+    }
+
+    /// The shard's points, in the order of `indices`. Panics if an index is
+    /// out of range for `cloud`.
+    pub fn extract<T: Copy>(&self, cloud: &PointCloud<T>) -> PointCloud<T> {
+        let mut out = PointCloud::new(cloud.dim()).expect("cloud dimension is positive");
+        for &i in &self.indices {
+            let coords = cloud.get(i).expect("shard index out of range");
+            out.push(coords).expect("same dimension");
+        }
+        out
+    }
+}
+
+#[cfg(feature = "io")]
+impl ShardDescriptor {
+    pub fn save<W: Write>(&self, mut w: W) -> io::Result<()> {
+        persist::write_header(&mut w, MAGIC, VERSION)?;
+        persist::write_usize(&mut w, self.id)?;
+        persist::write_usize(&mut w, self.count)?;
+        persist::write_f64s(&mut w, &self.lower)?;
+        persist::write_f64s(&mut w, &self.upper)?;
+        persist::write_usizes(&mut w, &self.indices)
+    }
+
+    /// Reads a descriptor written by [`ShardDescriptor::save`]. Fails with
+    /// `ErrorKind::InvalidData` on a foreign, newer or inconsistent file.
+    pub fn load<R: Read>(mut r: R) -> io::Result<Self> {
+        persist::read_header(&mut r, MAGIC, VERSION)?;
+        let d = ShardDescriptor {
+            id: persist::read_usize(&mut r)?,
+            count: persist::read_usize(&mut r)?,
+            lower: persist::read_f64s(&mut r)?,
+            upper: persist::read_f64s(&mut r)?,
+            indices: persist::read_usizes(&mut r)?,
+        };
+        if d.id >= d.count || d.lower.len() != d.upper.len() {
+            return Err(invalid("inconsistent shard descriptor"));
+        }
+        Ok(d)
+    }
+}
+
+// Lower and upper corners of the points `ids`.
+fn bounds<T: Into<f64> + Copy>(cloud: &PointCloud<T>, ids: &[usize]) -> (Vec<f64>, Vec<f64>) {
+    if ids.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+    let mut lower = vec![f64::INFINITY; cloud.dim()];
+    let mut upper = vec![f64::NEG_INFINITY; cloud.dim()];
+    for &i in ids {
+        let p = cloud.get(i).expect("index in range");
+        for (k, &v) in p.iter().enumerate() {
+            lower[k] = lower[k].min(v.into());
+            upper[k] = upper[k].max(v.into());
+        }
+    }
+    (lower, upper)
+}
+
+// Morton keys of all points: every coordinate is quantised within the
+// cloud's bounding box and the bits are interleaved, the top bit of each
+// axis first. Axes past the 64th don't contribute.
+fn morton_keys<T: Into<f64> + Copy>(cloud: &PointCloud<T>) -> Vec<u64> {
+    let all: Vec<usize> = (0..cloud.len()).collect();
+    let (lower, upper) = bounds(cloud, &all);
+    let axes = cloud.dim().min(64);
+    let bits = (64 / axes).min(32) as u32;
+    let cells = ((1u64 << bits) - 1) as f64;
+    cloud
+        .iter()
+        .map(|p| {
+            let q: Vec<u64> = (0..axes)
+                .map(|k| {
+                    let span = upper[k] - lower[k];
+                    let t = if span > 0.0 {
+                        (p[k].into() - lower[k]) / span
+                    } else {
+                        0.0
+                    };
+                    (t * cells).round() as u64
+                })
+                .collect();
+            let mut key = 0u64;
+            for b in (0..bits).rev() {
+                for &c in &q {
+                    key = (key << 1) | ((c >> b) & 1);
+                }
+            }
+            key
+        })
+        .collect()
+}
+
+/// Cuts `cloud` into `count` shards of equal size (within one point).
+/// Panics if `count` is zero.
+pub fn plan_shards<T>(
+    cloud: &PointCloud<T>,
+    count: usize,
+    strategy: ShardStrategy,
+) -> Vec<ShardDescriptor>
+where
+    T: Into<f64> + Copy,
+{
+    assert!(count > 0, "need at least one shard");
+    let groups: Vec<Vec<usize>> = match strategy {
+        ShardStrategy::Morton => {
+            let keys = morton_keys(cloud);
+            let mut order: Vec<usize> = (0..cloud.len()).collect();
+            order.sort_unstable_by_key(|&i| (keys[i], i));
+            let n = order.len();
+            (0..count)
+                .map(|s| order[n * s / count..n * (s + 1) / count].to_vec())
+                .collect()
+        }
+        ShardStrategy::Balanced => {
+            let points: Vec<Point<T>> = cloud.iter().map(|p| Point::new(p.to_vec())).collect();
+            let part = partition_balanced(&points, count);
+            (0..count).map(|s| part.bucket(s).to_vec()).collect()
+        }
+    };
+    groups
+        .into_iter()
+        .enumerate()
+        .map(|(id, mut indices)| {
+            indices.sort_unstable();
+            let (lower, upper) = bounds(cloud, &indices);
+            ShardDescriptor {
+                id,
+                count,
+                lower,
+                upper,
+                indices,
+            }
+        })
+        .collect()
+}
+
+fn check_parts<R>(shards: &[ShardDescriptor], results: &[Vec<R>]) {
+    assert_eq!(results.len(), shards.len(), "one result per shard");
+}
+
+/// Combines per-shard k-nearest results (local indices, as from a
+/// `SpatialIndex` over each extracted shard) into the global k nearest.
+/// `results[s]` belongs to `shards[s]`. Panics on a count mismatch.
+pub fn merge_k_nearest(
+    shards: &[ShardDescriptor],
+    results: &[Vec<Neighbor>],
+    k: usize,
+) -> Vec<Neighbor> {
+    let mut all = merge_within_radius(shards, results);
+    all.truncate(k);
+    all
+}
+
+/// Combines per-shard radius results into one list of global neighbours,
+/// nearest first, ties broken by index.
+pub fn merge_within_radius(shards: &[ShardDescriptor], results: &[Vec<Neighbor>]) -> Vec<Neighbor> {
+    check_parts(shards, results);
+    let mut all: Vec<Neighbor> = shards
+        .iter()
+        .zip(results)
+        .flat_map(|(s, r)| {
+            r.iter().map(|n| Neighbor {
+                index: s.to_global(n.index),
+                distance: n.distance,
+            })
+        })
+        .collect();
+    all.sort_by(|a, b| {
+        a.distance
+            .total_cmp(&b.distance)
+            .then(a.index.cmp(&b.index))
+    });
+    all
+}
+
+/// Puts per-point results (a label, a score; one per shard point, in shard
+/// order) back into global point order. Panics unless the shards cover the
+/// `len` points exactly once and each result has one value per point.
+pub fn scatter<R>(shards: &[ShardDescriptor], results: Vec<Vec<R>>, len: usize) -> Vec<R> {
+    check_parts(shards, &results);
+    let mut out: Vec<Option<R>> = std::iter::repeat_with(|| None).take(len).collect();
+    for (s, r) in shards.iter().zip(results) {
+        assert_eq!(r.len(), s.len(), "one result per shard point");
+        for (&i, v) in s.indices.iter().zip(r) {
+            assert!(out[i].replace(v).is_none(), "point in two shards");
+        }
+    }
+    out.into_iter()
+        .map(|v| v.expect("point in no shard"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::SpatialIndex;
+    use crate::kdtree::KdTree;
+    use crate::rng::Rng;
+
+    fn cloud() -> PointCloud<f64> {
+        let mut rng = Rng::seed_from_u64(256);
+        let flat: Vec<f64> = (0..600).map(|_| rng.range_f64(-10.0, 10.0)).collect();
+        PointCloud::from_flat_vec(flat, 3).unwrap()
+    }
+
+    #[test]
+    fn plans_cover_evenly() {
+        let c = cloud();
+        for strategy in [ShardStrategy::Morton, ShardStrategy::Balanced] {
+            let shards = plan_shards(&c, 6, strategy);
+            assert_eq!(shards, plan_shards(&c, 6, strategy));
+            assert!(shards.iter().all(|s| s.len() == 33 || s.len() == 34));
+            let ids: Vec<Vec<usize>> = shards.iter().map(|s| vec![s.id; s.len()]).collect();
+            let owner = scatter(&shards, ids, c.len());
+            for (i, p) in c.iter().enumerate() {
+                let s = &shards[owner[i]];
+                assert!(s.indices.binary_search(&i).is_ok());
+                assert!((0..3).all(|k| s.lower[k] <= p[k] && p[k] <= s.upper[k]));
+            }
+            assert_eq!(shards[1].extract(&c).get(0), c.get(shards[1].indices[0]));
+        }
+    }
+
+    #[test]
+    fn merged_queries_match_whole_cloud() {
+        let c = cloud();
+        let points = |c: &PointCloud<f64>| -> Vec<Point<f64>> {
+            c.iter().map(|p| Point::new(p.to_vec())).collect()
+        };
+        let whole = KdTree::new(&points(&c));
+        let shards = plan_shards(&c, 4, ShardStrategy::Balanced);
+        let trees: Vec<KdTree> = shards
+            .iter()
+            .map(|s| KdTree::new(&points(&s.extract(&c))))
+            .collect();
+        let q = [1.0, -2.0, 0.5];
+        let parts: Vec<Vec<Neighbor>> = trees.iter().map(|t| t.k_nearest(&q, 5)).collect();
+        assert_eq!(merge_k_nearest(&shards, &parts, 5), whole.k_nearest(&q, 5));
+        let parts: Vec<Vec<Neighbor>> = trees.iter().map(|t| t.within_radius(&q, 4.0)).collect();
+        let mut expected = whole.within_radius(&q, 4.0);
+        expected.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        assert_eq!(merge_within_radius(&shards, &parts), expected);
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn descriptor_round_trip() {
+        let shards = plan_shards(&cloud(), 3, ShardStrategy::Morton);
+        let mut buf = Vec::new();
+        shards[2].save(&mut buf).unwrap();
+        assert_eq!(ShardDescriptor::load(&buf[..]).unwrap(), shards[2]);
+        assert!(ShardDescriptor::load(&buf[..buf.len() - 1]).is_err());
+        buf[4] = 9;
+        assert!(ShardDescriptor::load(&buf[..]).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+// Shared, copy-on-write points.
+//
+// `SharedPoint` is a `Point` whose coordinates sit behind an `Arc`, so
+// cloning it for read-only fan-out across threads is a reference-count bump
+// rather than a copy. Mutation, whether through `make_mut`, indexing or the
+// in-place operators, only copies when the data is actually shared.
+
+use std::sync::Arc;
+
+use crate::{BuildStorage, Point, Storage, StorageMut};
+
+/// Coordinates behind an `Arc`, copied on write.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SharedStorage<T>(Arc<[T]>);
+
+impl<T> Storage<T> for SharedStorage<T> {
+    fn as_slice(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<T: Clone> StorageMut<T> for SharedStorage<T> {
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        Arc::make_mut(&mut self.0)
+    }
+}
+
+impl<T: Copy> BuildStorage<T> for SharedStorage<T> {
+    type Output = SharedStorage<T>;
+
+    fn build(coords: impl Iterator<Item = T>) -> Self::Output {
+        SharedStorage(coords.collect())
+    }
+}
+
+pub type SharedPoint<T> = Point<T, SharedStorage<T>>;
+
+impl<T> Point<T, SharedStorage<T>> {
+    pub fn new_shared(p: Vec<T>) -> Self {
+        Point::from_storage(SharedStorage(p.into()))
+    }
+
+    /// Mutable access, copying the coordinates first if they are shared.
+    pub fn make_mut(&mut self) -> &mut [T]
+    where
+        T: Clone,
+    {
+        self.as_mut_slice()
+    }
+
+    /// Whether this is the only handle to the coordinates.
+    pub fn is_unique(&self) -> bool {
+        Arc::strong_count(&self.storage().0) == 1
+    }
+
+    /// Whether both handles point at the same storage.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.storage().0, &other.storage().0)
+    }
+}
+
+impl<T: Copy> Point<T> {
+    /// Moves the coordinates into shared storage.
+    pub fn into_shared(self) -> SharedPoint<T> {
+        Point::new_shared(self.into_vec())
+    }
+}
+
+impl<T: Copy> From<Point<T>> for SharedPoint<T> {
+    fn from(point: Point<T>) -> Self {
+        point.into_shared()
+    }
+}
+
+impl<T: Copy> From<SharedPoint<T>> for Point<T> {
+    fn from(shared: SharedPoint<T>) -> Self {
+        shared.to_point()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clone_shares() {
+        let a = SharedPoint::new_shared(vec![1.0, 2.0, 3.0]);
+        let b = a.clone();
+        assert!(a.ptr_eq(&b));
+        assert!(!a.is_unique());
+        assert_eq!(b.data(), &[1.0, 2.0, 3.0]);
+        assert_eq!(b.dim(), 3);
+        assert_eq!(b.dist(), 14.0_f64.sqrt());
+        // Arithmetic builds new shared points; in-place ops copy on write.
+        let c = &a + &b;
+        assert!(c.is_unique() && c.data() == [2.0, 4.0, 6.0]);
+        let mut d = a.clone();
+        d *= 2.0;
+        assert!(!d.ptr_eq(&a));
+        assert_eq!((a.data(), d), (&[1.0, 2.0, 3.0][..], c));
+    }
+
+    #[test]
+    fn make_mut() {
+        let a = SharedPoint::new_shared(vec![1, 2, 3]);
+        let mut b = a.clone();
+        b.make_mut()[0] = 10;
+        assert!(!a.ptr_eq(&b));
+        assert_eq!(a.data(), &[1, 2, 3]);
+        assert_eq!(b.data(), &[10, 2, 3]);
+        assert!(b.is_unique());
+    }
+
+    #[test]
+    fn threads() {
+        let a = SharedPoint::new_shared((0..1000).map(|x| x as f64).collect());
+        let sums: Vec<f64> = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    let p = a.clone();
+                    s.spawn(move || p.data().iter().sum::<f64>())
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert!(sums.iter().all(|&s| s == 499500.0));
+    }
+
+    #[test]
+    fn conversions() {
+        let p = Point::new(vec![1, 2]);
+        let s: SharedPoint<i32> = p.clone().into();
+        let back: Point<i32> = s.into();
+        assert_eq!(back.data(), p.data());
+    }
+}
@@ -0,0 +1,150 @@
+// Vectorised kernels for `f32` and `f64` points, behind the `simd` feature.
+//
+// A plain `iter().zip().map().sum()` can't be vectorised: floating-point
+// addition isn't associative, so the compiler must add one product at a
+// time. These kernels keep `LANES` independent partial sums over fixed-size
+// chunks instead, which the compiler turns into SIMD registers on stable
+// Rust (SSE/AVX, NEON) without any intrinsics, and add them up at the end.
+// Results can differ from the scalar loops in the last bits because of the
+// different summation order. The spatial indices use the `f64` distance
+// kernel when the feature is on.
+
+use crate::{Point, Storage};
+
+const LANES: usize = 8;
+
+/// Float types with vectorised kernels.
+pub trait SimdFloat: Copy + Into<f64> {
+    /// Sum of `a[i] * b[i]`. Panics if the lengths differ, as do the others.
+    fn dot(a: &[Self], b: &[Self]) -> Self;
+    /// Sum of `(a[i] - b[i])^2`.
+    fn squared_distance(a: &[Self], b: &[Self]) -> Self;
+    fn add(a: &[Self], b: &[Self]) -> Vec<Self>;
+    fn sub(a: &[Self], b: &[Self]) -> Vec<Self>;
+    fn mul(a: &[Self], b: &[Self]) -> Vec<Self>;
+    fn sqrt(self) -> Self;
+}
+
+macro_rules! simd_float {
+    ($($t:ty),+) => {
+        $(
+            impl SimdFloat for $t {
+                fn dot(a: &[$t], b: &[$t]) -> $t {
+                    reduce(a, b, |x, y| x * y)
+                }
+
+                fn squared_distance(a: &[$t], b: &[$t]) -> $t {
+                    reduce(a, b, |x, y| (x - y) * (x - y))
+                }
+
+                fn add(a: &[$t], b: &[$t]) -> Vec<$t> {
+                    zip_map(a, b, |x, y| x + y)
+                }
+
+                fn sub(a: &[$t], b: &[$t]) -> Vec<$t> {
+                    zip_map(a, b, |x, y| x - y)
+                }
+
+                fn mul(a: &[$t], b: &[$t]) -> Vec<$t> {
+                    zip_map(a, b, |x, y| x * y)
+                }
+
+                fn sqrt(self) -> $t {
+                    <$t>::sqrt(self)
+                }
+            }
+        )+
+    };
+}
+
+simd_float!(f32, f64);
+
+// Sums `f(a[i], b[i])` in `LANES` interleaved accumulators.
+#[inline(always)]
+fn reduce<T>(a: &[T], b: &[T], f: impl Fn(T, T) -> T) -> T
+where
+    T: Copy + Default + std::ops::Add<Output = T>,
+{
+    assert_eq!(a.len(), b.len(), "points must share a dimension");
+    let (ca, ra) = a.as_chunks::<LANES>();
+    let (cb, rb) = b.as_chunks::<LANES>();
+    let mut acc = [T::default(); LANES];
+    for (x, y) in ca.iter().zip(cb) {
+        for l in 0..LANES {
+            acc[l] = acc[l] + f(x[l], y[l]);
+        }
+    }
+    for (l, (&x, &y)) in ra.iter().zip(rb).enumerate() {
+        acc[l] = acc[l] + f(x, y);
+    }
+    // Pairwise, so the final adds vectorise too.
+    let mut width = LANES;
+    while width > 1 {
+        width /= 2;
+        for l in 0..width {
+            acc[l] = acc[l] + acc[l + width];
+        }
+    }
+    acc[0]
+}
+
+#[inline(always)]
+fn zip_map<T: Copy>(a: &[T], b: &[T], f: impl Fn(T, T) -> T) -> Vec<T> {
+    assert_eq!(a.len(), b.len(), "points must share a dimension");
+    a.iter().zip(b).map(|(&x, &y)| f(x, y)).collect()
+}
+
+impl<T: SimdFloat, S: Storage<T>> Point<T, S> {
+    /// [`Point::dot`] with the vectorised kernel.
+    pub fn simd_dot<O: Storage<T>>(&self, other: &Point<T, O>) -> T {
+        T::dot(self.as_slice(), other.as_slice())
+    }
+
+    /// Euclidean distance to `other` with the vectorised kernel.
+    pub fn simd_distance<O: Storage<T>>(&self, other: &Point<T, O>) -> T {
+        T::squared_distance(self.as_slice(), other.as_slice()).sqrt()
+    }
+
+    /// Coordinate-wise sum. Panics if the dimensions differ, as do the
+    /// others.
+    pub fn simd_add<O: Storage<T>>(&self, other: &Point<T, O>) -> Point<T> {
+        Point::new(T::add(self.as_slice(), other.as_slice()))
+    }
+
+    pub fn simd_sub<O: Storage<T>>(&self, other: &Point<T, O>) -> Point<T> {
+        Point::new(T::sub(self.as_slice(), other.as_slice()))
+    }
+
+    pub fn simd_mul<O: Storage<T>>(&self, other: &Point<T, O>) -> Point<T> {
+        Point::new(T::mul(self.as_slice(), other.as_slice()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    #[test]
+    fn kernels_match_the_scalar_loops() {
+        let mut rng = Rng::seed_from_u64(268);
+        // Lengths around the lane width exercise the tails.
+        for dim in [1, 7, 8, 9, 256, 259] {
+            let a: Vec<f64> = (0..dim).map(|_| rng.normal()).collect();
+            let b: Vec<f64> = (0..dim).map(|_| rng.normal()).collect();
+            let (pa, pb) = (Point::new(a.clone()), Point::new(b.clone()));
+            assert!((pa.simd_dot(&pb) - pa.dot(&pb)).abs() < 1e-9);
+            assert!((pa.simd_distance(&pb) - pa.distance_to(&pb)).abs() < 1e-9);
+            assert_eq!(pa.simd_add(&pb).data(), (&pa + &pb).data());
+            assert_eq!(pa.simd_sub(&pb).data(), (&pa - &pb).data());
+            assert_eq!(pa.simd_mul(&pb).data(), (&pa * &pb).data());
+
+            let a32: Vec<f32> = a.iter().map(|&v| v as f32).collect();
+            let b32: Vec<f32> = b.iter().map(|&v| v as f32).collect();
+            let d = Point::new(a32).simd_distance(&Point::new(b32));
+            assert!((d as f64 - pa.distance_to(&pb)).abs() < 1e-3);
+        }
+        let arr = Point::from_array([3.0f32, 4.0]);
+        assert_eq!(arr.simd_distance(&Point::view(&[0.0, 0.0])), 5.0);
+    }
+}
@@ -0,0 +1,378 @@
+// Similarity search over embedding-style vectors.
+//
+// For cosine similarity the vectors are normalised to unit length and put in
+// a k-d tree: on the unit sphere `cos = 1 - d^2 / 2`, so the most similar
+// vectors are the nearest ones and a similarity threshold is a radius.
+//
+// Maximum inner product search is not a nearest-neighbour problem, since a
+// long vector can beat the one closest to the query. `MipsIndex` turns it
+// into one by appending `sqrt(M^2 - |x|^2)` to every vector, where `M` is the
+// largest norm, and `0` to the query: then `|q' - x'|^2 = |q|^2 + M^2 -
+// 2 q.x`, so the nearest augmented vector has the largest inner product.
+
+use crate::Point;
+use crate::index::SpatialIndex;
+use crate::kdtree::KdTree;
+use crate::linalg::{dot, norm};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Similarity {
+    Cosine,
+    InnerProduct,
+}
+
+/// One result: the index of a stored vector and its score.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Match {
+    pub index: usize,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimilaritySearch {
+    similarity: Similarity,
+    normalize: bool,
+    engine: Engine,
+}
+
+#[derive(Debug, Clone)]
+enum Engine {
+    // Unit vectors; ids refer to the input, zero vectors are left out.
+    Cosine { unit: KdTree, len: usize },
+    InnerProduct(MipsIndex),
+}
+
+impl SimilaritySearch {
+    /// Indexes `vectors`. Zero vectors never match under cosine similarity.
+    /// Panics if the vectors don't share a dimension.
+    pub fn new<T>(vectors: &[Point<T>], similarity: Similarity) -> Self
+    where
+        T: Into<f64> + Copy,
+    {
+        let engine = match similarity {
+            Similarity::Cosine => {
+                let dim = vectors.first().map_or(0, |v| v.dim());
+                let mut ids = Vec::new();
+                let mut flat = Vec::new();
+                for (i, v) in vectors.iter().enumerate() {
+                    assert_eq!(v.dim(), dim, "vectors must share a dimension");
+                    let v = to_f64(v);
+                    if let Some(u) = unit(&v) {
+                        ids.push(i);
+                        flat.extend(u);
+                    }
+                }
+                Engine::Cosine {
+                    unit: KdTree::from_parts(dim, ids, &flat),
+                    len: vectors.len(),
+                }
+            }
+            Similarity::InnerProduct => Engine::InnerProduct(MipsIndex::new(vectors)),
+        };
+        SimilaritySearch {
+            similarity,
+            normalize: false,
+            engine,
+        }
+    }
+
+    /// Rescales scores into `[0, 1]`: cosine `c` becomes `(1 + c) / 2`, and
+    /// inner products are first divided by `|query| * max |v|`. Thresholds
+    /// passed to `above_threshold` are then on the same scale.
+    pub fn normalize_scores(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    pub fn similarity(&self) -> Similarity {
+        self.similarity
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.engine {
+            Engine::Cosine { len, .. } => *len,
+            Engine::InnerProduct(mips) => mips.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The `k` best-scoring vectors, best first.
+    pub fn top_k(&self, query: &[f64], k: usize) -> Vec<Match> {
+        match &self.engine {
+            Engine::Cosine { unit: tree, .. } => match unit(query) {
+                Some(q) => tree
+                    .k_nearest(&q, k)
+                    .into_iter()
+                    .map(|n| self.cosine_match(n.index, n.distance))
+                    .collect(),
+                None => Vec::new(),
+            },
+            Engine::InnerProduct(mips) => mips
+                .top_k(query, k)
+                .into_iter()
+                .map(|m| self.inner_match(m, query))
+                .collect(),
+        }
+    }
+
+    /// Every vector scoring at least `threshold`, best first.
+    pub fn above_threshold(&self, query: &[f64], threshold: f64) -> Vec<Match> {
+        match &self.engine {
+            Engine::Cosine { unit: tree, .. } => {
+                let Some(q) = unit(query) else {
+                    return Vec::new();
+                };
+                let cos = if self.normalize {
+                    2.0 * threshold - 1.0
+                } else {
+                    threshold
+                };
+                if cos > 1.0 {
+                    return Vec::new();
+                }
+                let radius = (2.0 - 2.0 * cos).max(0.0).sqrt();
+                tree.within_radius(&q, radius)
+                    .into_iter()
+                    .map(|n| self.cosine_match(n.index, n.distance))
+                    .filter(|m| m.score >= threshold)
+                    .collect()
+            }
+            Engine::InnerProduct(mips) => {
+                let ip = if self.normalize {
+                    (2.0 * threshold - 1.0) * norm(query) * mips.max_norm
+                } else {
+                    threshold
+                };
+                mips.above_threshold(query, ip)
+                    .into_iter()
+                    .map(|m| self.inner_match(m, query))
+                    .filter(|m| !self.normalize || m.score >= threshold)
+                    .collect()
+            }
+        }
+    }
+
+    fn cosine_match(&self, index: usize, distance: f64) -> Match {
+        let cos = (1.0 - distance * distance / 2.0).clamp(-1.0, 1.0);
+        let score = if self.normalize {
+            (1.0 + cos) / 2.0
+        } else {
+            cos
+        };
+        Match { index, score }
+    }
+
+    fn inner_match(&self, m: Match, query: &[f64]) -> Match {
+        let Engine::InnerProduct(mips) = &self.engine else {
+            return m;
+        };
+        if !self.normalize {
+            return m;
+        }
+        let scale = norm(query) * mips.max_norm;
+        let s = if scale > 0.0 { m.score / scale } else { 0.0 };
+        Match {
+            index: m.index,
+            score: (1.0 + s) / 2.0,
+        }
+    }
+}
+
+/// Maximum inner product search on a k-d tree over norm-augmented vectors.
+#[derive(Debug, Clone)]
+pub struct MipsIndex {
+    vectors: Vec<Vec<f64>>,
+    max_norm: f64,
+    tree: KdTree,
+}
+
+impl MipsIndex {
+    /// Panics if the vectors don't share a dimension.
+    pub fn new<T>(vectors: &[Point<T>]) -> Self
+    where
+        T: Into<f64> + Copy,
+    {
+        let dim = vectors.first().map_or(0, |v| v.dim());
+        let vectors: Vec<Vec<f64>> = vectors
+            .iter()
+            .map(|v| {
+                assert_eq!(v.dim(), dim, "vectors must share a dimension");
+                to_f64(v)
+            })
+            .collect();
+        let max_norm = vectors.iter().map(|v| norm(v)).fold(0.0, f64::max);
+        let mut flat = Vec::with_capacity(vectors.len() * (dim + 1));
+        for v in &vectors {
+            flat.extend_from_slice(v);
+            flat.push((max_norm * max_norm - dot(v, v)).max(0.0).sqrt());
+        }
+        let tree = KdTree::from_parts(dim + 1, (0..vectors.len()).collect(), &flat);
+        MipsIndex {
+            vectors,
+            max_norm,
+            tree,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.vectors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vectors.is_empty()
+    }
+
+    /// The `k` vectors with the largest inner product with `query`.
+    pub fn top_k(&self, query: &[f64], k: usize) -> Vec<Match> {
+        self.tree
+            .k_nearest(&augment(query), k)
+            .into_iter()
+            .map(|n| self.exact(n.index, query))
+            .collect()
+    }
+
+    /// Every vector whose inner product with `query` is at least `threshold`,
+    /// largest first.
+    pub fn above_threshold(&self, query: &[f64], threshold: f64) -> Vec<Match> {
+        let r2 = dot(query, query) + self.max_norm * self.max_norm - 2.0 * threshold;
+        if r2 < 0.0 {
+            return Vec::new();
+        }
+        // Widen slightly so rounding can't drop a vector right at the threshold.
+        let radius = r2.sqrt() * (1.0 + 1e-12) + 1e-12;
+        let mut out: Vec<Match> = self
+            .tree
+            .within_radius(&augment(query), radius)
+            .into_iter()
+            .map(|n| self.exact(n.index, query))
+            .filter(|m| m.score >= threshold)
+            .collect();
+        out.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.index.cmp(&b.index)));
+        out
+    }
+
+    fn exact(&self, index: usize, query: &[f64]) -> Match {
+        Match {
+            index,
+            score: dot(&self.vectors[index], query),
+        }
+    }
+}
+
+fn augment(query: &[f64]) -> Vec<f64> {
+    let mut q = query.to_vec();
+    q.push(0.0);
+    q
+}
+
+fn to_f64<T: Into<f64> + Copy>(p: &Point<T>) -> Vec<f64> {
+    p.p.iter().map(|&v| v.into()).collect()
+}
+
+fn unit(v: &[f64]) -> Option<Vec<f64>> {
+    let n = norm(v);
+    (n > 0.0).then(|| v.iter().map(|x| x / n).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vectors() -> Vec<Point<f64>> {
+        vec![
+            Point::new(vec![1.0, 0.0, 0.0]),
+            Point::new(vec![10.0, 1.0, 0.0]),
+            Point::new(vec![0.0, 1.0, 0.0]),
+            Point::new(vec![-1.0, 0.0, 0.0]),
+            Point::new(vec![0.0, 0.0, 0.0]),
+            Point::new(vec![3.0, 3.0, 0.0]),
+        ]
+    }
+
+    #[test]
+    fn cosine_top_k() {
+        let search = SimilaritySearch::new(&vectors(), Similarity::Cosine);
+        let top = search.top_k(&[2.0, 0.0, 0.0], 3);
+        assert_eq!(
+            top.iter().map(|m| m.index).collect::<Vec<_>>(),
+            vec![0, 1, 5]
+        );
+        assert!((top[0].score - 1.0).abs() < 1e-12);
+        assert!((top[2].score - 0.5f64.sqrt()).abs() < 1e-12);
+        // The zero vector is never returned.
+        assert_eq!(search.top_k(&[1.0, 0.0, 0.0], 10).len(), 5);
+    }
+
+    #[test]
+    fn cosine_threshold() {
+        let search = SimilaritySearch::new(&vectors(), Similarity::Cosine);
+        let hits = search.above_threshold(&[1.0, 0.0, 0.0], 0.5);
+        assert_eq!(
+            hits.iter().map(|m| m.index).collect::<Vec<_>>(),
+            vec![0, 1, 5]
+        );
+        let all = search.above_threshold(&[1.0, 0.0, 0.0], -1.0);
+        assert_eq!(all.len(), 5);
+        assert_eq!(all.last().unwrap().index, 3);
+
+        let normalized = search.normalize_scores(true);
+        let hits = normalized.above_threshold(&[1.0, 0.0, 0.0], 0.75);
+        assert_eq!(hits.len(), 3);
+        assert_eq!(hits[0].score, 1.0);
+    }
+
+    #[test]
+    fn mips_matches_brute_force() {
+        let mut rng = crate::rng::Rng::seed_from_u64(21);
+        let vs: Vec<Point<f64>> = (0..300)
+            .map(|_| {
+                let scale = rng.range_f64(0.1, 5.0);
+                Point::new((0..4).map(|_| rng.normal() * scale).collect())
+            })
+            .collect();
+        let mips = MipsIndex::new(&vs);
+        for _ in 0..20 {
+            let q: Vec<f64> = (0..4).map(|_| rng.normal()).collect();
+            let mut scores: Vec<(usize, f64)> = vs.iter().map(|v| dot(v, &q)).enumerate().collect();
+            scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+            let top = mips.top_k(&q, 5);
+            assert_eq!(
+                top.iter().map(|m| m.index).collect::<Vec<_>>(),
+                scores[..5].iter().map(|s| s.0).collect::<Vec<_>>()
+            );
+            let above = mips.above_threshold(&q, 2.0);
+            assert_eq!(above.len(), scores.iter().filter(|s| s.1 >= 2.0).count());
+        }
+    }
+
+    #[test]
+    fn inner_product() {
+        let search = SimilaritySearch::new(&vectors(), Similarity::InnerProduct);
+        let top = search.top_k(&[1.0, 1.0, 0.0], 2);
+        assert_eq!(
+            top,
+            vec![
+                Match {
+                    index: 1,
+                    score: 11.0
+                },
+                Match {
+                    index: 5,
+                    score: 6.0
+                }
+            ]
+        );
+        let hits = search.above_threshold(&[1.0, 1.0, 0.0], 1.0);
+        assert_eq!(
+            hits.iter().map(|m| m.index).collect::<Vec<_>>(),
+            vec![1, 5, 0, 2]
+        );
+
+        let normalized = search.normalize_scores(true);
+        let top = normalized.top_k(&[1.0, 0.0, 0.0], 1);
+        assert!((top[0].score - (1.0 + 10.0 / 101f64.sqrt()) / 2.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,224 @@
+// Skeletons and linear-blend skinning.
+//
+// A `Skeleton` is a tree of joints given by their rest positions, each
+// parent added before its children. A pose gives every joint a local
+// transform about its own rest position: rotating the shoulder swings the
+// elbow and everything below it. Forward kinematics composes these down the
+// tree into one global transform per joint, taking rest space to posed
+// space.
+//
+// Linear-blend skinning moves an attached point cloud with the skeleton:
+// each point follows a weighted average of its joints' global transforms.
+// It is cheap and standard, with the usual artefacts (volume loss where
+// bones bend sharply). Good weights are usually painted by hand;
+// `distance_weights` gives a quick inverse-distance set to start from.
+
+use crate::transform::Transform;
+use crate::{Point, PointCloud, Storage};
+
+/// The joints a point follows and how strongly: `(joint, weight)` pairs.
+/// Weights are normalised by their sum when skinning.
+pub type Influences = Vec<(usize, f64)>;
+
+#[derive(Debug, Clone, Default)]
+pub struct Skeleton {
+    rest: Vec<Point<f64>>,
+    parents: Vec<Option<usize>>,
+}
+
+impl Skeleton {
+    pub fn new() -> Self {
+        Skeleton::default()
+    }
+
+    /// Adds a joint at rest position `position` and returns its index.
+    /// Panics if `parent` isn't an existing joint or the dimension differs
+    /// from the first joint's.
+    pub fn add_joint<T, S>(&mut self, position: &Point<T, S>, parent: Option<usize>) -> usize
+    where
+        T: Into<f64> + Copy,
+        S: Storage<T>,
+    {
+        assert!(
+            parent.is_none_or(|p| p < self.len()),
+            "parent must be an existing joint"
+        );
+        let position = Point::new(position.as_slice().iter().map(|&v| v.into()).collect());
+        if let Some(first) = self.rest.first() {
+            assert_eq!(position.dim(), first.dim(), "joint dimensions differ");
+        }
+        self.rest.push(position);
+        self.parents.push(parent);
+        self.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.rest.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rest.is_empty()
+    }
+
+    /// The dimension of the joints; `None` for an empty skeleton.
+    pub fn dim(&self) -> Option<usize> {
+        self.rest.first().map(|p| p.dim())
+    }
+
+    pub fn rest_positions(&self) -> &[Point<f64>] {
+        &self.rest
+    }
+
+    pub fn parent(&self, joint: usize) -> Option<usize> {
+        self.parents[joint]
+    }
+
+    /// Every joint's transform from rest to posed space, given each
+    /// joint's `local` transform about its rest position. Panics unless
+    /// there is one local transform per joint, of the joints' dimension.
+    pub fn global_transforms(&self, local: &[Transform]) -> Vec<Transform> {
+        assert_eq!(local.len(), self.len(), "one local transform per joint");
+        let mut global: Vec<Transform> = Vec::with_capacity(self.len());
+        for (i, l) in local.iter().enumerate() {
+            let pivot = self.rest[i].as_slice();
+            let back: Vec<f64> = pivot.iter().map(|v| -v).collect();
+            let about = Transform::translation(&back)
+                .then(l)
+                .then(&Transform::translation(pivot));
+            global.push(match self.parents[i] {
+                Some(p) => about.then(&global[p]),
+                None => about,
+            });
+        }
+        global
+    }
+
+    /// Where the joints are in the pose given by `local`, as for
+    /// [`Skeleton::global_transforms`].
+    pub fn posed_positions(&self, local: &[Transform]) -> Vec<Point<f64>> {
+        self.global_transforms(local)
+            .iter()
+            .zip(&self.rest)
+            .map(|(g, p)| g.apply(p))
+            .collect()
+    }
+
+    /// The rest-pose `cloud` moved into the pose given by `local`, each
+    /// point by the blend of its `influences`. A point without influences
+    /// stays put. Panics unless there are influences for every point, they
+    /// name existing joints, and the dimensions agree.
+    pub fn skin(
+        &self,
+        cloud: &PointCloud<f64>,
+        influences: &[Influences],
+        local: &[Transform],
+    ) -> PointCloud<f64> {
+        assert_eq!(influences.len(), cloud.len(), "influences for every point");
+        let global = self.global_transforms(local);
+        let mut data = Vec::with_capacity(cloud.as_flat().len());
+        for (p, inf) in cloud.iter().zip(influences) {
+            let total: f64 = inf.iter().map(|&(_, w)| w).sum();
+            if total == 0.0 {
+                data.extend_from_slice(p);
+                continue;
+            }
+            let p = Point::new(p.to_vec());
+            let mut blended = vec![0.0; cloud.dim()];
+            for &(joint, w) in inf {
+                let moved = global[joint].apply(&p);
+                blended
+                    .iter_mut()
+                    .zip(moved.iter())
+                    .for_each(|(b, m)| *b += w / total * m);
+            }
+            data.extend(blended);
+        }
+        PointCloud::from_flat_vec(data, cloud.dim()).expect("dimension unchanged")
+    }
+
+    /// Influences from the `count` nearest joints to every point of
+    /// `cloud`, weighted by inverse squared distance; a point on a joint
+    /// follows only that joint. Panics if the dimensions differ.
+    pub fn distance_weights(&self, cloud: &PointCloud<f64>, count: usize) -> Vec<Influences> {
+        cloud
+            .iter()
+            .map(|p| {
+                let p = Point::new(p.to_vec());
+                let mut near: Vec<(usize, f64)> = self
+                    .rest
+                    .iter()
+                    .enumerate()
+                    .map(|(j, r)| (j, p.distance_to(r)))
+                    .collect();
+                near.sort_by(|a, b| a.1.total_cmp(&b.1));
+                near.truncate(count);
+                if let Some(&(j, d)) = near.first()
+                    && d == 0.0
+                {
+                    return vec![(j, 1.0)];
+                }
+                near.iter().map(|&(j, d)| (j, 1.0 / (d * d))).collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    // Shoulder at the origin, elbow at (1, 0), wrist at (2, 0).
+    fn arm() -> Skeleton {
+        let mut s = Skeleton::new();
+        let shoulder = s.add_joint(&Point::new(vec![0.0, 0.0]), None);
+        let elbow = s.add_joint(&Point::new(vec![1.0, 0.0]), Some(shoulder));
+        s.add_joint(&Point::new(vec![2, 0]), Some(elbow));
+        s
+    }
+
+    fn close(a: &[f64], b: &[f64]) -> bool {
+        a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-12)
+    }
+
+    #[test]
+    fn forward_kinematics() {
+        let arm = arm();
+        assert_eq!((arm.len(), arm.dim(), arm.parent(2)), (3, Some(2), Some(1)));
+        let turn = Transform::rotation_2d(FRAC_PI_2);
+        let id = Transform::identity(2);
+        let posed = arm.posed_positions(&[turn.clone(), turn.clone(), id.clone()]);
+        assert!(close(posed[1].data(), &[0.0, 1.0]));
+        assert!(close(posed[2].data(), &[-1.0, 1.0]));
+        let rest = arm.posed_positions(&[id.clone(), id.clone(), id]);
+        assert!(close(rest[2].data(), &[2.0, 0.0]));
+        assert!(
+            std::panic::catch_unwind(|| Skeleton::new().add_joint(&Point::new(vec![0.0]), Some(0)))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn skinning_blends_joint_transforms() {
+        let arm = arm();
+        let cloud = PointCloud::from_flat(&[1.5, 0.0, 1.5, 0.0, 3.0, 3.0], 2).unwrap();
+        let influences = vec![vec![(1, 1.0)], vec![(0, 1.0), (1, 1.0)], vec![]];
+        let id = Transform::identity(2);
+        let elbow = Transform::rotation_2d(FRAC_PI_2);
+        let posed = arm.skin(&cloud, &influences, &[id.clone(), elbow, id]);
+        // Fully on the elbow: swings up. Half and half: halfway between.
+        assert!(close(posed.as_flat(), &[1.0, 0.5, 1.25, 0.25, 3.0, 3.0]));
+    }
+
+    #[test]
+    fn distance_weights_favour_near_joints() {
+        let arm = arm();
+        let cloud = PointCloud::from_flat(&[1.0, 0.0, 0.5, 0.5, 5.0, 0.0], 2).unwrap();
+        let w = arm.distance_weights(&cloud, 2);
+        assert_eq!(w[0], vec![(1, 1.0)]);
+        assert_eq!(w[1].len(), 2);
+        assert!((w[1][0].1 - w[1][1].1).abs() < 1e-12);
+        assert_eq!(w[2][0].0, 2);
+        assert!(w[2][0].1 > w[2][1].1);
+    }
+}
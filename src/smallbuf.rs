@@ -0,0 +1,150 @@
+// Coordinate storage with an inline fast path.
+//
+// Points of up to `INLINE` dimensions keep their coordinates inside the
+// `Point` itself, so the 2D/3D/4D cases never touch the heap. Larger points
+// spill to a `Vec`. The buffer derefs to a slice, which is all the rest of the
+// crate relies on.
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+pub(crate) const INLINE: usize = 4;
+
+#[derive(Clone)]
+pub(crate) enum SmallBuf<T> {
+    // Slots past `len` hold copies of the first element and are never read.
+    Inline { buf: [T; INLINE], len: u8 },
+    Heap(Vec<T>),
+}
+
+impl<T: Copy> SmallBuf<T> {
+    pub(crate) fn from_slice(s: &[T]) -> Self {
+        match s.first() {
+            Some(&first) if s.len() <= INLINE => {
+                let mut buf = [first; INLINE];
+                buf[..s.len()].copy_from_slice(s);
+                SmallBuf::Inline {
+                    buf,
+                    len: s.len() as u8,
+                }
+            }
+            _ => SmallBuf::Heap(s.to_vec()),
+        }
+    }
+}
+
+impl<T> SmallBuf<T> {
+    #[cfg(test)]
+    pub(crate) fn is_inline(&self) -> bool {
+        matches!(self, SmallBuf::Inline { .. })
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<T>
+    where
+        T: Copy,
+    {
+        match self {
+            SmallBuf::Inline { buf, len } => buf[..len as usize].to_vec(),
+            SmallBuf::Heap(v) => v,
+        }
+    }
+}
+
+impl<T> Deref for SmallBuf<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            SmallBuf::Inline { buf, len } => &buf[..*len as usize],
+            SmallBuf::Heap(v) => v,
+        }
+    }
+}
+
+impl<T> DerefMut for SmallBuf<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        match self {
+            SmallBuf::Inline { buf, len } => &mut buf[..*len as usize],
+            SmallBuf::Heap(v) => v,
+        }
+    }
+}
+
+impl<T: Copy> From<Vec<T>> for SmallBuf<T> {
+    fn from(v: Vec<T>) -> Self {
+        if v.len() <= INLINE && !v.is_empty() {
+            SmallBuf::from_slice(&v)
+        } else {
+            SmallBuf::Heap(v)
+        }
+    }
+}
+
+impl<T: Copy> FromIterator<T> for SmallBuf<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut iter = iter.into_iter();
+        let Some(first) = iter.next() else {
+            return SmallBuf::Heap(Vec::new());
+        };
+        let mut buf = [first; INLINE];
+        let mut len = 1;
+        for x in iter.by_ref() {
+            if len == INLINE {
+                let mut v = Vec::with_capacity(INLINE + 1 + iter.size_hint().0);
+                v.extend_from_slice(&buf);
+                v.push(x);
+                v.extend(iter);
+                return SmallBuf::Heap(v);
+            }
+            buf[len] = x;
+            len += 1;
+        }
+        SmallBuf::Inline {
+            buf,
+            len: len as u8,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SmallBuf<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq<Vec<T>> for SmallBuf<T> {
+    fn eq(&self, other: &Vec<T>) -> bool {
+        **self == other[..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inline_and_heap() {
+        let small: SmallBuf<f64> = vec![1.0, 2.0, 3.0].into();
+        assert!(small.is_inline());
+        assert_eq!(&*small, &[1.0, 2.0, 3.0]);
+
+        let big: SmallBuf<i32> = (0..6).collect();
+        assert!(!big.is_inline());
+        assert_eq!(big, vec![0, 1, 2, 3, 4, 5]);
+
+        let four: SmallBuf<i32> = (0..4).collect();
+        assert!(four.is_inline());
+        assert_eq!(four.len(), 4);
+
+        let empty: SmallBuf<i32> = std::iter::empty().collect();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn deref_mut() {
+        let mut buf: SmallBuf<i32> = vec![1, 2].into();
+        buf[1] = 5;
+        assert_eq!(buf, vec![1, 5]);
+        assert_eq!(format!("{buf:?}"), "[1, 5]");
+    }
+}
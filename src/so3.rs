@@ -0,0 +1,83 @@
+// The rotation group SO(3) and its Lie algebra so(3).
+//
+// A rotation vector `omega` is the axis scaled by the angle in radians;
+// `exp` turns it into the rotation and `log` recovers it, with the angle in
+// [0, pi]. Adding rotation vectors is only composition to first order, but
+// a small change `delta` to a rotation `q` written as `q * exp(delta)` is
+// an unconstrained three-number update, which is what optimisers over
+// orientations step along. `hat` and `vee` convert between rotation vectors
+// and the skew-symmetric matrices `[omega]x` with `[omega]x v = omega x v`.
+
+use crate::Point3;
+use crate::rotation::Quaternion;
+
+/// The rotation by `|omega|` radians about `omega`.
+pub fn exp(omega: &Point3<f64>) -> Quaternion {
+    Quaternion::exp([omega.x(), omega.y(), omega.z()])
+}
+
+/// The rotation vector of `q`, with angle in [0, pi]; zero for the
+/// identity. Assumes a unit quaternion.
+pub fn log(q: &Quaternion) -> Point3<f64> {
+    let [x, y, z] = q.log();
+    Point3::new(x, y, z)
+}
+
+/// The skew-symmetric matrix of `omega`, row by row.
+pub fn hat(omega: &Point3<f64>) -> Vec<Vec<f64>> {
+    let (x, y, z) = (omega.x(), omega.y(), omega.z());
+    vec![vec![0.0, -z, y], vec![z, 0.0, -x], vec![-y, x, 0.0]]
+}
+
+/// The vector of a skew-symmetric 3x3 matrix; the skew part is used if it
+/// isn't exactly skew. Panics if it isn't 3x3.
+pub fn vee(m: &[Vec<f64>]) -> Point3<f64> {
+    assert!(
+        m.len() == 3 && m.iter().all(|r| r.len() == 3),
+        "matrix must be 3x3"
+    );
+    Point3::new(
+        (m[2][1] - m[1][2]) / 2.0,
+        (m[0][2] - m[2][0]) / 2.0,
+        (m[1][0] - m[0][1]) / 2.0,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-12
+    }
+
+    #[test]
+    fn exp_and_log_invert() {
+        for omega in [
+            Point3::new(0.3, -0.2, 1.1),
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1e-9, 0.0, 2e-9),
+            Point3::new(0.0, 3.1, 0.0),
+        ] {
+            let back = log(&exp(&omega));
+            assert!(close(back.x(), omega.x()) && close(back.y(), omega.y()));
+            assert!(close(back.z(), omega.z()));
+        }
+        let q = exp(&Point3::new(0.0, 0.0, std::f64::consts::FRAC_PI_2));
+        let p = q.rotate(&Point3::new(1.0, 0.0, 0.0));
+        assert!(close(p.x(), 0.0) && close(p.y(), 1.0));
+    }
+
+    #[test]
+    fn hat_is_the_cross_product() {
+        let (w, v) = (Point3::new(1.0, 2.0, 3.0), Point3::new(-1.0, 0.5, 2.0));
+        let m = hat(&w);
+        let cross = w.cross(&v);
+        let mv: Vec<f64> = m
+            .iter()
+            .map(|r| r[0] * v.x() + r[1] * v.y() + r[2] * v.z())
+            .collect();
+        assert_eq!(mv, [cross.x(), cross.y(), cross.z()]);
+        assert_eq!(vee(&m).data(), w.data());
+    }
+}
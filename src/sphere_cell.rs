@@ -0,0 +1,304 @@
+// Hierarchical cells on the unit sphere, after Google's S2.
+//
+// The sphere is projected onto the six faces of the cube around it, and
+// each face is split as a quadtree 30 levels deep. A `CellId` packs the face
+// in the top 3 bits, then two bits per level for the quadrant, then a
+// single 1 bit marking where the level ends, so a cell's descendants are
+// exactly the ids in a contiguous range around it and sorting ids keeps
+// nearby cells together. Face coordinates pass through S2's quadratic
+// warp, which evens out cell areas between face centres and corners.
+// Unlike S2 the quadrants are in Z order rather than along a Hilbert curve.
+
+use crate::Point3;
+
+/// Deepest level; leaf cells there are around a centimetre on Earth.
+pub const MAX_LEVEL: u8 = 30;
+const POS_BITS: u32 = 2 * MAX_LEVEL as u32 + 1;
+const MAX_SIZE: u32 = 1 << MAX_LEVEL;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CellId(u64);
+
+// Face coordinate in [-1, 1] to the warped [0, 1], and back.
+fn uv_to_st(u: f64) -> f64 {
+    if u >= 0.0 {
+        0.5 * (1.0 + 3.0 * u).sqrt()
+    } else {
+        1.0 - 0.5 * (1.0 - 3.0 * u).sqrt()
+    }
+}
+
+fn st_to_uv(s: f64) -> f64 {
+    if s >= 0.5 {
+        (4.0 * s * s - 1.0) / 3.0
+    } else {
+        (1.0 - 4.0 * (1.0 - s) * (1.0 - s)) / 3.0
+    }
+}
+
+// Face `f` looks along axis `f % 3`, positive for faces 0 to 2; `u` and `v`
+// run along the next two axes in cyclic order.
+fn face_uv_to_xyz(face: u8, u: f64, v: f64) -> Point3<f64> {
+    let axis = (face % 3) as usize;
+    let mut p = [0.0; 3];
+    p[axis] = if face < 3 { 1.0 } else { -1.0 };
+    p[(axis + 1) % 3] = u;
+    p[(axis + 2) % 3] = v;
+    let len = (p[0] * p[0] + p[1] * p[1] + p[2] * p[2]).sqrt();
+    Point3::new(p[0] / len, p[1] / len, p[2] / len)
+}
+
+// Angle in radians between two vectors; `atan2` stays accurate for the tiny
+// angles of deep cells, where `acos` of the dot product doesn't.
+fn angle(a: &Point3<f64>, b: &Point3<f64>) -> f64 {
+    a.cross(b).dist().atan2(a.dot(b))
+}
+
+fn spread(mut x: u64) -> u64 {
+    // Moves bit k of a 32-bit value to bit 2k.
+    x &= 0xffff_ffff;
+    x = (x | (x << 16)) & 0x0000_ffff_0000_ffff;
+    x = (x | (x << 8)) & 0x00ff_00ff_00ff_00ff;
+    x = (x | (x << 4)) & 0x0f0f_0f0f_0f0f_0f0f;
+    x = (x | (x << 2)) & 0x3333_3333_3333_3333;
+    (x | (x << 1)) & 0x5555_5555_5555_5555
+}
+
+fn gather(mut x: u64) -> u64 {
+    x &= 0x5555_5555_5555_5555;
+    x = (x | (x >> 1)) & 0x3333_3333_3333_3333;
+    x = (x | (x >> 2)) & 0x0f0f_0f0f_0f0f_0f0f;
+    x = (x | (x >> 4)) & 0x00ff_00ff_00ff_00ff;
+    x = (x | (x >> 8)) & 0x0000_ffff_0000_ffff;
+    (x | (x >> 16)) & 0xffff_ffff
+}
+
+impl CellId {
+    /// The whole of cube face `face`, a level 0 cell. Panics unless
+    /// `face < 6`.
+    pub fn from_face(face: u8) -> Self {
+        assert!(face < 6, "a cube has six faces");
+        CellId(((face as u64) << POS_BITS) | 1 << (POS_BITS - 1))
+    }
+
+    /// The cell at `level` containing the direction of `p`, which needn't
+    /// be unit length. Panics on the zero vector or a level past
+    /// `MAX_LEVEL`.
+    pub fn from_point(p: &Point3<f64>, level: u8) -> Self {
+        assert!(level <= MAX_LEVEL, "cell level is at most {MAX_LEVEL}");
+        let xyz = [p.x(), p.y(), p.z()];
+        let axis = (0..3)
+            .max_by(|&a, &b| xyz[a].abs().total_cmp(&xyz[b].abs()))
+            .expect("three axes");
+        let w = xyz[axis];
+        assert!(w != 0.0, "the zero vector has no direction");
+        let face = axis as u8 + if w < 0.0 { 3 } else { 0 };
+        let u = xyz[(axis + 1) % 3] / w.abs();
+        let v = xyz[(axis + 2) % 3] / w.abs();
+        let ij = |x: f64| ((uv_to_st(x) * MAX_SIZE as f64) as u32).min(MAX_SIZE - 1);
+        let pos = spread(ij(u) as u64) << 1 | spread(ij(v) as u64);
+        CellId(((face as u64) << POS_BITS) | pos << 1 | 1).parent_at(level)
+    }
+
+    /// The raw 64-bit id, for storing; see [`CellId::from_raw`].
+    pub fn raw(&self) -> u64 {
+        self.0
+    }
+
+    /// An id from [`CellId::raw`], or `None` if it isn't a valid cell.
+    pub fn from_raw(id: u64) -> Option<Self> {
+        let valid = id >> POS_BITS < 6 && id != 0 && id.trailing_zeros().is_multiple_of(2);
+        valid.then_some(CellId(id))
+    }
+
+    pub fn face(&self) -> u8 {
+        (self.0 >> POS_BITS) as u8
+    }
+
+    pub fn level(&self) -> u8 {
+        MAX_LEVEL - (self.0.trailing_zeros() / 2) as u8
+    }
+
+    fn lsb(&self) -> u64 {
+        self.0 & self.0.wrapping_neg()
+    }
+
+    /// The ancestor at `level`, or the cell itself at its own level. Panics
+    /// if `level` is deeper than the cell.
+    pub fn parent_at(&self, level: u8) -> CellId {
+        assert!(
+            level <= self.level(),
+            "a parent can't be deeper than its child"
+        );
+        let lsb = 1u64 << (2 * (MAX_LEVEL - level) as u32);
+        CellId((self.0 & lsb.wrapping_neg()) | lsb)
+    }
+
+    /// The enclosing cell one level up; `None` for a face.
+    pub fn parent(&self) -> Option<CellId> {
+        let level = self.level();
+        (level > 0).then(|| self.parent_at(level - 1))
+    }
+
+    /// The four cells one level down, in Z order; `None` for a leaf.
+    pub fn children(&self) -> Option<[CellId; 4]> {
+        if self.level() == MAX_LEVEL {
+            return None;
+        }
+        let lsb = self.lsb();
+        let child = lsb >> 2;
+        Some(std::array::from_fn(|k| {
+            CellId(self.0 - lsb + (2 * k as u64 + 1) * child)
+        }))
+    }
+
+    /// Whether `other` is this cell or one of its descendants.
+    pub fn contains(&self, other: &CellId) -> bool {
+        let span = self.lsb() - 1;
+        (self.0 - span..=self.0 + span).contains(&other.0)
+    }
+
+    // The cell's corner in leaf coordinates and its size in leaves.
+    fn ij(&self) -> (u32, u32, u32) {
+        let pos = (self.0 & ((1u64 << POS_BITS) - 1)) >> 1;
+        let size = 1u32 << (MAX_LEVEL - self.level());
+        let mask = !(size - 1);
+        (
+            gather(pos >> 1) as u32 & mask,
+            gather(pos) as u32 & mask,
+            size,
+        )
+    }
+
+    fn at(&self, i: f64, j: f64) -> Point3<f64> {
+        let s = i / MAX_SIZE as f64;
+        let t = j / MAX_SIZE as f64;
+        face_uv_to_xyz(self.face(), st_to_uv(s), st_to_uv(t))
+    }
+
+    /// The unit vector at the middle of the cell.
+    pub fn center(&self) -> Point3<f64> {
+        let (i, j, size) = self.ij();
+        let half = size as f64 / 2.0;
+        self.at(i as f64 + half, j as f64 + half)
+    }
+
+    /// The four corners as unit vectors, counter-clockwise seen from
+    /// outside.
+    pub fn vertices(&self) -> [Point3<f64>; 4] {
+        let (i, j, size) = self.ij();
+        let (i0, j0) = (i as f64, j as f64);
+        let (i1, j1) = (i0 + size as f64, j0 + size as f64);
+        [
+            self.at(i0, j0),
+            self.at(i1, j0),
+            self.at(i1, j1),
+            self.at(i0, j1),
+        ]
+    }
+
+    // Largest angle from the centre to any point of the cell; cells are
+    // convex, so that is at a corner.
+    fn radius(&self) -> f64 {
+        let c = self.center();
+        self.vertices()
+            .iter()
+            .map(|v| angle(&c, v))
+            .fold(0.0, f64::max)
+    }
+}
+
+/// Cells covering the spherical cap of angular `radius` (radians) around
+/// the direction of `center`, sorted by id. Cells wholly inside the cap are
+/// kept as large as possible; cells on its edge are split down to
+/// `max_level`, so the covering may spill a little past the cap but never
+/// misses any of it.
+pub fn cover_cap(center: &Point3<f64>, radius: f64, max_level: u8) -> Vec<CellId> {
+    assert!(max_level <= MAX_LEVEL, "cell level is at most {MAX_LEVEL}");
+    let len = center.dist();
+    let c = Point3::new(center.x() / len, center.y() / len, center.z() / len);
+    let mut out = Vec::new();
+    let mut stack: Vec<CellId> = (0..6).rev().map(CellId::from_face).collect();
+    while let Some(cell) = stack.pop() {
+        let d = angle(&c, &cell.center());
+        let r = cell.radius();
+        if d > radius + r {
+            continue;
+        }
+        if d + r <= radius || cell.level() == max_level {
+            out.push(cell);
+        } else {
+            let children = cell.children().expect("above max_level");
+            stack.extend(children.iter().rev());
+        }
+    }
+    out.sort_unstable();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn random_unit(rng: &mut Rng) -> Point3<f64> {
+        let (x, y, z) = (rng.normal(), rng.normal(), rng.normal());
+        let len = (x * x + y * y + z * z).sqrt();
+        Point3::new(x / len, y / len, z / len)
+    }
+
+    #[test]
+    fn hierarchy() {
+        let p = Point3::new(0.3, -0.8, 0.5);
+        let leaf = CellId::from_point(&p, MAX_LEVEL);
+        assert_eq!(leaf.level(), MAX_LEVEL);
+        assert_eq!(leaf.face(), 4);
+        assert!(leaf.children().is_none());
+        assert!(angle(&leaf.center(), &p.normalize().try_into().unwrap()) < 1e-8);
+
+        let cell = CellId::from_point(&p, 12);
+        assert_eq!(cell, leaf.parent_at(12));
+        assert_eq!(cell.parent().unwrap().level(), 11);
+        assert!(cell.contains(&leaf) && !leaf.contains(&cell));
+        let kids = cell.children().unwrap();
+        assert_eq!(kids.iter().filter(|k| k.contains(&leaf)).count(), 1);
+        assert!(kids.iter().all(|k| k.parent() == Some(cell)));
+        assert_eq!(CellId::from_raw(cell.raw()), Some(cell));
+        assert_eq!(CellId::from_raw(0), None);
+
+        let face = CellId::from_face(4);
+        assert_eq!((face.level(), face.parent()), (0, None));
+        assert!(face.contains(&cell));
+    }
+
+    #[test]
+    fn points_land_in_their_cells() {
+        let mut rng = Rng::seed_from_u64(266);
+        for _ in 0..200 {
+            let p = random_unit(&mut rng);
+            for level in [0, 5, 17] {
+                let cell = CellId::from_point(&p, level);
+                assert!(angle(&cell.center(), &p) <= cell.radius() + 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn caps_are_covered() {
+        let mut rng = Rng::seed_from_u64(2662);
+        let c = random_unit(&mut rng);
+        let radius = 0.2;
+        let cover = cover_cap(&c, radius, 8);
+        assert!(cover.windows(2).all(|w| !w[0].contains(&w[1])));
+        assert!(cover.iter().any(|cell| cell.level() < 8));
+        for _ in 0..500 {
+            let p = random_unit(&mut rng);
+            let leaf = CellId::from_point(&p, MAX_LEVEL);
+            let covered = cover.iter().any(|cell| cell.contains(&leaf));
+            if angle(&c, &p) <= radius {
+                assert!(covered);
+            }
+        }
+        assert_eq!(cover_cap(&c, 4.0, 0).len(), 6);
+    }
+}
@@ -0,0 +1,398 @@
+// Per-axis summaries of point clouds that a few outliers can't wreck, and
+// the classical moments of any stream of points.
+//
+// The robust functions treat every coordinate axis as its own sample and
+// return one value per axis, or `None` for an empty cloud. Medians and
+// quantiles use selection rather than a full sort.
+//
+// The moment functions take anything that iterates over points (a slice of
+// `Point`s, `PointCloud::iter`, rows of a buffer) and make one pass with
+// Welford's updates, so long streams don't lose precision to cancellation.
+// Variances and covariances are population ones, dividing by the number of
+// points. They return `None` for no points and panic if the points don't
+// share a dimension.
+
+use crate::{Point, PointCloud};
+
+// The values of coordinate `k` over all points.
+fn column<T: Into<f64> + Copy>(cloud: &PointCloud<T>, k: usize) -> Vec<f64> {
+    cloud.iter().map(|p| p[k].into()).collect()
+}
+
+fn per_axis<T, F>(cloud: &PointCloud<T>, mut f: F) -> Option<Vec<f64>>
+where
+    T: Into<f64> + Copy,
+    F: FnMut(Vec<f64>) -> f64,
+{
+    if cloud.is_empty() {
+        return None;
+    }
+    Some((0..cloud.dim()).map(|k| f(column(cloud, k))).collect())
+}
+
+// Median of a non-empty sample, reordering it; the mean of the two middle
+// values when the length is even.
+fn median_of(v: &mut [f64]) -> f64 {
+    let (n, mid) = (v.len(), v.len() / 2);
+    let (below, &mut upper, _) = v.select_nth_unstable_by(mid, f64::total_cmp);
+    if n % 2 == 1 {
+        return upper;
+    }
+    let lower = below.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    (lower + upper) / 2.0
+}
+
+fn check_fraction(fraction: f64) {
+    assert!(
+        (0.0..0.5).contains(&fraction),
+        "trim fraction must be in [0, 0.5)"
+    );
+}
+
+/// Median of each axis.
+pub fn median<T: Into<f64> + Copy>(cloud: &PointCloud<T>) -> Option<Vec<f64>> {
+    per_axis(cloud, |mut v| median_of(&mut v))
+}
+
+/// Median absolute deviation from the median of each axis. Multiply by
+/// 1.4826 for a consistent estimate of a normal standard deviation.
+pub fn mad<T: Into<f64> + Copy>(cloud: &PointCloud<T>) -> Option<Vec<f64>> {
+    per_axis(cloud, |mut v| {
+        let m = median_of(&mut v);
+        v.iter_mut().for_each(|x| *x = (*x - m).abs());
+        median_of(&mut v)
+    })
+}
+
+/// Mean of each axis after dropping the lowest and highest `fraction` of the
+/// values (rounded down to whole points). Panics unless `fraction` is in
+/// `[0, 0.5)`.
+pub fn trimmed_mean<T: Into<f64> + Copy>(cloud: &PointCloud<T>, fraction: f64) -> Option<Vec<f64>> {
+    check_fraction(fraction);
+    per_axis(cloud, |mut v| {
+        let cut = (fraction * v.len() as f64) as usize;
+        v.sort_unstable_by(f64::total_cmp);
+        let kept = &v[cut..v.len() - cut];
+        kept.iter().sum::<f64>() / kept.len() as f64
+    })
+}
+
+/// Mean of each axis after clamping the lowest and highest `fraction` of the
+/// values to the nearest value kept. Panics unless `fraction` is in
+/// `[0, 0.5)`.
+pub fn winsorized_mean<T: Into<f64> + Copy>(
+    cloud: &PointCloud<T>,
+    fraction: f64,
+) -> Option<Vec<f64>> {
+    check_fraction(fraction);
+    per_axis(cloud, |mut v| {
+        let n = v.len();
+        let cut = (fraction * n as f64) as usize;
+        v.sort_unstable_by(f64::total_cmp);
+        let (lo, hi) = (v[cut], v[n - 1 - cut]);
+        v.iter().map(|x| x.clamp(lo, hi)).sum::<f64>() / n as f64
+    })
+}
+
+// Puts the values of the given sorted, distinct ranks at those positions of
+// `v`, as a full sort would, by selecting the middle rank and recursing into
+// either side.
+fn select_ranks(v: &mut [f64], ranks: &[usize]) {
+    if ranks.is_empty() {
+        return;
+    }
+    let m = ranks.len() / 2;
+    let r = ranks[m];
+    let (left, _, right) = v.select_nth_unstable_by(r, f64::total_cmp);
+    select_ranks(left, &ranks[..m]);
+    let shifted: Vec<usize> = ranks[m + 1..].iter().map(|&x| x - r - 1).collect();
+    select_ranks(right, &shifted);
+}
+
+/// The quantiles `qs` of each axis, interpolating linearly between order
+/// statistics (so `0.5` is the median and `0.0` and `1.0` the extremes).
+/// Entry `i` of the result holds quantile `qs[i]` of every axis. All
+/// quantiles of an axis come from one multi-way selection, without sorting.
+/// Panics if a quantile is outside `[0, 1]`.
+pub fn quantiles_axis<T: Into<f64> + Copy>(
+    cloud: &PointCloud<T>,
+    qs: &[f64],
+) -> Option<Vec<Vec<f64>>> {
+    assert!(
+        qs.iter().all(|q| (0.0..=1.0).contains(q)),
+        "quantiles must be in [0, 1]"
+    );
+    if cloud.is_empty() {
+        return None;
+    }
+    let n = cloud.len();
+    let positions: Vec<f64> = qs.iter().map(|q| q * (n - 1) as f64).collect();
+    let mut ranks: Vec<usize> = positions
+        .iter()
+        .flat_map(|&h| [h.floor() as usize, (h.ceil() as usize).min(n - 1)])
+        .collect();
+    ranks.sort_unstable();
+    ranks.dedup();
+    let mut out = vec![Vec::with_capacity(cloud.dim()); qs.len()];
+    for k in 0..cloud.dim() {
+        let mut v = column(cloud, k);
+        select_ranks(&mut v, &ranks);
+        for (o, &h) in out.iter_mut().zip(&positions) {
+            let lo = h.floor() as usize;
+            let hi = (h.ceil() as usize).min(n - 1);
+            o.push(v[lo] + (h - lo as f64) * (v[hi] - v[lo]));
+        }
+    }
+    Some(out)
+}
+
+/// Quantile `q` of each axis; see [`quantiles_axis`].
+pub fn quantile_axis<T: Into<f64> + Copy>(cloud: &PointCloud<T>, q: f64) -> Option<Vec<f64>> {
+    quantiles_axis(cloud, &[q]).map(|mut v| v.remove(0))
+}
+
+/// The centroid of `points`.
+pub fn mean_point<T, P>(points: impl IntoIterator<Item = P>) -> Option<Point<f64>>
+where
+    T: Into<f64> + Copy,
+    P: AsRef<[T]>,
+{
+    let (mean, _, _) = moments(points, false)?;
+    Some(Point::new(mean))
+}
+
+/// The variance of each axis.
+pub fn variance_axis<T, P>(points: impl IntoIterator<Item = P>) -> Option<Vec<f64>>
+where
+    T: Into<f64> + Copy,
+    P: AsRef<[T]>,
+{
+    let (_, squares, n) = moments(points, false)?;
+    Some(squares.iter().map(|s| s / n).collect())
+}
+
+/// The standard deviation of each axis.
+pub fn std_axis<T, P>(points: impl IntoIterator<Item = P>) -> Option<Vec<f64>>
+where
+    T: Into<f64> + Copy,
+    P: AsRef<[T]>,
+{
+    variance_axis(points).map(|v| v.iter().map(|x| x.sqrt()).collect())
+}
+
+/// The covariance matrix of the axes, as rows.
+pub fn covariance<T, P>(points: impl IntoIterator<Item = P>) -> Option<Vec<Vec<f64>>>
+where
+    T: Into<f64> + Copy,
+    P: AsRef<[T]>,
+{
+    let (mean, comoments, n) = moments(points, true)?;
+    let dim = mean.len();
+    Some(
+        comoments
+            .chunks_exact(dim)
+            .map(|row| row.iter().map(|c| c / n).collect())
+            .collect(),
+    )
+}
+
+/// The smallest coordinate on each axis.
+pub fn min_axis<T, P>(points: impl IntoIterator<Item = P>) -> Option<Vec<T>>
+where
+    T: PartialOrd + Copy,
+    P: AsRef<[T]>,
+{
+    extremes(points, |v, best| v < best)
+}
+
+/// The largest coordinate on each axis.
+pub fn max_axis<T, P>(points: impl IntoIterator<Item = P>) -> Option<Vec<T>>
+where
+    T: PartialOrd + Copy,
+    P: AsRef<[T]>,
+{
+    extremes(points, |v, best| v > best)
+}
+
+/// The point of per-axis medians (the mean of the two middle values on an
+/// axis with an even count). Needs a copy of every coordinate.
+pub fn median_point<T, P>(points: impl IntoIterator<Item = P>) -> Option<Point<f64>>
+where
+    T: Into<f64> + Copy,
+    P: AsRef<[T]>,
+{
+    let mut columns: Vec<Vec<f64>> = Vec::new();
+    for p in points {
+        let p = p.as_ref();
+        if columns.is_empty() {
+            columns = vec![Vec::new(); p.len()];
+        }
+        assert_eq!(p.len(), columns.len(), "points must share a dimension");
+        columns
+            .iter_mut()
+            .zip(p)
+            .for_each(|(c, &v)| c.push(v.into()));
+    }
+    if columns.first().is_none_or(|c| c.is_empty()) {
+        return None;
+    }
+    Some(Point::new(
+        columns.iter_mut().map(|c| median_of(c)).collect(),
+    ))
+}
+
+// One pass of Welford's algorithm: the mean, the sums of squared deviations
+// from it (every pair of axes, row-major, if `cross`, else each axis), and
+// the count.
+fn moments<T, P>(
+    points: impl IntoIterator<Item = P>,
+    cross: bool,
+) -> Option<(Vec<f64>, Vec<f64>, f64)>
+where
+    T: Into<f64> + Copy,
+    P: AsRef<[T]>,
+{
+    let mut points = points.into_iter().peekable();
+    let dim = points.peek()?.as_ref().len();
+    let mut mean = vec![0.0; dim];
+    let mut sums = vec![0.0; if cross { dim * dim } else { dim }];
+    let mut n = 0.0;
+    let mut before = vec![0.0; dim];
+    for p in points {
+        let p = p.as_ref();
+        assert_eq!(p.len(), dim, "points must share a dimension");
+        n += 1.0;
+        for k in 0..dim {
+            let v = p[k].into();
+            before[k] = v - mean[k];
+            mean[k] += before[k] / n;
+        }
+        if cross {
+            for i in 0..dim {
+                let after = p[i].into() - mean[i];
+                for j in 0..dim {
+                    sums[i * dim + j] += after * before[j];
+                }
+            }
+        } else {
+            for k in 0..dim {
+                sums[k] += before[k] * (p[k].into() - mean[k]);
+            }
+        }
+    }
+    Some((mean, sums, n))
+}
+
+fn extremes<T, P>(
+    points: impl IntoIterator<Item = P>,
+    better: impl Fn(T, T) -> bool,
+) -> Option<Vec<T>>
+where
+    T: PartialOrd + Copy,
+    P: AsRef<[T]>,
+{
+    let mut points = points.into_iter();
+    let mut best = points.next()?.as_ref().to_vec();
+    for p in points {
+        let p = p.as_ref();
+        assert_eq!(p.len(), best.len(), "points must share a dimension");
+        for (b, &v) in best.iter_mut().zip(p) {
+            if better(v, *b) {
+                *b = v;
+            }
+        }
+    }
+    Some(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cloud() -> PointCloud<f64> {
+        // The second axis has one wild outlier.
+        let flat = [
+            1.0, 10.0, 2.0, 11.0, 3.0, 9.0, 4.0, 10.0, 5.0, 1000.0, 6.0, 10.0, 7.0, 12.0, 8.0, 8.0,
+            9.0, 10.0, 10.0, 10.0,
+        ];
+        PointCloud::from_flat(&flat, 2).unwrap()
+    }
+
+    #[test]
+    fn medians() {
+        let c = cloud();
+        assert_eq!(median(&c), Some(vec![5.5, 10.0]));
+        assert_eq!(mad(&c), Some(vec![2.5, 0.5]));
+        let odd = PointCloud::from_flat(&[3, 1, 2], 1).unwrap();
+        assert_eq!(median(&odd), Some(vec![2.0]));
+        assert_eq!(median(&PointCloud::<f64>::new(2).unwrap()), None);
+    }
+
+    #[test]
+    fn trimmed_and_winsorized() {
+        let c = cloud();
+        assert_eq!(trimmed_mean(&c, 0.0).unwrap()[0], 5.5);
+        // Dropping one value from each end removes the outlier.
+        assert_eq!(trimmed_mean(&c, 0.1), Some(vec![5.5, 10.25]));
+        // Clamping it instead: 8 -> 9 and 1000 -> 12.
+        assert_eq!(winsorized_mean(&c, 0.1), Some(vec![5.5, 10.3]));
+        assert_eq!(winsorized_mean(&c, 0.0).unwrap()[1], 109.0);
+    }
+
+    #[test]
+    fn quantiles() {
+        let c = cloud();
+        let qs = quantiles_axis(&c, &[0.0, 0.25, 0.5, 0.9, 1.0]).unwrap();
+        assert_eq!(qs[0], vec![1.0, 8.0]);
+        assert_eq!(qs[1], vec![3.25, 10.0]);
+        assert_eq!(Some(qs[2].clone()), median(&c));
+        assert_eq!(qs[4], vec![10.0, 1000.0]);
+        assert!((qs[3][1] - 110.8).abs() < 1e-9);
+        assert_eq!(quantile_axis(&c, 0.5), median(&c));
+
+        let mut v: Vec<f64> = (0..100).map(|i| ((i * 37) % 100) as f64).collect();
+        select_ranks(&mut v, &[3, 50, 51, 97]);
+        assert_eq!((v[3], v[50], v[51], v[97]), (3.0, 50.0, 51.0, 97.0));
+        assert_eq!(
+            quantile_axis(&PointCloud::<f64>::new(1).unwrap(), 0.5),
+            None
+        );
+    }
+
+    #[test]
+    fn moments_of_streams() {
+        let pts = [
+            Point::new(vec![1.0, 2.0]),
+            Point::new(vec![3.0, 6.0]),
+            Point::new(vec![5.0, 4.0]),
+            Point::new(vec![7.0, 8.0]),
+        ];
+        assert_eq!(mean_point(&pts).unwrap().data(), [4.0, 5.0]);
+        assert_eq!(variance_axis(&pts), Some(vec![5.0, 5.0]));
+        assert_eq!(std_axis(&pts).unwrap()[0], 5f64.sqrt());
+        let cov = covariance(&pts).unwrap();
+        assert_eq!(cov, vec![vec![5.0, 4.0], vec![4.0, 5.0]]);
+        // Far from the origin the one-pass updates keep their precision.
+        let shifted: Vec<Vec<f64>> = pts
+            .iter()
+            .map(|p| p.iter().map(|v| v + 1e9).collect())
+            .collect();
+        let far = covariance(&shifted).unwrap();
+        assert!((far[0][1] - 4.0).abs() < 1e-6);
+        assert!(mean_point(Vec::<Vec<f64>>::new()).is_none());
+        assert!(std::panic::catch_unwind(|| mean_point([vec![1.0], vec![1.0, 2.0]])).is_err());
+    }
+
+    #[test]
+    fn extremes_and_median_point() {
+        let c = cloud();
+        assert_eq!(min_axis(c.iter()), Some(vec![1.0, 8.0]));
+        assert_eq!(max_axis(c.iter()), Some(vec![10.0, 1000.0]));
+        assert_eq!(median_point(c.iter()).unwrap().data(), [5.5, 10.0]);
+        assert_eq!(mean_point(c.iter()).unwrap().data(), [5.5, 109.0]);
+        let ints = [[3, -1], [1, 4], [2, 0]];
+        assert_eq!(min_axis(ints), Some(vec![1, -1]));
+        assert_eq!(median_point(ints).unwrap().data(), [2.0, 0.0]);
+        assert_eq!(max_axis(Vec::<[i32; 2]>::new()), None);
+    }
+}
@@ -0,0 +1,236 @@
+// Where a point's coordinates live.
+//
+// `Point<T, S>` is generic over its storage backend. The default,
+// `VecStorage`, owns the coordinates, inline for up to four dimensions and
+// on the heap beyond. `ArrayStorage<T, N>` owns a fixed-size array, so such
+// points (`PointN`) are `Copy` and never allocate. `SliceStorage` borrows a
+// slice, e.g. a row of a `PointCloud`, without copying it, and
+// `SharedStorage` (see `shared`) keeps the coordinates behind an `Arc`.
+//
+// All backends share the read-only API (dimension, norms, distances,
+// indexing, iteration), and the mutable backends add in-place access. The
+// arithmetic operators work on every backend and across backends: a result
+// is built in the left operand's backend when that backend owns its
+// coordinates, so arrays stay arrays, and in the default storage for a
+// borrowed slice.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use crate::Point;
+use crate::smallbuf::SmallBuf;
+
+/// Read access to a contiguous run of coordinates.
+pub trait Storage<T> {
+    fn as_slice(&self) -> &[T];
+}
+
+/// Storage whose coordinates can be changed in place.
+pub trait StorageMut<T>: Storage<T> {
+    fn as_mut_slice(&mut self) -> &mut [T];
+}
+
+/// Storage that arithmetic on its points can build results in.
+pub trait BuildStorage<T>: Storage<T> {
+    /// Where the results go.
+    type Output: StorageMut<T>;
+
+    /// Collects a result with the same dimension as the operands.
+    fn build(coords: impl Iterator<Item = T>) -> Self::Output;
+}
+
+/// Owned coordinates of any dimension; the default storage of `Point`.
+#[derive(Clone)]
+pub struct VecStorage<T>(SmallBuf<T>);
+
+impl<T: Copy> VecStorage<T> {
+    pub(crate) fn from_slice(s: &[T]) -> Self {
+        VecStorage(SmallBuf::from_slice(s))
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<T> {
+        self.0.into_vec()
+    }
+}
+
+impl<T> VecStorage<T> {
+    #[cfg(test)]
+    pub(crate) fn is_inline(&self) -> bool {
+        self.0.is_inline()
+    }
+}
+
+impl<T> Deref for VecStorage<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for VecStorage<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.0
+    }
+}
+
+impl<T: Copy> From<Vec<T>> for VecStorage<T> {
+    fn from(v: Vec<T>) -> Self {
+        VecStorage(v.into())
+    }
+}
+
+impl<T: Copy> FromIterator<T> for VecStorage<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        VecStorage(iter.into_iter().collect())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for VecStorage<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: PartialEq> PartialEq<Vec<T>> for VecStorage<T> {
+    fn eq(&self, other: &Vec<T>) -> bool {
+        self.0 == *other
+    }
+}
+
+impl<T> Storage<T> for VecStorage<T> {
+    fn as_slice(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<T> StorageMut<T> for VecStorage<T> {
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.0
+    }
+}
+
+impl<T: Copy> BuildStorage<T> for VecStorage<T> {
+    type Output = VecStorage<T>;
+
+    fn build(coords: impl Iterator<Item = T>) -> Self::Output {
+        coords.collect()
+    }
+}
+
+/// Exactly `N` owned coordinates in an array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ArrayStorage<T, const N: usize>(pub [T; N]);
+
+impl<T, const N: usize> Storage<T> for ArrayStorage<T, N> {
+    fn as_slice(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<T, const N: usize> StorageMut<T> for ArrayStorage<T, N> {
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.0
+    }
+}
+
+impl<T, const N: usize> BuildStorage<T> for ArrayStorage<T, N> {
+    type Output = ArrayStorage<T, N>;
+
+    fn build(mut coords: impl Iterator<Item = T>) -> Self::Output {
+        ArrayStorage(std::array::from_fn(|_| {
+            coords.next().expect("operands share the dimension N")
+        }))
+    }
+}
+
+/// Coordinates borrowed from elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SliceStorage<'a, T>(pub &'a [T]);
+
+impl<T> Storage<T> for SliceStorage<'_, T> {
+    fn as_slice(&self) -> &[T] {
+        self.0
+    }
+}
+
+impl<T: Copy> BuildStorage<T> for SliceStorage<'_, T> {
+    type Output = VecStorage<T>;
+
+    fn build(coords: impl Iterator<Item = T>) -> Self::Output {
+        coords.collect()
+    }
+}
+
+impl<T, S: Storage<T>> Point<T, S> {
+    /// A point backed by `storage`.
+    pub fn from_storage(storage: S) -> Self {
+        Point {
+            p: storage,
+            coords: PhantomData,
+        }
+    }
+
+    pub fn storage(&self) -> &S {
+        &self.p
+    }
+
+    pub fn into_storage(self) -> S {
+        self.p
+    }
+
+    /// A copy of the coordinates in the default storage.
+    pub fn to_point(&self) -> Point<T>
+    where
+        T: Copy,
+    {
+        Point::from_storage(VecStorage::from_slice(self.p.as_slice()))
+    }
+}
+
+impl<T, const N: usize> Point<T, ArrayStorage<T, N>> {
+    /// A point stored in an array, of dimension `N`.
+    pub fn from_array(coords: [T; N]) -> Self {
+        Point::from_storage(ArrayStorage(coords))
+    }
+}
+
+impl<'a, T> Point<T, SliceStorage<'a, T>> {
+    /// A point that borrows `coords` instead of copying them.
+    pub fn view(coords: &'a [T]) -> Self {
+        Point::from_storage(SliceStorage(coords))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backends_share_the_api() {
+        let owned = Point::new(vec![3.0, 4.0]);
+        let array = Point::from_array([3.0, 4.0]);
+        let flat = [0.0, 3.0, 4.0];
+        let view = Point::view(&flat[1..]);
+        assert_eq!((owned.dim(), array.dim(), view.dim()), (2, 2, 2));
+        assert_eq!((owned.dist(), array.dist(), view.dist()), (5.0, 5.0, 5.0));
+        assert_eq!(array.dot(&view), 25.0);
+        assert_eq!(view.distance_to(&owned), 0.0);
+        assert_eq!(view[1], 4.0);
+        assert_eq!(view.to_point().data(), owned.data());
+        // Operators mix backends; a borrowed left operand gives an owned
+        // result, an array one an array.
+        assert_eq!((&view + &owned).into_vec(), vec![6.0, 8.0]);
+        assert_eq!((array * 2.0 - owned).to_array(), [3.0, 4.0]);
+
+        // Array points are `Copy` and can be edited in place.
+        let mut a = array;
+        a[0] = 0.0;
+        assert_eq!(
+            (a.as_slice(), array.as_slice()),
+            (&[0.0, 4.0][..], &[3.0, 4.0][..])
+        );
+        assert_eq!(a.into_storage(), ArrayStorage([0.0, 4.0]));
+    }
+}
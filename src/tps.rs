@@ -0,0 +1,236 @@
+// Thin-plate-spline warps between landmark sets (Bookstein, 1989).
+//
+// The warp is an affine map plus a sum of radial kernels centred on the
+// source landmarks, fitted so every source landmark lands on its target and
+// the bending in between is as small as possible. The kernel is the
+// polyharmonic one that makes this the thin-plate energy: `r^2 log r` in
+// 2D, `-r` in 3D, and `r^3` on a line; higher dimensions use `-r` as well,
+// which still interpolates smoothly. A positive `smoothing` trades exact
+// interpolation for less bending, for noisy landmarks.
+//
+// Fitting solves one dense system of `n + dim + 1` equations, so it suits
+// the tens to hundreds of landmarks of registration work; applying the
+// warp costs `O(n)` per point.
+
+use crate::deform::Deformer;
+use crate::linalg::{Matrix, norm};
+use crate::transform::Transform;
+use crate::{Point, Storage};
+
+#[derive(Debug, Clone)]
+pub struct ThinPlateSpline {
+    source: Vec<Vec<f64>>,
+    // One row of kernel weights per landmark, one column per output axis.
+    weights: Vec<Vec<f64>>,
+    // Constant row then one row per input axis, as for `weights`.
+    affine: Vec<Vec<f64>>,
+}
+
+impl ThinPlateSpline {
+    /// The warp taking every `source` landmark exactly to its `target`;
+    /// `None` if the landmarks don't pin down the affine part (all on a
+    /// line in 2D, a plane in 3D, and so on). Panics unless the landmark
+    /// lists match in length and dimension.
+    pub fn fit<T, S>(source: &[Point<T, S>], target: &[Point<T, S>]) -> Option<Self>
+    where
+        T: Into<f64> + Copy,
+        S: Storage<T>,
+    {
+        ThinPlateSpline::fit_smoothed(source, target, 0.0)
+    }
+
+    /// As [`ThinPlateSpline::fit`], but approximating the targets: larger
+    /// `smoothing` bends less and misses the targets by more, and very
+    /// large values tend to the least-squares affine fit.
+    pub fn fit_smoothed<T, S>(
+        source: &[Point<T, S>],
+        target: &[Point<T, S>],
+        smoothing: f64,
+    ) -> Option<Self>
+    where
+        T: Into<f64> + Copy,
+        S: Storage<T>,
+    {
+        assert_eq!(source.len(), target.len(), "one target per landmark");
+        let to_f64 =
+            |p: &Point<T, S>| -> Vec<f64> { p.as_slice().iter().map(|&v| v.into()).collect() };
+        let src: Vec<Vec<f64>> = source.iter().map(to_f64).collect();
+        let dst: Vec<Vec<f64>> = target.iter().map(to_f64).collect();
+        let dim = src.first()?.len();
+        assert!(
+            src.iter().chain(&dst).all(|p| p.len() == dim),
+            "landmarks must share a dimension"
+        );
+        let n = src.len();
+        // [K + smoothing I, P; P^T, 0] with P = [1, source].
+        let size = n + dim + 1;
+        let mut system = Matrix::zeros(size, size);
+        for i in 0..n {
+            for j in 0..n {
+                system[(i, j)] = kernel(dim, distance(&src[i], &src[j]));
+            }
+            system[(i, i)] += smoothing;
+            system[(i, n)] = 1.0;
+            system[(n, i)] = 1.0;
+            for k in 0..dim {
+                system[(i, n + 1 + k)] = src[i][k];
+                system[(n + 1 + k, i)] = src[i][k];
+            }
+        }
+        let mut weights = vec![vec![0.0; dim]; n];
+        let mut affine = vec![vec![0.0; dim]; dim + 1];
+        for axis in 0..dim {
+            let mut rhs = vec![0.0; size];
+            for i in 0..n {
+                rhs[i] = dst[i][axis];
+            }
+            let x = system.solve(&rhs)?;
+            for i in 0..n {
+                weights[i][axis] = x[i];
+            }
+            for k in 0..=dim {
+                affine[k][axis] = x[n + k];
+            }
+        }
+        Some(ThinPlateSpline {
+            source: src,
+            weights,
+            affine,
+        })
+    }
+
+    pub fn dim(&self) -> usize {
+        self.affine[0].len()
+    }
+
+    /// The affine part of the warp: what is left far from the landmarks.
+    pub fn affine(&self) -> Transform {
+        let dim = self.dim();
+        let linear: Vec<Vec<f64>> = (0..dim)
+            .map(|out| (0..dim).map(|k| self.affine[k + 1][out]).collect())
+            .collect();
+        Transform::new(&linear, &self.affine[0]).expect("square linear part")
+    }
+
+    /// The bending energy of the warp, summed over output axes; zero for
+    /// a purely affine warp.
+    pub fn bending_energy(&self) -> f64 {
+        let dim = self.dim();
+        let mut energy = 0.0;
+        for (i, p) in self.source.iter().enumerate() {
+            for (j, q) in self.source.iter().enumerate() {
+                let k = kernel(dim, distance(p, q));
+                energy += k
+                    * (0..dim)
+                        .map(|a| self.weights[i][a] * self.weights[j][a])
+                        .sum::<f64>();
+            }
+        }
+        energy
+    }
+}
+
+impl Deformer for ThinPlateSpline {
+    /// Panics if `p` has the wrong dimension.
+    fn deform(&self, p: &[f64]) -> Vec<f64> {
+        let dim = self.dim();
+        assert_eq!(p.len(), dim, "point and warp dimensions differ");
+        let mut q = self.affine[0].clone();
+        for (k, &x) in p.iter().enumerate() {
+            q.iter_mut()
+                .zip(&self.affine[k + 1])
+                .for_each(|(q, a)| *q += a * x);
+        }
+        for (s, w) in self.source.iter().zip(&self.weights) {
+            let u = kernel(dim, distance(p, s));
+            q.iter_mut().zip(w).for_each(|(q, w)| *q += u * w);
+        }
+        q
+    }
+}
+
+// The thin-plate kernel for `dim` dimensions at distance `r`.
+fn kernel(dim: usize, r: f64) -> f64 {
+    match dim {
+        1 => r * r * r,
+        2 if r > 0.0 => r * r * r.ln(),
+        2 => 0.0,
+        _ => -r,
+    }
+}
+
+fn distance(a: &[f64], b: &[f64]) -> f64 {
+    let d: Vec<f64> = a.iter().zip(b).map(|(x, y)| x - y).collect();
+    norm(&d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PointCloud;
+
+    fn points(coords: &[[f64; 2]]) -> Vec<Point<f64>> {
+        coords.iter().map(|c| Point::new(c.to_vec())).collect()
+    }
+
+    #[test]
+    fn interpolates_landmarks() {
+        let source = points(&[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0], [0.5, 0.5]]);
+        let target = points(&[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0], [0.6, 0.7]]);
+        let tps = ThinPlateSpline::fit(&source, &target).unwrap();
+        for (s, t) in source.iter().zip(&target) {
+            let q = tps.deform(s.data());
+            assert!(q.iter().zip(t.data()).all(|(a, b)| (a - b).abs() < 1e-9));
+        }
+        assert!(tps.bending_energy() > 0.0);
+        // The warp fades out smoothly between the landmarks.
+        let q = tps.deform(&[0.25, 0.25]);
+        assert!(q[0] > 0.25 && q[0] < 0.6 && q[1] > 0.25 && q[1] < 0.7);
+
+        let mut cloud = PointCloud::from_flat(&[0.5, 0.5, 0.0, 1.0], 2).unwrap();
+        tps.apply(&mut cloud);
+        assert!((cloud.as_flat()[0] - 0.6).abs() < 1e-9 && (cloud.as_flat()[3] - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn affine_landmarks_give_an_affine_warp() {
+        let map = |p: &[f64]| vec![2.0 * p[0] - p[1] + 1.0, p[0] + 3.0 * p[1] - 2.0, p[2] + 0.5];
+        let coords = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [1.0, 1.0, 1.0],
+            [0.3, 0.8, 0.1],
+        ];
+        let source: Vec<Point<f64>> = coords.iter().map(|c| Point::new(c.to_vec())).collect();
+        let target: Vec<Point<f64>> = coords.iter().map(|c| Point::new(map(c))).collect();
+        let tps = ThinPlateSpline::fit(&source, &target).unwrap();
+        assert!(tps.bending_energy() < 1e-9);
+        let far = [10.0, -4.0, 7.0];
+        let q = tps.deform(&far);
+        assert!(q.iter().zip(map(&far)).all(|(a, b)| (a - b).abs() < 1e-8));
+        let affine = tps.affine().apply(&Point::new(far.to_vec()));
+        assert!(
+            affine
+                .iter()
+                .zip(map(&far))
+                .all(|(a, b)| (a - b).abs() < 1e-8)
+        );
+    }
+
+    #[test]
+    fn smoothing_and_degenerate_landmarks() {
+        let source = points(&[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0], [0.5, 0.5]]);
+        let target = points(&[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0], [0.5, 0.9]]);
+        let exact = ThinPlateSpline::fit(&source, &target).unwrap();
+        let smooth = ThinPlateSpline::fit_smoothed(&source, &target, 1.0).unwrap();
+        assert!(smooth.bending_energy() < exact.bending_energy());
+        let y = smooth.deform(&[0.5, 0.5])[1];
+        assert!(y > 0.5 && y < 0.9);
+
+        let line = points(&[[0.0, 0.0], [1.0, 1.0], [2.0, 2.0]]);
+        assert!(ThinPlateSpline::fit(&line, &line).is_none());
+        assert!(std::panic::catch_unwind(|| ThinPlateSpline::fit(&line, &line[..2])).is_err());
+    }
+}
@@ -0,0 +1,716 @@
+// Affine transforms `x -> A x + t` in any dimension.
+//
+// Besides applying and composing transforms this splits them into parts that
+// are easier to reason about. `decompose` factors the linear part as
+// rotation * scale * shear, in that order from the left, by Gram-Schmidt on
+// its columns. `polar` gives the closest rotation and the symmetric stretch
+// left over, by Newton iteration on `(R + R^-T) / 2`.
+
+use std::cmp::Ordering;
+use std::ops::Mul;
+
+use crate::linalg::Matrix;
+use crate::{Error, Point, PointCloud};
+
+const TOL: f64 = 1e-9;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transform {
+    linear: Matrix,
+    translation: Vec<f64>,
+}
+
+/// The parts of an invertible affine transform: a point is sheared, then
+/// scaled along the axes, then rotated, then translated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decomposition {
+    pub translation: Vec<f64>,
+    /// A proper rotation, row-major.
+    pub rotation: Vec<Vec<f64>>,
+    /// Per-axis scale; the last one is negative when the transform mirrors.
+    pub scale: Vec<f64>,
+    /// Unit upper-triangular, row-major.
+    pub shear: Vec<Vec<f64>>,
+}
+
+/// The linear part of a transform as `rotation * stretch`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polar {
+    /// Orthogonal; a mirror when the determinant is negative.
+    pub rotation: Vec<Vec<f64>>,
+    /// Symmetric positive definite.
+    pub stretch: Vec<Vec<f64>>,
+}
+
+impl Transform {
+    pub fn identity(dim: usize) -> Self {
+        Transform {
+            linear: Matrix::identity(dim),
+            translation: vec![0.0; dim],
+        }
+    }
+
+    /// `x -> linear x + translation` with `linear` given row by row.
+    pub fn new(linear: &[Vec<f64>], translation: &[f64]) -> Result<Self, Error> {
+        let dim = translation.len();
+        if dim == 0 {
+            return Err(Error::ZeroDimension);
+        }
+        if let Some(row) = linear.iter().find(|r| r.len() != dim) {
+            return Err(Error::DimensionMismatch {
+                expected: dim,
+                found: row.len(),
+            });
+        }
+        if linear.len() != dim {
+            return Err(Error::DimensionMismatch {
+                expected: dim,
+                found: linear.len(),
+            });
+        }
+        Ok(Transform {
+            linear: Matrix::from_rows(linear),
+            translation: translation.to_vec(),
+        })
+    }
+
+    pub fn translation(offset: &[f64]) -> Self {
+        Transform {
+            linear: Matrix::identity(offset.len()),
+            translation: offset.to_vec(),
+        }
+    }
+
+    pub fn scaling(factors: &[f64]) -> Self {
+        let mut t = Transform::identity(factors.len());
+        for (i, &f) in factors.iter().enumerate() {
+            t.linear[(i, i)] = f;
+        }
+        t
+    }
+
+    /// Counter-clockwise rotation of the plane by `theta` radians.
+    pub fn rotation_2d(theta: f64) -> Self {
+        Transform {
+            linear: rotation_2d(theta),
+            translation: vec![0.0, 0.0],
+        }
+    }
+
+    /// Rotation by `angle` radians in the plane of axes `i` and `j`, from `i`
+    /// towards `j`, leaving the other axes fixed; in n dimensions every
+    /// rotation is a product of these. Panics unless `i` and `j` are
+    /// distinct axes below `dim`.
+    pub fn rotation_plane(dim: usize, i: usize, j: usize, angle: f64) -> Self {
+        assert!(
+            i != j && i < dim && j < dim,
+            "rotation needs two distinct axes"
+        );
+        let mut t = Transform::identity(dim);
+        let (s, c) = angle.sin_cos();
+        t.linear[(i, i)] = c;
+        t.linear[(j, j)] = c;
+        t.linear[(i, j)] = -s;
+        t.linear[(j, i)] = s;
+        t
+    }
+
+    /// The shear `x_i += k x_j`. Panics unless `i` and `j` are distinct axes
+    /// below `dim`.
+    pub fn from_shear(dim: usize, i: usize, j: usize, k: f64) -> Self {
+        assert!(
+            i != j && i < dim && j < dim,
+            "shear needs two distinct axes"
+        );
+        let mut t = Transform::identity(dim);
+        t.linear[(i, j)] = k;
+        t
+    }
+
+    pub fn dim(&self) -> usize {
+        self.translation.len()
+    }
+
+    /// The linear part, row by row.
+    pub fn linear(&self) -> Vec<Vec<f64>> {
+        self.linear.to_rows()
+    }
+
+    pub fn offset(&self) -> &[f64] {
+        &self.translation
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.linear.determinant()
+    }
+
+    /// Maps `p`. Panics if its dimension differs from the transform's.
+    pub fn apply<T>(&self, p: &Point<T>) -> Point<f64>
+    where
+        T: Into<f64> + Copy,
+    {
+        assert_eq!(p.dim(), self.dim(), "point and transform dimensions differ");
+        let x: Vec<f64> = p.iter().map(|&v| v.into()).collect();
+        let mut y = self.linear.mul_vec(&x);
+        y.iter_mut()
+            .zip(&self.translation)
+            .for_each(|(v, t)| *v += t);
+        Point::new(y)
+    }
+
+    /// The transform that applies `self` first and then `next`.
+    pub fn then(&self, next: &Transform) -> Transform {
+        assert_eq!(self.dim(), next.dim(), "transform dimensions differ");
+        let mut translation = next.linear.mul_vec(&self.translation);
+        translation
+            .iter_mut()
+            .zip(&next.translation)
+            .for_each(|(v, t)| *v += t);
+        Transform {
+            linear: &next.linear * &self.linear,
+            translation,
+        }
+    }
+
+    /// `None` if the linear part is singular.
+    pub fn inverse(&self) -> Option<Transform> {
+        let linear = self.linear.inverse()?;
+        let translation = linear
+            .mul_vec(&self.translation)
+            .iter()
+            .map(|v| -v)
+            .collect();
+        Some(Transform {
+            linear,
+            translation,
+        })
+    }
+
+    /// The transform a fraction `t` of the way from `self` to `other`, for
+    /// `t` in [0, 1]. Both are decomposed; translation, scale and shear are
+    /// interpolated linearly and the rotation along the shortest arc. `None`
+    /// if either is singular. Panics if the dimensions differ.
+    pub fn interpolate(&self, other: &Transform, t: f64) -> Option<Transform> {
+        assert_eq!(self.dim(), other.dim(), "transform dimensions differ");
+        let (a, b) = (self.decompose()?, other.decompose()?);
+        let rotation = interpolate_rotation(
+            &Matrix::from_rows(&a.rotation),
+            &Matrix::from_rows(&b.rotation),
+            t,
+        )?;
+        let shear = a
+            .shear
+            .iter()
+            .zip(&b.shear)
+            .map(|(x, y)| lerp(x, y, t))
+            .collect();
+        Some(
+            Decomposition {
+                translation: lerp(&a.translation, &b.translation, t),
+                rotation: rotation.to_rows(),
+                scale: lerp(&a.scale, &b.scale, t),
+                shear,
+            }
+            .recompose(),
+        )
+    }
+
+    /// Maps every point of `cloud` in place, reporting each edit to its
+    /// observers. Panics if the dimensions differ.
+    pub fn apply_in_place(&self, cloud: &mut PointCloud<f64>) {
+        assert_eq!(
+            cloud.dim(),
+            self.dim(),
+            "cloud and transform dimensions differ"
+        );
+        for i in 0..cloud.len() {
+            cloud.update(i, |p| {
+                let y = self.linear.mul_vec(p);
+                for ((v, y), t) in p.iter_mut().zip(y).zip(&self.translation) {
+                    *v = y + t;
+                }
+            });
+        }
+    }
+
+    /// [`Transform::apply_in_place`] spread over all cores.
+    #[cfg(feature = "parallel")]
+    pub fn par_apply_in_place(&self, cloud: &mut PointCloud<f64>) {
+        assert_eq!(
+            cloud.dim(),
+            self.dim(),
+            "cloud and transform dimensions differ"
+        );
+        cloud.par_update_all(|p| {
+            let y = self.linear.mul_vec(p);
+            for ((v, y), t) in p.iter_mut().zip(y).zip(&self.translation) {
+                *v = y + t;
+            }
+        });
+    }
+
+    /// Splits the transform into rotation, scale, shear and translation;
+    /// `None` if the linear part is singular.
+    pub fn decompose(&self) -> Option<Decomposition> {
+        let n = self.dim();
+        let a = &self.linear;
+        let scale_tol = TOL * frobenius(a).max(1.0);
+        // Modified Gram-Schmidt: a = q u with u upper triangular.
+        let mut q: Vec<Vec<f64>> = (0..n).map(|j| a.column(j)).collect();
+        let mut u = Matrix::zeros(n, n);
+        for j in 0..n {
+            for i in 0..j {
+                let r: f64 = q[i].iter().zip(&q[j]).map(|(x, y)| x * y).sum();
+                u[(i, j)] = r;
+                let qi = q[i].clone();
+                q[j].iter_mut().zip(&qi).for_each(|(v, w)| *v -= r * w);
+            }
+            let len = q[j].iter().map(|v| v * v).sum::<f64>().sqrt();
+            if len <= scale_tol {
+                return None;
+            }
+            u[(j, j)] = len;
+            q[j].iter_mut().for_each(|v| *v /= len);
+        }
+        // Columns of `q` are the rotation's columns; keep it proper by moving
+        // a mirror into the last scale.
+        let mut rotation = Matrix::zeros(n, n);
+        for (j, col) in q.iter().enumerate() {
+            for (i, &v) in col.iter().enumerate() {
+                rotation[(i, j)] = v;
+            }
+        }
+        if rotation.determinant() < 0.0 {
+            for i in 0..n {
+                rotation[(i, n - 1)] = -rotation[(i, n - 1)];
+            }
+            u[(n - 1, n - 1)] = -u[(n - 1, n - 1)];
+        }
+        let scale: Vec<f64> = (0..n).map(|i| u[(i, i)]).collect();
+        let shear = (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|j| match j.cmp(&i) {
+                        Ordering::Less => 0.0,
+                        Ordering::Equal => 1.0,
+                        Ordering::Greater => u[(i, j)] / u[(i, i)],
+                    })
+                    .collect()
+            })
+            .collect();
+        Some(Decomposition {
+            translation: self.translation.clone(),
+            rotation: rotation.to_rows(),
+            scale,
+            shear,
+        })
+    }
+
+    /// Polar decomposition of the linear part; `None` if it is singular.
+    pub fn polar(&self) -> Option<Polar> {
+        let r = closest_rotation(&self.linear)?;
+        let s = &r.transpose() * &self.linear;
+        let mut stretch = s.clone();
+        for i in 0..s.rows() {
+            for j in 0..s.cols() {
+                stretch[(i, j)] = 0.5 * (s[(i, j)] + s[(j, i)]);
+            }
+        }
+        Some(Polar {
+            rotation: r.to_rows(),
+            stretch: stretch.to_rows(),
+        })
+    }
+
+    /// True for rotations plus translations: distances and handedness are
+    /// preserved.
+    pub fn is_rigid(&self) -> bool {
+        self.gram_scale().is_some_and(|s2| (s2 - 1.0).abs() <= TOL)
+    }
+
+    /// True for rigid motions with a uniform scale: angles and handedness are
+    /// preserved.
+    pub fn is_similarity(&self) -> bool {
+        self.gram_scale().is_some()
+    }
+
+    // The `s^2` with `A^T A = s^2 I` and `det A > 0`, if there is one.
+    fn gram_scale(&self) -> Option<f64> {
+        let n = self.dim();
+        let g = &self.linear.transpose() * &self.linear;
+        let s2 = (0..n).map(|i| g[(i, i)]).sum::<f64>() / n as f64;
+        let uniform = (0..n).all(|i| {
+            (0..n).all(|j| {
+                let want = if i == j { s2 } else { 0.0 };
+                (g[(i, j)] - want).abs() <= TOL * s2.max(1.0)
+            })
+        });
+        (uniform && s2 > 0.0 && self.determinant() > 0.0).then_some(s2)
+    }
+}
+
+/// `a * b` applies `b` first, like the matrix product; `b.then(&a)`.
+impl Mul for &Transform {
+    type Output = Transform;
+
+    fn mul(self, rhs: &Transform) -> Transform {
+        rhs.then(self)
+    }
+}
+
+impl Mul for Transform {
+    type Output = Transform;
+
+    fn mul(self, rhs: Transform) -> Transform {
+        rhs.then(&self)
+    }
+}
+
+// The orthogonal factor of the polar decomposition of `m`.
+pub(crate) fn closest_rotation(m: &Matrix) -> Option<Matrix> {
+    let mut r = m.clone();
+    for _ in 0..100 {
+        let inv_t = r.inverse()?.transpose();
+        let mut next = r.clone();
+        let mut change = 0.0f64;
+        for i in 0..r.rows() {
+            for j in 0..r.cols() {
+                next[(i, j)] = 0.5 * (r[(i, j)] + inv_t[(i, j)]);
+                change = change.max((next[(i, j)] - r[(i, j)]).abs());
+            }
+        }
+        r = next;
+        if change <= 1e-15 {
+            break;
+        }
+    }
+    Some(r)
+}
+
+fn rotation_2d(theta: f64) -> Matrix {
+    let (s, c) = theta.sin_cos();
+    Matrix::from_rows(&[vec![c, -s], vec![s, c]])
+}
+
+// Rotation by `angle` about the unit `axis` (Rodrigues' formula).
+fn rotation_3d(axis: [f64; 3], angle: f64) -> Matrix {
+    let (s, c) = angle.sin_cos();
+    let [x, y, z] = axis;
+    let k = Matrix::from_rows(&[vec![0.0, -z, y], vec![z, 0.0, -x], vec![-y, x, 0.0]]);
+    let k2 = &k * &k;
+    let mut r = Matrix::identity(3);
+    for i in 0..3 {
+        for j in 0..3 {
+            r[(i, j)] += s * k[(i, j)] + (1.0 - c) * k2[(i, j)];
+        }
+    }
+    r
+}
+
+// Axis and angle of a 3D rotation matrix, the angle in [0, pi].
+fn axis_angle(r: &Matrix) -> ([f64; 3], f64) {
+    let trace = r[(0, 0)] + r[(1, 1)] + r[(2, 2)];
+    let angle = ((trace - 1.0) / 2.0).clamp(-1.0, 1.0).acos();
+    let skew = [
+        r[(2, 1)] - r[(1, 2)],
+        r[(0, 2)] - r[(2, 0)],
+        r[(1, 0)] - r[(0, 1)],
+    ];
+    let len = skew.iter().map(|v| v * v).sum::<f64>().sqrt();
+    if len > 1e-6 {
+        return (skew.map(|v| v / len), angle);
+    }
+    if angle < 1.0 {
+        return ([1.0, 0.0, 0.0], 0.0);
+    }
+    // Half turn: r = 2 a a^T - I, read the axis off the largest diagonal.
+    let k = (0..3)
+        .max_by(|&a, &b| r[(a, a)].total_cmp(&r[(b, b)]))
+        .unwrap();
+    let ak = ((r[(k, k)] + 1.0) / 2.0).max(0.0).sqrt();
+    let mut axis = [0.0; 3];
+    for (j, a) in axis.iter_mut().enumerate() {
+        *a = if j == k {
+            ak
+        } else {
+            (r[(k, j)] + r[(j, k)]) / (4.0 * ak)
+        };
+    }
+    (axis, angle)
+}
+
+// Rotation a fraction `t` of the way from `r0` to `r1`: along the shortest
+// arc in 2D and 3D, and by re-orthogonalising the blended matrices in other
+// dimensions, which fails when they are half a turn apart.
+fn interpolate_rotation(r0: &Matrix, r1: &Matrix, t: f64) -> Option<Matrix> {
+    let rel = &r0.transpose() * r1;
+    let step = match r0.rows() {
+        1 => Matrix::identity(1),
+        2 => rotation_2d(t * rel[(1, 0)].atan2(rel[(0, 0)])),
+        3 => {
+            let (axis, angle) = axis_angle(&rel);
+            rotation_3d(axis, t * angle)
+        }
+        _ => {
+            let mut blend = r0.clone();
+            for i in 0..r0.rows() {
+                for j in 0..r0.cols() {
+                    blend[(i, j)] = (1.0 - t) * r0[(i, j)] + t * r1[(i, j)];
+                }
+            }
+            return closest_rotation(&blend);
+        }
+    };
+    Some(r0 * &step)
+}
+
+fn lerp(a: &[f64], b: &[f64], t: f64) -> Vec<f64> {
+    a.iter().zip(b).map(|(x, y)| x + t * (y - x)).collect()
+}
+
+fn frobenius(m: &Matrix) -> f64 {
+    (0..m.rows())
+        .flat_map(|i| m.row(i).iter())
+        .map(|v| v * v)
+        .sum::<f64>()
+        .sqrt()
+}
+
+impl Decomposition {
+    /// Puts the parts back together.
+    pub fn recompose(&self) -> Transform {
+        let n = self.scale.len();
+        let rotation = Matrix::from_rows(&self.rotation);
+        let mut scaled_shear = Matrix::from_rows(&self.shear);
+        for i in 0..n {
+            for j in 0..n {
+                scaled_shear[(i, j)] *= self.scale[i];
+            }
+        }
+        Transform {
+            linear: &rotation * &scaled_shear,
+            translation: self.translation.clone(),
+        }
+    }
+}
+
+/// Keyframed transforms, sampled by interpolating between the keys either
+/// side of the requested time.
+#[derive(Debug, Clone, Default)]
+pub struct TransformTrack {
+    // Sorted by time, no two at the same time.
+    keys: Vec<(f64, Transform)>,
+}
+
+impl TransformTrack {
+    pub fn new() -> Self {
+        TransformTrack { keys: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Sets the key at `time`, replacing any key already there.
+    pub fn insert(&mut self, time: f64, transform: Transform) {
+        match self.keys.binary_search_by(|k| k.0.total_cmp(&time)) {
+            Ok(i) => self.keys[i].1 = transform,
+            Err(i) => self.keys.insert(i, (time, transform)),
+        }
+    }
+
+    /// Key times in order.
+    pub fn times(&self) -> impl Iterator<Item = f64> + '_ {
+        self.keys.iter().map(|k| k.0)
+    }
+
+    /// The transform at `time`. Before the first key and after the last the
+    /// nearest key holds. `None` if the track is empty or the two keys around
+    /// `time` can't be interpolated (see [`Transform::interpolate`]).
+    pub fn sample(&self, time: f64) -> Option<Transform> {
+        let i = self.keys.partition_point(|k| k.0 <= time);
+        if i == 0 {
+            return self.keys.first().map(|k| k.1.clone());
+        }
+        if i == self.keys.len() {
+            return self.keys.last().map(|k| k.1.clone());
+        }
+        let (t0, a) = &self.keys[i - 1];
+        let (t1, b) = &self.keys[i];
+        a.interpolate(b, (time - t0) / (t1 - t0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: &[Vec<f64>], b: &[Vec<f64>]) -> bool {
+        a.iter()
+            .flatten()
+            .zip(b.iter().flatten())
+            .all(|(x, y)| (x - y).abs() < 1e-9)
+    }
+
+    #[test]
+    fn apply_compose_invert() {
+        let t = Transform::rotation_2d(std::f64::consts::FRAC_PI_2)
+            .then(&Transform::translation(&[1.0, 0.0]));
+        let p = t.apply(&Point::new(vec![1.0, 0.0]));
+        assert!((p[0] - 1.0).abs() < 1e-12 && (p[1] - 1.0).abs() < 1e-12);
+        let back = t.inverse().unwrap().apply(&p);
+        assert!((back[0] - 1.0).abs() < 1e-12 && back[1].abs() < 1e-12);
+        assert!(Transform::scaling(&[1.0, 0.0]).inverse().is_none());
+        assert_eq!(
+            Transform::new(&[vec![1.0]], &[0.0, 0.0]),
+            Err(Error::DimensionMismatch {
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+
+    #[test]
+    fn plane_rotations_shears_and_products() {
+        let r = Transform::rotation_plane(4, 1, 3, std::f64::consts::FRAC_PI_2);
+        let p = Point::new(vec![1.0, 2.0, 3.0, 0.0]);
+        let q = r.apply(&p);
+        assert!(close(&[q.data().to_vec()], &[vec![1.0, 0.0, 3.0, 2.0]]));
+        assert!(r.is_rigid());
+        let a = Transform::rotation_plane(2, 0, 1, 0.3);
+        assert!(close(&a.linear(), &Transform::rotation_2d(0.3).linear()));
+
+        let sh = Transform::from_shear(3, 0, 2, 2.0);
+        assert_eq!(sh.apply(&Point::new(vec![1, 1, 1])).data(), [3.0, 1.0, 1.0]);
+
+        let t = Transform::translation(&[1.0, 0.0, 0.0, 0.0]);
+        let s = Transform::scaling(&[2.0; 4]);
+        // The right-hand factor acts first.
+        assert_eq!((&t * &s).apply(&p).data(), [3.0, 4.0, 6.0, 0.0]);
+        assert_eq!((s * t).apply(&p).data(), [4.0, 4.0, 6.0, 0.0]);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_apply() {
+        let t = Transform::rotation_2d(0.4).then(&Transform::translation(&[1.0, -2.0]));
+        let flat: Vec<f64> = (0..200).map(|i| i as f64 * 0.5).collect();
+        let mut a = PointCloud::from_flat(&flat, 2).unwrap();
+        let mut b = a.clone();
+        t.apply_in_place(&mut a);
+        t.par_apply_in_place(&mut b);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn decompose_round_trip() {
+        let t = Transform::new(
+            &[
+                vec![1.0, 2.0, 0.5],
+                vec![-0.3, 1.5, 0.0],
+                vec![0.2, 0.1, -2.0],
+            ],
+            &[1.0, 2.0, 3.0],
+        )
+        .unwrap();
+        let d = t.decompose().unwrap();
+        let r = Matrix::from_rows(&d.rotation);
+        assert!((r.determinant() - 1.0).abs() < 1e-9);
+        assert!(close(
+            &(&r.transpose() * &r).to_rows(),
+            &Matrix::identity(3).to_rows()
+        ));
+        assert!(d.scale[2] < 0.0, "mirror goes into the last scale");
+        assert_eq!(d.shear[1][0], 0.0);
+        assert_eq!(d.shear[2][2], 1.0);
+        assert!(close(&d.recompose().linear(), &t.linear()));
+        assert!(Transform::scaling(&[1.0, 0.0]).decompose().is_none());
+    }
+
+    #[test]
+    fn polar() {
+        let t = Transform::rotation_2d(0.7).then(&Transform::scaling(&[2.0, 3.0]));
+        let p = t.polar().unwrap();
+        let r = Matrix::from_rows(&p.rotation);
+        let s = Matrix::from_rows(&p.stretch);
+        assert!(close(&(&r * &s).to_rows(), &t.linear()));
+        assert!(close(
+            &(&r.transpose() * &r).to_rows(),
+            &Matrix::identity(2).to_rows()
+        ));
+        assert_eq!(s[(0, 1)], s[(1, 0)]);
+    }
+
+    #[test]
+    fn rigid_and_similarity() {
+        let rot = Transform::rotation_2d(1.2).then(&Transform::translation(&[3.0, 4.0]));
+        assert!(rot.is_rigid() && rot.is_similarity());
+        let sim = rot.then(&Transform::scaling(&[2.0, 2.0]));
+        assert!(!sim.is_rigid() && sim.is_similarity());
+        let mirror = Transform::scaling(&[1.0, -1.0]);
+        assert!(!mirror.is_rigid() && !mirror.is_similarity());
+        assert!(!Transform::scaling(&[1.0, 2.0]).is_similarity());
+    }
+
+    #[test]
+    fn interpolate() {
+        let a = Transform::rotation_2d(0.2).then(&Transform::translation(&[0.0, 2.0]));
+        let b = Transform::rotation_2d(1.0)
+            .then(&Transform::scaling(&[3.0, 3.0]))
+            .then(&Transform::translation(&[4.0, 0.0]));
+        assert!(close(
+            &a.interpolate(&b, 0.0).unwrap().linear(),
+            &a.linear()
+        ));
+        assert!(close(
+            &a.interpolate(&b, 1.0).unwrap().linear(),
+            &b.linear()
+        ));
+        let mid = a.interpolate(&b, 0.5).unwrap();
+        assert!(mid.is_similarity());
+        let d = mid.decompose().unwrap();
+        assert!((d.rotation[1][0].atan2(d.rotation[0][0]) - 0.6).abs() < 1e-9);
+        assert!((d.scale[0] - 2.0).abs() < 1e-9);
+        assert!(close(&[mid.offset().to_vec()], &[vec![2.0, 1.0]]));
+    }
+
+    #[test]
+    fn interpolate_3d_rotation() {
+        let axis = [0.0, 0.6, 0.8];
+        let from = Transform {
+            linear: rotation_3d(axis, 0.3),
+            translation: vec![0.0; 3],
+        };
+        let to = Transform {
+            linear: rotation_3d(axis, 3.0),
+            translation: vec![0.0; 3],
+        };
+        let mid = from.interpolate(&to, 0.5).unwrap();
+        assert!(mid.is_rigid());
+        assert!(close(&mid.linear(), &rotation_3d(axis, 1.65).to_rows()));
+        let (_, half_turn) = axis_angle(&rotation_3d(axis, std::f64::consts::PI));
+        assert!((half_turn - std::f64::consts::PI).abs() < 1e-9);
+    }
+
+    #[test]
+    fn track() {
+        let mut track = TransformTrack::new();
+        assert!(track.sample(0.0).is_none());
+        track.insert(2.0, Transform::translation(&[10.0]));
+        track.insert(0.0, Transform::identity(1));
+        track.insert(2.0, Transform::translation(&[4.0]));
+        assert_eq!(track.times().collect::<Vec<_>>(), vec![0.0, 2.0]);
+        let at = |t: f64| track.sample(t).unwrap().offset()[0];
+        assert_eq!(at(-1.0), 0.0);
+        assert!((at(0.5) - 1.0).abs() < 1e-12);
+        assert_eq!(at(3.0), 4.0);
+
+        let mut cloud = PointCloud::from_flat(&[1.0, 2.0], 1).unwrap();
+        track.sample(1.0).unwrap().apply_in_place(&mut cloud);
+        assert_eq!(cloud.as_flat(), &[3.0, 4.0]);
+    }
+}
@@ -0,0 +1,311 @@
+// Traveling-salesman tour heuristics.
+//
+// Tours are closed: the salesman returns from the last point in `order` to
+// the first. Every function takes the metric as a closure so any distance
+// between points can be used.
+
+use crate::Point;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tour {
+    /// Indices into the input points, in visiting order.
+    pub order: Vec<usize>,
+    /// Length of the closed tour under the metric it was built with.
+    pub length: f64,
+}
+
+struct Distances {
+    n: usize,
+    d: Vec<f64>,
+}
+
+impl Distances {
+    fn new<T, M>(points: &[Point<T>], metric: M) -> Self
+    where
+        M: Fn(&Point<T>, &Point<T>) -> f64,
+    {
+        let n = points.len();
+        let mut d = vec![0.0; n * n];
+        for i in 0..n {
+            for j in i + 1..n {
+                let v = metric(&points[i], &points[j]);
+                d[i * n + j] = v;
+                d[j * n + i] = v;
+            }
+        }
+        Distances { n, d }
+    }
+
+    fn get(&self, i: usize, j: usize) -> f64 {
+        self.d[i * self.n + j]
+    }
+
+    fn tour_length(&self, order: &[usize]) -> f64 {
+        if order.len() < 2 {
+            return 0.0;
+        }
+        let closing = self.get(order[order.len() - 1], order[0]);
+        order.windows(2).map(|w| self.get(w[0], w[1])).sum::<f64>() + closing
+    }
+}
+
+/// Builds a tour by always moving to the closest unvisited point.
+pub fn nearest_neighbor<T, M>(points: &[Point<T>], start: usize, metric: M) -> Tour
+where
+    M: Fn(&Point<T>, &Point<T>) -> f64,
+{
+    let dist = Distances::new(points, metric);
+    Tour {
+        order: nn_order(&dist, start),
+        length: 0.0,
+    }
+    .with_length(&dist)
+}
+
+/// Improves `tour` by reversing segments while that shortens it.
+pub fn two_opt<T, M>(points: &[Point<T>], tour: &Tour, metric: M) -> Tour
+where
+    M: Fn(&Point<T>, &Point<T>) -> f64,
+{
+    let dist = Distances::new(points, metric);
+    let mut order = tour.order.clone();
+    two_opt_pass(&dist, &mut order);
+    Tour { order, length: 0.0 }.with_length(&dist)
+}
+
+/// Improves `tour` by relocating runs of one to three points, possibly
+/// reversed, while that shortens it.
+pub fn or_opt<T, M>(points: &[Point<T>], tour: &Tour, metric: M) -> Tour
+where
+    M: Fn(&Point<T>, &Point<T>) -> f64,
+{
+    let dist = Distances::new(points, metric);
+    let mut order = tour.order.clone();
+    or_opt_pass(&dist, &mut order);
+    Tour { order, length: 0.0 }.with_length(&dist)
+}
+
+/// Nearest neighbour from point 0 followed by alternating 2-opt and Or-opt
+/// until neither improves the tour.
+pub fn solve<T, M>(points: &[Point<T>], metric: M) -> Tour
+where
+    M: Fn(&Point<T>, &Point<T>) -> f64,
+{
+    let dist = Distances::new(points, metric);
+    let mut order = nn_order(&dist, 0);
+    loop {
+        let a = two_opt_pass(&dist, &mut order);
+        let b = or_opt_pass(&dist, &mut order);
+        if !a && !b {
+            break;
+        }
+    }
+    Tour { order, length: 0.0 }.with_length(&dist)
+}
+
+impl Tour {
+    fn with_length(mut self, dist: &Distances) -> Self {
+        self.length = dist.tour_length(&self.order);
+        self
+    }
+}
+
+fn nn_order(dist: &Distances, start: usize) -> Vec<usize> {
+    let n = dist.n;
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut visited = vec![false; n];
+    let mut order = Vec::with_capacity(n);
+    let mut current = start;
+    visited[current] = true;
+    order.push(current);
+    for _ in 1..n {
+        let next = (0..n)
+            .filter(|&j| !visited[j])
+            .min_by(|&a, &b| dist.get(current, a).total_cmp(&dist.get(current, b)))
+            .unwrap();
+        visited[next] = true;
+        order.push(next);
+        current = next;
+    }
+    order
+}
+
+const EPS: f64 = 1e-12;
+
+// Returns whether anything changed.
+fn two_opt_pass(dist: &Distances, order: &mut [usize]) -> bool {
+    let n = order.len();
+    if n < 4 {
+        return false;
+    }
+    let mut changed = false;
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 0..n - 1 {
+            for j in i + 2..n {
+                // Edges (a, b) and (c, d); skip the pair sharing the closing edge.
+                if i == 0 && j == n - 1 {
+                    continue;
+                }
+                let (a, b) = (order[i], order[i + 1]);
+                let (c, d) = (order[j], order[(j + 1) % n]);
+                let delta = dist.get(a, c) + dist.get(b, d) - dist.get(a, b) - dist.get(c, d);
+                if delta < -EPS {
+                    order[i + 1..=j].reverse();
+                    improved = true;
+                    changed = true;
+                }
+            }
+        }
+    }
+    changed
+}
+
+fn or_opt_pass(dist: &Distances, order: &mut Vec<usize>) -> bool {
+    let n = order.len();
+    if n < 5 {
+        return false;
+    }
+    let mut changed = false;
+    let mut improved = true;
+    while improved {
+        improved = false;
+        'search: for len in 1..=3 {
+            for start in 0..n {
+                // Runs never include the first or last position, so they
+                // don't wrap around the end of `order`.
+                let end = start + len - 1;
+                if end >= n - 1 || start == 0 {
+                    continue;
+                }
+                let prev = order[start - 1];
+                let next = order[end + 1];
+                let first = order[start];
+                let last = order[end];
+                let removed_gain =
+                    dist.get(prev, first) + dist.get(last, next) - dist.get(prev, next);
+
+                let rest: Vec<usize> = order[..start]
+                    .iter()
+                    .chain(&order[end + 1..])
+                    .copied()
+                    .collect();
+                let m = rest.len();
+                for k in 0..m {
+                    let (u, v) = (rest[k], rest[(k + 1) % m]);
+                    if u == prev && v == next {
+                        continue;
+                    }
+                    let forward = dist.get(u, first) + dist.get(last, v) - dist.get(u, v);
+                    let backward = dist.get(u, last) + dist.get(first, v) - dist.get(u, v);
+                    let (cost, reversed) = if backward < forward {
+                        (backward, true)
+                    } else {
+                        (forward, false)
+                    };
+                    if cost < removed_gain - EPS {
+                        let mut run: Vec<usize> = order[start..=end].to_vec();
+                        if reversed {
+                            run.reverse();
+                        }
+                        let mut new_order = rest[..=k].to_vec();
+                        new_order.extend(run);
+                        new_order.extend(&rest[k + 1..]);
+                        *order = new_order;
+                        improved = true;
+                        changed = true;
+                        break 'search;
+                    }
+                }
+            }
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn euclid(a: &Point<f64>, b: &Point<f64>) -> f64 {
+        (a - b).dist()
+    }
+
+    fn square() -> Vec<Point<f64>> {
+        // Corners of a unit square in a crossing order.
+        vec![
+            Point::new(vec![0.0, 0.0]),
+            Point::new(vec![1.0, 1.0]),
+            Point::new(vec![1.0, 0.0]),
+            Point::new(vec![0.0, 1.0]),
+        ]
+    }
+
+    #[test]
+    fn nearest_neighbor_tour() {
+        let tour = nearest_neighbor(&square(), 0, euclid);
+        assert_eq!(tour.order.len(), 4);
+        assert_eq!(tour.order[0], 0);
+        assert!((tour.length - 4.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn two_opt_uncrosses() {
+        let pts = square();
+        let crossed = Tour {
+            order: vec![0, 1, 2, 3],
+            length: 0.0,
+        };
+        let fixed = two_opt(&pts, &crossed, euclid);
+        assert!((fixed.length - 4.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn or_opt_relocates() {
+        // Points on a line visited out of order.
+        let pts: Vec<Point<f64>> = (0..6).map(|i| Point::new(vec![i as f64, 0.0])).collect();
+        let tour = Tour {
+            order: vec![0, 1, 4, 2, 3, 5],
+            length: 0.0,
+        };
+        let better = or_opt(&pts, &tour, euclid);
+        assert!((better.length - 10.0).abs() < 1e-12);
+        let mut sorted = better.order.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..6).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn solve_circle() {
+        // Points on a circle, shuffled: the optimal tour is the polygon.
+        let n = 12;
+        let mut idx: Vec<usize> = (0..n).collect();
+        crate::rng::Rng::seed_from_u64(5).shuffle(&mut idx);
+        let pts: Vec<Point<f64>> = idx
+            .iter()
+            .map(|&i| {
+                let t = i as f64 / n as f64 * std::f64::consts::TAU;
+                Point::new(vec![t.cos(), t.sin()])
+            })
+            .collect();
+        let tour = solve(&pts, euclid);
+        let perimeter = n as f64 * 2.0 * (std::f64::consts::PI / n as f64).sin();
+        assert!((tour.length - perimeter).abs() < 1e-9);
+    }
+
+    #[test]
+    fn manhattan_metric() {
+        let pts = square();
+        let tour = solve(&pts, |a, b| {
+            a.data()
+                .iter()
+                .zip(b.data())
+                .map(|(x, y)| (x - y).abs())
+                .sum()
+        });
+        assert!((tour.length - 4.0).abs() < 1e-12);
+    }
+}
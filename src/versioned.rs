@@ -0,0 +1,293 @@
+// A point cloud with undo and redo.
+//
+// Every edit is recorded as a delta holding just enough to apply it in either
+// direction: the coordinates an add inserted or a remove took out, and the
+// before and after coordinates of the points a transform touched. Undo pops a
+// delta and reverses it onto the redo stack; a fresh edit clears redo.
+
+use crate::cloud::{Change, ObserverId};
+use crate::{Error, Point, PointCloud};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Delta<T> {
+    Add {
+        index: usize,
+        coords: Vec<T>,
+    },
+    Remove {
+        index: usize,
+        coords: Vec<T>,
+    },
+    Transform {
+        indices: Vec<usize>,
+        before: Vec<T>,
+        after: Vec<T>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct VersionedCloud<T> {
+    cloud: PointCloud<T>,
+    undo: Vec<Delta<T>>,
+    redo: Vec<Delta<T>>,
+}
+
+impl<T: Copy> VersionedCloud<T> {
+    /// Starts an empty history at `cloud`.
+    pub fn new(cloud: PointCloud<T>) -> Self {
+        VersionedCloud {
+            cloud,
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// The current state.
+    pub fn cloud(&self) -> &PointCloud<T> {
+        &self.cloud
+    }
+
+    pub fn into_cloud(self) -> PointCloud<T> {
+        self.cloud
+    }
+
+    /// Registers `f` to hear about every edit, including those made by undo
+    /// and redo.
+    pub fn on_change<F>(&mut self, f: F) -> ObserverId
+    where
+        F: FnMut(&Change<'_, T>) + Send + Sync + 'static,
+    {
+        self.cloud.on_change(f)
+    }
+
+    pub fn remove_observer(&mut self, id: ObserverId) -> bool {
+        self.cloud.remove_observer(id)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Number of edits that can be undone.
+    pub fn history_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Appends a point and returns its index.
+    pub fn add(&mut self, coords: &[T]) -> Result<usize, Error> {
+        let index = self.cloud.push(coords)?;
+        self.record(Delta::Add {
+            index,
+            coords: coords.to_vec(),
+        });
+        Ok(index)
+    }
+
+    /// Removes point `i`, shifting later points down.
+    pub fn remove(&mut self, i: usize) -> Option<Point<T>> {
+        let p = self.cloud.remove(i)?;
+        self.record(Delta::Remove {
+            index: i,
+            coords: p.to_vec(),
+        });
+        Some(p)
+    }
+
+    /// Applies `f` to the coordinates of each listed point as one edit.
+    /// Panics if an index is out of range.
+    pub fn transform<F: FnMut(&mut [T])>(&mut self, indices: &[usize], mut f: F) {
+        let dim = self.cloud.dim();
+        let mut before = Vec::with_capacity(indices.len() * dim);
+        let mut after = Vec::with_capacity(indices.len() * dim);
+        for &i in indices {
+            let found = self.cloud.update(i, |coords| {
+                before.extend_from_slice(coords);
+                f(coords);
+                after.extend_from_slice(coords);
+            });
+            assert!(found, "point index {i} out of range");
+        }
+        self.record(Delta::Transform {
+            indices: indices.to_vec(),
+            before,
+            after,
+        });
+    }
+
+    /// Applies `f` to every point as one edit.
+    pub fn transform_all<F: FnMut(&mut [T])>(&mut self, f: F) {
+        let all: Vec<usize> = (0..self.cloud.len()).collect();
+        self.transform(&all, f);
+    }
+
+    /// Reverts the latest edit; false if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(delta) = self.undo.pop() else {
+            return false;
+        };
+        self.apply(&delta, false);
+        self.redo.push(delta);
+        true
+    }
+
+    /// Reapplies the latest undone edit; false if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(delta) = self.redo.pop() else {
+            return false;
+        };
+        self.apply(&delta, true);
+        self.undo.push(delta);
+        true
+    }
+
+    /// Shrinks the undo history: runs of transforms on the same points merge
+    /// into one step, then all but the latest `keep` steps are dropped. The
+    /// current state and the redo stack are unchanged.
+    pub fn compact(&mut self, keep: usize) {
+        let mut merged: Vec<Delta<T>> = Vec::with_capacity(self.undo.len());
+        for delta in self.undo.drain(..) {
+            if let (
+                Some(Delta::Transform {
+                    indices: prev,
+                    after: prev_after,
+                    ..
+                }),
+                Delta::Transform { indices, after, .. },
+            ) = (merged.last_mut(), &delta)
+                && prev == indices
+            {
+                prev_after.clone_from(after);
+                continue;
+            }
+            merged.push(delta);
+        }
+        let drop = merged.len().saturating_sub(keep);
+        merged.drain(..drop);
+        self.undo = merged;
+    }
+
+    /// Forgets all history, keeping the current state.
+    pub fn clear_history(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    fn record(&mut self, delta: Delta<T>) {
+        self.redo.clear();
+        self.undo.push(delta);
+    }
+
+    fn apply(&mut self, delta: &Delta<T>, forward: bool) {
+        let dim = self.cloud.dim();
+        match (delta, forward) {
+            (Delta::Add { index, coords }, true) | (Delta::Remove { index, coords }, false) => {
+                self.cloud
+                    .insert(*index, coords)
+                    .expect("history matches the cloud dimension");
+            }
+            (Delta::Add { index, .. }, false) | (Delta::Remove { index, .. }, true) => {
+                self.cloud.remove(*index);
+            }
+            (
+                Delta::Transform {
+                    indices,
+                    before,
+                    after,
+                },
+                forward,
+            ) => {
+                // Walk backwards on undo so a point listed twice ends up with
+                // its first `before`.
+                let coords = if forward { after } else { before };
+                for k in 0..indices.len() {
+                    let k = if forward { k } else { indices.len() - 1 - k };
+                    self.cloud
+                        .set(indices[k], &coords[k * dim..(k + 1) * dim])
+                        .expect("history matches the cloud dimension");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cloud() -> VersionedCloud<f64> {
+        VersionedCloud::new(PointCloud::from_flat(&[0.0, 0.0, 1.0, 1.0], 2).unwrap())
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut v = cloud();
+        let start = v.cloud().clone();
+        assert_eq!(v.add(&[2.0, 2.0]), Ok(2));
+        v.remove(0).unwrap();
+        v.transform_all(|p| p[0] += 10.0);
+        assert_eq!(v.cloud().as_flat(), &[11.0, 1.0, 12.0, 2.0]);
+        let end = v.cloud().clone();
+
+        while v.undo() {}
+        assert_eq!(v.cloud(), &start);
+        assert!(!v.can_undo());
+        while v.redo() {}
+        assert_eq!(v.cloud(), &end);
+
+        v.undo();
+        v.add(&[5.0, 5.0]).unwrap();
+        assert!(!v.can_redo());
+        assert!(v.add(&[1.0]).is_err());
+        assert_eq!(v.history_len(), 3);
+    }
+
+    #[test]
+    fn repeated_index() {
+        let mut v = cloud();
+        v.transform(&[1, 1], |p| p[1] *= 3.0);
+        assert_eq!(v.cloud().get(1), Some(&[1.0, 9.0][..]));
+        v.undo();
+        assert_eq!(v.cloud().get(1), Some(&[1.0, 1.0][..]));
+    }
+
+    #[test]
+    fn compact() {
+        let mut v = cloud();
+        v.add(&[3.0, 3.0]).unwrap();
+        for _ in 0..5 {
+            v.transform(&[0, 2], |p| p[0] += 1.0);
+        }
+        v.compact(10);
+        assert_eq!(v.history_len(), 2);
+        assert_eq!(v.cloud().as_flat(), &[5.0, 0.0, 1.0, 1.0, 8.0, 3.0]);
+        v.undo();
+        assert_eq!(v.cloud().as_flat(), &[0.0, 0.0, 1.0, 1.0, 3.0, 3.0]);
+        v.redo();
+        v.compact(1);
+        assert_eq!(v.history_len(), 1);
+        v.undo();
+        assert!(!v.undo());
+        assert_eq!(v.cloud().len(), 3);
+    }
+
+    #[test]
+    fn undo_is_observed() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let mut v = cloud();
+        let count = Arc::new(AtomicUsize::new(0));
+        let c = Arc::clone(&count);
+        v.on_change(move |_| {
+            c.fetch_add(1, Ordering::Relaxed);
+        });
+        v.transform(&[0, 1], |p| p[0] = -1.0);
+        v.undo();
+        v.redo();
+        assert_eq!(count.load(Ordering::Relaxed), 6);
+    }
+}
@@ -0,0 +1,163 @@
+// Mapping between normalized device coordinates and pixels.
+//
+// NDC run from -1 to 1 on every axis with y pointing up, as projections
+// produce them; pixels count from the viewport's top-left corner with y
+// pointing down. Depth maps from [-1, 1] to the viewport's depth range,
+// [0, 1] by default.
+
+use crate::{Point2, Point3};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    /// Top-left corner, in pixels.
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub min_depth: f64,
+    pub max_depth: f64,
+}
+
+impl Viewport {
+    /// A `width` by `height` viewport with its top-left corner at `(x, y)`.
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Viewport {
+            x,
+            y,
+            width,
+            height,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }
+    }
+
+    pub fn with_depth(self, min_depth: f64, max_depth: f64) -> Self {
+        Viewport {
+            min_depth,
+            max_depth,
+            ..self
+        }
+    }
+
+    /// Width over height, what a projection needs to keep circles round.
+    pub fn aspect(&self) -> f64 {
+        self.width / self.height
+    }
+
+    /// The largest viewport of the given aspect ratio centred inside this
+    /// one, with bars on two sides (letterbox or pillarbox) as needed.
+    pub fn fit(&self, aspect: f64) -> Viewport {
+        let (width, height) = if aspect > self.aspect() {
+            (self.width, self.width / aspect)
+        } else {
+            (self.height * aspect, self.height)
+        };
+        Viewport {
+            x: self.x + (self.width - width) / 2.0,
+            y: self.y + (self.height - height) / 2.0,
+            width,
+            height,
+            ..*self
+        }
+    }
+
+    fn pixel(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.x + (x + 1.0) / 2.0 * self.width,
+            self.y + (1.0 - y) / 2.0 * self.height,
+        )
+    }
+
+    fn device(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            (x - self.x) / self.width * 2.0 - 1.0,
+            1.0 - (y - self.y) / self.height * 2.0,
+        )
+    }
+
+    /// NDC to pixel coordinates.
+    pub fn to_screen<T>(&self, ndc: &Point2<T>) -> Point2<f64>
+    where
+        T: Into<f64> + Copy,
+    {
+        let (x, y) = self.pixel(ndc.x().into(), ndc.y().into());
+        Point2::new(x, y)
+    }
+
+    /// Pixel coordinates back to NDC.
+    pub fn to_ndc<T>(&self, screen: &Point2<T>) -> Point2<f64>
+    where
+        T: Into<f64> + Copy,
+    {
+        let (x, y) = self.device(screen.x().into(), screen.y().into());
+        Point2::new(x, y)
+    }
+
+    /// NDC to pixel coordinates and depth in the depth range.
+    pub fn to_screen3<T>(&self, ndc: &Point3<T>) -> Point3<f64>
+    where
+        T: Into<f64> + Copy,
+    {
+        let (x, y) = self.pixel(ndc.x().into(), ndc.y().into());
+        let z = self.min_depth + (ndc.z().into() + 1.0) / 2.0 * (self.max_depth - self.min_depth);
+        Point3::new(x, y, z)
+    }
+
+    /// Pixel coordinates and depth back to NDC.
+    pub fn to_ndc3<T>(&self, screen: &Point3<T>) -> Point3<f64>
+    where
+        T: Into<f64> + Copy,
+    {
+        let (x, y) = self.device(screen.x().into(), screen.y().into());
+        let z =
+            (screen.z().into() - self.min_depth) / (self.max_depth - self.min_depth) * 2.0 - 1.0;
+        Point3::new(x, y, z)
+    }
+
+    /// Whether the pixel position lies inside the viewport.
+    pub fn contains<T>(&self, screen: &Point2<T>) -> bool
+    where
+        T: Into<f64> + Copy,
+    {
+        let (x, y) = (screen.x().into(), screen.y().into());
+        (self.x..=self.x + self.width).contains(&x) && (self.y..=self.y + self.height).contains(&y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_both_ways() {
+        let vp = Viewport::new(0.0, 0.0, 800.0, 600.0);
+        let s = vp.to_screen(&Point2::new(-1, 1));
+        assert_eq!((s.x(), s.y()), (0.0, 0.0));
+        let s = vp.to_screen(&Point2::new(0.5, -1.0));
+        assert_eq!((s.x(), s.y()), (600.0, 600.0));
+        let back = vp.to_ndc(&s);
+        assert_eq!((back.x(), back.y()), (0.5, -1.0));
+        assert!(vp.contains(&s) && !vp.contains(&Point2::new(801, 0)));
+
+        let vp = vp.with_depth(0.1, 0.9);
+        let s = vp.to_screen3(&Point3::new(0.0, 0.0, -1.0));
+        assert_eq!((s.x(), s.y(), s.z()), (400.0, 300.0, 0.1));
+        assert_eq!(vp.to_ndc3(&s).z(), -1.0);
+    }
+
+    #[test]
+    fn fits_an_aspect_ratio() {
+        let vp = Viewport::new(10.0, 0.0, 800.0, 600.0);
+        assert_eq!(vp.aspect(), 4.0 / 3.0);
+        let wide = vp.fit(2.0);
+        assert_eq!(
+            (wide.x, wide.y, wide.width, wide.height),
+            (10.0, 100.0, 800.0, 400.0)
+        );
+        let tall = vp.fit(1.0);
+        assert_eq!(
+            (tall.x, tall.y, tall.width, tall.height),
+            (110.0, 0.0, 600.0, 600.0)
+        );
+    }
+}
@@ -0,0 +1,202 @@
+// Vantage-point trees: exact nearest-neighbour search that needs nothing but
+// a metric.
+//
+// Each node picks a vantage item and splits the rest at the median distance
+// `mu` to it: the inside child holds items within `mu`, the outside child the
+// others. The triangle inequality tells a query which children can hold
+// anything closer than the current k-th best.
+
+use crate::index::{KBest, MetricIndex, MetricItem, Neighbor, RadiusResults};
+
+#[derive(Debug, Clone)]
+struct Node {
+    item: usize,
+    mu: f64,
+    inside: Option<usize>,
+    outside: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VpTree<I> {
+    items: Vec<I>,
+    nodes: Vec<Node>,
+}
+
+impl<I: MetricItem> VpTree<I> {
+    pub fn new(items: Vec<I>) -> Self {
+        let mut tree = VpTree {
+            items,
+            nodes: Vec::new(),
+        };
+        let all: Vec<usize> = (0..tree.items.len()).collect();
+        tree.build(all);
+        tree
+    }
+
+    pub fn items(&self) -> &[I] {
+        &self.items
+    }
+
+    fn build(&mut self, mut ids: Vec<usize>) -> Option<usize> {
+        if ids.is_empty() {
+            return None;
+        }
+        // The first item is the vantage point; input order is as good as
+        // random for the median split.
+        let vp = ids.swap_remove(0);
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            item: vp,
+            mu: 0.0,
+            inside: None,
+            outside: None,
+        });
+        if ids.is_empty() {
+            return Some(node);
+        }
+        let mut by_dist: Vec<(f64, usize)> = ids
+            .iter()
+            .map(|&i| (self.items[vp].distance(&self.items[i]), i))
+            .collect();
+        let mid = by_dist.len() / 2;
+        by_dist.select_nth_unstable_by(mid, |a, b| a.0.total_cmp(&b.0));
+        let mu = by_dist[mid].0;
+        let inside: Vec<usize> = by_dist.iter().filter(|e| e.0 <= mu).map(|e| e.1).collect();
+        let outside: Vec<usize> = by_dist.iter().filter(|e| e.0 > mu).map(|e| e.1).collect();
+        let inside = self.build(inside);
+        let outside = self.build(outside);
+        let n = &mut self.nodes[node];
+        n.mu = mu;
+        n.inside = inside;
+        n.outside = outside;
+        Some(node)
+    }
+
+    fn search(
+        &self,
+        node: Option<usize>,
+        query: &I,
+        best: &mut KBest,
+        keep: &dyn Fn(usize) -> bool,
+    ) {
+        let Some(node) = node else { return };
+        let n = &self.nodes[node];
+        let d = self.items[n.item].distance(query);
+        if keep(n.item) {
+            best.push(n.item, d);
+        }
+        // Visit the side the query falls in first so `worst` shrinks sooner.
+        if d <= n.mu {
+            if d - best.worst() <= n.mu {
+                self.search(n.inside, query, best, keep);
+            }
+            if d + best.worst() >= n.mu {
+                self.search(n.outside, query, best, keep);
+            }
+        } else {
+            if d + best.worst() >= n.mu {
+                self.search(n.outside, query, best, keep);
+            }
+            if d - best.worst() <= n.mu {
+                self.search(n.inside, query, best, keep);
+            }
+        }
+    }
+
+    fn search_radius(
+        &self,
+        node: Option<usize>,
+        query: &I,
+        radius: f64,
+        out: &mut RadiusResults,
+        keep: &dyn Fn(usize) -> bool,
+    ) {
+        let Some(node) = node else { return };
+        let n = &self.nodes[node];
+        let d = self.items[n.item].distance(query);
+        if keep(n.item) {
+            out.push(n.item, d);
+        }
+        if d - radius <= n.mu {
+            self.search_radius(n.inside, query, radius, out, keep);
+        }
+        if d + radius >= n.mu {
+            self.search_radius(n.outside, query, radius, out, keep);
+        }
+    }
+}
+
+impl<I: MetricItem> MetricIndex<I> for VpTree<I> {
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn k_nearest_where(&self, query: &I, k: usize, keep: &dyn Fn(usize) -> bool) -> Vec<Neighbor> {
+        let mut best = KBest::new(k);
+        if !self.nodes.is_empty() {
+            self.search(Some(0), query, &mut best, keep);
+        }
+        best.into_neighbors()
+    }
+
+    fn within_radius_where(
+        &self,
+        query: &I,
+        radius: f64,
+        keep: &dyn Fn(usize) -> bool,
+    ) -> Vec<Neighbor> {
+        let mut out = RadiusResults::new(radius);
+        if !self.nodes.is_empty() {
+            self.search_radius(Some(0), query, radius, &mut out, keep);
+        }
+        out.into_neighbors()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point;
+    use crate::index::LinearScan;
+    use crate::rng::Rng;
+
+    #[test]
+    fn matches_linear_scan() {
+        let mut rng = Rng::seed_from_u64(31);
+        let pts: Vec<Point<f64>> = (0..300)
+            .map(|_| Point::new((0..3).map(|_| rng.range_f64(0.0, 10.0)).collect()))
+            .collect();
+        let tree = VpTree::new(pts.clone());
+        let scan = LinearScan::new(pts);
+        for _ in 0..25 {
+            let q = Point::new((0..3).map(|_| rng.range_f64(0.0, 10.0)).collect());
+            assert_eq!(tree.k_nearest(&q, 5), scan.k_nearest(&q, 5));
+            assert_eq!(tree.within_radius(&q, 2.0), scan.within_radius(&q, 2.0));
+            let keep = |i: usize| i.is_multiple_of(3);
+            assert_eq!(
+                tree.k_nearest_where(&q, 5, &keep),
+                scan.k_nearest_where(&q, 5, &keep)
+            );
+            assert_eq!(
+                tree.within_radius_where(&q, 3.0, &keep),
+                scan.within_radius_where(&q, 3.0, &keep)
+            );
+        }
+    }
+
+    #[test]
+    fn integers_under_absolute_difference() {
+        struct N(i64);
+        impl MetricItem for N {
+            fn distance(&self, other: &Self) -> f64 {
+                (self.0 - other.0).abs() as f64
+            }
+        }
+        let tree = VpTree::new((0..50).map(|i| N(i * 3)).collect());
+        let hits = tree.k_nearest(&N(31), 2);
+        assert_eq!(hits[0].index, 10);
+        assert_eq!(hits[0].distance, 1.0);
+        assert_eq!(hits[1].index, 11);
+        assert!(VpTree::<N>::new(Vec::new()).nearest(&N(0)).is_none());
+    }
+}
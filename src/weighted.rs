@@ -0,0 +1,173 @@
+// Statistics of point sets where every point may carry a mass.
+//
+// Weights are optional throughout: `None` gives every point weight one.
+// Weights must be non-negative; a set whose weights sum to zero has no
+// centroid, covariance or mass distribution, and the functions say so by
+// returning `None`.
+
+use crate::Point;
+use crate::lp::{self, Lp};
+
+pub(crate) fn weight(weights: Option<&[f64]>, i: usize) -> f64 {
+    weights.map_or(1.0, |w| w[i])
+}
+
+pub(crate) fn check<T>(points: &[Point<T>], weights: Option<&[f64]>) -> Option<(usize, f64)> {
+    if let Some(w) = weights {
+        assert_eq!(w.len(), points.len(), "one weight per point");
+        assert!(w.iter().all(|&x| x >= 0.0), "weights must be non-negative");
+    }
+    let dim = points.first()?.len();
+    assert!(
+        points.iter().all(|p| p.len() == dim),
+        "points must share a dimension"
+    );
+    let total: f64 = (0..points.len()).map(|i| weight(weights, i)).sum();
+    (total > 0.0).then_some((dim, total))
+}
+
+/// The weighted mean of the points. Panics if there isn't one weight per
+/// point, a weight is negative, or the points don't share a dimension.
+pub fn centroid<T>(points: &[Point<T>], weights: Option<&[f64]>) -> Option<Point<f64>>
+where
+    T: Into<f64> + Copy,
+{
+    let (dim, total) = check(points, weights)?;
+    let mut c = vec![0.0; dim];
+    for (i, p) in points.iter().enumerate() {
+        let w = weight(weights, i);
+        for (cj, &v) in c.iter_mut().zip(p.iter()) {
+            *cj += w * v.into();
+        }
+    }
+    c.iter_mut().for_each(|v| *v /= total);
+    Some(Point::new(c))
+}
+
+/// The weighted covariance matrix about the weighted mean, normalised by the
+/// total weight. Same panics as [`centroid`].
+pub fn covariance<T>(points: &[Point<T>], weights: Option<&[f64]>) -> Option<Vec<Vec<f64>>>
+where
+    T: Into<f64> + Copy,
+{
+    let mean = centroid(points, weights)?;
+    let (dim, total) = check(points, weights)?;
+    let mut cov = vec![vec![0.0; dim]; dim];
+    for (i, p) in points.iter().enumerate() {
+        let w = weight(weights, i);
+        let d: Vec<f64> = p
+            .iter()
+            .zip(mean.iter())
+            .map(|(&v, m)| v.into() - m)
+            .collect();
+        for (row, &da) in cov.iter_mut().zip(&d) {
+            for (c, &db) in row.iter_mut().zip(&d) {
+                *c += w * da * db;
+            }
+        }
+    }
+    cov.iter_mut().flatten().for_each(|c| *c /= total);
+    Some(cov)
+}
+
+/// Earth mover's distance between two weighted point sets under `metric`.
+///
+/// Each side's masses are scaled to sum to one, so this is the least average
+/// distance mass has to travel to turn one distribution into the other. It
+/// solves the transport problem as a dense linear program and is meant for
+/// sets of up to a few dozen points.
+pub fn emd<T, M>(
+    a: &[Point<T>],
+    wa: Option<&[f64]>,
+    b: &[Point<T>],
+    wb: Option<&[f64]>,
+    metric: M,
+) -> Option<f64>
+where
+    T: Into<f64> + Copy,
+    M: Fn(&Point<T>, &Point<T>) -> f64,
+{
+    let (_, ta) = check(a, wa)?;
+    let (_, tb) = check(b, wb)?;
+    let (n, m) = (a.len(), b.len());
+    // Flow f[i * m + j] from a[i] to b[j]; maximise the negated cost.
+    let cost: Vec<f64> = (0..n * m).map(|k| -metric(&a[k / m], &b[k % m])).collect();
+    let supply: Vec<f64> = (0..n).map(|i| weight(wa, i) / ta).collect();
+    let demand: Vec<f64> = (0..m).map(|j| weight(wb, j) / tb).collect();
+    let mut rows = Vec::with_capacity(n + m + 1 + n * m);
+    let mut rhs = Vec::with_capacity(rows.capacity());
+    for (i, &s) in supply.iter().enumerate() {
+        rows.push(
+            (0..n * m)
+                .map(|k| f64::from(u8::from(k / m == i)))
+                .collect(),
+        );
+        rhs.push(s);
+    }
+    for (j, &d) in demand.iter().enumerate() {
+        rows.push(
+            (0..n * m)
+                .map(|k| f64::from(u8::from(k % m == j)))
+                .collect(),
+        );
+        rhs.push(d);
+    }
+    // All the mass has to move; with the capacities above this makes every
+    // row and column sum exact. Both totals are one up to rounding, and the
+    // smaller keeps the program feasible.
+    let total = supply.iter().sum::<f64>().min(demand.iter().sum());
+    rows.push(vec![-1.0; n * m]);
+    rhs.push(-total);
+    for k in 0..n * m {
+        let mut row = vec![0.0; n * m];
+        row[k] = -1.0;
+        rows.push(row);
+        rhs.push(0.0);
+    }
+    match lp::maximize(&cost, &rows, &rhs) {
+        Lp::Optimal { value, .. } => Some(-value),
+        // Flows are bounded and the uniform plan is feasible.
+        Lp::Infeasible | Lp::Unbounded => unreachable!("transport problem is always solvable"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn euclid(a: &Point<f64>, b: &Point<f64>) -> f64 {
+        (a - b).dist()
+    }
+
+    fn pts(v: &[(f64, f64)]) -> Vec<Point<f64>> {
+        v.iter().map(|&(x, y)| Point::new(vec![x, y])).collect()
+    }
+
+    #[test]
+    fn centroid_and_covariance() {
+        let p = pts(&[(0.0, 0.0), (2.0, 0.0), (2.0, 2.0)]);
+        assert_eq!(centroid(&p, None).unwrap().data(), &[4.0 / 3.0, 2.0 / 3.0]);
+        let w = [2.0, 1.0, 1.0];
+        assert_eq!(centroid(&p, Some(&w)).unwrap().data(), &[1.0, 0.5]);
+        // Doubling every weight changes nothing.
+        let w2 = [4.0, 2.0, 2.0];
+        assert_eq!(covariance(&p, Some(&w)), covariance(&p, Some(&w2)));
+        let c = covariance(&p, Some(&w)).unwrap();
+        assert_eq!(c[0][0], 1.0);
+        assert_eq!(c[0][1], c[1][0]);
+        assert!(centroid(&p, Some(&[0.0, 0.0, 0.0])).is_none());
+        assert!(centroid::<f64>(&[], None).is_none());
+    }
+
+    #[test]
+    fn earth_movers() {
+        let a = pts(&[(0.0, 0.0), (1.0, 0.0)]);
+        let b = pts(&[(0.0, 3.0), (1.0, 3.0)]);
+        assert!((emd(&a, None, &b, None, euclid).unwrap() - 3.0).abs() < 1e-9);
+        // All of b's mass sits on its first point: half of a's mass moves
+        // straight up, the other half diagonally.
+        let d = emd(&a, None, &b, Some(&[1.0, 0.0]), euclid).unwrap();
+        assert!((d - (1.5 + 10f64.sqrt() / 2.0)).abs() < 1e-9);
+        assert!(emd(&a, None, &a, Some(&[3.0, 3.0]), euclid).unwrap().abs() < 1e-9);
+    }
+}